@@ -0,0 +1,75 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::Manifest;
+use crate::outline::Symbol;
+
+const CACHE_FILE: &str = ".codeindex-cache.json";
+
+/// Maps a (content, extension, manifest-config) fingerprint to the symbols
+/// last extracted under it, so unchanged files can skip re-parsing on the
+/// next run.
+#[derive(Default, Serialize, Deserialize)]
+pub struct IndexCache {
+    entries: HashMap<u64, Vec<Symbol>>,
+}
+
+impl IndexCache {
+    /// Loads the cache from `root`, or an empty one if it doesn't exist yet
+    /// or fails to parse.
+    pub fn load(root: &Path) -> Self {
+        fs::read_to_string(root.join(CACHE_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persists the cache to `root`, overwriting any previous file.
+    pub fn save(&self, root: &Path) -> std::io::Result<()> {
+        let contents = serde_json::to_string(self).unwrap_or_default();
+        fs::write(root.join(CACHE_FILE), contents)
+    }
+
+    /// Returns the symbols previously extracted from this exact `content`,
+    /// under this exact `extension`/`manifest` configuration.
+    pub fn get(
+        &self,
+        content: &str,
+        extension: Option<&str>,
+        manifest: &Manifest,
+    ) -> Option<&Vec<Symbol>> {
+        self.entries.get(&cache_key(content, extension, manifest))
+    }
+
+    /// Records `symbols` as the result of indexing `content` under this
+    /// `extension`/`manifest` configuration.
+    pub fn insert(
+        &mut self,
+        content: &str,
+        extension: Option<&str>,
+        manifest: &Manifest,
+        symbols: Vec<Symbol>,
+    ) {
+        self.entries
+            .insert(cache_key(content, extension, manifest), symbols);
+    }
+}
+
+/// Hashes `content` together with `extension` and the manifest fingerprint
+/// for that extension, so two files with identical bytes but different
+/// extensions/languages, or a manifest edit that changes which grammar or
+/// kinds apply to `extension`, don't collide on a stale cache entry.
+fn cache_key(content: &str, extension: Option<&str>, manifest: &Manifest) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    extension.hash(&mut hasher);
+    if let Some(extension) = extension {
+        manifest.fingerprint_for(extension).hash(&mut hasher);
+    }
+    hasher.finish()
+}
@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+/// One straight-line run of statements with no internal branching: a node
+/// range plus the blocks control can fall through to next.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasicBlock {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub successors: Vec<usize>,
+}
+
+/// A function body's control-flow graph: one [`BasicBlock`] per entry, with
+/// `entry` the index of the block execution starts in.
+///
+/// Attached to function-like [`crate::outline::Symbol`]s by
+/// [`crate::outline::get_symbols`] so downstream consumers (the `dot` output,
+/// search) can reason about nesting and loops via [`dominators`]/[`reloop`]
+/// instead of raw line ranges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+    pub entry: usize,
+}
+
+/// Builds the CFG for the function/method body rooted at `node` (typically a
+/// `@definition.function`'s body block).
+///
+/// Blocks are split at branch/loop/return statements using a kind-name
+/// heuristic (`"if"`, `"for"`, `"while"`, `"loop"`, `"match"`, `"switch"`,
+/// `"return"`, `"break"`, `"continue"`) so the same walk works across the
+/// grammars this crate already indexes, rather than one CFG builder per
+/// language.
+pub fn build(node: Node, source_code: &str) -> Cfg {
+    let mut builder = Builder {
+        source_code,
+        blocks: Vec::new(),
+    };
+    let entry = builder.new_block();
+    builder.walk_block(node, entry);
+    Cfg {
+        blocks: builder.blocks,
+        entry,
+    }
+}
+
+struct Builder<'a> {
+    source_code: &'a str,
+    blocks: Vec<BasicBlock>,
+}
+
+impl<'a> Builder<'a> {
+    fn new_block(&mut self) -> usize {
+        self.blocks.push(BasicBlock {
+            start_line: 0,
+            end_line: 0,
+            successors: Vec::new(),
+        });
+        self.blocks.len() - 1
+    }
+
+    fn line_of(&self, node: Node) -> usize {
+        node.start_position().row + 1
+    }
+
+    /// Walks the statements under `node` (a block/body), threading `current`
+    /// through straight-line code and branching at control-flow statements.
+    /// Returns the block execution falls through to after `node`, or `None`
+    /// if every path out of `node` returns/breaks/continues.
+    fn walk_block(&mut self, node: Node, mut current: usize) -> Option<usize> {
+        let mut cursor = node.walk();
+        for statement in node.named_children(&mut cursor) {
+            if self.blocks[current].start_line == 0 {
+                self.blocks[current].start_line = self.line_of(statement);
+            }
+            self.blocks[current].end_line = statement.end_position().row + 1;
+
+            let kind = statement.kind();
+            if kind.contains("return") || kind.contains("break") || kind.contains("continue") {
+                return None;
+            }
+            if kind.contains("if") {
+                current = self.walk_if(statement, current)?;
+            } else if kind.contains("while") || kind.contains("for") || kind.contains("loop") {
+                current = self.walk_loop(statement, current);
+            } else if kind.contains("match") || kind.contains("switch") {
+                current = self.walk_match(statement, current)?;
+            }
+        }
+        Some(current)
+    }
+
+    /// `if`/`else if`/`else`: branch from `current` into a block per arm,
+    /// then join the arms (and the implicit no-`else` fallthrough) into a
+    /// fresh successor block.
+    fn walk_if(&mut self, node: Node, current: usize) -> Option<usize> {
+        let join = self.new_block();
+        let mut any_fallthrough = false;
+
+        if let Some(consequence) = node.child_by_field_name("consequence") {
+            let then_block = self.new_block();
+            self.blocks[current].successors.push(then_block);
+            if let Some(exit) = self.walk_block(consequence, then_block) {
+                self.blocks[exit].successors.push(join);
+                any_fallthrough = true;
+            }
+        }
+
+        match node.child_by_field_name("alternative") {
+            Some(alternative) => {
+                let else_block = self.new_block();
+                self.blocks[current].successors.push(else_block);
+                if let Some(exit) = self.walk_block(alternative, else_block) {
+                    self.blocks[exit].successors.push(join);
+                    any_fallthrough = true;
+                }
+            }
+            None => {
+                // No `else`: falling off the `if` reaches the join directly.
+                self.blocks[current].successors.push(join);
+                any_fallthrough = true;
+            }
+        }
+
+        any_fallthrough.then_some(join)
+    }
+
+    /// `while`/`for`/`loop`: a back-edge from the loop body to a header
+    /// block, plus a forward edge out to the block following the loop.
+    fn walk_loop(&mut self, node: Node, current: usize) -> usize {
+        let header = self.new_block();
+        self.blocks[current].successors.push(header);
+
+        let body_entry = self.new_block();
+        self.blocks[header].successors.push(body_entry);
+        if let Some(body) = node.child_by_field_name("body") {
+            if let Some(body_exit) = self.walk_block(body, body_entry) {
+                self.blocks[body_exit].successors.push(header); // back-edge
+            }
+        }
+
+        let after = self.new_block();
+        self.blocks[header].successors.push(after);
+        after
+    }
+
+    /// `match`/`switch`: branch from `current` into one block per arm, then
+    /// join the arms that fall through into a fresh successor block.
+    fn walk_match(&mut self, node: Node, current: usize) -> Option<usize> {
+        let join = self.new_block();
+        let mut any_fallthrough = false;
+        let mut cursor = node.walk();
+
+        for arm in node.named_children(&mut cursor) {
+            let arm_block = self.new_block();
+            self.blocks[current].successors.push(arm_block);
+            if let Some(exit) = self.walk_block(arm, arm_block) {
+                self.blocks[exit].successors.push(join);
+                any_fallthrough = true;
+            }
+        }
+
+        any_fallthrough.then_some(join)
+    }
+}
+
+/// Computes each block's dominator set via the standard iterative data-flow:
+/// `dom[entry] = {entry}`, `dom[b] = all blocks` for every other `b`, then
+/// `dom[b] = {b} ∪ (⋂ dom[p] for predecessors p)` repeated to a fixpoint.
+pub fn dominators(cfg: &Cfg) -> Vec<HashSet<usize>> {
+    let all: HashSet<usize> = (0..cfg.blocks.len()).collect();
+    let mut dom: Vec<HashSet<usize>> = cfg
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(id, _)| {
+            if id == cfg.entry {
+                [cfg.entry].into()
+            } else {
+                all.clone()
+            }
+        })
+        .collect();
+
+    let predecessors = predecessors_of(cfg);
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in 0..cfg.blocks.len() {
+            if block == cfg.entry {
+                continue;
+            }
+            let mut new_dom = predecessors[block]
+                .iter()
+                .map(|&p| dom[p].clone())
+                .reduce(|a, b| a.intersection(&b).copied().collect())
+                .unwrap_or_default();
+            new_dom.insert(block);
+            if new_dom != dom[block] {
+                dom[block] = new_dom;
+                changed = true;
+            }
+        }
+    }
+    dom
+}
+
+/// Whether `a` dominates `b`, i.e. every path from the entry to `b` passes
+/// through `a`.
+pub fn dominates(dom: &[HashSet<usize>], a: usize, b: usize) -> bool {
+    dom[b].contains(&a)
+}
+
+fn predecessors_of(cfg: &Cfg) -> Vec<Vec<usize>> {
+    let mut predecessors = vec![Vec::new(); cfg.blocks.len()];
+    for (id, block) in cfg.blocks.iter().enumerate() {
+        for &successor in &block.successors {
+            predecessors[successor].push(id);
+        }
+    }
+    predecessors
+}
+
+/// A back-edge: one whose target dominates its source, i.e. the edge jumps
+/// back up into a block the source is nested under (a loop header).
+fn back_edges(cfg: &Cfg, dom: &[HashSet<usize>]) -> Vec<(usize, usize)> {
+    cfg.blocks
+        .iter()
+        .enumerate()
+        .flat_map(|(id, block)| block.successors.iter().map(move |&succ| (id, succ)))
+        .filter(|&(from, to)| dominates(dom, to, from))
+        .collect()
+}
+
+/// A CFG is reducible iff every back-edge (by dominance) targets a loop
+/// header that dominates every block reachable from it without first
+/// leaving back out through the entry — equivalently, iff no back-edge's
+/// source can reach a block outside its target's dominance tree other than
+/// by passing through the target again. We use the standard simpler
+/// sufficient check: the graph is reducible iff removing all back-edges
+/// (as identified by dominance) leaves a DAG.
+pub fn is_reducible(cfg: &Cfg, dom: &[HashSet<usize>]) -> bool {
+    let back = back_edges(cfg, dom).into_iter().collect::<HashSet<_>>();
+    let mut visiting = vec![false; cfg.blocks.len()];
+    let mut done = vec![false; cfg.blocks.len()];
+
+    fn has_cycle(
+        block: usize,
+        cfg: &Cfg,
+        back: &HashSet<(usize, usize)>,
+        visiting: &mut [bool],
+        done: &mut [bool],
+    ) -> bool {
+        if done[block] {
+            return false;
+        }
+        visiting[block] = true;
+        for &successor in &cfg.blocks[block].successors {
+            if back.contains(&(block, successor)) {
+                continue;
+            }
+            if visiting[successor] || has_cycle(successor, cfg, back, visiting, done) {
+                return true;
+            }
+        }
+        visiting[block] = false;
+        done[block] = true;
+        false
+    }
+
+    !has_cycle(cfg.entry, cfg, &back, &mut visiting, &mut done)
+}
+
+/// A structured control-flow shape, as produced by the "relooper" pass: the
+/// edge set reassembled into nested blocks/loops/branches instead of a flat
+/// graph, so downstream code search can reason about nesting directly.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    /// A single block, followed by whatever comes next.
+    Simple(usize, Option<Box<Shape>>),
+    /// A loop whose body is `inner`, followed by whatever follows the loop.
+    Loop(Box<Shape>, Option<Box<Shape>>),
+    /// Several mutually-unreachable branches, rejoining at `next`.
+    Multiple(Vec<Shape>, Option<Box<Shape>>),
+}
+
+/// Converts `cfg`'s edge set into a [`Shape`] tree, using `dom` to find loop
+/// headers (blocks that are the target of a back-edge) and to decide which
+/// successors belong to a nested loop/branch body versus to `next`.
+pub fn reloop(cfg: &Cfg, dom: &[HashSet<usize>]) -> Option<Shape> {
+    let back = back_edges(cfg, dom).into_iter().collect::<HashSet<_>>();
+    let loop_headers: HashSet<usize> = back.iter().map(|&(_, to)| to).collect();
+    shape_from(
+        cfg.entry,
+        cfg,
+        dom,
+        &back,
+        &loop_headers,
+        &mut HashSet::new(),
+    )
+}
+
+fn shape_from(
+    block: usize,
+    cfg: &Cfg,
+    dom: &[HashSet<usize>],
+    back: &HashSet<(usize, usize)>,
+    loop_headers: &HashSet<usize>,
+    emitted: &mut HashSet<usize>,
+) -> Option<Shape> {
+    if !emitted.insert(block) {
+        return None;
+    }
+
+    // Successors that are genuinely forward edges and dominated by this
+    // block belong to this block's nested body; the rest is what follows.
+    let forward: Vec<usize> = cfg.blocks[block]
+        .successors
+        .iter()
+        .copied()
+        .filter(|&succ| !back.contains(&(block, succ)))
+        .collect();
+
+    if loop_headers.contains(&block) {
+        let (body, after): (Vec<usize>, Vec<usize>) = forward
+            .into_iter()
+            .partition(|&succ| dominates(dom, block, succ) && succ != block);
+        let inner = body
+            .first()
+            .and_then(|&b| shape_from(b, cfg, dom, back, loop_headers, emitted))
+            .unwrap_or(Shape::Simple(block, None));
+        let next = after
+            .first()
+            .and_then(|&b| shape_from(b, cfg, dom, back, loop_headers, emitted));
+        return Some(Shape::Loop(Box::new(inner), next.map(Box::new)));
+    }
+
+    match forward.len() {
+        0 => Some(Shape::Simple(block, None)),
+        1 => {
+            let next = shape_from(forward[0], cfg, dom, back, loop_headers, emitted);
+            Some(Shape::Simple(block, next.map(Box::new)))
+        }
+        _ => {
+            // Branches that dominate nothing past themselves (i.e. rejoin
+            // immediately) are mutually-unreachable arms; the first
+            // successor reachable from more than one arm is the join point.
+            let join = forward.iter().copied().find(|&succ| {
+                !forward
+                    .iter()
+                    .any(|&other| other != succ && dominates(dom, other, succ))
+            });
+            let branches: Vec<usize> = forward
+                .iter()
+                .copied()
+                .filter(|&succ| Some(succ) != join)
+                .collect();
+            let arms = branches
+                .into_iter()
+                .filter_map(|b| shape_from(b, cfg, dom, back, loop_headers, emitted))
+                .collect();
+            let next = join.and_then(|j| shape_from(j, cfg, dom, back, loop_headers, emitted));
+            Some(Shape::Multiple(arms, next.map(Box::new)).pair_with(block))
+        }
+    }
+}
+
+impl Shape {
+    /// Prefixes this shape with its originating branch block as a `Simple`
+    /// wrapper, since `Multiple` itself only records the arms and join.
+    fn pair_with(self, block: usize) -> Shape {
+        Shape::Simple(block, Some(Box::new(self)))
+    }
+}
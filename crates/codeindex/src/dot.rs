@@ -0,0 +1,144 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::outline::Symbol;
+
+/// The two Graphviz graph flavors: directed (`digraph`, edges drawn with
+/// `->`) and undirected (`graph`, edges drawn with `--`). Call graphs are
+/// inherently directed, but the `Kind` is kept generic rather than hard-coded
+/// so other graph-shaped output (e.g. an include graph) can reuse this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Digraph,
+    Graph,
+}
+
+impl Kind {
+    /// The edge operator Graphviz expects for this graph kind.
+    fn edgeop(self) -> &'static str {
+        match self {
+            Kind::Digraph => "->",
+            Kind::Graph => "--",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Digraph => write!(f, "digraph"),
+            Kind::Graph => write!(f, "graph"),
+        }
+    }
+}
+
+/// A call/reference edge from one symbol to another found by name within the
+/// same file, labeled with the 1-based line the calling definition starts on.
+struct Edge<'a> {
+    from: &'a str,
+    to: &'a str,
+    line: usize,
+}
+
+/// Renders `symbols` extracted from `path` as a Graphviz `digraph`: one node
+/// per definition (labeled with its signature) and one directed edge per
+/// call/reference to another definition found in the same file.
+///
+/// Reference detection is a best-effort textual scan of each symbol's
+/// signature for `other_name(`, so it only catches calls to symbols also
+/// defined (and indexed) in this file — good enough to sketch a module's
+/// call structure, not a full resolver.
+pub fn to_dot(path: &Path, symbols: &[Symbol]) -> String {
+    let kind = Kind::Digraph;
+    let mut out = format!("{kind} \"{}\" {{\n", escape(&path.display().to_string()));
+
+    for symbol in symbols {
+        out.push_str(&node_line(symbol));
+    }
+    for edge in find_edges(symbols) {
+        out.push_str(&edge_line(kind, &edge));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Writes a single `name [attr=..., ...];` node declaration, including the
+/// block count of the definition's `cfg` and any `dead_locals` it flagged,
+/// when it has one - so a module's call graph also surfaces which functions
+/// are worth a closer look before falling back to raw line ranges.
+fn node_line(symbol: &Symbol) -> String {
+    let blocks = symbol
+        .cfg
+        .as_ref()
+        .map(|cfg| format!(", blocks={}", cfg.blocks.len()))
+        .unwrap_or_default();
+    let dead_locals = (!symbol.dead_locals.is_empty())
+        .then(|| {
+            format!(
+                ", dead_locals=\"{}\"",
+                escape(&symbol.dead_locals.join(", "))
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "  \"{}\" [label=\"{}\", kind=\"{}\", line={}{blocks}{dead_locals}];\n",
+        escape(&symbol.name),
+        escape(&symbol.signature),
+        escape(&symbol.kind),
+        symbol.line,
+    )
+}
+
+/// Writes a single `from -> to [attr=..., ...];` edge declaration.
+fn edge_line(kind: Kind, edge: &Edge) -> String {
+    format!(
+        "  \"{}\" {} \"{}\" [label=\"{}\"];\n",
+        escape(edge.from),
+        kind.edgeop(),
+        escape(edge.to),
+        edge.line,
+    )
+}
+
+/// Finds, for every symbol, every other symbol whose name appears as a call
+/// (`name(`) in its signature text.
+fn find_edges(symbols: &[Symbol]) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    for caller in symbols {
+        for callee in symbols {
+            if caller.name == callee.name {
+                continue;
+            }
+            if calls(&caller.signature, &callee.name) {
+                edges.push(Edge {
+                    from: &caller.name,
+                    to: &callee.name,
+                    line: caller.line,
+                });
+            }
+        }
+    }
+    edges
+}
+
+/// Whether `text` contains `name` immediately followed by `(`, bounded by
+/// non-identifier characters (so `foo(` matches but `barfoo(` doesn't).
+fn calls(text: &str, name: &str) -> bool {
+    if name.is_empty() {
+        return false;
+    }
+    text.match_indices(name).any(|(start, _)| {
+        let before_ok = text[..start]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !c.is_alphanumeric() && c != '_');
+        let after = &text[start + name.len()..];
+        before_ok && after.trim_start().starts_with('(')
+    })
+}
+
+/// Escapes double quotes and backslashes for embedding in a DOT string literal.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,2 @@
+/// Outline query for C: functions, structs, enums, and unions.
+pub const OUTLINE_QUERY: &str = include_str!("../../queries/c/outline.scm");
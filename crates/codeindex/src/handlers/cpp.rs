@@ -0,0 +1,2 @@
+/// Outline query for C++: functions, methods, classes, structs, and enums.
+pub const OUTLINE_QUERY: &str = include_str!("../../queries/cpp/outline.scm");
@@ -0,0 +1,2 @@
+/// Outline query for C#: methods, classes, interfaces, structs, and enums.
+pub const OUTLINE_QUERY: &str = include_str!("../../queries/csharp/outline.scm");
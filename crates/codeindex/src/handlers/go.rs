@@ -0,0 +1,2 @@
+/// Outline query for Go: functions, methods, structs, and interfaces.
+pub const OUTLINE_QUERY: &str = include_str!("../../queries/go/outline.scm");
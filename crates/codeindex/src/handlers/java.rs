@@ -0,0 +1,2 @@
+/// Outline query for Java: methods, classes, interfaces, enums, and fields.
+pub const OUTLINE_QUERY: &str = include_str!("../../queries/java/outline.scm");
@@ -1,18 +1,102 @@
+mod c;
+mod cpp;
+mod csharp;
+mod go;
+mod java;
 mod javascript;
 mod python;
 mod rust;
 mod typescript;
 
 use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
 
-use tree_sitter::TreeCursor;
+use tree_sitter::{Language, Query};
 
-pub fn get_handlers(extension: &str) -> HashMap<&'static str, fn(&mut TreeCursor, &str) -> String> {
-    match extension {
-        "js" | "jsx" => javascript::get_handlers(),
-        "ts" | "tsx" => typescript::get_handlers(),
-        "rs" => rust::get_handlers(),
-        "py" => python::get_handlers(),
-        _ => HashMap::new(),
+/// Built-in grammar -> outline query source. Seeded once from the built-in
+/// handler modules and then mutable via [`register`], so a downstream crate
+/// can add a grammar this core crate has never heard of without editing this
+/// file — the same role `codeindex.toml`'s `kinds` list plays for trimming
+/// constructs, just one level up, for adding a whole language.
+fn registry() -> &'static RwLock<HashMap<&'static str, &'static str>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<&'static str, &'static str>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        RwLock::new(HashMap::from([
+            ("javascript", javascript::OUTLINE_QUERY),
+            ("typescript", typescript::OUTLINE_QUERY),
+            ("tsx", typescript::OUTLINE_QUERY),
+            ("rust", rust::OUTLINE_QUERY),
+            ("python", python::OUTLINE_QUERY),
+            ("go", go::OUTLINE_QUERY),
+            ("java", java::OUTLINE_QUERY),
+            ("c", c::OUTLINE_QUERY),
+            ("cpp", cpp::OUTLINE_QUERY),
+            ("c_sharp", csharp::OUTLINE_QUERY),
+        ]))
+    })
+}
+
+/// Registers (or overrides) the outline query source for `grammar`, so
+/// support for a new tree-sitter grammar can be added from outside this
+/// crate. `query_source` must use the same `@definition.*`/`@name`
+/// convention as the built-in `.scm` files.
+pub fn register(grammar: &'static str, query_source: &'static str) {
+    registry()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(grammar, query_source);
+}
+
+/// Returns the raw outline query source for `grammar`, if the language is
+/// supported. Queries use `@definition.*` captures (function, method, field,
+/// class, struct, enum, trait, interface, impl, ...) together with `@name` on the
+/// definition's name node, so new constructs are added by editing the
+/// relevant `.scm` file rather than adding Rust code.
+fn query_source(grammar: &str) -> Option<&'static str> {
+    registry()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(grammar)
+        .copied()
+}
+
+/// Looks up and compiles the outline query for `grammar` against `language`,
+/// keeping only the top-level constructs named in `allowed_kinds` (e.g.
+/// `function_item`); `None` keeps everything this build's query supports.
+///
+/// This is the registry hand-written per-construct cursor walkers were
+/// replaced with: adding a language or construct means adding a capture to
+/// a `.scm` file, not a new walking function. `allowed_kinds` in turn lets a
+/// project's `codeindex.toml` manifest trim that registry down without a
+/// rebuild.
+///
+/// Returns `None` if the grammar isn't recognized or the query fails to
+/// compile.
+pub fn get_handler(
+    grammar: &str,
+    language: Language,
+    allowed_kinds: Option<&[String]>,
+) -> Option<Query> {
+    let source = query_source(grammar)?;
+    match allowed_kinds {
+        Some(kinds) => Query::new(language, &filter_by_kind(source, kinds)).ok(),
+        None => Query::new(language, source).ok(),
     }
 }
+
+/// Keeps only the query's top-level S-expression blocks whose outermost node
+/// kind (e.g. `function_item` in `(function_item name: ...) @definition...`)
+/// appears in `kinds`.
+fn filter_by_kind(source: &str, kinds: &[String]) -> String {
+    source
+        .split("\n\n")
+        .filter(|block| {
+            block
+                .trim_start()
+                .strip_prefix('(')
+                .and_then(|rest| rest.split_whitespace().next())
+                .is_some_and(|kind| kinds.iter().any(|allowed| allowed == kind))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
@@ -1,16 +1,37 @@
 use tree_sitter::Language;
 use {
-    tree_sitter_javascript as ts_js, tree_sitter_python as ts_python, tree_sitter_rust as ts_rust,
-    tree_sitter_typescript as ts_ts,
+    tree_sitter_c as ts_c, tree_sitter_c_sharp as ts_csharp, tree_sitter_cpp as ts_cpp,
+    tree_sitter_go as ts_go, tree_sitter_java as ts_java, tree_sitter_javascript as ts_js,
+    tree_sitter_python as ts_python, tree_sitter_rust as ts_rust, tree_sitter_typescript as ts_ts,
 };
 
-pub fn get_language(extension: Option<&str>) -> Option<Language> {
-    match extension {
-        Some("rs") => Some(ts_rust::language()),
-        Some("js") | Some("jsx") => Some(ts_js::language()),
-        Some("ts") => Some(ts_ts::language_typescript()),
-        Some("tsx") => Some(ts_ts::language_tsx()),
-        Some("py") => Some(ts_python::language()),
+use crate::manifest::Manifest;
+
+/// Compiles the tree-sitter grammar for `grammar`, one of the names returned
+/// by [`Manifest::grammar_for_extension`] (`"rust"`, `"javascript"`,
+/// `"typescript"`, `"tsx"`, `"python"`, `"go"`, `"java"`, `"c"`, `"cpp"`,
+/// `"c_sharp"`).
+pub fn get_language(grammar: &str) -> Option<Language> {
+    match grammar {
+        "rust" => Some(ts_rust::language()),
+        "javascript" => Some(ts_js::language()),
+        "typescript" => Some(ts_ts::language_typescript()),
+        "tsx" => Some(ts_ts::language_tsx()),
+        "python" => Some(ts_python::language()),
+        "go" => Some(ts_go::language()),
+        "java" => Some(ts_java::language()),
+        "c" => Some(ts_c::language()),
+        "cpp" => Some(ts_cpp::language()),
+        "c_sharp" => Some(ts_csharp::language()),
         _ => None,
     }
 }
+
+/// Resolves `extension` to its grammar name and compiled `Language` via
+/// `manifest`, or `None` if the extension is unrecognized or its language is
+/// disabled.
+pub fn resolve<'m>(extension: &str, manifest: &'m Manifest) -> Option<(&'m str, Language)> {
+    let grammar = manifest.grammar_for_extension(extension)?;
+    let language = get_language(grammar)?;
+    Some((grammar, language))
+}
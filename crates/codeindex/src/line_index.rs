@@ -0,0 +1,189 @@
+//! Maps byte offsets in a source file to `{line, col}` coordinates and back,
+//! so a [`crate::outline::Symbol`]'s `byte_range`/`line` can be rendered as
+//! an editor jump target instead of a raw offset.
+//!
+//! Built once per file: [`LineIndex::new`] scans the text collecting the
+//! byte offset of the start of every line; [`LineIndex::line_col`] then
+//! binary-searches that list instead of rescanning, and
+//! [`LineIndex::offset`] is its inverse. Columns are tracked in both UTF-8
+//! bytes and UTF-16 code units, since editor protocols (e.g. LSP) speak
+//! UTF-16 while `byte_range` is UTF-8 byte offsets into the source text.
+
+/// A resolved source position: 0-based line, plus the column in both UTF-8
+/// bytes and UTF-16 code units. The two columns only differ on lines
+/// containing multi-byte characters (e.g. `'メ'`, which is 3 UTF-8 bytes but
+/// a single UTF-16 code unit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineCol {
+    pub line: u32,
+    pub col_utf8: u32,
+    pub col_utf16: u32,
+}
+
+/// Per line, the UTF-8 byte offset (relative to the line's start) of a
+/// multi-byte char together with its UTF-8 and UTF-16 lengths, so a UTF-8
+/// column can be converted to a UTF-16 column (and back) without rescanning
+/// the line's text.
+struct MultiByteChar {
+    utf8_offset: u32,
+    utf8_len: u32,
+    utf16_len: u32,
+}
+
+pub struct LineIndex {
+    /// Byte offset of the start of each line; index 0 is always 0. A
+    /// trailing newline produces one extra, empty final line, matching how
+    /// editors number the line after the last `\n`.
+    line_starts: Vec<u32>,
+    /// `multi_byte[line]` holds that line's multi-byte chars, in order.
+    multi_byte: Vec<Vec<MultiByteChar>>,
+    text_len: u32,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0u32];
+        let mut multi_byte: Vec<Vec<MultiByteChar>> = vec![Vec::new()];
+
+        let mut line_start = 0u32;
+        for (byte_offset, ch) in text.char_indices() {
+            let byte_offset = byte_offset as u32;
+            let utf8_len = ch.len_utf8() as u32;
+            let utf16_len = ch.len_utf16() as u32;
+            if utf8_len > 1 {
+                multi_byte.last_mut().unwrap().push(MultiByteChar {
+                    utf8_offset: byte_offset - line_start,
+                    utf8_len,
+                    utf16_len,
+                });
+            }
+            if ch == '\n' {
+                line_start = byte_offset + utf8_len;
+                line_starts.push(line_start);
+                multi_byte.push(Vec::new());
+            }
+        }
+
+        Self {
+            line_starts,
+            multi_byte,
+            text_len: text.len() as u32,
+        }
+    }
+
+    /// Translates a UTF-8 byte `offset` into the source text into its line
+    /// and column, or `None` if `offset` is past the end of the text.
+    pub fn line_col(&self, offset: u32) -> Option<LineCol> {
+        if offset > self.text_len {
+            return None;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let col_utf8 = offset - self.line_starts[line];
+        let col_utf16 = self.utf8_col_to_utf16(line, col_utf8);
+        Some(LineCol {
+            line: line as u32,
+            col_utf8,
+            col_utf16,
+        })
+    }
+
+    /// Translates a resolved [`LineCol`] back into a UTF-8 byte offset into
+    /// the source text, using `col_utf8` directly (it's already exact; a
+    /// position built from a UTF-16-only `col_utf16` should go through
+    /// [`Self::offset_from_utf16`] instead).
+    pub fn offset(&self, pos: LineCol) -> Option<u32> {
+        let line_start = *self.line_starts.get(pos.line as usize)?;
+        Some(line_start + pos.col_utf8)
+    }
+
+    /// Translates a `(line, col_utf16)` position - as reported by an
+    /// editor/LSP client - into a UTF-8 byte offset.
+    pub fn offset_from_utf16(&self, line: u32, col_utf16: u32) -> Option<u32> {
+        let line_start = *self.line_starts.get(line as usize)?;
+        let col_utf8 = self.utf16_col_to_utf8(line as usize, col_utf16);
+        Some(line_start + col_utf8)
+    }
+
+    fn utf8_col_to_utf16(&self, line: usize, col_utf8: u32) -> u32 {
+        let mut col_utf16 = col_utf8;
+        for ch in &self.multi_byte[line] {
+            if ch.utf8_offset + ch.utf8_len <= col_utf8 {
+                col_utf16 -= ch.utf8_len - ch.utf16_len;
+            } else {
+                break;
+            }
+        }
+        col_utf16
+    }
+
+    fn utf16_col_to_utf8(&self, line: usize, col_utf16: u32) -> u32 {
+        let mut seen_utf16 = 0u32;
+        let mut seen_utf8 = 0u32;
+        for ch in &self.multi_byte[line] {
+            let ascii_run = ch.utf8_offset - seen_utf8;
+            if seen_utf16 + ascii_run >= col_utf16 {
+                return seen_utf8 + (col_utf16 - seen_utf16);
+            }
+            seen_utf16 += ascii_run;
+            seen_utf8 += ascii_run;
+            seen_utf16 += ch.utf16_len;
+            seen_utf8 += ch.utf8_len;
+        }
+        seen_utf8 + (col_utf16 - seen_utf16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let text = "fn main() {\n    let x = 1;\n}\n";
+        let index = LineIndex::new(text);
+
+        let pos = index.line_col(16).unwrap();
+        assert_eq!(pos.line, 1);
+        assert_eq!(pos.col_utf8, 4);
+        assert_eq!(pos.col_utf16, 4);
+        assert_eq!(index.offset(pos), Some(16));
+    }
+
+    #[test]
+    fn test_trailing_newline_produces_empty_final_line() {
+        let text = "a\nb\n";
+        let index = LineIndex::new(text);
+        // Offset 4 is one past the last '\n': the empty line after it.
+        let pos = index.line_col(4).unwrap();
+        assert_eq!(pos.line, 2);
+        assert_eq!(pos.col_utf8, 0);
+    }
+
+    #[test]
+    fn test_offset_past_eof_is_none() {
+        let text = "abc";
+        let index = LineIndex::new(text);
+        assert!(index.line_col(4).is_none());
+        assert_eq!(index.line_col(3).unwrap().col_utf8, 3);
+    }
+
+    #[test]
+    fn test_multi_byte_char_utf16_conversion() {
+        // 'メ' is 3 bytes in UTF-8, 1 code unit in UTF-16.
+        let text = "let x = \"メ\";\n";
+        let index = LineIndex::new(text);
+
+        let before = index.line_col(9).unwrap();
+        assert_eq!(before.col_utf8, 9);
+        assert_eq!(before.col_utf16, 9);
+
+        // Right after the 3-byte char: UTF-8 column advanced by 3, UTF-16
+        // column by only 1.
+        let after = index.line_col(12).unwrap();
+        assert_eq!(after.col_utf8, 12);
+        assert_eq!(after.col_utf16, 10);
+
+        assert_eq!(index.offset(after), Some(12));
+        assert_eq!(index.offset_from_utf16(0, 10), Some(12));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use tree_sitter::Node;
+
+use crate::cfg::Cfg;
+
+/// A fixed-universe bitset over local-variable indices, used to represent a
+/// live-variable set at a single program point (CFG block).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitset {
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    fn new(len: usize) -> Self {
+        Bitset {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, bit: usize) {
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    pub fn contains(&self, bit: usize) -> bool {
+        self.words[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    /// Unions `other` into `self` in place, returning whether anything changed.
+    fn union_with(&mut self, other: &Bitset) -> bool {
+        let mut changed = false;
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            let merged = *word | other_word;
+            if merged != *word {
+                changed = true;
+                *word = merged;
+            }
+        }
+        changed
+    }
+
+    /// `self \ other`: the bits set in `self` but not in `other`.
+    fn difference(&self, other: &Bitset) -> Bitset {
+        Bitset {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(word, other_word)| word & !other_word)
+                .collect(),
+        }
+    }
+
+    pub fn iter<'a>(&'a self, locals: &'a [String]) -> impl Iterator<Item = &'a str> + 'a {
+        locals
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| self.contains(*index))
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+/// Def-use and liveness information for one function body's [`Cfg`]: which
+/// locals exist, where each is defined/used, and which locals are live
+/// coming into and going out of each basic block.
+pub struct DefUse {
+    pub locals: Vec<String>,
+    pub def_lines: HashMap<String, Vec<usize>>,
+    pub use_lines: HashMap<String, Vec<usize>>,
+    pub live_in: Vec<Bitset>,
+    pub live_out: Vec<Bitset>,
+}
+
+impl DefUse {
+    /// Locals that are defined but never read anywhere in the function body.
+    /// A cheap dead-binding check that does not consult `live_in`/`live_out`:
+    /// a local with any read at all is not flagged, even if no read can see
+    /// a particular definition of it.
+    pub fn dead_locals(&self) -> Vec<&str> {
+        self.locals
+            .iter()
+            .filter(|name| !self.use_lines.contains_key(name.as_str()))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Runs backward liveness analysis over `cfg`, a function body parsed from
+/// `node`/`source_code` (see [`crate::cfg::build`]).
+///
+/// Each local variable gets an index; `use[b]` and `def[b]` are computed by
+/// walking identifiers inside block `b`'s line range in source order (a read
+/// counts toward `use[b]` only if nothing earlier in the block already wrote
+/// that local), then `live_in`/`live_out` are iterated to a fixpoint:
+/// `live_out[b] = ⋃ live_in[s]` over successors `s`, and
+/// `live_in[b] = use[b] ∪ (live_out[b] \ def[b])`.
+pub fn analyze(node: Node, source_code: &str, cfg: &Cfg) -> DefUse {
+    let mut locals: Vec<String> = Vec::new();
+    let mut index_of: HashMap<String, usize> = HashMap::new();
+    let mut def_lines: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut use_lines: HashMap<String, Vec<usize>> = HashMap::new();
+
+    // Intern local names in first-seen order.
+    let names = collect_names(node, source_code);
+    for name in names {
+        index_of.entry(name.clone()).or_insert_with(|| {
+            locals.push(name);
+            locals.len() - 1
+        });
+    }
+
+    let mut block_use = vec![Bitset::new(locals.len()); cfg.blocks.len()];
+    let mut block_def = vec![Bitset::new(locals.len()); cfg.blocks.len()];
+
+    for (name, is_def, line) in walk_references(node, source_code) {
+        let Some(&local) = index_of.get(&name) else {
+            continue;
+        };
+        let lines = if is_def {
+            &mut def_lines
+        } else {
+            &mut use_lines
+        };
+        lines.entry(name).or_default().push(line);
+
+        let Some(block) = cfg
+            .blocks
+            .iter()
+            .position(|b| b.start_line <= line && line <= b.end_line)
+        else {
+            continue;
+        };
+        if is_def {
+            block_def[block].insert(local);
+        } else if !block_def[block].contains(local) {
+            block_use[block].insert(local);
+        }
+    }
+
+    let mut live_in = vec![Bitset::new(locals.len()); cfg.blocks.len()];
+    let mut live_out = vec![Bitset::new(locals.len()); cfg.blocks.len()];
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in 0..cfg.blocks.len() {
+            let mut out = Bitset::new(locals.len());
+            for &successor in &cfg.blocks[block].successors {
+                out.union_with(&live_in[successor]);
+            }
+            if out != live_out[block] {
+                live_out[block] = out;
+                changed = true;
+            }
+
+            let mut new_in = block_use[block].clone();
+            new_in.union_with(&live_out[block].difference(&block_def[block]));
+            if new_in != live_in[block] {
+                live_in[block] = new_in;
+                changed = true;
+            }
+        }
+    }
+
+    DefUse {
+        locals,
+        def_lines,
+        use_lines,
+        live_in,
+        live_out,
+    }
+}
+
+/// Collects every distinct local-variable name that's ever assigned to,
+/// declared, or read, in first-occurrence order.
+fn collect_names(node: Node, source_code: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    for (name, _, _) in walk_references(node, source_code) {
+        if !seen.contains(&name) {
+            seen.push(name);
+        }
+    }
+    seen
+}
+
+/// Walks every identifier under `node`, yielding `(name, is_def, line)` for
+/// each: `is_def` is true when the identifier is the target of a
+/// declaration/assignment (heuristically, the `name`/`left`/`pattern` field
+/// of a `*_declaration`/`assignment_expression`-like node), false for a
+/// plain read. This is a generic heuristic over node-kind substrings, same
+/// as the one `crate::cfg` uses to find branches/loops, so it applies
+/// across the grammars this crate indexes without per-language code.
+fn walk_references(node: Node, source_code: &str) -> Vec<(String, bool, usize)> {
+    let mut out = Vec::new();
+    visit(node, source_code, &mut out);
+    out
+}
+
+fn visit(node: Node, source_code: &str, out: &mut Vec<(String, bool, usize)>) {
+    if node.kind().contains("identifier") && !node.kind().contains("field") {
+        if let Ok(name) = node.utf8_text(source_code.as_bytes()) {
+            let is_def = node
+                .parent()
+                .map(|parent| {
+                    let kind = parent.kind();
+                    (kind.contains("declaration") || kind.contains("assignment"))
+                        && parent
+                            .child_by_field_name("name")
+                            .or_else(|| parent.child_by_field_name("left"))
+                            .or_else(|| parent.child_by_field_name("pattern"))
+                            == Some(node)
+                })
+                .unwrap_or(false);
+            out.push((name.to_string(), is_def, node.start_position().row + 1));
+        }
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        visit(child, source_code, out);
+    }
+}
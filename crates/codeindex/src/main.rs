@@ -1,177 +1,189 @@
 use std::error::Error;
-use std::fs;
+use std::path::Path;
+
+mod cache;
+mod cfg;
+mod dot;
+mod handlers;
+mod languages;
+mod line_index;
+mod liveness;
+mod manifest;
+mod outline;
+mod output;
+mod semantic;
+mod walker;
+mod watch;
+
+/// Parsed `--search`/`--top-k` invocation: embed `query` and return the
+/// `top_k` most similar indexed symbols.
+#[derive(Debug, PartialEq)]
+struct SearchArgs {
+    query: String,
+    top_k: usize,
+}
 
-use ignore::overrides::OverrideBuilder;
-use ignore::WalkBuilder;
-use tree_sitter::{Node, Parser};
-use {
-    tree_sitter_javascript as ts_js, tree_sitter_python as ts_python, tree_sitter_rust as ts_rust,
-    tree_sitter_typescript as ts_ts,
-};
+/// What `main` should do, as selected by the CLI flags `parse_args` saw.
+#[derive(Debug, PartialEq)]
+enum Mode {
+    /// The original one-off walk: parse every file once and print its outline
+    /// in the given format.
+    Walk(output::OutputFormat),
+    /// `--watch`: walk once (in the given format) to seed the index, then
+    /// hand off to `watch::watch` for incremental re-indexing as files change.
+    Watch(output::OutputFormat),
+    /// `--search`/`--top-k`: embed a query and print the closest symbols.
+    Search(SearchArgs),
+}
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut overrides = OverrideBuilder::new(".");
-    overrides.add("!.git")?; // ignore .git directory
-
-    for result in WalkBuilder::new("./")
-        .overrides(overrides.build()?)
-        .hidden(false)
-        .build()
-    {
-        match result {
-            Ok(entry) => process_entry(entry)?,
-            Err(err) => eprintln!("Error: {}", err),
-        }
+/// Parses the value of `--output`: `text`/`plain`, `json`, `tags`/`ctags`, or
+/// `dot`.
+fn parse_output_format(value: &str) -> Result<output::OutputFormat, Box<dyn Error>> {
+    match value {
+        "text" | "plain" => Ok(output::OutputFormat::Plain),
+        "json" => Ok(output::OutputFormat::Json),
+        "tags" | "ctags" => Ok(output::OutputFormat::Ctags),
+        "dot" => Ok(output::OutputFormat::Dot),
+        other => Err(format!("unrecognized --output format: {other}").into()),
     }
-    Ok(())
 }
 
-fn process_entry(entry: ignore::DirEntry) -> Result<(), Box<dyn Error>> {
-    // Skip the root directory
-    if entry.depth() == 0 {
-        return Ok(());
-    }
+fn parse_args() -> Result<Mode, Box<dyn Error>> {
+    parse_args_from(std::env::args().skip(1))
+}
 
-    // Strip the './' prefix and print the path
-    let path = entry.path().strip_prefix("./").unwrap_or(entry.path());
-    println!("{}", path.display());
-
-    // Check if path is a file
-    if path.is_file() {
-        match fs::read_to_string(path) {
-            Ok(code) => {
-                let mut parser = Parser::new();
-                let language = get_language(path.extension().and_then(std::ffi::OsStr::to_str));
-
-                if let Some(language) = language {
-                    parser.set_language(language).unwrap();
-                } else {
-                    return Ok(()); // Ignore other file types
-                }
-
-                let tree = parser.parse(&code, None).unwrap();
-                let root_node = tree.root_node();
-                let outline = get_outline(root_node, &code);
-                for signature in outline {
-                    println!("  {}", signature);
-                }
+/// Does the actual work of `parse_args`, over an arbitrary arg iterator so
+/// tests can exercise CLI ordering without touching `std::env::args`.
+///
+/// `--top-k` is stored separately from `--search` and only folded into the
+/// final `SearchArgs` once the whole command line has been seen, so it
+/// applies no matter which flag came first on the command line.
+fn parse_args_from(args: impl Iterator<Item = String>) -> Result<Mode, Box<dyn Error>> {
+    let mut args = args;
+    let mut watch = false;
+    let mut format = output::OutputFormat::Plain;
+    let mut query: Option<String> = None;
+    let mut top_k: Option<usize> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--watch" => watch = true,
+            "--output" => {
+                let value = args.next().ok_or("--output requires a format")?;
+                format = parse_output_format(&value)?;
+            }
+            "--search" => {
+                query = Some(args.next().ok_or("--search requires a query")?);
             }
-            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
-                // Skip binary files
+            "--top-k" => {
+                top_k = Some(
+                    args.next()
+                        .ok_or("--top-k requires a number")?
+                        .parse()
+                        .map_err(|_| "--top-k must be a positive integer")?,
+                );
             }
-            Err(e) => return Err(e.into()),
+            other => return Err(format!("unrecognized argument: {other}").into()),
         }
     }
-    Ok(())
-}
 
-fn get_language(extension: Option<&str>) -> Option<tree_sitter::Language> {
-    match extension {
-        Some("rs") => Some(ts_rust::language()),
-        Some("js") | Some("jsx") => Some(ts_js::language()),
-        Some("ts") => Some(ts_ts::language_typescript()),
-        Some("tsx") => Some(ts_ts::language_tsx()),
-        Some("py") => Some(ts_python::language()),
-        _ => None,
-    }
+    Ok(match query {
+        Some(query) => Mode::Search(SearchArgs {
+            query,
+            top_k: top_k.unwrap_or(10),
+        }),
+        None if watch => Mode::Watch(format),
+        None => Mode::Walk(format),
+    })
 }
 
-fn get_outline(node: Node, source_code: &str) -> Vec<String> {
-    let mut signatures = Vec::new();
-
-    if node.kind() == "source_file" {
-        let mut cursor = node.walk();
-        if cursor.goto_first_child() {
-            loop {
-                let child_kind = cursor.node().kind();
-
-                // Lookup the handler for this kind of node
-                if let Some(handler) = get_handler(child_kind) {
-                    let signature = handler(&mut cursor, source_code);
-                    signatures.push(signature);
-                }
+/// Embeds `args.query` and prints the `args.top_k` most similar indexed
+/// symbols via `semantic::search`.
+///
+/// `semantic::index_symbols`/`semantic::search` need a live qdrant
+/// `Collection` (storage, WAL, shard config, ...) to call into, and nothing
+/// in this crate's snapshot constructs one - same gap as the rest of this
+/// tree's missing scaffolding. A host process that already holds a
+/// `Collection` (e.g. an embedding qdrant node) is expected to call
+/// `semantic::index_symbols`/`semantic::search` directly instead of going
+/// through this standalone CLI.
+fn run_search(args: SearchArgs) -> Result<(), Box<dyn Error>> {
+    Err(format!(
+        "semantic search for {:?} (top {}) requires a `Collection` handle that this standalone \
+         CLI has no constructor for; call `semantic::search` from a host process that holds one",
+        args.query, args.top_k,
+    )
+    .into())
+}
 
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
+fn main() -> Result<(), Box<dyn Error>> {
+    match parse_args()? {
+        Mode::Walk(format) => walker::process_entries(format),
+        Mode::Watch(format) => {
+            walker::process_entries(format)?;
+            watch::watch(Path::new("."))
         }
+        Mode::Search(search) => run_search(search),
     }
+}
 
-    for child in node.children(&mut node.walk()) {
-        let mut child_signatures = get_outline(child, source_code);
-        signatures.append(&mut child_signatures);
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    signatures
-}
+    fn args(values: &[&str]) -> impl Iterator<Item = String> {
+        values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
 
-// This function returns a function that can handle nodes of the given kind
-fn get_handler(kind: &str) -> Option<fn(&mut tree_sitter::TreeCursor, &str) -> String> {
-    match kind {
-        "function_item" => Some(handle_function),
-        "struct_item" => Some(handle_struct),
-        // Add more cases here as needed
-        _ => None,
+    #[test]
+    fn search_then_top_k() {
+        let mode = parse_args_from(args(&["--search", "foo", "--top-k", "50"])).unwrap();
+        assert_eq!(
+            mode,
+            Mode::Search(SearchArgs {
+                query: "foo".to_string(),
+                top_k: 50,
+            })
+        );
     }
-}
 
-// Handler for function_item
-fn handle_function(cursor: &mut tree_sitter::TreeCursor, source_code: &str) -> String {
-    let mut function_signature = String::new();
-    if cursor.goto_first_child() {
-        loop {
-            let node = cursor.node();
-            match node.kind() {
-                "identifier" => {
-                    let start_byte = node.start_byte();
-                    let end_byte = node.end_byte();
-                    let child_name = &source_code[start_byte..end_byte];
-                    function_signature.push_str(&format!("fn {}", child_name));
-                }
-                "parameters" => {
-                    let start_byte = node.start_byte();
-                    let end_byte = node.end_byte();
-                    let parameters = &source_code[start_byte..end_byte];
-                    function_signature.push_str(&format!("{}", parameters));
-                }
-                "type_identifier" => {
-                    let start_byte = node.start_byte();
-                    let end_byte = node.end_byte();
-                    let return_type = &source_code[start_byte..end_byte];
-                    function_signature.push_str(&format!(" -> {}", return_type));
-                }
-                _ => {}
-            }
+    #[test]
+    fn top_k_then_search() {
+        let mode = parse_args_from(args(&["--top-k", "50", "--search", "foo"])).unwrap();
+        assert_eq!(
+            mode,
+            Mode::Search(SearchArgs {
+                query: "foo".to_string(),
+                top_k: 50,
+            })
+        );
+    }
 
-            if !cursor.goto_next_sibling() {
-                break;
-            }
-        }
-        cursor.goto_parent();
+    #[test]
+    fn search_without_top_k_defaults_to_ten() {
+        let mode = parse_args_from(args(&["--search", "foo"])).unwrap();
+        assert_eq!(
+            mode,
+            Mode::Search(SearchArgs {
+                query: "foo".to_string(),
+                top_k: 10,
+            })
+        );
     }
-    function_signature
-}
 
-// Handler for struct_item
-fn handle_struct(cursor: &mut tree_sitter::TreeCursor, source_code: &str) -> String {
-    let mut struct_signature = String::new();
-    if cursor.goto_first_child() {
-        loop {
-            let node = cursor.node();
-            if node.kind() == "identifier" {
-                let start_byte = node.start_byte();
-                let end_byte = node.end_byte();
-                let child_name = &source_code[start_byte..end_byte];
-                struct_signature.push_str(&format!("struct {} {{", child_name));
-            }
-            // You may want to handle fields here...
+    #[test]
+    fn watch_with_output_format() {
+        let mode = parse_args_from(args(&["--watch", "--output", "json"])).unwrap();
+        assert_eq!(mode, Mode::Watch(output::OutputFormat::Json));
+    }
 
-            if !cursor.goto_next_sibling() {
-                break;
-            }
-        }
-        cursor.goto_parent();
+    #[test]
+    fn no_args_walks_plain() {
+        let mode = parse_args_from(args(&[])).unwrap();
+        assert_eq!(mode, Mode::Walk(output::OutputFormat::Plain));
     }
-    struct_signature
 }
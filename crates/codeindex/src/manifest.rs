@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+const MANIFEST_FILE: &str = "codeindex.toml";
+
+/// Per-project indexing manifest, loaded from `codeindex.toml` at the repo
+/// root. Lets a project enable/disable languages and restrict which
+/// construct kinds get extracted without recompiling, e.g.:
+///
+/// ```toml
+/// name = "my-project"
+///
+/// [languages.rust]
+/// kinds = ["function_item", "struct_item"]
+///
+/// [languages.python]
+/// enabled = false
+/// ```
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    languages: HashMap<String, LanguageConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct LanguageConfig {
+    #[serde(default = "default_true")]
+    enabled: bool,
+    /// Extra file extensions mapped to this grammar, on top of the built-in
+    /// defaults.
+    #[serde(default)]
+    extensions: Vec<String>,
+    /// Node kinds to extract, e.g. `["function_item", "struct_item"]`.
+    /// Empty means "everything this build's query supports".
+    #[serde(default)]
+    kinds: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Built-in extension -> grammar name mapping, used when the manifest
+/// doesn't claim an extension itself.
+fn default_grammar(extension: &str) -> Option<&'static str> {
+    match extension {
+        "rs" => Some("rust"),
+        "js" | "jsx" => Some("javascript"),
+        "ts" => Some("typescript"),
+        "tsx" => Some("tsx"),
+        "py" => Some("python"),
+        "go" => Some("go"),
+        "java" => Some("java"),
+        "c" | "h" => Some("c"),
+        "cc" | "cpp" | "cxx" | "hpp" | "hxx" => Some("cpp"),
+        "cs" => Some("c_sharp"),
+        _ => None,
+    }
+}
+
+impl Manifest {
+    /// Loads `codeindex.toml` from `root`, if present and valid; the
+    /// all-languages-enabled default manifest otherwise.
+    pub fn load(root: &Path) -> Self {
+        fs::read_to_string(root.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|raw| toml::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    /// The grammar name for `extension` (a manifest mapping first, the
+    /// built-in default otherwise), or `None` if unrecognized or disabled.
+    pub fn grammar_for_extension(&self, extension: &str) -> Option<&str> {
+        let mapped = self
+            .languages
+            .iter()
+            .find(|(_, config)| config.extensions.iter().any(|ext| ext == extension))
+            .map(|(name, _)| name.as_str());
+
+        let grammar = mapped.or_else(|| default_grammar(extension))?;
+
+        self.languages
+            .get(grammar)
+            .map_or(true, |config| config.enabled)
+            .then_some(grammar)
+    }
+
+    /// Node kinds to keep for `grammar`, or `None` for "keep everything this
+    /// build's query supports".
+    pub fn allowed_kinds(&self, grammar: &str) -> Option<&[String]> {
+        self.languages
+            .get(grammar)
+            .map(|config| config.kinds.as_slice())
+            .filter(|kinds| !kinds.is_empty())
+    }
+
+    /// A hashable summary of everything about `extension` that changes what
+    /// `get_symbols` extracts from a file with that extension: its resolved
+    /// grammar (or lack of one) plus the kinds restriction on that grammar.
+    /// Used as part of the content cache key, so flipping a construct on/off
+    /// in the manifest invalidates cached entries instead of silently
+    /// returning the symbols extracted under the old configuration.
+    pub fn fingerprint_for(&self, extension: &str) -> (Option<&str>, Option<&[String]>) {
+        let grammar = self.grammar_for_extension(extension);
+        let kinds = grammar.and_then(|grammar| self.allowed_kinds(grammar));
+        (grammar, kinds)
+    }
+}
@@ -1,35 +1,311 @@
-use tree_sitter::Node;
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, QueryCursor};
 
+use crate::cfg::{self, Cfg};
 use crate::handlers;
+use crate::languages;
+use crate::liveness;
+use crate::manifest::Manifest;
 
-pub fn get_outline(node: Node, source_code: &str, extension: Option<&str>) -> Vec<String> {
-    let mut cursor = node.walk();
-    let mut signatures = Vec::new();
+/// A single function/method parameter, parsed from the `@params` capture's
+/// text on a best-effort basis: split on top-level commas, then `name: Type`
+/// or bare `name` depending on whether the grammar annotates parameter
+/// types.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Param {
+    pub name: String,
+    pub type_annotation: Option<String>,
+}
 
-    // Get the handlers for this language
-    let handlers = handlers::get_handlers(extension.unwrap_or(""));
+/// A single definition extracted from a source file: a function, struct,
+/// class, trait, or similar top-level construct.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    /// The capture name after `definition.`, e.g. `function`, `struct`.
+    pub kind: String,
+    pub name: String,
+    /// Enclosing definitions' names, outermost first, e.g. `["Foo"]` for a
+    /// method named `bar` defined in `impl Foo`.
+    pub qualified_path: Vec<String>,
+    pub signature: String,
+    /// Parsed from the `@params` capture, if the query defines one for this
+    /// construct. Empty for constructs with no parameter list (structs,
+    /// classes, ...) or grammars whose query doesn't capture it.
+    pub params: Vec<Param>,
+    /// Raw text of the `@return_type` capture, if the query defines one.
+    pub return_type: Option<String>,
+    /// Raw text of the `@generics` capture (type parameters), if the query
+    /// defines one.
+    pub generics: Option<String>,
+    /// Raw text of the `@visibility` capture (`pub`, `public`, an
+    /// accessibility modifier, ...), if the query defines one.
+    pub visibility: Option<String>,
+    /// Leading `///`/`/** */`/`#`-comment block or, lacking one, a Python
+    /// docstring, with no signature text mixed in.
+    pub doc_comment: Option<String>,
+    /// Byte offsets of the definition in the source file.
+    pub byte_range: std::ops::Range<usize>,
+    /// Nesting depth under the node the query was run against.
+    pub depth: usize,
+    /// 1-based source line the definition starts on.
+    pub line: usize,
+    /// Control-flow graph of this definition's body, for constructs that
+    /// have one (functions, methods). `None` for bodyless definitions
+    /// (structs, fields, ...) or grammars whose node has no `body` field.
+    pub cfg: Option<Cfg>,
+    /// Locals that are defined but never read anywhere in this body, per
+    /// [`liveness::DefUse::dead_locals`]. Empty when `cfg` is `None`.
+    pub dead_locals: Vec<String>,
+}
 
-    if cursor.goto_first_child() {
-        loop {
-            let child_node = cursor.node();
-            let child_kind = child_node.kind();
+/// Extracts the symbols defined under `node` using the query-driven handler
+/// for `extension`, as enabled and restricted by `manifest`.
+///
+/// Each match against a `@definition.*` capture produces one symbol: its name
+/// comes from the `@name` capture and its signature is sliced from the
+/// `@signature` capture (falling back to `@name`), with any leading doc
+/// comment or docstring prepended. Grammars whose `.scm` query also defines
+/// `@params`/`@return_type`/`@generics`/`@visibility` captures get those
+/// parsed out into the matching `Symbol` fields; grammars that don't leave
+/// them at their defaults (`params` empty, the rest `None`).
+pub fn get_symbols(
+    node: Node,
+    source_code: &str,
+    extension: Option<&str>,
+    manifest: &Manifest,
+) -> Vec<Symbol> {
+    let extension = extension.unwrap_or("");
+    let mut symbols = Vec::new();
 
-            // Lookup the handler for this kind of node
-            if let Some(handler) = handlers.get(child_kind) {
-                let signature = handler(&mut cursor, source_code);
-                signatures.push(signature);
-            }
+    let (grammar, language) = match languages::resolve(extension, manifest) {
+        Some(resolved) => resolved,
+        None => return symbols,
+    };
+    let query = match handlers::get_handler(grammar, language, manifest.allowed_kinds(grammar)) {
+        Some(query) => query,
+        None => return symbols,
+    };
+
+    let name_index = query.capture_index_for_name("name");
+    let signature_index = query.capture_index_for_name("signature");
+    let params_index = query.capture_index_for_name("params");
+    let return_type_index = query.capture_index_for_name("return_type");
+    let generics_index = query.capture_index_for_name("generics");
+    let visibility_index = query.capture_index_for_name("visibility");
+
+    let mut cursor = QueryCursor::new();
+    for query_match in cursor.matches(&query, node, source_code.as_bytes()) {
+        let definition = query_match.captures.iter().find(|capture| {
+            query.capture_names()[capture.index as usize].starts_with("definition.")
+        });
+        let definition = match definition {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let kind = query.capture_names()[definition.index as usize]
+            .trim_start_matches("definition.")
+            .to_string();
 
-            // If the node has no children or we're done processing the children,
-            // we move on to the next sibling.
-            if !cursor.goto_first_child() {
-                while !cursor.goto_next_sibling() {
-                    if !cursor.goto_parent() {
-                        return signatures;
-                    }
-                }
+        let captured_text = |wanted_index: Option<u32>| {
+            wanted_index
+                .and_then(|index| query_match.captures.iter().find(|c| c.index == index))
+                .map(|capture| source_code[capture.node.byte_range()].to_string())
+        };
+
+        let name = captured_text(name_index).unwrap_or_default();
+        let signature = captured_text(signature_index).unwrap_or_else(|| name.clone());
+        let params = captured_text(params_index)
+            .map(|text| parse_params(&text))
+            .unwrap_or_default();
+        let return_type = captured_text(return_type_index);
+        let generics = captured_text(generics_index);
+        let visibility = captured_text(visibility_index);
+
+        // Grammars like tree-sitter-python wrap a decorated definition (e.g.
+        // `@staticmethod` above a function) in its own node around the
+        // definition node the query matched on. Widen to that wrapper so the
+        // decorators are prefixed onto the emitted signature, and use it for
+        // depth too so the wrapper doesn't count as an extra nesting level.
+        let decorated = definition
+            .node
+            .parent()
+            .filter(|parent| parent.kind() == "decorated_definition");
+        let signature = decorated
+            .map(|parent| source_code[parent.byte_range()].to_string())
+            .unwrap_or(signature);
+        let depth_node = decorated.unwrap_or(definition.node);
+
+        // Leading doc comment, if any: contiguous `///`/`/** */`/`#` comment
+        // siblings immediately above the declaration (or, lacking those, a
+        // Python-style docstring as the first statement of the body). This
+        // is often the strongest semantic signal for search, so it's kept
+        // both on its own and folded into the signature for readability.
+        let doc_comment = leading_doc_comment(depth_node, source_code)
+            .or_else(|| python_docstring(definition.node, source_code));
+        let signature = match &doc_comment {
+            Some(doc) => format!("{doc}\n{signature}"),
+            None => signature,
+        };
+
+        let (cfg, dead_locals) = match definition.node.child_by_field_name("body") {
+            Some(body) => {
+                let cfg = cfg::build(body, source_code);
+                let dead_locals = liveness::analyze(body, source_code, &cfg)
+                    .dead_locals()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+                (Some(cfg), dead_locals)
             }
+            None => (None, Vec::new()),
+        };
+
+        symbols.push(Symbol {
+            kind,
+            name,
+            qualified_path: enclosing_names(definition.node, node, source_code),
+            signature,
+            params,
+            return_type,
+            generics,
+            visibility,
+            doc_comment,
+            byte_range: definition.node.byte_range(),
+            depth: depth_below(node, depth_node),
+            line: definition.node.start_position().row + 1,
+            cfg,
+            dead_locals,
+        });
+    }
+
+    symbols
+}
+
+/// Names of `node`'s ancestors (up to, but not including, `root`) that carry
+/// a `name` field, outermost first - e.g. `["Foo"]` for a method inside
+/// `impl Foo`. Generic across grammars because `name:` is the same field tree-
+/// sitter definition queries already capture `@name` from.
+fn enclosing_names(node: Node, root: Node, source_code: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        if parent == root {
+            break;
+        }
+        if let Some(name_node) = parent.child_by_field_name("name") {
+            names.push(source_code[name_node.byte_range()].to_string());
+        }
+        current = parent;
+    }
+    names.reverse();
+    names
+}
+
+/// Splits a parenthesized parameter-list capture's text into [`Param`]s.
+fn parse_params(source: &str) -> Vec<Param> {
+    let inner = source.trim().trim_start_matches('(').trim_end_matches(')');
+    split_top_level(inner, ',')
+        .into_iter()
+        .map(|raw| raw.trim().to_string())
+        .filter(|raw| !raw.is_empty())
+        .map(|raw| match raw.split_once(':') {
+            Some((name, type_annotation)) => Param {
+                name: name.trim().to_string(),
+                type_annotation: Some(type_annotation.trim().to_string()),
+            },
+            None => Param {
+                name: raw,
+                type_annotation: None,
+            },
+        })
+        .collect()
+}
+
+/// Splits `text` on top-level occurrences of `separator`, ignoring ones
+/// nested inside `()`, `[]`, `{}`, or `<>` - so `a: Vec<(i32, i32)>, b`
+/// splits into two parameters, not four.
+fn split_top_level(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut current = String::new();
+    for ch in text.chars() {
+        match ch {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth -= 1,
+            _ => {}
+        }
+        if ch == separator && depth <= 0 {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(ch);
+        }
+    }
+    if !current.trim().is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Collects contiguous comment siblings immediately preceding `node` (e.g.
+/// `///` or `/** */` above a Rust/TS/JS declaration, `#` lines above a Python
+/// one), in source order. Returns `None` if `node` isn't directly preceded by
+/// a comment.
+fn leading_doc_comment(node: Node, source_code: &str) -> Option<String> {
+    let mut comments = Vec::new();
+    let mut sibling = node.prev_sibling();
+    while let Some(current) = sibling {
+        if !matches!(current.kind(), "comment" | "line_comment" | "block_comment") {
+            break;
         }
+        comments.push(source_code[current.byte_range()].to_string());
+        sibling = current.prev_sibling();
     }
-    signatures
+    if comments.is_empty() {
+        return None;
+    }
+    comments.reverse();
+    Some(comments.join("\n"))
+}
+
+/// Python convention: the first statement of a function/class body, if it's
+/// a bare string literal, is the docstring.
+fn python_docstring(node: Node, source_code: &str) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let first_statement = body.named_child(0)?;
+    if first_statement.kind() != "expression_statement" {
+        return None;
+    }
+    let string_node = first_statement.named_child(0)?;
+    if string_node.kind() != "string" {
+        return None;
+    }
+    Some(source_code[string_node.byte_range()].to_string())
+}
+
+/// Counts how many ancestors separate `node` from `root` (exclusive of both).
+fn depth_below(root: Node, node: Node) -> usize {
+    let mut depth = 0;
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        if parent == root {
+            return depth;
+        }
+        depth += 1;
+        current = parent;
+    }
+    depth
+}
+
+/// Extracts an outline of the definitions under `node`, formatted as one line
+/// of signature text per symbol, indented by its nesting depth.
+pub fn get_outline(
+    node: Node,
+    source_code: &str,
+    extension: Option<&str>,
+    manifest: &Manifest,
+) -> Vec<String> {
+    get_symbols(node, source_code, extension, manifest)
+        .into_iter()
+        .map(|symbol| format!("{}{}", "  ".repeat(symbol.depth), symbol.signature))
+        .collect()
 }
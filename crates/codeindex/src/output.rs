@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use crate::dot;
+use crate::outline::Symbol;
+
+/// Output formats `process_entries` can emit per file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One indented signature line per symbol (the original behavior).
+    Plain,
+    /// One JSON object per file: `{"file": ..., "symbols": [...]}`.
+    Json,
+    /// Universal ctags `tags` file format.
+    Ctags,
+    /// A Graphviz `digraph` of the file's definitions and the calls between
+    /// them; pipe into `dot -Tsvg` to visualize a module's call structure.
+    Dot,
+}
+
+/// Renders `symbols` for `path` in `format`, one string per output line.
+pub fn format_symbols(path: &Path, symbols: &[Symbol], format: OutputFormat) -> Vec<String> {
+    match format {
+        OutputFormat::Plain => {
+            let mut lines = vec![path.display().to_string()];
+            lines.extend(
+                symbols
+                    .iter()
+                    .map(|symbol| format!("{}{}", "  ".repeat(symbol.depth), symbol.signature)),
+            );
+            lines
+        }
+        OutputFormat::Json => {
+            let entry = serde_json::json!({
+                "file": path.display().to_string(),
+                "symbols": symbols,
+            });
+            vec![entry.to_string()]
+        }
+        OutputFormat::Ctags => symbols
+            .iter()
+            .map(|symbol| format_ctag(path, symbol))
+            .collect(),
+        OutputFormat::Dot => vec![dot::to_dot(path, symbols)],
+    }
+}
+
+/// Formats a single symbol as a ctags `tags` line:
+/// `{name}\t{file}\t{line};"\t{kind}`
+fn format_ctag(path: &Path, symbol: &Symbol) -> String {
+    format!(
+        "{}\t{}\t{};\"\t{}",
+        symbol.name,
+        path.display(),
+        symbol.line,
+        symbol.kind,
+    )
+}
@@ -0,0 +1,156 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use collection::collection::Collection;
+use collection::operations::point_ops::{
+    PointInsertOperations, PointOperations, PointStruct, WriteOrdering,
+};
+use collection::operations::types::{CollectionError, SearchRequest, SearchRequestBatch};
+use collection::operations::CollectionUpdateOperations;
+use segment::data_types::vectors::NamedVectorStruct;
+use segment::types::{PointIdType, ScoreType, ScoredPoint, WithPayloadInterface};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::runtime::Handle;
+
+use crate::outline::Symbol;
+
+/// Turns extracted source text into a fixed-size vector for storage in the
+/// qdrant collection.
+///
+/// Real deployments should plug in an actual embedding model; this trait
+/// exists so `index_symbols`/`search` don't need to know which one.
+pub trait Embedder {
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+/// Derives a stable point id from `file` and `symbol`'s identity (its
+/// qualified path, name, kind, and byte range), so re-indexing a file
+/// upserts its existing points instead of colliding with another file's -
+/// a bare per-slice position isn't unique across files or across a single
+/// file's own re-indexing runs.
+fn point_id_for(file: &Path, symbol: &Symbol) -> PointIdType {
+    let mut hasher = DefaultHasher::new();
+    file.hash(&mut hasher);
+    symbol.kind.hash(&mut hasher);
+    symbol.qualified_path.hash(&mut hasher);
+    symbol.name.hash(&mut hasher);
+    symbol.byte_range.start.hash(&mut hasher);
+    symbol.byte_range.end.hash(&mut hasher);
+    PointIdType::NumId(hasher.finish())
+}
+
+/// Builds the payload stored alongside a symbol's embedding, so search hits
+/// can be mapped back to a file location without a second lookup.
+fn symbol_payload(file: &Path, symbol: &Symbol) -> serde_json::Value {
+    json!({
+        "file": file.to_string_lossy(),
+        "kind": symbol.kind,
+        "name": symbol.name,
+        "signature": symbol.signature,
+        "start_byte": symbol.byte_range.start,
+        "end_byte": symbol.byte_range.end,
+    })
+}
+
+/// Embeds the symbols extracted from `file` with `embedder` and upserts them
+/// into `collection`, one point per symbol keyed by [`point_id_for`].
+///
+/// This is intentionally a thin wrapper over `Collection::update_from_client`
+/// so indexing reuses the same write path (WAL, replication, consistency)
+/// as every other client of the collection.
+pub async fn index_symbols(
+    collection: &Collection,
+    file: &Path,
+    symbols: &[Symbol],
+    embedder: &dyn Embedder,
+) -> Result<(), CollectionError> {
+    let points: Vec<PointStruct> = symbols
+        .iter()
+        .map(|symbol| PointStruct {
+            id: point_id_for(file, symbol),
+            vector: embedder.embed(&symbol.signature).into(),
+            payload: Some(symbol_payload(file, symbol).try_into().unwrap_or_default()),
+        })
+        .collect();
+
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    let operation = CollectionUpdateOperations::PointOperation(PointOperations::UpsertPoints(
+        PointInsertOperations::PointsList(points),
+    ));
+
+    collection
+        .update_from_client(operation, true, WriteOrdering::default())
+        .await?;
+    Ok(())
+}
+
+/// One ranked hit returned by [`search`]: the location `index_symbols`
+/// recorded for a symbol, plus its similarity score against the query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub file: PathBuf,
+    pub kind: String,
+    pub name: String,
+    pub signature: String,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub score: ScoreType,
+}
+
+/// Embeds `query` with `embedder` and returns the `top_k` most similar
+/// symbols previously upserted by [`index_symbols`], ranked by score - so a
+/// caller can ask "where is auth handled" and get matching functions back
+/// instead of grepping names.
+///
+/// Mirrors the single-request-batch shape `ShardOperation::search` already
+/// uses in this crate, since `Collection` proxies a shard (or shard group)
+/// the same way.
+pub async fn search(
+    collection: &Collection,
+    query: &str,
+    top_k: usize,
+    embedder: &dyn Embedder,
+) -> Result<Vec<SearchHit>, CollectionError> {
+    let request = SearchRequest {
+        vector: NamedVectorStruct::Default(embedder.embed(query)),
+        filter: None,
+        params: None,
+        limit: top_k,
+        offset: 0,
+        page_token: None,
+        with_payload: Some(WithPayloadInterface::Bool(true)),
+        with_vector: None,
+        score_threshold: None,
+        order_by: None,
+    };
+    let batch = Arc::new(SearchRequestBatch {
+        searches: vec![request],
+    });
+
+    let mut batch_results = collection.search(batch, &Handle::current()).await?;
+    let hits = batch_results.pop().unwrap_or_default();
+
+    Ok(hits.into_iter().filter_map(scored_point_to_hit).collect())
+}
+
+/// Maps a raw `ScoredPoint` back to the file location `symbol_payload`
+/// recorded for it. Points missing a payload, or one missing an expected
+/// field, are skipped rather than failing the whole search.
+fn scored_point_to_hit(point: ScoredPoint) -> Option<SearchHit> {
+    let payload = serde_json::to_value(point.payload?).ok()?;
+    Some(SearchHit {
+        file: PathBuf::from(payload.get("file")?.as_str()?),
+        kind: payload.get("kind")?.as_str()?.to_string(),
+        name: payload.get("name")?.as_str()?.to_string(),
+        signature: payload.get("signature")?.as_str()?.to_string(),
+        start_byte: payload.get("start_byte")?.as_u64()? as usize,
+        end_byte: payload.get("end_byte")?.as_u64()? as usize,
+        score: point.score,
+    })
+}
@@ -1,67 +1,120 @@
 use std::error::Error;
 use std::fs;
 use std::io::ErrorKind::InvalidData;
+use std::path::Path;
+use std::sync::Mutex;
 
 use ignore::overrides::OverrideBuilder;
-use ignore::WalkBuilder;
+use ignore::{WalkBuilder, WalkState};
 use tree_sitter::Parser;
 
-use crate::languages::get_language;
-use crate::outline::get_outline;
+use crate::cache::IndexCache;
+use crate::languages;
+use crate::manifest::Manifest;
+use crate::outline::get_symbols;
+use crate::output::{format_symbols, OutputFormat};
 
-pub fn process_entries() -> Result<(), Box<dyn Error>> {
-    let mut overrides = OverrideBuilder::new(".");
+/// Walks and parses the tree in parallel, using one worker per available
+/// core, and writes the content-hash cache back out once the walk finishes.
+///
+/// Output is serialized through `stdout_lock` so each file's lines stay
+/// together even though files are processed out of order.
+pub fn process_entries(format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let root = Path::new(".");
+    let mut overrides = OverrideBuilder::new(root);
     overrides.add("!.git")?; // ignore .git directory
+    let overrides = overrides.build()?;
 
-    for result in WalkBuilder::new("./")
-        .overrides(overrides.build()?)
+    let stdout_lock = Mutex::new(());
+    let cache = Mutex::new(IndexCache::load(root));
+    let manifest = Manifest::load(root);
+
+    WalkBuilder::new(root)
+        .overrides(overrides)
         .hidden(false)
-        .build()
-    {
-        match result {
-            Ok(entry) => process_entry(entry)?,
-            Err(err) => eprintln!("Error: {}", err),
-        }
-    }
+        .build_parallel()
+        .run(|| {
+            Box::new(|result| {
+                match result {
+                    Ok(entry) => {
+                        if let Err(err) =
+                            process_entry(entry, format, &cache, &stdout_lock, &manifest)
+                        {
+                            eprintln!("Error: {}", err);
+                        }
+                    }
+                    Err(err) => eprintln!("Error: {}", err),
+                }
+                WalkState::Continue
+            })
+        });
+
+    cache.into_inner().unwrap().save(root)?;
     Ok(())
 }
 
-fn process_entry(entry: ignore::DirEntry) -> Result<(), Box<dyn Error>> {
+fn process_entry(
+    entry: ignore::DirEntry,
+    format: OutputFormat,
+    cache: &Mutex<IndexCache>,
+    stdout_lock: &Mutex<()>,
+    manifest: &Manifest,
+) -> Result<(), Box<dyn Error>> {
     // Skip the root directory
     if entry.depth() == 0 {
         return Ok(());
     }
 
-    // Strip the './' prefix and print the path
+    // Strip the './' prefix
     let path = entry.path().strip_prefix("./").unwrap_or(entry.path());
-    println!("{}", path.display());
 
     // Check if path is a file
-    if path.is_file() {
-        match fs::read_to_string(path) {
-            Ok(code) => {
-                let mut parser = Parser::new();
-                let language = get_language(path.extension().and_then(std::ffi::OsStr::to_str));
-
-                if let Some(language) = language {
-                    parser.set_language(language).unwrap();
-                } else {
-                    return Ok(()); // Ignore other file types
-                }
+    if !path.is_file() {
+        return Ok(());
+    }
 
-                let extension = path.extension().and_then(std::ffi::OsStr::to_str);
-                let tree = parser.parse(&code, None).unwrap();
-                let root_node = tree.root_node();
-                let outline = get_outline(root_node, &code, extension);
-                for signature in outline {
-                    println!("  {}", signature);
-                }
-            }
-            Err(e) if e.kind() == InvalidData => {
-                // Skip binary files
+    match fs::read_to_string(path) {
+        Ok(code) => {
+            let extension = path.extension().and_then(std::ffi::OsStr::to_str);
+
+            if let Some(symbols) = cache.lock().unwrap().get(&code, extension, manifest) {
+                print_symbols(path, symbols, format, stdout_lock);
+                return Ok(());
             }
-            Err(e) => return Err(e.into()),
+
+            let Some((_, language)) = extension.and_then(|ext| languages::resolve(ext, manifest))
+            else {
+                return Ok(()); // Ignore other file types, or ones disabled in the manifest
+            };
+
+            let mut parser = Parser::new();
+            parser.set_language(language).unwrap();
+
+            let tree = parser.parse(&code, None).unwrap();
+            let symbols = get_symbols(tree.root_node(), &code, extension, manifest);
+
+            print_symbols(path, &symbols, format, stdout_lock);
+            cache
+                .lock()
+                .unwrap()
+                .insert(&code, extension, manifest, symbols);
         }
+        Err(e) if e.kind() == InvalidData => {
+            // Skip binary files
+        }
+        Err(e) => return Err(e.into()),
     }
     Ok(())
 }
+
+fn print_symbols(
+    path: &Path,
+    symbols: &[crate::outline::Symbol],
+    format: OutputFormat,
+    stdout_lock: &Mutex<()>,
+) {
+    let _guard = stdout_lock.lock().unwrap();
+    for line in format_symbols(path, symbols, format) {
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use tree_sitter::{InputEdit, Parser, Point, Range, Tree};
+
+use crate::languages;
+use crate::manifest::Manifest;
+use crate::outline::get_symbols;
+
+/// How long to wait for the event stream to go quiet before reindexing the
+/// paths that changed, so a burst of filesystem events from one save (or a
+/// build tool touching several files at once) collapses into a single pass
+/// per path instead of one reindex per event.
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(200);
+
+/// Caches the parsed tree and source of every indexed file so a later change
+/// can be re-parsed incrementally instead of from scratch.
+#[derive(Default)]
+struct IndexCache {
+    trees: HashMap<PathBuf, (String, Tree)>,
+    manifest: Manifest,
+}
+
+impl IndexCache {
+    /// Parses `path`, reusing the previous tree for this path (if any): an
+    /// `InputEdit` derived from the diff against the previous source is
+    /// applied to the old tree before reparsing, so tree-sitter only
+    /// re-derives the subtrees that actually changed, and only the symbols
+    /// overlapping `old_tree.changed_ranges(&new_tree)` are re-emitted.
+    fn reindex(&mut self, path: &Path) -> Option<()> {
+        let extension = path.extension().and_then(std::ffi::OsStr::to_str)?;
+        let (_, language) = languages::resolve(extension, &self.manifest)?;
+        let new_source = fs::read_to_string(path).ok()?;
+
+        let mut parser = Parser::new();
+        parser.set_language(language).ok()?;
+
+        let new_tree;
+        let changed_ranges;
+        match self.trees.get_mut(path) {
+            Some((old_source, _)) if *old_source == new_source => return Some(()),
+            Some((old_source, old_tree)) => {
+                old_tree.edit(&compute_edit(old_source, &new_source));
+                new_tree = parser.parse(&new_source, Some(old_tree))?;
+                changed_ranges = Some(old_tree.changed_ranges(&new_tree).collect::<Vec<_>>());
+            }
+            None => {
+                new_tree = parser.parse(&new_source, None)?;
+                changed_ranges = None;
+            }
+        }
+
+        let symbols = get_symbols(
+            new_tree.root_node(),
+            &new_source,
+            Some(extension),
+            &self.manifest,
+        );
+        let changed: Vec<_> = match &changed_ranges {
+            Some(ranges) => symbols
+                .iter()
+                .filter(|symbol| {
+                    ranges
+                        .iter()
+                        .any(|range| overlaps(&symbol.byte_range, range))
+                })
+                .collect(),
+            None => symbols.iter().collect(),
+        };
+
+        if !changed.is_empty() {
+            println!("{}", path.display());
+            for symbol in changed {
+                println!("  {}{}", "  ".repeat(symbol.depth), symbol.signature);
+            }
+        }
+
+        self.trees
+            .insert(path.to_path_buf(), (new_source, new_tree));
+        Some(())
+    }
+}
+
+/// Whether a symbol's byte range overlaps a tree-sitter changed range.
+fn overlaps(byte_range: &std::ops::Range<usize>, range: &Range) -> bool {
+    byte_range.start < range.end_byte && range.start_byte < byte_range.end
+}
+
+/// Derives the `InputEdit` tree-sitter needs to reuse `old`'s subtrees when
+/// reparsing `new`, from the common prefix/suffix between the two texts -
+/// the smallest single edit that explains the diff, which is exactly the
+/// shape a single keystroke or save produces.
+fn compute_edit(old: &str, new: &str) -> InputEdit {
+    let old_bytes = old.as_bytes();
+    let new_bytes = new.as_bytes();
+
+    let common_prefix = old_bytes
+        .iter()
+        .zip(new_bytes)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let max_suffix = (old_bytes.len() - common_prefix).min(new_bytes.len() - common_prefix);
+    let common_suffix = old_bytes[common_prefix..]
+        .iter()
+        .rev()
+        .zip(new_bytes[common_prefix..].iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let start_byte = common_prefix;
+    let old_end_byte = old_bytes.len() - common_suffix;
+    let new_end_byte = new_bytes.len() - common_suffix;
+
+    InputEdit {
+        start_byte,
+        old_end_byte,
+        new_end_byte,
+        start_position: point_at(old, start_byte),
+        old_end_position: point_at(old, old_end_byte),
+        new_end_position: point_at(new, new_end_byte),
+    }
+}
+
+/// The row/column `Point` tree-sitter expects for byte offset `byte` into `text`.
+fn point_at(text: &str, byte: usize) -> Point {
+    let mut row = 0;
+    let mut line_start = 0;
+    for (index, value) in text.as_bytes()[..byte].iter().enumerate() {
+        if *value == b'\n' {
+            row += 1;
+            line_start = index + 1;
+        }
+    }
+    Point {
+        row,
+        column: byte - line_start,
+    }
+}
+
+/// Watches `root` for file changes and incrementally re-indexes whichever
+/// files changed, reusing their previous tree-sitter trees and debouncing
+/// bursts of events into one reindex per path per quiet period.
+///
+/// Runs until the watcher is dropped or an unrecoverable error occurs; the
+/// one-off walk performed by [`crate::walker::process_entries`] seeds the
+/// initial index before this loop takes over.
+pub fn watch(root: &Path) -> Result<(), Box<dyn Error>> {
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+
+    let mut cache = IndexCache {
+        manifest: Manifest::load(root),
+        ..IndexCache::default()
+    };
+
+    let mut pending: HashSet<PathBuf> = HashSet::new();
+    loop {
+        let timeout = if pending.is_empty() {
+            Duration::from_secs(3600)
+        } else {
+            DEBOUNCE_WINDOW
+        };
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                for path in event.paths {
+                    if path.is_file() {
+                        pending.insert(path);
+                    }
+                }
+            }
+            Ok(Err(err)) => eprintln!("Watch error: {}", err),
+            Err(RecvTimeoutError::Timeout) => {
+                for path in pending.drain() {
+                    cache.reindex(&path);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    for path in pending {
+        cache.reindex(&path);
+    }
+    Ok(())
+}
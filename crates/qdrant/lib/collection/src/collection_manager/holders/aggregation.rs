@@ -0,0 +1,304 @@
+//! Cross-segment aggregation over numeric payload fields, modeled as a
+//! two-phase collect-then-merge: each segment independently folds its own
+//! points into an [`AggregationIntermediate`] (a histogram bucket-count map,
+//! per-range partial counts, or a metric's raw `(sum, count, min, max)`),
+//! and [`SegmentHolder::aggregate`] then [`merge_intermediates`]s every
+//! segment's tree into one before [`finalize`] turns it into the finished
+//! [`AggregationResult`] (dividing sum by count for averages, filling empty
+//! histogram buckets, etc).
+//!
+//! `merge_intermediates` is associative and commutative by construction -
+//! bucket counts are summed, range counts are summed element-wise, and
+//! `(sum, count, min, max)` combine the same way regardless of which side
+//! is folded into which first - so segments can be processed in parallel
+//! and in any order, and the same intermediate shape could in principle be
+//! produced on a remote shard and merged here centrally.
+//!
+//! Filter evaluation only understands [`Condition::Field`] and nested
+//! [`Condition::Filter`] (`must`/`should`/`must_not`); `HasId`/`IsEmpty`/
+//! `IsNull`/`Nested` conditions are treated as non-matching rather than
+//! panicking, since this module's job is aggregation, not being a second
+//! query engine - a full point filter belongs to the segment's own index.
+
+use std::collections::HashMap;
+
+use segment::entry::entry_point::{OperationResult, SegmentEntry};
+use segment::types::{Condition, Filter, Payload, PayloadKeyType};
+use serde::{Deserialize, Serialize};
+
+use crate::collection_manager::holders::segment_holder::SegmentHolder;
+
+/// What to compute in [`SegmentHolder::aggregate`].
+#[derive(Debug, Clone)]
+pub enum AggregationSpec {
+    /// Fixed-width histogram: points are bucketed by `floor(value / interval)`.
+    Histogram { interval: f64 },
+    /// Counts per half-open range `[boundaries[i], boundaries[i + 1])`, plus
+    /// one bucket below the first boundary and one at/above the last.
+    Range { boundaries: Vec<f64> },
+    /// A single scalar metric.
+    Metric(MetricKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+/// A segment's partial aggregation state, before
+/// [`SegmentHolder::aggregate`] folds every segment's tree into one via
+/// [`merge_intermediates`]. Kept deliberately simple (plain maps/numbers)
+/// so it's independently serializable, per this module's doc comment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AggregationIntermediate {
+    Histogram(HashMap<i64, u64>),
+    Range(Vec<u64>),
+    /// Running `(count, sum, min, max)` - average is only computed once,
+    /// in [`finalize`], from `sum / count`.
+    Metric {
+        count: u64,
+        sum: f64,
+        min: f64,
+        max: f64,
+    },
+}
+
+impl AggregationIntermediate {
+    fn identity(spec: &AggregationSpec) -> Self {
+        match spec {
+            AggregationSpec::Histogram { .. } => AggregationIntermediate::Histogram(HashMap::new()),
+            AggregationSpec::Range { boundaries } => {
+                AggregationIntermediate::Range(vec![0; boundaries.len() + 1])
+            }
+            AggregationSpec::Metric(_) => AggregationIntermediate::Metric {
+                count: 0,
+                sum: 0.0,
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+            },
+        }
+    }
+
+    fn add_value(&mut self, spec: &AggregationSpec, value: f64) {
+        match (self, spec) {
+            (
+                AggregationIntermediate::Histogram(buckets),
+                AggregationSpec::Histogram { interval },
+            ) => {
+                let bucket = (value / interval).floor() as i64;
+                *buckets.entry(bucket).or_insert(0) += 1;
+            }
+            (AggregationIntermediate::Range(counts), AggregationSpec::Range { boundaries }) => {
+                let bucket = boundaries
+                    .iter()
+                    .position(|&boundary| value < boundary)
+                    .unwrap_or(boundaries.len());
+                counts[bucket] += 1;
+            }
+            (
+                AggregationIntermediate::Metric {
+                    count,
+                    sum,
+                    min,
+                    max,
+                },
+                AggregationSpec::Metric(_),
+            ) => {
+                *count += 1;
+                *sum += value;
+                *min = min.min(value);
+                *max = max.max(value);
+            }
+            _ => unreachable!("AggregationIntermediate variant must match its AggregationSpec"),
+        }
+    }
+}
+
+/// Finished aggregation produced by [`finalize`]ing the merged
+/// [`AggregationIntermediate`] tree from every segment.
+#[derive(Debug, Clone)]
+pub enum AggregationResult {
+    /// Bucket start value -> point count, sorted ascending by bucket.
+    Histogram(Vec<(f64, u64)>),
+    /// One count per `[boundaries[i], boundaries[i + 1])` range, in the same
+    /// order as `AggregationSpec::Range::boundaries` (plus the below-first
+    /// and at-or-above-last buckets at the start/end).
+    Range(Vec<u64>),
+    Metric {
+        count: u64,
+        sum: f64,
+        min: Option<f64>,
+        max: Option<f64>,
+        avg: Option<f64>,
+    },
+}
+
+/// Folds `b` into `a`, associatively and commutatively - see this module's
+/// doc comment. Panics if `a`/`b` are different variants, which would mean
+/// two segments were aggregated under different specs.
+pub fn merge_intermediates(
+    a: AggregationIntermediate,
+    b: AggregationIntermediate,
+) -> AggregationIntermediate {
+    match (a, b) {
+        (AggregationIntermediate::Histogram(mut a), AggregationIntermediate::Histogram(b)) => {
+            for (bucket, count) in b {
+                *a.entry(bucket).or_insert(0) += count;
+            }
+            AggregationIntermediate::Histogram(a)
+        }
+        (AggregationIntermediate::Range(mut a), AggregationIntermediate::Range(b)) => {
+            for (a_count, b_count) in a.iter_mut().zip(b) {
+                *a_count += b_count;
+            }
+            AggregationIntermediate::Range(a)
+        }
+        (
+            AggregationIntermediate::Metric {
+                count: a_count,
+                sum: a_sum,
+                min: a_min,
+                max: a_max,
+            },
+            AggregationIntermediate::Metric {
+                count: b_count,
+                sum: b_sum,
+                min: b_min,
+                max: b_max,
+            },
+        ) => AggregationIntermediate::Metric {
+            count: a_count + b_count,
+            sum: a_sum + b_sum,
+            min: a_min.min(b_min),
+            max: a_max.max(b_max),
+        },
+        (a, _) => panic!("cannot merge mismatched AggregationIntermediate variants: {a:?}"),
+    }
+}
+
+/// Converts a merged [`AggregationIntermediate`] into the finished
+/// [`AggregationResult`]: dividing sum by count for averages, sorting and
+/// labeling histogram buckets by their start value, and filling every empty
+/// bucket between the lowest and highest occupied one with a zero count.
+pub fn finalize(
+    intermediate: AggregationIntermediate,
+    spec: &AggregationSpec,
+) -> AggregationResult {
+    match intermediate {
+        AggregationIntermediate::Histogram(buckets) => {
+            let interval = match spec {
+                AggregationSpec::Histogram { interval } => *interval,
+                _ => unreachable!("Histogram intermediate must pair with a Histogram spec"),
+            };
+            let (Some(&lowest), Some(&highest)) = (buckets.keys().min(), buckets.keys().max())
+            else {
+                return AggregationResult::Histogram(Vec::new());
+            };
+            let buckets: Vec<(f64, u64)> = (lowest..=highest)
+                .map(|bucket| {
+                    let count = buckets.get(&bucket).copied().unwrap_or(0);
+                    (bucket as f64 * interval, count)
+                })
+                .collect();
+            AggregationResult::Histogram(buckets)
+        }
+        AggregationIntermediate::Range(counts) => AggregationResult::Range(counts),
+        AggregationIntermediate::Metric {
+            count,
+            sum,
+            min,
+            max,
+        } => AggregationResult::Metric {
+            count,
+            sum,
+            min: (count > 0).then_some(min),
+            max: (count > 0).then_some(max),
+            avg: (count > 0).then_some(sum / count as f64),
+        },
+    }
+}
+
+/// Folds every point of `segment` matching `filter` into an
+/// [`AggregationIntermediate`] for `spec`, reading `field` out of each
+/// point's payload. Points missing `field` or whose value isn't numeric are
+/// skipped.
+fn aggregate_segment(
+    segment: &dyn SegmentEntry,
+    field: &PayloadKeyType,
+    spec: &AggregationSpec,
+    filter: Option<&Filter>,
+) -> OperationResult<AggregationIntermediate> {
+    let mut intermediate = AggregationIntermediate::identity(spec);
+
+    for point_id in segment.iter_points() {
+        let payload = segment.payload(point_id)?;
+        if let Some(filter) = filter {
+            if !matches_filter(&payload, filter) {
+                continue;
+            }
+        }
+        if let Some(value) = payload.get(field.as_str()).and_then(|value| value.as_f64()) {
+            intermediate.add_value(spec, value);
+        }
+    }
+
+    Ok(intermediate)
+}
+
+/// Evaluates `filter`'s `must`/`should`/`must_not` against `payload` -
+/// see this module's doc comment for which condition kinds are understood.
+fn matches_filter(payload: &Payload, filter: &Filter) -> bool {
+    let must_ok = filter
+        .must
+        .as_ref()
+        .is_none_or(|conditions| conditions.iter().all(|c| matches_condition(payload, c)));
+    let should_ok = filter
+        .should
+        .as_ref()
+        .is_none_or(|conditions| conditions.iter().any(|c| matches_condition(payload, c)));
+    let must_not_ok = filter
+        .must_not
+        .as_ref()
+        .is_none_or(|conditions| conditions.iter().all(|c| !matches_condition(payload, c)));
+    must_ok && should_ok && must_not_ok
+}
+
+fn matches_condition(payload: &Payload, condition: &Condition) -> bool {
+    match condition {
+        Condition::Field(field_condition) => payload
+            .get(field_condition.key.as_str())
+            .is_some_and(|value| field_condition.check(value)),
+        Condition::Filter(nested) => matches_filter(payload, nested),
+        // Not a query engine - see this module's doc comment.
+        _ => false,
+    }
+}
+
+impl SegmentHolder {
+    /// Computes `spec` over `field` across every contained segment,
+    /// optionally restricted to points matching `filter`, without
+    /// materializing the full point set: each segment independently
+    /// produces an [`AggregationIntermediate`], which are folded together
+    /// with [`merge_intermediates`] (associative/commutative, so segments
+    /// could be folded in any order) before [`finalize`] produces the
+    /// merged [`AggregationResult`].
+    pub fn aggregate(
+        &self,
+        field: &PayloadKeyType,
+        spec: AggregationSpec,
+        filter: Option<&Filter>,
+    ) -> OperationResult<AggregationResult> {
+        let mut merged = AggregationIntermediate::identity(&spec);
+        for (_segment_id, locked_segment) in self.iter() {
+            let segment_arc = locked_segment.get();
+            let read_segment = segment_arc.read();
+            let segment_intermediate = aggregate_segment(&*read_segment, field, &spec, filter)?;
+            merged = merge_intermediates(merged, segment_intermediate);
+        }
+        Ok(finalize(merged, &spec))
+    }
+}
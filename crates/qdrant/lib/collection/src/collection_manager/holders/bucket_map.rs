@@ -0,0 +1,170 @@
+//! Disk-backed, bounded-memory set of `u64` keys, modeled on Solana's
+//! bucket map: a single memory-mapped file split into `2^capacity_pow2`
+//! fixed-capacity buckets. A key hashes to one bucket and lives in that
+//! bucket's fixed-size slot array; when a bucket overflows, the whole
+//! structure doubles `capacity_pow2` and rehashes every key into a fresh
+//! file. Resident memory is therefore the mapped file, not a growing
+//! in-process collection, and the set survives a restart since it's backed
+//! by a real file rather than `ProxySegment::deleted_points`'s in-memory
+//! `RoaringTreemap`.
+//!
+//! [`BucketMap`] deliberately exposes the same `contains`/`insert`/`extend`
+//! shape `ProxySegment` already uses against `deleted_points`
+//! (`delete_filtered`'s `.extend(...)`, `move_if_exists`'s `.contains(...)`)
+//! so it's a drop-in alternative for callers willing to trade RAM for a
+//! slower, disk-backed lookup - see
+//! [`super::proxy_segment::ProxySegment::export_deleted_points_to_bucket_map`]
+//! and
+//! [`super::proxy_segment::ProxySegment::import_deleted_points_from_bucket_map`],
+//! which spill to and restore from one without requiring every existing
+//! `deleted_points` read path in `ProxySegment` to change.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use memmap2::MmapMut;
+
+const SLOTS_PER_BUCKET: usize = 32;
+const SLOT_BYTES: usize = 8;
+const EMPTY_SLOT: u64 = u64::MAX;
+const HEADER_BYTES: usize = 8;
+
+/// A disk-backed set of `u64` keys (point offsets), laid out as a header
+/// (`capacity_pow2` as a little-endian `u64`) followed by `2^capacity_pow2`
+/// buckets of [`SLOTS_PER_BUCKET`] slots each. An empty slot holds
+/// [`EMPTY_SLOT`]; point offsets are never `u64::MAX` in practice since that
+/// would require more points than any real segment holds.
+pub struct BucketMap {
+    path: PathBuf,
+    mmap: MmapMut,
+    capacity_pow2: u32,
+}
+
+impl BucketMap {
+    /// Creates a new, empty bucket map file at `path` with `2^capacity_pow2`
+    /// buckets.
+    pub fn create(path: &Path, capacity_pow2: u32) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(path)?;
+        file.set_len(file_len(capacity_pow2))?;
+        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+        mmap[..HEADER_BYTES].copy_from_slice(&(capacity_pow2 as u64).to_le_bytes());
+        for slot in mmap[HEADER_BYTES..].chunks_mut(SLOT_BYTES) {
+            slot.copy_from_slice(&EMPTY_SLOT.to_le_bytes());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            mmap,
+            capacity_pow2,
+        })
+    }
+
+    /// Opens an existing bucket map file written by [`Self::create`].
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        let capacity_pow2 = u64::from_le_bytes(mmap[..HEADER_BYTES].try_into().unwrap()) as u32;
+        Ok(Self {
+            path: path.to_path_buf(),
+            mmap,
+            capacity_pow2,
+        })
+    }
+
+    pub fn contains(&self, key: u64) -> bool {
+        self.slot_of(key).is_some()
+    }
+
+    /// Inserts `key`, growing and rehashing the whole map if its bucket is
+    /// already full. Returns `true` if the key was newly inserted.
+    pub fn insert(&mut self, key: u64) -> io::Result<bool> {
+        if self.contains(key) {
+            return Ok(false);
+        }
+        loop {
+            if self.try_insert_into_bucket(key) {
+                return Ok(true);
+            }
+            // This key's bucket is full: double capacity and rehash
+            // everything before retrying.
+            self.grow()?;
+        }
+    }
+
+    pub fn extend(&mut self, keys: impl IntoIterator<Item = u64>) -> io::Result<()> {
+        for key in keys {
+            self.insert(key)?;
+        }
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.mmap[HEADER_BYTES..]
+            .chunks(SLOT_BYTES)
+            .filter_map(|slot| {
+                let value = u64::from_le_bytes(slot.try_into().unwrap());
+                (value != EMPTY_SLOT).then_some(value)
+            })
+    }
+
+    fn bucket_offset(&self, key: u64) -> usize {
+        let bucket_count = 1usize << self.capacity_pow2;
+        let bucket_index = (xxhash_rust::xxh3::xxh3_64(&key.to_le_bytes()) as usize)
+            & (bucket_count - 1);
+        HEADER_BYTES + bucket_index * SLOTS_PER_BUCKET * SLOT_BYTES
+    }
+
+    fn slot_of(&self, key: u64) -> Option<usize> {
+        let base = self.bucket_offset(key);
+        for slot in 0..SLOTS_PER_BUCKET {
+            let offset = base + slot * SLOT_BYTES;
+            let value = u64::from_le_bytes(self.mmap[offset..offset + SLOT_BYTES].try_into().unwrap());
+            if value == key {
+                return Some(offset);
+            }
+        }
+        None
+    }
+
+    /// Writes `key` into the first empty slot of its bucket. Returns `false`
+    /// if the bucket is already full, leaving the map unchanged.
+    fn try_insert_into_bucket(&mut self, key: u64) -> bool {
+        let base = self.bucket_offset(key);
+        for slot in 0..SLOTS_PER_BUCKET {
+            let offset = base + slot * SLOT_BYTES;
+            let value = u64::from_le_bytes(self.mmap[offset..offset + SLOT_BYTES].try_into().unwrap());
+            if value == EMPTY_SLOT {
+                self.mmap[offset..offset + SLOT_BYTES].copy_from_slice(&key.to_le_bytes());
+                return true;
+            }
+        }
+        false
+    }
+
+    fn grow(&mut self) -> io::Result<()> {
+        let new_capacity_pow2 = self.capacity_pow2 + 1;
+        let grown_path = self.path.with_extension("grow");
+        let mut grown = Self::create(&grown_path, new_capacity_pow2)?;
+        for key in self.iter() {
+            // The freshly doubled map can't overflow from rehashing a set
+            // that just barely overflowed the old, smaller one.
+            assert!(
+                grown.try_insert_into_bucket(key),
+                "bucket map overflowed immediately after doubling capacity"
+            );
+        }
+        std::fs::rename(&grown_path, &self.path)?;
+        grown.path = self.path.clone();
+        *self = grown;
+        Ok(())
+    }
+}
+
+fn file_len(capacity_pow2: u32) -> u64 {
+    (HEADER_BYTES + (1usize << capacity_pow2) * SLOTS_PER_BUCKET * SLOT_BYTES) as u64
+}
@@ -0,0 +1,261 @@
+//! Crash-consistent, rollbackable batches of mutations on
+//! [`super::proxy_segment::ProxySegment`].
+//!
+//! Borrowing persy's journal/transaction model: `begin_batch` snapshots the
+//! three shared sets (`deleted_points`, `deleted_indexes`, `created_indexes`)
+//! and opens an append-only [`BatchLog`] next to the proxy's journal.
+//! Every mutation recorded against the returned [`BatchGuard`] is appended
+//! to that log together with enough of the point's prior state to undo it,
+//! *before* the mutation is applied. `commit_batch` marks the log
+//! `Committed`; `rollback_batch` walks the recorded undo entries backwards,
+//! restoring `write_segment` point-by-point and resetting the three shared
+//! sets to their pre-batch snapshots, then marks the log `RolledBack`.
+//! [`recover_batch_log`] is the startup counterpart: it replays a log left
+//! behind by a crash and reports whether the batch it describes completed.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use roaring::RoaringTreemap;
+use segment::data_types::named_vectors::NamedVectors;
+use segment::entry::entry_point::{OperationError, OperationResult};
+use segment::types::{Payload, PayloadFieldSchema, PayloadKeyType, PointIdType, SeqNumberType};
+use serde::{Deserialize, Serialize};
+
+/// Lifecycle of a batch, persisted alongside its undo records so a crash
+/// mid-batch can be told apart from one that crashed mid-commit or
+/// mid-rollback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecoverStatus {
+    /// The batch has been opened and is recording mutations.
+    Started,
+    /// Every mutation in the batch has been applied and recorded; the
+    /// caller is about to decide commit or rollback.
+    Prepared,
+    /// The batch was committed: its mutations stand.
+    Committed,
+    /// The batch was rolled back: its mutations were undone.
+    RolledBack,
+}
+
+/// The state of one point immediately before a batch mutation touched it,
+/// or `None` if the point did not exist in `write_segment` yet - meaning the
+/// undo action is a delete rather than a restore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct PointUndo {
+    point_id: PointIdType,
+    op_num: SeqNumberType,
+    prior_vectors: Option<NamedVectors>,
+    prior_payload: Option<Payload>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum LogLine {
+    Status(RecoverStatus),
+    Undo(PointUndo),
+}
+
+/// Append-only on-disk log backing one [`BatchGuard`].
+struct BatchLog {
+    path: PathBuf,
+    file: File,
+}
+
+impl BatchLog {
+    fn open(segment_path: &Path) -> OperationResult<Self> {
+        let path = batch_log_path(segment_path);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|err| {
+                OperationError::service_error(format!(
+                    "failed to open proxy batch log at {path:?}: {err}"
+                ))
+            })?;
+        Ok(Self { path, file })
+    }
+
+    fn append(&mut self, line: &LogLine) -> OperationResult<()> {
+        let mut text = serde_json::to_string(line).map_err(|err| {
+            OperationError::service_error(format!("failed to encode proxy batch record: {err}"))
+        })?;
+        text.push('\n');
+        self.file.write_all(text.as_bytes()).map_err(|err| {
+            OperationError::service_error(format!("failed to append to proxy batch log: {err}"))
+        })
+    }
+
+    fn remove(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn batch_log_path(segment_path: &Path) -> PathBuf {
+    segment_path.join("proxy_batch.journal")
+}
+
+/// A guard for one in-flight batch, returned by `ProxySegment::begin_batch`.
+/// Every mutation made while a batch is active should go through
+/// [`BatchGuard::record`] before being applied, so
+/// `ProxySegment::rollback_batch` can undo it.
+pub struct BatchGuard {
+    log: BatchLog,
+    pre_deleted_points: RoaringTreemap,
+    pre_deleted_indexes: HashSet<PayloadKeyType>,
+    pre_created_indexes: HashMap<PayloadKeyType, PayloadFieldSchema>,
+    undo_log: Vec<PointUndo>,
+    status: RecoverStatus,
+}
+
+impl BatchGuard {
+    pub(super) fn begin(
+        segment_path: &Path,
+        deleted_points: RoaringTreemap,
+        deleted_indexes: HashSet<PayloadKeyType>,
+        created_indexes: HashMap<PayloadKeyType, PayloadFieldSchema>,
+    ) -> OperationResult<Self> {
+        let mut log = BatchLog::open(segment_path)?;
+        log.append(&LogLine::Status(RecoverStatus::Started))?;
+        Ok(Self {
+            log,
+            pre_deleted_points: deleted_points,
+            pre_deleted_indexes: deleted_indexes,
+            pre_created_indexes: created_indexes,
+            undo_log: Vec::new(),
+            status: RecoverStatus::Started,
+        })
+    }
+
+    /// Records the prior state of `point_id` before a mutation is applied to
+    /// it. `prior_vectors`/`prior_payload` should be `None` when the point
+    /// did not exist in `write_segment` before this mutation - rollback will
+    /// then delete the point instead of restoring it.
+    pub(super) fn record(
+        &mut self,
+        point_id: PointIdType,
+        op_num: SeqNumberType,
+        prior_vectors: Option<NamedVectors>,
+        prior_payload: Option<Payload>,
+    ) -> OperationResult<()> {
+        let undo = PointUndo {
+            point_id,
+            op_num,
+            prior_vectors,
+            prior_payload,
+        };
+        self.log.append(&LogLine::Undo(undo.clone()))?;
+        self.undo_log.push(undo);
+        Ok(())
+    }
+
+    /// Marks every mutation applied; called once the caller has finished
+    /// applying every op in the batch, before deciding commit or rollback.
+    pub(super) fn prepare(&mut self) -> OperationResult<()> {
+        self.status = RecoverStatus::Prepared;
+        self.log.append(&LogLine::Status(RecoverStatus::Prepared))
+    }
+
+    /// Takes ownership of the recorded undo entries in reverse (most
+    /// recent first) order, so the caller can apply each one to
+    /// `write_segment` to restore pre-batch state.
+    pub(super) fn take_undo_reversed(&mut self) -> Vec<PointUndo> {
+        let mut entries = std::mem::take(&mut self.undo_log);
+        entries.reverse();
+        entries
+    }
+
+    pub(super) fn pre_deleted_points(&self) -> RoaringTreemap {
+        self.pre_deleted_points.clone()
+    }
+
+    pub(super) fn pre_deleted_indexes(&self) -> HashSet<PayloadKeyType> {
+        self.pre_deleted_indexes.clone()
+    }
+
+    pub(super) fn pre_created_indexes(&self) -> HashMap<PayloadKeyType, PayloadFieldSchema> {
+        self.pre_created_indexes.clone()
+    }
+
+    pub(super) fn finish(mut self, status: RecoverStatus) -> OperationResult<()> {
+        self.status = status;
+        self.log.append(&LogLine::Status(status))?;
+        // The batch is fully resolved either way; nothing left to recover.
+        self.log.remove();
+        Ok(())
+    }
+}
+
+/// Undo action a caller should apply to `write_segment` to reverse one
+/// recorded mutation, built by [`PointUndo::into_action`] and consumed by
+/// `ProxySegment::rollback_batch`.
+pub(super) enum UndoAction {
+    Restore {
+        point_id: PointIdType,
+        op_num: SeqNumberType,
+        vectors: NamedVectors,
+        payload: Option<Payload>,
+    },
+    Delete {
+        point_id: PointIdType,
+        op_num: SeqNumberType,
+    },
+}
+
+impl PointUndo {
+    pub(super) fn into_action(self) -> UndoAction {
+        match self.prior_vectors {
+            Some(vectors) => UndoAction::Restore {
+                point_id: self.point_id,
+                op_num: self.op_num,
+                vectors,
+                payload: self.prior_payload,
+            },
+            None => UndoAction::Delete {
+                point_id: self.point_id,
+                op_num: self.op_num,
+            },
+        }
+    }
+}
+
+/// Replays a batch log left behind by a crash, returning the status the
+/// batch was last known to be in. `Committed`/`RolledBack` mean the batch
+/// already resolved and there's nothing to do. `Started`/`Prepared` mean
+/// the process died mid-batch; the caller should treat any trailing,
+/// uncommitted mutations as never having happened and fall back to the
+/// pre-batch state recorded in the first entries of the log.
+pub fn recover_batch_log(segment_path: &Path) -> OperationResult<RecoverStatus> {
+    let path = batch_log_path(segment_path);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(RecoverStatus::Committed)
+        }
+        Err(err) => {
+            return Err(OperationError::service_error(format!(
+                "failed to open proxy batch log at {path:?}: {err}"
+            )))
+        }
+    };
+
+    let mut status = RecoverStatus::Started;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|err| {
+            OperationError::service_error(format!("failed to read proxy batch log: {err}"))
+        })?;
+        if line.is_empty() {
+            continue;
+        }
+        let entry: LogLine = serde_json::from_str(&line).map_err(|err| {
+            OperationError::service_error(format!("failed to decode proxy batch log: {err}"))
+        })?;
+        if let LogLine::Status(new_status) = entry {
+            status = new_status;
+        }
+    }
+    Ok(status)
+}
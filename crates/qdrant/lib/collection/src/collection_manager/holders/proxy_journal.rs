@@ -0,0 +1,188 @@
+//! Write-ahead journal for in-memory [`super::proxy_segment::ProxySegment`]
+//! mutations.
+//!
+//! `ProxySegment::flush` refuses to persist anything while `deleted_points`,
+//! `created_indexes`, or `deleted_indexes` are non-empty, because folding
+//! them into the wrapped segment would mean rewriting it on every flush.
+//! That leaves those three in-memory sets unrecoverable if the process dies
+//! mid-optimization. Borrowing persy's journal/recovery model, every
+//! mutation to those sets is appended here as a [`JournalRecord`] and
+//! fsynced whenever the proxy is flushed with `sync = true`.
+//! [`recover_proxy_journal`] replays an existing journal back into the three
+//! sets before an optimizer resumes work on a proxy, and
+//! [`ProxyJournal::clear`] truncates the journal once the proxy is
+//! dismantled and its changes are folded into a real segment.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use roaring::RoaringTreemap;
+use segment::entry::entry_point::{OperationError, OperationResult};
+use segment::types::{PayloadFieldSchema, PayloadKeyType, PointIdType, SeqNumberType};
+use serde::{Deserialize, Serialize};
+
+use super::proxy_segment::point_id_to_offset;
+
+/// One journaled proxy mutation, in the order it was applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum JournalRecord {
+    DeleteRecord {
+        point_id: PointIdType,
+        op_num: SeqNumberType,
+    },
+    CreateIndex {
+        key: PayloadKeyType,
+        schema: PayloadFieldSchema,
+        op_num: SeqNumberType,
+    },
+    DropIndex {
+        key: PayloadKeyType,
+        op_num: SeqNumberType,
+    },
+}
+
+/// Appends [`JournalRecord`]s for one `ProxySegment` to a file keyed by the
+/// wrapped segment's path, so the three shared sets can be rebuilt after a
+/// crash instead of being silently lost by `flush`.
+pub struct ProxyJournal {
+    path: PathBuf,
+    file: File,
+}
+
+impl ProxyJournal {
+    /// Opens (creating if necessary) the journal file for `segment_path`.
+    pub fn open(segment_path: &Path) -> OperationResult<Self> {
+        let path = journal_path(segment_path);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|err| {
+                OperationError::service_error(format!(
+                    "failed to open proxy journal at {path:?}: {err}"
+                ))
+            })?;
+        Ok(Self { path, file })
+    }
+
+    fn append(&mut self, record: &JournalRecord) -> OperationResult<()> {
+        let mut line = serde_json::to_string(record).map_err(|err| {
+            OperationError::service_error(format!("failed to encode proxy journal record: {err}"))
+        })?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).map_err(|err| {
+            OperationError::service_error(format!("failed to append to proxy journal: {err}"))
+        })
+    }
+
+    pub fn record_delete(&mut self, point_id: PointIdType, op_num: SeqNumberType) -> OperationResult<()> {
+        self.append(&JournalRecord::DeleteRecord { point_id, op_num })
+    }
+
+    pub fn record_create_index(
+        &mut self,
+        key: PayloadKeyType,
+        schema: PayloadFieldSchema,
+        op_num: SeqNumberType,
+    ) -> OperationResult<()> {
+        self.append(&JournalRecord::CreateIndex {
+            key,
+            schema,
+            op_num,
+        })
+    }
+
+    pub fn record_drop_index(&mut self, key: PayloadKeyType, op_num: SeqNumberType) -> OperationResult<()> {
+        self.append(&JournalRecord::DropIndex { key, op_num })
+    }
+
+    /// Fsyncs the journal file, called from `ProxySegment::flush(true)` so a
+    /// crash right after a flush can't lose records the flush claimed to
+    /// have durably recorded.
+    pub fn sync(&self) -> OperationResult<()> {
+        self.file.sync_all().map_err(|err| {
+            OperationError::service_error(format!("failed to fsync proxy journal: {err}"))
+        })
+    }
+
+    /// Truncates the journal once the proxy is dismantled and its in-memory
+    /// changes have been folded into a real segment, so a later proxy on the
+    /// same path doesn't replay stale records.
+    pub fn clear(&mut self) -> OperationResult<()> {
+        let _ = fs::remove_file(&self.path);
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| {
+                OperationError::service_error(format!(
+                    "failed to reopen proxy journal at {:?}: {err}",
+                    self.path
+                ))
+            })?;
+        Ok(())
+    }
+}
+
+fn journal_path(segment_path: &Path) -> PathBuf {
+    segment_path.join("proxy.journal")
+}
+
+/// Replays the journal for `segment_path`, if one exists, reconstructing the
+/// `deleted_points`, `created_indexes`, and `deleted_indexes` sets a
+/// `ProxySegment` over that path had accumulated before a crash.
+pub fn recover_proxy_journal(
+    segment_path: &Path,
+) -> OperationResult<(
+    RoaringTreemap,
+    HashMap<PayloadKeyType, PayloadFieldSchema>,
+    HashSet<PayloadKeyType>,
+)> {
+    let mut deleted_points = RoaringTreemap::new();
+    let mut created_indexes = HashMap::new();
+    let mut deleted_indexes = HashSet::new();
+
+    let path = journal_path(segment_path);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((deleted_points, created_indexes, deleted_indexes))
+        }
+        Err(err) => {
+            return Err(OperationError::service_error(format!(
+                "failed to open proxy journal at {path:?}: {err}"
+            )))
+        }
+    };
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|err| {
+            OperationError::service_error(format!("failed to read proxy journal: {err}"))
+        })?;
+        if line.is_empty() {
+            continue;
+        }
+        let record: JournalRecord = serde_json::from_str(&line).map_err(|err| {
+            OperationError::service_error(format!(
+                "failed to decode proxy journal record: {err}"
+            ))
+        })?;
+        match record {
+            JournalRecord::DeleteRecord { point_id, .. } => {
+                deleted_points.insert(point_id_to_offset(point_id));
+            }
+            JournalRecord::CreateIndex { key, schema, .. } => {
+                deleted_indexes.remove(&key);
+                created_indexes.insert(key, schema);
+            }
+            JournalRecord::DropIndex { key, .. } => {
+                created_indexes.remove(&key);
+                deleted_indexes.insert(key);
+            }
+        }
+    }
+
+    Ok((deleted_points, created_indexes, deleted_indexes))
+}
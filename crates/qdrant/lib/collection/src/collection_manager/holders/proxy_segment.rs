@@ -1,26 +1,127 @@
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use crossbeam_epoch as epoch;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
+use roaring::RoaringTreemap;
 use segment::data_types::named_vectors::NamedVectors;
 use segment::data_types::vectors::VectorElementType;
 use segment::entry::entry_point::{OperationResult, SegmentEntry, SegmentFailedState};
 use segment::index::field_index::CardinalityEstimation;
 use segment::telemetry::SegmentTelemetry;
 use segment::types::{
-    Condition, Filter, Payload, PayloadFieldSchema, PayloadKeyType, PayloadKeyTypeRef, PointIdType,
+    Filter, Payload, PayloadFieldSchema, PayloadKeyType, PayloadKeyTypeRef, PointIdType,
     ScoredPoint, SearchParams, SegmentConfig, SegmentInfo, SegmentType, SeqNumberType, WithPayload,
     WithVector,
 };
 
+use crate::collection_manager::holders::bucket_map::BucketMap;
+use crate::collection_manager::holders::lock_manager::LockManager;
+use crate::collection_manager::holders::proxy_batch::{BatchGuard, RecoverStatus, UndoAction};
+use crate::collection_manager::holders::proxy_journal::ProxyJournal;
 use crate::collection_manager::holders::segment_holder::LockedSegment;
+use crate::collection_manager::holders::snapshot_checksum::{checksum_path, write_snapshot_checksum};
+use crate::collection_manager::holders::snapshot_compression::{
+    compress_snapshot_archive, SnapshotCompression,
+};
 
-type LockedRmSet = Arc<RwLock<HashSet<PointIdType>>>;
+type LockedRmSet = Arc<RwLock<RoaringTreemap>>;
 type LockedFieldsSet = Arc<RwLock<HashSet<PayloadKeyType>>>;
 type LockedFieldsMap = Arc<RwLock<HashMap<PayloadKeyType, PayloadFieldSchema>>>;
+type LockedDeleteQueue = Arc<RwLock<Vec<DeleteOperation>>>;
+
+/// A single delete recorded on the shared append-only delete queue, paired
+/// with the `op_num` that caused it so a later replay can tell a genuine
+/// delete apart from one already superseded by a newer upsert of the same
+/// point.
+#[derive(Debug, Clone, Copy)]
+pub struct DeleteOperation {
+    pub point_id: PointIdType,
+    pub op_num: SeqNumberType,
+}
+
+/// How far a single `ProxySegment` has replayed the shared delete queue.
+/// Kept separate from the queue itself so multiple proxies - which, like
+/// `deleted_points`, may share one queue while wrapping different
+/// underlying segments - can each replay it at their own pace.
+#[derive(Debug, Default)]
+pub struct DeleteCursor {
+    next_index: AtomicUsize,
+}
+
+/// Folds a [`PointIdType`] down to the `u64` key the shared tombstone
+/// [`RoaringTreemap`] is indexed by. Numeric ids map straight through;
+/// UUIDs are folded by XOR-ing their two halves, which is not invertible but
+/// is enough for the membership checks `deleted_points` is used for here.
+pub(crate) fn point_id_to_offset(point_id: PointIdType) -> u64 {
+    match point_id {
+        PointIdType::NumId(num_id) => num_id,
+        PointIdType::Uuid(uuid) => {
+            let (high, low) = uuid.as_u64_pair();
+            high ^ low
+        }
+    }
+}
+
+/// Attempts to reserve `len` bytes in a scratch buffer, surfacing a failed
+/// allocation as an [`OperationResult`] instead of aborting - the building
+/// block [`ProxySegment::try_upsert_point`]/[`ProxySegment::try_delete_vector`]
+/// use to back off under memory pressure rather than unwind.
+fn try_reserve_bytes(len: usize) -> OperationResult<()> {
+    let mut scratch: Vec<u8> = Vec::new();
+    scratch.try_reserve(len).map_err(|err| {
+        OperationError::service_error(format!(
+            "failed to reserve {len} bytes for a fallible point mutation: {err}"
+        ))
+    })
+}
+
+/// Total byte size of the vector data `vectors` carries, used to size the
+/// [`try_reserve_bytes`] check a fallible upsert performs before it touches
+/// `write_segment`.
+fn named_vectors_byte_len(vectors: &NamedVectors) -> usize {
+    vectors
+        .iter()
+        .map(|(_, vector)| vector.len() * std::mem::size_of::<VectorElementType>())
+        .sum()
+}
+
+/// `true` if `a` and `b` carry the same named vectors (same names, same
+/// values), used by [`ProxySegment::upsert_point_outcome`] to tell a
+/// no-op re-upsert apart from one that actually replaces a point's vectors.
+fn named_vectors_equal(a: &NamedVectors, b: &NamedVectors) -> bool {
+    let mut a: Vec<_> = a.iter().collect();
+    let mut b: Vec<_> = b.iter().collect();
+    a.sort_by_key(|(name, _)| *name);
+    b.sort_by_key(|(name, _)| *name);
+    a == b
+}
+
+/// Outcome of [`ProxySegment::upsert_point_outcome`], surfaced so an
+/// incremental re-indexer can tell whether a sync actually mutated the
+/// segment without re-querying `info()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    /// The point did not exist before this call.
+    Inserted,
+    /// The point existed with different vector data, now overwritten.
+    Replaced,
+    /// The point already existed with exactly this vector data.
+    Unchanged,
+}
+
+/// Outcome of [`ProxySegment::delete_vector_outcome`], for the same reason
+/// as [`UpsertOutcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteOutcome {
+    /// A vector was actually removed.
+    Removed,
+    /// There was nothing to remove; the point or vector was already absent.
+    AlreadyAbsent,
+}
 
 /// This object is a wrapper around read-only segment.
 /// It could be used to provide all read and write operations while wrapped segment is being optimized (i.e. not available for writing)
@@ -32,9 +133,30 @@ pub struct ProxySegment {
     /// May contain points which are not in wrapped_segment,
     /// because the set is shared among all proxy segments
     deleted_points: LockedRmSet,
+    /// Shared, ordered log of every delete across all proxies that share
+    /// `deleted_points`, so a rebuilt wrapped segment can replay exactly the
+    /// deletes it missed instead of trusting a point-in-time copy of the set.
+    delete_queue: LockedDeleteQueue,
+    delete_cursor: DeleteCursor,
     deleted_indexes: LockedFieldsSet,
     created_indexes: LockedFieldsMap,
     last_flushed_version: Arc<RwLock<Option<SeqNumberType>>>,
+    /// Write-ahead log of `deleted_points`/`created_indexes`/
+    /// `deleted_indexes` mutations, so they survive a crash even though
+    /// `flush` can't fold them into the wrapped segment on every call.
+    /// `None` when no journal was requested (e.g. in tests).
+    journal: Option<ProxyJournal>,
+    /// The currently open batch, if `begin_batch` was called and the batch
+    /// hasn't been committed or rolled back yet. `upsert_point`,
+    /// `delete_point`, `delete_filtered`, and `set_payload` record the prior
+    /// state of every point they touch here while it's `Some`.
+    active_batch: Option<BatchGuard>,
+    /// Guards point-granular mutations (`upsert_point`, `delete_point`,
+    /// `delete_vector`, `set_payload`, `delete_payload`) so two of them
+    /// touching unrelated points don't serialize on each other.
+    /// `delete_filtered` takes this manager's `global_exclusive` mode
+    /// instead, since it doesn't know its key set up front.
+    lock_manager: LockManager,
 }
 
 impl ProxySegment {
@@ -44,17 +166,228 @@ impl ProxySegment {
         deleted_points: LockedRmSet,
         created_indexes: LockedFieldsMap,
         deleted_indexes: LockedFieldsSet,
+        delete_queue: LockedDeleteQueue,
     ) -> Self {
         ProxySegment {
             write_segment,
             wrapped_segment: segment,
             deleted_points,
+            delete_queue,
+            delete_cursor: DeleteCursor::default(),
             created_indexes,
             deleted_indexes,
             last_flushed_version: Arc::new(RwLock::new(None)),
+            journal: None,
+            active_batch: None,
+            lock_manager: LockManager::new(),
+        }
+    }
+
+    /// Opens (or creates) a write-ahead journal for this proxy at the
+    /// wrapped segment's data path and attaches it, so subsequent deletes
+    /// and index changes are durably recorded. See
+    /// [`crate::collection_manager::holders::proxy_journal`].
+    pub fn with_journal(mut self) -> OperationResult<Self> {
+        let segment_path = self.wrapped_segment.get().read().data_path();
+        self.journal = Some(ProxyJournal::open(&segment_path)?);
+        Ok(self)
+    }
+
+    /// Clears this proxy's journal, called once an optimizer has finished
+    /// rebuilding and the proxy itself is about to be dropped, so a future
+    /// proxy over the same path doesn't replay stale records. The in-memory
+    /// sets are left as they are.
+    pub fn dismantle(&mut self) -> OperationResult<()> {
+        if let Some(journal) = &mut self.journal {
+            journal.clear()?;
+        }
+        Ok(())
+    }
+
+    /// Opens a batch: `upsert_point`, `delete_point`, `delete_filtered`, and
+    /// `set_payload` will record the prior state of every point they touch
+    /// until [`Self::commit_batch`] or [`Self::rollback_batch`] is called.
+    /// Only one batch may be open on a proxy at a time.
+    pub fn begin_batch(&mut self) -> OperationResult<()> {
+        if self.active_batch.is_some() {
+            return Err(OperationError::service_error(
+                "a batch is already open on this proxy segment".to_string(),
+            ));
+        }
+        let segment_path = self.wrapped_segment.get().read().data_path();
+        self.active_batch = Some(BatchGuard::begin(
+            &segment_path,
+            self.deleted_points.read().clone(),
+            self.deleted_indexes.read().clone(),
+            self.created_indexes.read().clone(),
+        )?);
+        Ok(())
+    }
+
+    /// Accepts every mutation recorded since `begin_batch`; the batch log is
+    /// cleared and the proxy returns to recording nothing.
+    pub fn commit_batch(&mut self) -> OperationResult<()> {
+        let Some(mut batch) = self.active_batch.take() else {
+            return Err(OperationError::service_error(
+                "commit_batch called with no batch open".to_string(),
+            ));
+        };
+        batch.prepare()?;
+        batch.finish(RecoverStatus::Committed)
+    }
+
+    /// Undoes every mutation recorded since `begin_batch`: restores
+    /// `write_segment` point-by-point from the recorded prior state (or
+    /// deletes points that didn't exist before the batch), then resets
+    /// `deleted_points`/`deleted_indexes`/`created_indexes` to their
+    /// pre-batch snapshots.
+    pub fn rollback_batch(&mut self) -> OperationResult<()> {
+        let Some(mut batch) = self.active_batch.take() else {
+            return Err(OperationError::service_error(
+                "rollback_batch called with no batch open".to_string(),
+            ));
+        };
+        batch.prepare()?;
+
+        for undo in batch.take_undo_reversed() {
+            match undo.into_action() {
+                UndoAction::Restore {
+                    point_id,
+                    op_num,
+                    vectors,
+                    payload,
+                } => {
+                    let write_segment = self.write_segment.get();
+                    let mut write_segment_guard = write_segment.write();
+                    write_segment_guard.upsert_point(op_num, point_id, vectors)?;
+                    if let Some(payload) = payload {
+                        write_segment_guard.set_full_payload(op_num, point_id, &payload)?;
+                    }
+                }
+                UndoAction::Delete { point_id, op_num } => {
+                    self.write_segment
+                        .get()
+                        .write()
+                        .delete_point(op_num, point_id)?;
+                }
+            }
+        }
+
+        *self.deleted_points.write() = batch.pre_deleted_points();
+        *self.deleted_indexes.write() = batch.pre_deleted_indexes();
+        *self.created_indexes.write() = batch.pre_created_indexes();
+
+        batch.finish(RecoverStatus::RolledBack)
+    }
+
+    /// Records `point_id`'s current `write_segment` state (or the lack of
+    /// one) into the open batch, if any, before it gets mutated. A no-op
+    /// when no batch is open.
+    fn capture_for_batch(
+        &mut self,
+        point_id: PointIdType,
+        op_num: SeqNumberType,
+    ) -> OperationResult<()> {
+        if self.active_batch.is_none() {
+            return Ok(());
+        }
+        let write_segment = self.write_segment.get();
+        let write_segment_guard = write_segment.read();
+        let (prior_vectors, prior_payload) = if write_segment_guard.has_point(point_id) {
+            (
+                Some(write_segment_guard.all_vectors(point_id)?),
+                Some(write_segment_guard.payload(point_id)?),
+            )
+        } else {
+            (None, None)
+        };
+        drop(write_segment_guard);
+        if let Some(batch) = &mut self.active_batch {
+            batch.record(point_id, op_num, prior_vectors, prior_payload)?;
+        }
+        Ok(())
+    }
+
+    /// Captures a [`ProxySnapshot`]: the current version plus a frozen,
+    /// `Arc`-shared copy of `deleted_points`. A long-running scan built on
+    /// top of the snapshot sees one consistent tombstone generation instead
+    /// of racing concurrent `delete_point`/`delete_filtered` calls, and the
+    /// frozen copy is released once the snapshot (and every clone of it) is
+    /// dropped.
+    pub fn read_snapshot(&self) -> ProxySnapshot {
+        ProxySnapshot {
+            wrapped_segment: self.wrapped_segment.clone(),
+            write_segment: self.write_segment.clone(),
+            tombstones: Arc::new(self.deleted_points.read().clone()),
+            version: self.version(),
         }
     }
 
+    /// Advances this proxy's [`DeleteCursor`] through the shared delete
+    /// queue, applying each entry to `deleted_points` only if it isn't
+    /// already superseded by a newer write - i.e. only if
+    /// `op_num > point_version(point_id)`. The optimizer calls this once the
+    /// wrapped segment has finished rebuilding, so a delete and a re-upsert
+    /// of the same point that raced the rebuild replay deterministically
+    /// instead of depending on which one happened to land in the bitmap
+    /// first.
+    pub fn advance_delete_cursor(&self) {
+        let queue = self.delete_queue.read();
+        let mut index = self.delete_cursor.next_index.load(Ordering::Acquire);
+        while let Some(op) = queue.get(index) {
+            let superseded = self
+                .point_version(op.point_id)
+                .is_some_and(|version| op.op_num <= version);
+            if !superseded {
+                self.deleted_points
+                    .write()
+                    .insert(point_id_to_offset(op.point_id));
+            }
+            index += 1;
+        }
+        self.delete_cursor.next_index.store(index, Ordering::Release);
+    }
+
+    /// Retires every tombstone in `deleted_points` whose delete has op_num
+    /// `<= up_to_version` - i.e. whose absence is already durable in a real
+    /// segment beyond that version, per `last_flushed_version` - so the
+    /// shared set doesn't grow for the entire lifetime of a proxy.
+    ///
+    /// Following pagecache's delayed-reuse discipline, the actual removal
+    /// isn't applied inline: it's scheduled via a [`crossbeam_epoch`]
+    /// [`epoch::Guard::defer`] so a `search`/`read_filtered` call that's
+    /// already holding a read guard on `deleted_points` (or a
+    /// [`ProxySnapshot`] taken before this call) keeps observing the ids it
+    /// started with, while later callers that pin a fresh epoch see the
+    /// compacted set once it's safe to reclaim.
+    ///
+    /// Invariant callers must uphold: `up_to_version` must not exceed the
+    /// lowest version any *other* active proxy sharing this `deleted_points`
+    /// still depends on - the optimizer should only call this with the
+    /// version it just successfully flushed past, never speculatively.
+    pub fn compact_tombstones(&self, up_to_version: SeqNumberType) {
+        let retireable: Vec<u64> = self
+            .delete_queue
+            .read()
+            .iter()
+            .filter(|op| op.op_num <= up_to_version)
+            .map(|op| point_id_to_offset(op.point_id))
+            .collect();
+        if retireable.is_empty() {
+            return;
+        }
+
+        let deleted_points = Arc::clone(&self.deleted_points);
+        let guard = epoch::pin();
+        guard.defer(move || {
+            let mut deleted_points = deleted_points.write();
+            for offset in retireable {
+                deleted_points.remove(offset);
+            }
+        });
+        guard.flush();
+    }
+
     /// Ensure that write segment have same indexes as wrapped segment
     pub fn replicate_field_indexes(&mut self, op_num: SeqNumberType) -> OperationResult<()> {
         let existing_indexes = self.write_segment.get().read().get_indexed_fields();
@@ -89,13 +422,223 @@ impl ProxySegment {
         Ok(())
     }
 
+    /// Takes a snapshot exactly as [`SegmentEntry::take_snapshot`] does, then
+    /// compresses the resulting archive with `compression` in place,
+    /// following lsm-tree's per-call codec choice rather than a fixed
+    /// collection-wide setting.
+    pub fn take_compressed_snapshot(
+        &self,
+        temp_path: &Path,
+        snapshot_dir_path: &Path,
+        compression: SnapshotCompression,
+    ) -> OperationResult<PathBuf> {
+        let archive_path = self.take_snapshot(temp_path, snapshot_dir_path)?;
+        let compressed_path = compress_snapshot_archive(&archive_path, compression)?;
+        if compressed_path != archive_path {
+            // The plain-tar checksum take_snapshot wrote no longer matches
+            // the file on disk once it's compressed under a new name.
+            let _ = std::fs::remove_file(checksum_path(&archive_path));
+            write_snapshot_checksum(&compressed_path)?;
+        }
+        Ok(compressed_path)
+    }
+
+    /// Spills the current `deleted_points` tombstone set out to a disk-backed
+    /// [`BucketMap`] at `path`, for proxies over very large wrapped segments
+    /// where keeping every tombstone in the in-memory `RoaringTreemap` is no
+    /// longer acceptable. The in-memory set is left untouched; callers that
+    /// want to actually shed the memory should drop their reference to it
+    /// after confirming the bucket map covers the same ids (e.g. by loading
+    /// it back with [`Self::import_deleted_points_from_bucket_map`] into a
+    /// fresh proxy instance).
+    pub fn export_deleted_points_to_bucket_map(
+        &self,
+        path: &Path,
+        capacity_pow2: u32,
+    ) -> OperationResult<BucketMap> {
+        let mut bucket_map = BucketMap::create(path, capacity_pow2).map_err(|err| {
+            OperationError::service_error(format!(
+                "failed to create deleted_points bucket map at {path:?}: {err}"
+            ))
+        })?;
+        bucket_map
+            .extend(self.deleted_points.read().iter())
+            .map_err(|err| {
+                OperationError::service_error(format!(
+                    "failed to populate deleted_points bucket map at {path:?}: {err}"
+                ))
+            })?;
+        Ok(bucket_map)
+    }
+
+    /// Restores `deleted_points` from a [`BucketMap`] previously written by
+    /// [`Self::export_deleted_points_to_bucket_map`], e.g. after reopening a
+    /// proxy whose tombstones were spilled to disk across a restart.
+    pub fn import_deleted_points_from_bucket_map(&self, bucket_map: &BucketMap) {
+        let mut deleted_points = self.deleted_points.write();
+        for offset in bucket_map.iter() {
+            deleted_points.insert(offset);
+        }
+    }
+
+    /// Fallible, allocation-aware counterpart to `upsert_point`: attempts a
+    /// `Vec::try_reserve`-style reservation sized to `vectors` before
+    /// touching `write_segment`, mirroring the `Vec::try_push` pattern, so a
+    /// bulk indexer streaming millions of points can back off instead of
+    /// unwinding when a segment can no longer grow.
+    pub fn try_upsert_point(
+        &mut self,
+        op_num: SeqNumberType,
+        point_id: PointIdType,
+        vectors: NamedVectors,
+    ) -> OperationResult<bool> {
+        try_reserve_bytes(named_vectors_byte_len(&vectors))?;
+        self.upsert_point(op_num, point_id, vectors)
+    }
+
+    /// Fallible counterpart to `delete_vector`. Deleting never grows an
+    /// allocation, but a bulk indexer streaming both upserts and deletes
+    /// wants one fallible surface regardless of mutation kind, so this
+    /// reserves a (trivially satisfiable) zero-byte budget and shares
+    /// `try_upsert_point`'s failure path rather than special-casing deletes.
+    pub fn try_delete_vector(
+        &mut self,
+        op_num: SeqNumberType,
+        point_id: PointIdType,
+        vector_name: &str,
+    ) -> OperationResult<bool> {
+        try_reserve_bytes(0)?;
+        self.delete_vector(op_num, point_id, vector_name)
+    }
+
+    /// Additive counterpart to `upsert_point` that surfaces an
+    /// [`UpsertOutcome`] instead of discarding everything but a `bool`, so an
+    /// incremental code-index sync can count genuine mutations and decide
+    /// whether to bump the segment version. Keeps `upsert_point`'s existing
+    /// version-gating behavior - this just reads the point's prior vectors
+    /// first and compares them against what's being written.
+    pub fn upsert_point_outcome(
+        &mut self,
+        op_num: SeqNumberType,
+        point_id: PointIdType,
+        vectors: NamedVectors,
+    ) -> OperationResult<UpsertOutcome> {
+        let prior_vectors = self
+            .has_point(point_id)
+            .then(|| self.all_vectors(point_id))
+            .transpose()?;
+        let outcome = match &prior_vectors {
+            None => UpsertOutcome::Inserted,
+            Some(prior) if named_vectors_equal(prior, &vectors) => UpsertOutcome::Unchanged,
+            Some(_) => UpsertOutcome::Replaced,
+        };
+        self.upsert_point(op_num, point_id, vectors)?;
+        Ok(outcome)
+    }
+
+    /// Additive counterpart to `delete_vector` that surfaces a
+    /// [`DeleteOutcome`] instead of a bare `bool`, for the same reason as
+    /// [`Self::upsert_point_outcome`].
+    pub fn delete_vector_outcome(
+        &mut self,
+        op_num: SeqNumberType,
+        point_id: PointIdType,
+        vector_name: &str,
+    ) -> OperationResult<DeleteOutcome> {
+        let removed = self.delete_vector(op_num, point_id, vector_name)?;
+        Ok(if removed {
+            DeleteOutcome::Removed
+        } else {
+            DeleteOutcome::AlreadyAbsent
+        })
+    }
+
+    /// Drops every point for which `predicate` returns `false`, in one pass
+    /// over the id mapping instead of collecting every id to delete and then
+    /// calling `delete_point` once per id - the latter would take this
+    /// proxy's per-point lock on every single deletion.
+    /// Takes the same `global_exclusive` lock `delete_filtered` does, since
+    /// like that method it doesn't know its key set up front, and tombstones
+    /// every matching wrapped-segment point in a single batched
+    /// `deleted_points` write rather than one `insert` per point. Returns
+    /// the number of points removed; `op_num` is threaded through to each
+    /// batched delete exactly as `delete_filtered` does, since every
+    /// mutation in this file is version-gated on it.
+    pub fn retain<F>(&mut self, op_num: SeqNumberType, mut predicate: F) -> OperationResult<usize>
+    where
+        F: FnMut(PointIdType, &NamedVectors) -> bool,
+    {
+        let _exclusive = self.lock_manager.global_exclusive();
+
+        let mut wrapped_to_tombstone = Vec::new();
+        {
+            let wrapped_segment = self.wrapped_segment.get();
+            let wrapped_segment_guard = wrapped_segment.read();
+            let deleted_points_guard = self.deleted_points.read();
+            for point_id in wrapped_segment_guard.read_range(None, None) {
+                if deleted_points_guard.contains(point_id_to_offset(point_id)) {
+                    continue;
+                }
+                let vectors = wrapped_segment_guard.all_vectors(point_id)?;
+                if !predicate(point_id, &vectors) {
+                    wrapped_to_tombstone.push(point_id);
+                }
+            }
+        }
+
+        let mut write_segment_to_delete = Vec::new();
+        {
+            let write_segment = self.write_segment.get();
+            let write_segment_guard = write_segment.read();
+            for point_id in write_segment_guard.read_range(None, None) {
+                let vectors = write_segment_guard.all_vectors(point_id)?;
+                if !predicate(point_id, &vectors) {
+                    write_segment_to_delete.push(point_id);
+                }
+            }
+        }
+
+        let mut removed = 0;
+        if !wrapped_to_tombstone.is_empty() {
+            removed += wrapped_to_tombstone.len();
+            if self.active_batch.is_some() {
+                for &point_id in &wrapped_to_tombstone {
+                    self.capture_for_batch(point_id, op_num)?;
+                }
+            }
+            let mut deleted_points_guard = self.deleted_points.write();
+            deleted_points_guard.extend(
+                wrapped_to_tombstone
+                    .iter()
+                    .copied()
+                    .map(point_id_to_offset),
+            );
+        }
+
+        if !write_segment_to_delete.is_empty() {
+            if self.active_batch.is_some() {
+                for &point_id in &write_segment_to_delete {
+                    self.capture_for_batch(point_id, op_num)?;
+                }
+            }
+            let mut write_segment = self.write_segment.get().write();
+            for point_id in write_segment_to_delete {
+                if write_segment.delete_point(op_num, point_id)? {
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
     fn move_if_exists(
         &self,
         op_num: SeqNumberType,
         point_id: PointIdType,
     ) -> OperationResult<bool> {
         let deleted_points_guard = self.deleted_points.upgradable_read();
-        if deleted_points_guard.contains(&point_id) {
+        if deleted_points_guard.contains(point_id_to_offset(point_id)) {
             // Point is already removed from wrapped segment
             return Ok(false);
         }
@@ -113,7 +656,7 @@ impl ProxySegment {
 
         {
             let mut deleted_points_write = RwLockUpgradableReadGuard::upgrade(deleted_points_guard);
-            deleted_points_write.insert(point_id);
+            deleted_points_write.insert(point_id_to_offset(point_id));
         }
 
         let segment_arc = self.write_segment.get();
@@ -124,30 +667,79 @@ impl ProxySegment {
 
         Ok(true)
     }
+}
+
+/// A consistent, point-in-time view over a [`ProxySegment`], captured by
+/// [`ProxySegment::read_snapshot`]. `iter_points`/`read_range`/
+/// `read_filtered` on a snapshot all check the same frozen tombstone
+/// generation, so paging through one snapshot never observes a point
+/// appearing or disappearing mid-scan the way repeated direct calls on
+/// `ProxySegment` can. Holds `Arc`-cloned handles rather than borrowing the
+/// `ProxySegment` it was taken from, so it stays usable - and concurrent
+/// writers stay unblocked - for as long as the snapshot itself is kept
+/// around; the frozen tombstone copy is released once the last clone of the
+/// snapshot is dropped.
+pub struct ProxySnapshot {
+    wrapped_segment: LockedSegment,
+    write_segment: LockedSegment,
+    tombstones: Arc<RoaringTreemap>,
+    version: SeqNumberType,
+}
+
+impl ProxySnapshot {
+    /// Sequence number the snapshot was taken at.
+    pub fn version(&self) -> SeqNumberType {
+        self.version
+    }
+
+    /// All point ids visible in this snapshot, wrapped segment and write
+    /// segment combined, with everything in `tombstones` filtered out.
+    pub fn iter_points(&self) -> impl Iterator<Item = PointIdType> + '_ {
+        let tombstones = Arc::clone(&self.tombstones);
+        self.wrapped_segment
+            .get()
+            .read()
+            .read_range(None, None)
+            .into_iter()
+            .filter(move |point_id| !tombstones.contains(point_id_to_offset(*point_id)))
+            .chain(self.write_segment.get().read().read_range(None, None))
+    }
+
+    /// Same as [`SegmentEntry::read_range`], but checked against this
+    /// snapshot's frozen tombstones instead of `ProxySegment::deleted_points`.
+    pub fn read_range(&self, from: Option<PointIdType>, to: Option<PointIdType>) -> Vec<PointIdType> {
+        let mut read_points = self.wrapped_segment.get().read().read_range(from, to);
+        read_points.retain(|point_id| !self.tombstones.contains(point_id_to_offset(*point_id)));
+        let mut write_segment_points = self.write_segment.get().read().read_range(from, to);
+        read_points.append(&mut write_segment_points);
+        read_points.sort_unstable();
+        read_points
+    }
 
-    fn add_deleted_points_condition_to_filter(
+    /// Same as [`SegmentEntry::read_filtered`], but checked against this
+    /// snapshot's frozen tombstones instead of `ProxySegment::deleted_points`.
+    pub fn read_filtered(
         &self,
+        offset: Option<PointIdType>,
+        limit: Option<usize>,
         filter: Option<&Filter>,
-        deleted_points: &HashSet<PointIdType>,
-    ) -> Filter {
-        let wrapper_condition = Condition::HasId(deleted_points.clone().into());
-        match filter {
-            None => Filter::new_must_not(wrapper_condition),
-            Some(f) => {
-                let mut new_filter = f.clone();
-                let must_not = new_filter.must_not;
-
-                let new_must_not = match must_not {
-                    None => Some(vec![wrapper_condition]),
-                    Some(mut conditions) => {
-                        conditions.push(wrapper_condition);
-                        Some(conditions)
-                    }
-                };
-                new_filter.must_not = new_must_not;
-                new_filter
-            }
+    ) -> Vec<PointIdType> {
+        let wrapped_limit =
+            limit.map(|limit| limit.saturating_add(self.tombstones.len() as usize));
+        let mut read_points =
+            self.wrapped_segment
+                .get()
+                .read()
+                .read_filtered(offset, wrapped_limit, filter);
+        read_points.retain(|point_id| !self.tombstones.contains(point_id_to_offset(*point_id)));
+        if let Some(limit) = limit {
+            read_points.truncate(limit);
         }
+        let mut write_segment_points =
+            self.write_segment.get().read().read_filtered(offset, limit, filter);
+        read_points.append(&mut write_segment_points);
+        read_points.sort_unstable();
+        read_points
     }
 }
 
@@ -181,39 +773,26 @@ impl SegmentEntry for ProxySegment {
     ) -> OperationResult<Vec<ScoredPoint>> {
         let deleted_points = self.deleted_points.read();
 
-        // Some point might be deleted after temporary segment creation
-        // We need to prevent them from being found by search request
-        // That is why we need to pass additional filter for deleted points
-        let do_update_filter = !deleted_points.is_empty();
-        let mut wrapped_result = if do_update_filter {
-            // ToDo: Come up with better way to pass deleted points into Filter
-            // e.g. implement AtomicRefCell for Serializer.
-            // This copy might slow process down if there will be a lot of deleted points
-            let wrapped_filter =
-                self.add_deleted_points_condition_to_filter(filter, &deleted_points);
-
-            self.wrapped_segment.get().read().search(
-                vector_name,
-                vector,
-                with_payload,
-                with_vector,
-                Some(&wrapped_filter),
-                top,
-                params,
-                is_stopped,
-            )?
-        } else {
-            self.wrapped_segment.get().read().search(
-                vector_name,
-                vector,
-                with_payload,
-                with_vector,
-                filter,
-                top,
-                params,
-                is_stopped,
-            )?
-        };
+        // Some points might be deleted after the write segment was created. Rather than
+        // cloning the whole tombstone set into a `HasId` exclusion on every query - an
+        // O(deleted) allocation - over-fetch from the wrapped segment by the tombstone
+        // popcount and drop any of its hits that are tombstoned before merging with the
+        // write segment's results, so the merged top-k stays correct post-reconciliation.
+        let wrapped_top = top.saturating_add(deleted_points.len() as usize);
+        let mut wrapped_result = self.wrapped_segment.get().read().search(
+            vector_name,
+            vector,
+            with_payload,
+            with_vector,
+            filter,
+            wrapped_top,
+            params,
+            is_stopped,
+        )?;
+        wrapped_result.retain(|scored_point| {
+            !deleted_points.contains(point_id_to_offset(scored_point.id))
+        });
+        wrapped_result.truncate(top);
 
         let mut write_result = self.write_segment.get().read().search(
             vector_name,
@@ -243,39 +822,25 @@ impl SegmentEntry for ProxySegment {
     ) -> OperationResult<Vec<Vec<ScoredPoint>>> {
         let deleted_points = self.deleted_points.read();
 
-        // Some point might be deleted after temporary segment creation
-        // We need to prevent them from being found by search request
-        // That is why we need to pass additional filter for deleted points
-        let do_update_filter = !deleted_points.is_empty();
-        let mut wrapped_results = if do_update_filter {
-            // ToDo: Come up with better way to pass deleted points into Filter
-            // e.g. implement AtomicRefCell for Serializer.
-            // This copy might slow process down if there will be a lot of deleted points
-            let wrapped_filter =
-                self.add_deleted_points_condition_to_filter(filter, &deleted_points);
-
-            self.wrapped_segment.get().read().search_batch(
-                vector_name,
-                vectors,
-                with_payload,
-                with_vector,
-                Some(&wrapped_filter),
-                top,
-                params,
-                is_stopped,
-            )?
-        } else {
-            self.wrapped_segment.get().read().search_batch(
-                vector_name,
-                vectors,
-                with_payload,
-                with_vector,
-                filter,
-                top,
-                params,
-                is_stopped,
-            )?
-        };
+        // See the comment in `search` above: over-fetch by the tombstone popcount and
+        // reconcile against the bitmap afterwards instead of cloning it into a filter.
+        let wrapped_top = top.saturating_add(deleted_points.len() as usize);
+        let mut wrapped_results = self.wrapped_segment.get().read().search_batch(
+            vector_name,
+            vectors,
+            with_payload,
+            with_vector,
+            filter,
+            wrapped_top,
+            params,
+            is_stopped,
+        )?;
+        for wrapped_result in wrapped_results.iter_mut() {
+            wrapped_result.retain(|scored_point| {
+                !deleted_points.contains(point_id_to_offset(scored_point.id))
+            });
+            wrapped_result.truncate(top);
+        }
         let mut write_results = self.write_segment.get().read().search_batch(
             vector_name,
             vectors,
@@ -298,6 +863,8 @@ impl SegmentEntry for ProxySegment {
         point_id: PointIdType,
         vectors: NamedVectors,
     ) -> OperationResult<bool> {
+        let _point_lock = self.lock_manager.acquire_write(point_id);
+        self.capture_for_batch(point_id, op_num)?;
         self.move_if_exists(op_num, point_id)?;
         self.write_segment
             .get()
@@ -310,9 +877,20 @@ impl SegmentEntry for ProxySegment {
         op_num: SeqNumberType,
         point_id: PointIdType,
     ) -> OperationResult<bool> {
+        let _point_lock = self.lock_manager.acquire_write(point_id);
+        self.capture_for_batch(point_id, op_num)?;
         let mut was_deleted = false;
         if self.wrapped_segment.get().read().has_point(point_id) {
-            was_deleted = self.deleted_points.write().insert(point_id);
+            was_deleted = self
+                .deleted_points
+                .write()
+                .insert(point_id_to_offset(point_id));
+            self.delete_queue
+                .write()
+                .push(DeleteOperation { point_id, op_num });
+            if let Some(journal) = &mut self.journal {
+                journal.record_delete(point_id, op_num)?;
+            }
         }
         let was_deleted_in_writable = self
             .write_segment
@@ -342,6 +920,7 @@ impl SegmentEntry for ProxySegment {
         point_id: PointIdType,
         vector_name: &str,
     ) -> OperationResult<bool> {
+        let _point_lock = self.lock_manager.acquire_write(point_id);
         self.move_if_exists(op_num, point_id)?;
         self.write_segment
             .get()
@@ -368,6 +947,8 @@ impl SegmentEntry for ProxySegment {
         point_id: PointIdType,
         payload: &Payload,
     ) -> OperationResult<bool> {
+        let _point_lock = self.lock_manager.acquire_write(point_id);
+        self.capture_for_batch(point_id, op_num)?;
         self.move_if_exists(op_num, point_id)?;
         self.write_segment
             .get()
@@ -381,6 +962,7 @@ impl SegmentEntry for ProxySegment {
         point_id: PointIdType,
         key: PayloadKeyTypeRef,
     ) -> OperationResult<bool> {
+        let _point_lock = self.lock_manager.acquire_write(point_id);
         self.move_if_exists(op_num, point_id)?;
         self.write_segment
             .get()
@@ -405,7 +987,11 @@ impl SegmentEntry for ProxySegment {
         vector_name: &str,
         point_id: PointIdType,
     ) -> OperationResult<Option<Vec<VectorElementType>>> {
-        return if self.deleted_points.read().contains(&point_id) {
+        return if self
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(point_id))
+        {
             self.write_segment
                 .get()
                 .read()
@@ -443,7 +1029,11 @@ impl SegmentEntry for ProxySegment {
     }
 
     fn payload(&self, point_id: PointIdType) -> OperationResult<Payload> {
-        return if self.deleted_points.read().contains(&point_id) {
+        return if self
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(point_id))
+        {
             self.write_segment.get().read().payload(point_id)
         } else {
             {
@@ -461,6 +1051,9 @@ impl SegmentEntry for ProxySegment {
     fn iter_points(&self) -> Box<dyn Iterator<Item = PointIdType> + '_> {
         // iter_points is not available for Proxy implementation
         // Due to internal locks it is almost impossible to return iterator with proper owning, lifetimes, e.t.c.
+        // Callers that need to iterate a proxy should take a `read_snapshot` instead, which
+        // captures a consistent point-in-time view and can iterate without racing concurrent
+        // deletes.
         unimplemented!("call to iter_points is not implemented for Proxy segment")
     }
 
@@ -471,19 +1064,16 @@ impl SegmentEntry for ProxySegment {
         filter: Option<&'a Filter>,
     ) -> Vec<PointIdType> {
         let deleted_points = self.deleted_points.read();
-        let mut read_points = if deleted_points.is_empty() {
-            self.wrapped_segment
-                .get()
-                .read()
-                .read_filtered(offset, limit, filter)
-        } else {
-            let wrapped_filter =
-                self.add_deleted_points_condition_to_filter(filter, &deleted_points);
+        let wrapped_limit = limit.map(|limit| limit.saturating_add(deleted_points.len() as usize));
+        let mut read_points =
             self.wrapped_segment
                 .get()
                 .read()
-                .read_filtered(offset, limit, Some(&wrapped_filter))
-        };
+                .read_filtered(offset, wrapped_limit, filter);
+        read_points.retain(|idx| !deleted_points.contains(point_id_to_offset(*idx)));
+        if let Some(limit) = limit {
+            read_points.truncate(limit);
+        }
         let mut write_segment_points = self
             .write_segment
             .get()
@@ -499,7 +1089,7 @@ impl SegmentEntry for ProxySegment {
         let deleted_points = self.deleted_points.read();
         let mut read_points = self.wrapped_segment.get().read().read_range(from, to);
         if !deleted_points.is_empty() {
-            read_points.retain(|idx| !deleted_points.contains(idx))
+            read_points.retain(|idx| !deleted_points.contains(point_id_to_offset(*idx)))
         }
         let mut write_segment_points = self.write_segment.get().read().read_range(from, to);
         read_points.append(&mut write_segment_points);
@@ -508,7 +1098,11 @@ impl SegmentEntry for ProxySegment {
     }
 
     fn has_point(&self, point_id: PointIdType) -> bool {
-        return if self.deleted_points.read().contains(&point_id) {
+        return if self
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(point_id))
+        {
             self.write_segment.get().read().has_point(point_id)
         } else {
             self.write_segment.get().read().has_point(point_id)
@@ -517,7 +1111,7 @@ impl SegmentEntry for ProxySegment {
     }
 
     fn available_point_count(&self) -> usize {
-        let deleted_points_count = self.deleted_points.read().len();
+        let deleted_points_count = self.deleted_points.read().len() as usize;
         let wrapped_segment_count = self.wrapped_segment.get().read().available_point_count();
         let write_segment_count = self.write_segment.get().read().available_point_count();
         (wrapped_segment_count + write_segment_count).saturating_sub(deleted_points_count)
@@ -528,7 +1122,7 @@ impl SegmentEntry for ProxySegment {
     }
 
     fn estimate_point_count<'a>(&'a self, filter: Option<&'a Filter>) -> CardinalityEstimation {
-        let deleted_point_count = self.deleted_points.read().len();
+        let deleted_point_count = self.deleted_points.read().len() as usize;
 
         let (wrapped_segment_est, total_wrapped_size) = {
             let wrapped_segment = self.wrapped_segment.get();
@@ -576,7 +1170,7 @@ impl SegmentEntry for ProxySegment {
         // This is a best estimate
         let num_vectors = {
             let vector_name_count = self.config().vector_data.len();
-            let deleted_points_count = self.deleted_points.read().len();
+            let deleted_points_count = self.deleted_points.read().len() as usize;
             (wrapped_info.num_vectors + write_info.num_vectors)
                 .saturating_sub(deleted_points_count * vector_name_count)
         };
@@ -606,6 +1200,15 @@ impl SegmentEntry for ProxySegment {
         let deleted_indexes_guard = self.deleted_indexes.read();
         let created_indexes_guard = self.created_indexes.read();
 
+        // Whichever branch below runs, the journal is the only durable
+        // record of a non-empty `deleted_points`/`created_indexes`/
+        // `deleted_indexes`, so make sure it's on disk before returning.
+        if sync {
+            if let Some(journal) = &self.journal {
+                journal.sync()?;
+            }
+        }
+
         if deleted_points_guard.is_empty()
             && deleted_indexes_guard.is_empty()
             && created_indexes_guard.is_empty()
@@ -642,6 +1245,9 @@ impl SegmentEntry for ProxySegment {
         }
         self.deleted_indexes.write().insert(key.into());
         self.created_indexes.write().remove(key);
+        if let Some(journal) = &mut self.journal {
+            journal.record_drop_index(key.into(), op_num)?;
+        }
         self.write_segment
             .get()
             .write()
@@ -673,6 +1279,9 @@ impl SegmentEntry for ProxySegment {
             .write()
             .insert(key.into(), payload_schema.to_owned());
         self.deleted_indexes.write().remove(key);
+        if let Some(journal) = &mut self.journal {
+            journal.record_create_index(key.into(), payload_schema.to_owned(), op_num)?;
+        }
 
         Ok(true)
     }
@@ -700,6 +1309,11 @@ impl SegmentEntry for ProxySegment {
         op_num: SeqNumberType,
         filter: &'a Filter,
     ) -> OperationResult<usize> {
+        // The filter's matching key set isn't known up front, so this can't
+        // take a single per-point lock; block out new point locks and wait
+        // for outstanding ones to drain instead.
+        let _exclusive = self.lock_manager.global_exclusive();
+
         let mut deleted_points = 0;
 
         let points_to_delete =
@@ -709,8 +1323,13 @@ impl SegmentEntry for ProxySegment {
                 .read_filtered(None, None, Some(filter));
         if !points_to_delete.is_empty() {
             deleted_points += points_to_delete.len();
+            if self.active_batch.is_some() {
+                for &point_id in &points_to_delete {
+                    self.capture_for_batch(point_id, op_num)?;
+                }
+            }
             let mut deleted_points_guard = self.deleted_points.write();
-            deleted_points_guard.extend(points_to_delete);
+            deleted_points_guard.extend(points_to_delete.into_iter().map(point_id_to_offset));
         }
 
         deleted_points += self
@@ -747,13 +1366,16 @@ impl SegmentEntry for ProxySegment {
             // snapshot wrapped segment data into the temporary dir
             wrapped_segment_guard.take_snapshot(temp_path, snapshot_dir_path)?
         };
+        write_snapshot_checksum(&archive_path)?;
 
         // snapshot write_segment
         let write_segment_rw = self.write_segment.get();
         let write_segment_guard = write_segment_rw.read();
 
         // Write segment is not unique to the proxy segment, therefore it might overwrite an existing snapshot.
-        write_segment_guard.take_snapshot(temp_path, snapshot_dir_path)?;
+        let write_segment_archive_path =
+            write_segment_guard.take_snapshot(temp_path, snapshot_dir_path)?;
+        write_snapshot_checksum(&write_segment_archive_path)?;
 
         Ok(archive_path)
     }
@@ -768,7 +1390,7 @@ mod tests {
     use std::fs::read_dir;
 
     use segment::data_types::vectors::{only_default_vector, DEFAULT_VECTOR_NAME};
-    use segment::types::{FieldCondition, PayloadSchemaType};
+    use segment::types::{Condition, FieldCondition, PayloadSchemaType};
     use serde_json::json;
     use tempfile::{Builder, TempDir};
 
@@ -782,7 +1404,7 @@ mod tests {
         let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
         let original_segment = LockedSegment::new(build_segment_1(dir.path()));
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -795,6 +1417,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         let vec4 = vec![1.1, 1.0, 0.0, 1.0];
@@ -850,7 +1473,7 @@ mod tests {
         let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
         let original_segment = LockedSegment::new(build_segment_1(dir.path()));
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -863,6 +1486,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         let vec4 = vec![1.1, 1.0, 0.0, 1.0];
@@ -915,7 +1539,7 @@ mod tests {
         let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
         let original_segment = LockedSegment::new(random_segment(dir.path(), 100, 200, 4));
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -928,6 +1552,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         let query_vector = vec![1.0, 1.0, 1.0, 1.0];
@@ -970,7 +1595,7 @@ mod tests {
         let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
         let original_segment = LockedSegment::new(random_segment(dir.path(), 100, 200, 4));
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -983,6 +1608,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         let q1 = vec![1.0, 1.0, 1.0, 0.1];
@@ -1032,7 +1658,7 @@ mod tests {
 
     fn wrap_proxy(dir: &TempDir, original_segment: LockedSegment) -> ProxySegment {
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -1045,6 +1671,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         )
     }
 
@@ -1112,7 +1739,7 @@ mod tests {
         let original_segment = LockedSegment::new(build_segment_1(dir.path()));
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
 
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
             HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
@@ -1130,6 +1757,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         proxy_segment.replicate_field_indexes(0).unwrap();
@@ -1172,7 +1800,7 @@ mod tests {
         let original_segment = LockedSegment::new(build_segment_1(dir.path()));
         let original_segment_2 = LockedSegment::new(build_segment_2(dir.path()));
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -1185,6 +1813,7 @@ mod tests {
             deleted_points.clone(),
             created_indexes.clone(),
             deleted_indexes.clone(),
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         let mut proxy_segment2 = ProxySegment::new(
@@ -1193,6 +1822,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         let vec4 = vec![1.1, 1.0, 0.0, 1.0];
@@ -1235,12 +1865,105 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_take_snapshot_verifies_and_detects_corruption() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+
+        let proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
+        );
+
+        let snapshot_dir = Builder::new().prefix("snapshot_dir").tempdir().unwrap();
+        let temp_dir = Builder::new().prefix("temp_dir").tempdir().unwrap();
+
+        let archive_path = proxy_segment
+            .take_snapshot(temp_dir.path(), snapshot_dir.path())
+            .unwrap();
+
+        crate::collection_manager::holders::snapshot_checksum::verify_snapshot(&archive_path)
+            .expect("freshly written archive must verify against its own checksum");
+
+        // Flip a byte in the archive: verification must now fail instead of
+        // silently handing a truncated/corrupted archive to the caller.
+        let mut bytes = std::fs::read(&archive_path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&archive_path, bytes).unwrap();
+
+        assert!(
+            crate::collection_manager::holders::snapshot_checksum::verify_snapshot(&archive_path)
+                .is_err(),
+            "a corrupted archive must fail verification"
+        );
+    }
+
+    #[test]
+    fn test_take_compressed_snapshot_round_trips_each_codec() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+
+        let proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
+        );
+
+        for (compression, expected_extension) in [
+            (SnapshotCompression::None, "tar"),
+            (SnapshotCompression::Lz4, "lz4"),
+            (SnapshotCompression::Deflate(6), "gz"),
+        ] {
+            let snapshot_dir = Builder::new().prefix("snapshot_dir").tempdir().unwrap();
+            let temp_dir = Builder::new().prefix("temp_dir").tempdir().unwrap();
+
+            let archive_path = proxy_segment
+                .take_compressed_snapshot(temp_dir.path(), snapshot_dir.path(), compression)
+                .unwrap();
+            assert_eq!(
+                archive_path.extension().unwrap().to_str().unwrap(),
+                expected_extension
+            );
+
+            let decompressed =
+                crate::collection_manager::holders::snapshot_compression::decompress_snapshot_archive(
+                    &archive_path,
+                )
+                .unwrap();
+            assert!(!decompressed.is_empty());
+            // A valid tar starts with a file name in its first header block.
+            assert!(decompressed.len() >= 512);
+        }
+    }
+
     #[test]
     fn test_point_vector_count() {
         let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
         let original_segment = LockedSegment::new(build_segment_1(dir.path()));
         let write_segment = LockedSegment::new(empty_segment(dir.path()));
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -1253,6 +1976,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         // We have 5 points by default, assert counts
@@ -1334,7 +2058,7 @@ mod tests {
 
         let original_segment = LockedSegment::new(original_segment);
         let write_segment = LockedSegment::new(write_segment);
-        let deleted_points = Arc::new(RwLock::new(HashSet::<PointIdType>::new()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
 
         let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
         let created_indexes = Arc::new(RwLock::new(
@@ -1347,6 +2071,7 @@ mod tests {
             deleted_points,
             created_indexes,
             deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
         );
 
         // Assert counts from original segment
@@ -1413,4 +2138,472 @@ mod tests {
         assert_eq!(segment_info.num_points, 3);
         assert_eq!(segment_info.num_vectors, 4);
     }
+
+    #[test]
+    fn test_advance_delete_cursor_skips_superseded_delete() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+        let delete_queue = Arc::new(RwLock::new(Vec::new()));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            delete_queue,
+        );
+
+        // Point 1 exists in the wrapped segment built by `build_segment_1`.
+        proxy_segment.delete_point(100, 1.into()).unwrap();
+        assert!(proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+
+        // A later upsert bumps the point's version past the queued delete's
+        // op_num, so replaying the queue must not re-delete it.
+        let vec1 = vec![1.0, 0.0, 1.0, 1.0];
+        proxy_segment
+            .upsert_point(101, 1.into(), only_default_vector(&vec1))
+            .unwrap();
+        proxy_segment.deleted_points.write().remove(point_id_to_offset(1.into()));
+
+        proxy_segment.advance_delete_cursor();
+        assert!(
+            !proxy_segment
+                .deleted_points
+                .read()
+                .contains(point_id_to_offset(1.into())),
+            "replaying a delete superseded by a newer upsert must not re-delete the point"
+        );
+
+        // Replaying again is a no-op: the cursor has already consumed every
+        // queued entry.
+        proxy_segment.advance_delete_cursor();
+        assert!(!proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+    }
+
+    #[test]
+    fn test_read_snapshot_is_unaffected_by_concurrent_delete() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+        let delete_queue = Arc::new(RwLock::new(Vec::new()));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            delete_queue,
+        );
+
+        let snapshot = proxy_segment.read_snapshot();
+        let ids_before: HashSet<PointIdType> = snapshot.iter_points().collect();
+        assert!(ids_before.contains(&1.into()));
+
+        // Deleting after the snapshot was taken must not affect it: both
+        // read_range and read_filtered against the snapshot should still see
+        // point 1.
+        proxy_segment.delete_point(100, 1.into()).unwrap();
+
+        assert!(snapshot.read_range(None, None).contains(&1.into()));
+        assert!(snapshot.read_filtered(None, None, None).contains(&1.into()));
+
+        // A fresh snapshot, taken after the delete, reflects the new state.
+        let snapshot_after = proxy_segment.read_snapshot();
+        assert!(!snapshot_after.read_range(None, None).contains(&1.into()));
+    }
+
+    #[test]
+    fn test_compact_tombstones_retires_flushed_deletes() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+        let delete_queue = Arc::new(RwLock::new(Vec::new()));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            delete_queue,
+        );
+
+        proxy_segment.delete_point(100, 1.into()).unwrap();
+        assert!(proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+
+        // Nothing to compact yet: the delete hasn't been flushed past.
+        proxy_segment.compact_tombstones(50);
+        assert!(proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+
+        // Once the caller reports a flush past op_num 100, the tombstone is
+        // eligible for retirement; epoch reclamation runs once enough
+        // epochs have advanced past the deferred callback.
+        proxy_segment.compact_tombstones(100);
+        for _ in 0..128 {
+            let guard = epoch::pin();
+            guard.flush();
+        }
+        assert!(!proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+    }
+
+    #[test]
+    fn test_lock_manager_serializes_same_point_not_different_points() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        use crate::collection_manager::holders::lock_manager::LockManager;
+
+        let manager = Arc::new(LockManager::new());
+
+        // Locks on different points must not block each other.
+        let guard_a = manager.acquire_write(1.into());
+        let (tx, rx) = mpsc::channel();
+        let manager_clone = Arc::clone(&manager);
+        let handle = std::thread::spawn(move || {
+            let _guard_b = manager_clone.acquire_write(2.into());
+            tx.send(()).unwrap();
+        });
+        rx.recv_timeout(Duration::from_secs(5))
+            .expect("a lock on a different point must not block on an outstanding one");
+        handle.join().unwrap();
+        drop(guard_a);
+
+        // A second lock on the *same* point must block until the first is
+        // released.
+        let guard_c = manager.acquire_write(3.into());
+        let (tx2, rx2) = mpsc::channel();
+        let manager_clone2 = Arc::clone(&manager);
+        let handle2 = std::thread::spawn(move || {
+            let _guard_d = manager_clone2.acquire_write(3.into());
+            tx2.send(()).unwrap();
+        });
+        assert!(
+            rx2.recv_timeout(Duration::from_millis(200)).is_err(),
+            "a second writer on the same point must block while the first is held"
+        );
+        drop(guard_c);
+        rx2.recv_timeout(Duration::from_secs(5))
+            .expect("releasing the first writer must unblock the second");
+        handle2.join().unwrap();
+    }
+
+    #[test]
+    fn test_deleted_points_bucket_map_round_trip() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+        let delete_queue = Arc::new(RwLock::new(Vec::new()));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            delete_queue,
+        );
+
+        proxy_segment.delete_point(100, 1.into()).unwrap();
+        proxy_segment.delete_point(101, 2.into()).unwrap();
+
+        let bucket_map_path = dir.path().join("deleted_points.bucket_map");
+        let bucket_map = proxy_segment
+            .export_deleted_points_to_bucket_map(&bucket_map_path, 2)
+            .unwrap();
+        assert!(bucket_map.contains(point_id_to_offset(1.into())));
+        assert!(bucket_map.contains(point_id_to_offset(2.into())));
+        assert!(!bucket_map.contains(point_id_to_offset(3.into())));
+
+        let fresh_deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+        let fresh_proxy_segment = ProxySegment::new(
+            LockedSegment::new(build_segment_1(dir.path())),
+            LockedSegment::new(empty_segment(dir.path())),
+            fresh_deleted_points,
+            Arc::new(RwLock::new(HashMap::new())),
+            Arc::new(RwLock::new(HashSet::new())),
+            Arc::new(RwLock::new(Vec::new())),
+        );
+        fresh_proxy_segment.import_deleted_points_from_bucket_map(&bucket_map);
+        assert!(fresh_proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+        assert!(fresh_proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(2.into())));
+    }
+
+    #[test]
+    fn test_bucket_map_grows_past_initial_capacity() {
+        use crate::collection_manager::holders::bucket_map::BucketMap;
+
+        let dir = Builder::new().prefix("bucket_map_dir").tempdir().unwrap();
+        let path = dir.path().join("grow.bucket_map");
+        // A single-bucket map overflows once its one slot array is full, so
+        // these inserts force several grow-and-rehash rounds.
+        let mut bucket_map = BucketMap::create(&path, 0).unwrap();
+        for key in 0..200u64 {
+            assert!(bucket_map.insert(key).unwrap());
+        }
+        for key in 0..200u64 {
+            assert!(bucket_map.contains(key));
+        }
+        assert!(!bucket_map.contains(12345));
+    }
+
+    #[test]
+    fn test_rollback_batch_restores_pre_batch_state() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+        let delete_queue = Arc::new(RwLock::new(Vec::new()));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            delete_queue,
+        );
+
+        // Point 4 is not in write_segment yet (only in the wrapped segment);
+        // point 1 is deleted by the batch. Both
+        // should be undone by rollback_batch.
+        proxy_segment.begin_batch().unwrap();
+
+        let vec4 = vec![1.1, 1.0, 0.0, 1.0];
+        proxy_segment
+            .upsert_point(100, 4.into(), only_default_vector(&vec4))
+            .unwrap();
+        proxy_segment.delete_point(101, 1.into()).unwrap();
+
+        assert!(proxy_segment.write_segment.get().read().has_point(4.into()));
+        assert!(proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+
+        proxy_segment.rollback_batch().unwrap();
+
+        assert!(!proxy_segment.write_segment.get().read().has_point(4.into()));
+        assert!(!proxy_segment
+            .deleted_points
+            .read()
+            .contains(point_id_to_offset(1.into())));
+        assert!(proxy_segment.active_batch.is_none());
+    }
+
+    #[test]
+    fn test_commit_batch_keeps_mutations() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+        let delete_queue = Arc::new(RwLock::new(Vec::new()));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            delete_queue,
+        );
+
+        proxy_segment.begin_batch().unwrap();
+        let vec4 = vec![1.1, 1.0, 0.0, 1.0];
+        proxy_segment
+            .upsert_point(100, 4.into(), only_default_vector(&vec4))
+            .unwrap();
+        proxy_segment.commit_batch().unwrap();
+
+        assert!(proxy_segment.write_segment.get().read().has_point(4.into()));
+        assert!(proxy_segment.active_batch.is_none());
+    }
+
+    #[test]
+    fn test_try_reserve_bytes_fails_on_unsatisfiable_size() {
+        // `isize::MAX` bytes can never be satisfied by a real allocator, so
+        // this exercises the failure path without actually allocating
+        // anything.
+        assert!(try_reserve_bytes(isize::MAX as usize).is_err());
+        assert!(try_reserve_bytes(0).is_ok());
+    }
+
+    #[test]
+    fn test_delete_vector_outcome_distinguishes_removed_from_already_absent() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
+        );
+
+        let outcome = proxy_segment
+            .delete_vector_outcome(100, 2.into(), DEFAULT_VECTOR_NAME)
+            .unwrap();
+        assert_eq!(outcome, DeleteOutcome::Removed);
+
+        // Deleting it again shouldn't chain anything.
+        let outcome = proxy_segment
+            .delete_vector_outcome(101, 2.into(), DEFAULT_VECTOR_NAME)
+            .unwrap();
+        assert_eq!(outcome, DeleteOutcome::AlreadyAbsent);
+    }
+
+    #[test]
+    fn test_upsert_point_outcome_distinguishes_inserted_replaced_unchanged() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
+        );
+
+        let vec_new = vec![1.1, 1.0, 0.0, 1.0];
+        let outcome = proxy_segment
+            .upsert_point_outcome(100, 99.into(), only_default_vector(&vec_new))
+            .unwrap();
+        assert_eq!(outcome, UpsertOutcome::Inserted);
+
+        let outcome = proxy_segment
+            .upsert_point_outcome(101, 99.into(), only_default_vector(&vec_new))
+            .unwrap();
+        assert_eq!(outcome, UpsertOutcome::Unchanged);
+
+        let vec_replacement = vec![0.0, 0.0, 0.0, 0.0];
+        let outcome = proxy_segment
+            .upsert_point_outcome(102, 99.into(), only_default_vector(&vec_replacement))
+            .unwrap();
+        assert_eq!(outcome, UpsertOutcome::Replaced);
+    }
+
+    #[test]
+    fn test_retain_drops_points_failing_predicate_in_one_pass() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let original_segment = LockedSegment::new(build_segment_1(dir.path()));
+        let write_segment = LockedSegment::new(empty_segment(dir.path()));
+        let deleted_points = Arc::new(RwLock::new(RoaringTreemap::new()));
+
+        let deleted_indexes = Arc::new(RwLock::new(HashSet::<PayloadKeyType>::new()));
+        let created_indexes = Arc::new(RwLock::new(
+            HashMap::<PayloadKeyType, PayloadFieldSchema>::new(),
+        ));
+
+        let mut proxy_segment = ProxySegment::new(
+            original_segment,
+            write_segment,
+            deleted_points,
+            created_indexes,
+            deleted_indexes,
+            Arc::new(RwLock::new(Vec::new())),
+        );
+
+        // We have 5 points by default; keep only point 3.
+        let removed = proxy_segment
+            .retain(100, |point_id, _vectors| point_id == 3.into())
+            .unwrap();
+        assert_eq!(removed, 4);
+
+        let segment_info = proxy_segment.info();
+        assert_eq!(segment_info.num_points, 1);
+        assert!(proxy_segment.has_point(3.into()));
+        assert!(!proxy_segment.has_point(1.into()));
+
+        // Running it again is a no-op: the single surviving point still
+        // satisfies the predicate.
+        let removed_again = proxy_segment
+            .retain(101, |point_id, _vectors| point_id == 3.into())
+            .unwrap();
+        assert_eq!(removed_again, 0);
+    }
+
+    #[test]
+    fn test_named_vectors_byte_len_sums_all_named_vectors() {
+        let vectors = NamedVectors::from([
+            ("a".into(), vec![0.0, 0.0, 0.0]),
+            ("b".into(), vec![0.0]),
+        ]);
+        let expected = 4 * std::mem::size_of::<VectorElementType>();
+        assert_eq!(named_vectors_byte_len(&vectors), expected);
+    }
 }
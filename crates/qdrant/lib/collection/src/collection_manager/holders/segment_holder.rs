@@ -1,20 +1,32 @@
-use std::cmp::{max, min};
-use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::cmp::{max, min, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
 use std::ops::{Deref, Mul};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use itertools::Itertools;
-use parking_lot::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
+use parking_lot::{
+    Condvar, Mutex, RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard,
+};
 use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
+use segment::data_types::named_vectors::NamedVectors;
 use segment::entry::entry_point::{OperationError, OperationResult, SegmentEntry};
 use segment::segment::Segment;
-use segment::types::{PointIdType, SeqNumberType};
+use segment::types::{Payload, PointIdType, SeqNumberType};
 
 use crate::collection_manager::holders::proxy_segment::ProxySegment;
+use crate::collection_manager::holders::segment_wal::SegmentWal;
+use crate::collection_manager::holders::snapshot_checksum::{
+    read_snapshot_checksum, verify_snapshot, write_snapshot_checksum,
+};
+use crate::collection_manager::holders::snapshot_compression::{
+    compress_snapshot_archive, SnapshotCompression,
+};
+use crate::collection_manager::holders::snapshot_lock::acquire_snapshot_lock;
 use crate::operations::types::CollectionError;
 
 pub type SegmentId = usize;
@@ -22,6 +34,118 @@ pub type SegmentId = usize;
 const DROP_SPIN_TIMEOUT: Duration = Duration::from_millis(10);
 const DROP_DATA_TIMEOUT: Duration = Duration::from_secs(60 * 60);
 
+/// How many of the most-recently-retired segments `SegmentHolder` always
+/// keeps un-reclaimed, regardless of epoch safety - gives a pending flush
+/// targeting the same on-disk path (started just before the segment was
+/// swapped out) time to land before anything under it is deleted.
+const DEFAULT_MIN_RETIRED_AHEAD: usize = 2;
+
+/// How many batches [`OperationJournal`] keeps around at once, oldest
+/// dropped first once the bound is hit - an editor-style undo history, not
+/// a durable log.
+const DEFAULT_MAX_JOURNAL_BATCHES: usize = 32;
+
+pub type BatchId = u64;
+
+/// A point's recorded state at a point in time: which segment holds it and
+/// its vectors/payload there, or `Absent` if the point didn't exist in any
+/// segment - replaying an `Absent` state is a delete rather than a restore.
+#[derive(Debug, Clone)]
+enum PointState {
+    Absent,
+    Present {
+        segment_id: SegmentId,
+        vectors: NamedVectors,
+        payload: Payload,
+    },
+}
+
+/// A single point's journal entry: its state immediately before and
+/// immediately after the operation that touched it. Covers a plain
+/// in-place upsert (`before`/`after` name the same segment), a fresh insert
+/// (`before` is `Absent`), a delete (`after` is `Absent`), and the
+/// move-to-appendable path (`before`/`after` name different segments) with
+/// the same shape, since undo/redo only care about where the point ends up
+/// and what it looks like there, not which internal path produced that.
+/// [`SegmentHolder::undo`] replays `before` entries in reverse batch order;
+/// [`SegmentHolder::redo`] replays `after` entries back in the original
+/// order.
+#[derive(Debug, Clone)]
+struct PointJournalEntry {
+    point_id: PointIdType,
+    before: PointState,
+    after: PointState,
+}
+
+/// The recorded mutations of one batch opened by [`SegmentHolder::begin_batch`].
+struct OperationBatch {
+    /// The op_num the batch was originally applied under - once segment
+    /// flushes have durably persisted at least this version, the batch's
+    /// mutations are on disk regardless of whether anyone undoes them, so
+    /// it's safe to drop from the journal.
+    op_num: SeqNumberType,
+    entries: Vec<PointJournalEntry>,
+}
+
+/// Bounded stack of recent batches, capturing enough of each mutated
+/// point's prior/subsequent state to undo or redo a batch applied through
+/// [`SegmentHolder::apply_points_to_appendable_journaled`]. Modeled on an
+/// editor's undo history rather than [`SegmentWal`]: it's in-memory only
+/// and capped at [`DEFAULT_MAX_JOURNAL_BATCHES`] batches, since its job is
+/// reverting a just-applied transaction, not surviving a crash.
+struct OperationJournal {
+    batches: VecDeque<(BatchId, OperationBatch)>,
+    next_batch_id: u64,
+    max_batches: usize,
+}
+
+impl OperationJournal {
+    fn new(max_batches: usize) -> Self {
+        Self {
+            batches: VecDeque::new(),
+            next_batch_id: 0,
+            max_batches,
+        }
+    }
+
+    fn begin_batch(&mut self, op_num: SeqNumberType) -> BatchId {
+        let batch_id = self.next_batch_id;
+        self.next_batch_id += 1;
+        self.batches.push_back((
+            batch_id,
+            OperationBatch {
+                op_num,
+                entries: Vec::new(),
+            },
+        ));
+        while self.batches.len() > self.max_batches {
+            self.batches.pop_front();
+        }
+        batch_id
+    }
+
+    fn record(&mut self, batch_id: BatchId, entry: PointJournalEntry) {
+        if let Some((_, batch)) = self.batches.iter_mut().find(|(id, _)| *id == batch_id) {
+            batch.entries.push(entry);
+        }
+    }
+
+    fn get(&self, batch_id: BatchId) -> Option<&OperationBatch> {
+        self.batches
+            .iter()
+            .find(|(id, _)| *id == batch_id)
+            .map(|(_, batch)| batch)
+    }
+
+    /// Drops every batch whose op_num is already durable as of
+    /// `max_persisted_version` - there's nothing left for `undo`/`redo` to
+    /// usefully revert once a batch's changes are on disk regardless.
+    fn retire_flushed(&mut self, max_persisted_version: SeqNumberType) {
+        self.batches
+            .retain(|(_, batch)| batch.op_num > max_persisted_version);
+    }
+}
+
 /// Object, which unifies the access to different types of segments, but still allows to
 /// access the original type of the segment if it is required for more efficient operations.
 pub enum LockedSegment {
@@ -29,6 +153,124 @@ pub enum LockedSegment {
     Proxy(Arc<RwLock<ProxySegment>>),
 }
 
+/// Compression choice for
+/// [`SegmentHolder::snapshot_all_segments_with_options`], which checksums
+/// every archive regardless of this setting. Lz4 is the default:
+/// a good speed/ratio tradeoff for vector/payload data, with
+/// [`SnapshotCompression::Deflate`] available for a denser-but-slower option.
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotOptions {
+    pub compression: SnapshotCompression,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            compression: SnapshotCompression::Lz4,
+        }
+    }
+}
+
+/// One archive produced by
+/// [`SegmentHolder::snapshot_all_segments_with_options`]: which segment it
+/// belongs to, where it landed on disk, and the xxh3 checksum recorded for
+/// it (also written to the archive's own `.checksum` sidecar by
+/// [`write_snapshot_checksum`]).
+#[derive(Debug, Clone)]
+pub struct SnapshotManifestEntry {
+    pub segment_id: SegmentId,
+    pub archive_path: PathBuf,
+    pub checksum: u64,
+}
+
+/// Verifies every archive in `manifest` against its recorded checksum,
+/// failing on the first mismatch or unreadable entry - meant to be called
+/// before unpacking any archive from a
+/// [`SegmentHolder::snapshot_all_segments_with_options`] manifest, so a
+/// truncated or bit-rotted archive is caught up front rather than partway
+/// through a restore.
+pub fn verify_snapshot_manifest(manifest: &[SnapshotManifestEntry]) -> OperationResult<()> {
+    for entry in manifest {
+        verify_snapshot(&entry.archive_path)?;
+    }
+    Ok(())
+}
+
+/// One segment's entry in an [`IncrementalSnapshotManifest`]: the content
+/// hash it was archived under, and the filename (relative to the
+/// snapshot directory) of the archive holding it.
+#[derive(Debug, Clone)]
+pub struct IncrementalManifestEntry {
+    pub content_hash: u64,
+    pub archive_filename: String,
+}
+
+/// Per-segment state carried between successive
+/// [`SegmentHolder::snapshot_all_segments_incremental`] calls, so a segment
+/// whose content hash hasn't changed can be recognized and skipped instead
+/// of re-archived.
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalSnapshotManifest {
+    pub entries: HashMap<SegmentId, IncrementalManifestEntry>,
+}
+
+/// Outcome of one [`SegmentHolder::snapshot_all_segments_incremental`] call:
+/// which segments needed a fresh archive versus which were unchanged and
+/// had their existing archive reused.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotReport {
+    pub newly_archived: Vec<SegmentId>,
+    pub reused: Vec<SegmentId>,
+}
+
+/// A stable content hash for `segment`, combining its on-disk data path
+/// with its current version/op-number - two snapshots of the same
+/// unmutated (e.g. sealed, non-appendable) segment produce the same hash,
+/// while any write that bumps the segment's version changes it.
+fn segment_content_hash(segment: &dyn SegmentEntry) -> u64 {
+    let mut bytes = segment
+        .data_path()
+        .to_string_lossy()
+        .into_owned()
+        .into_bytes();
+    bytes.extend_from_slice(&segment.version().to_le_bytes());
+    xxhash_rust::xxh3::xxh3_64(&bytes)
+}
+
+/// How [`SegmentHolder::deduplicate_points_with_policy`] resolves a point id
+/// held by more than one segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeduplicationPolicy {
+    /// Keep the point with the highest stored version, discarding staler
+    /// copies - the same behavior [`SegmentHolder::deduplicate_points`]
+    /// always had.
+    KeepHighestVersion,
+    /// Keep the copy in whichever duplicate segment has the lowest
+    /// [`SegmentId`], regardless of version.
+    KeepFirstSegment,
+    /// Keep the copy in whichever duplicate segment holds the most points
+    /// overall, regardless of version.
+    KeepLargestSegment,
+}
+
+/// One point id's resolution under [`SegmentHolder::deduplicate_points_with_policy`]:
+/// which segment's copy was kept and the versions of the copies discarded
+/// in favor of it.
+#[derive(Debug, Clone)]
+pub struct DeduplicationDecision {
+    pub point_id: PointIdType,
+    pub kept_segment_id: SegmentId,
+    pub discarded_versions: Vec<SeqNumberType>,
+}
+
+/// Audit trail returned alongside the removed-point count by
+/// [`SegmentHolder::deduplicate_points_with_policy`], one entry per
+/// duplicated point id actually resolved.
+#[derive(Debug, Clone, Default)]
+pub struct DeduplicationReport {
+    pub decisions: Vec<DeduplicationDecision>,
+}
+
 /// Internal structure for deduplication of points. Used for BinaryHeap
 #[derive(Eq, PartialEq)]
 struct DedupPoint {
@@ -107,6 +349,184 @@ impl LockedSegment {
             }
         }
     }
+
+    /// A single, non-spinning `Arc::try_unwrap` attempt, used by
+    /// [`SegmentHolder::collect_retired`]: once the epoch scheme has
+    /// determined a retired segment is safe to reclaim, there's nothing
+    /// left worth spinning to wait for the way [`Self::drop_data`] does.
+    /// Returns the segment back, not yet dropped, if a clone somehow still
+    /// exists outside the guarantees the epoch scheme tracks (e.g. a
+    /// `LockedSegment` held past the scope of a pinned reader).
+    fn try_drop_data_once(self) -> Result<OperationResult<()>, LockedSegment> {
+        match self {
+            LockedSegment::Original(segment) => match Arc::try_unwrap(segment) {
+                Ok(raw) => Ok(raw.into_inner().drop_data()),
+                Err(segment) => Err(LockedSegment::Original(segment)),
+            },
+            LockedSegment::Proxy(proxy) => match Arc::try_unwrap(proxy) {
+                Ok(raw) => Ok(raw.into_inner().drop_data()),
+                Err(proxy) => Err(LockedSegment::Proxy(proxy)),
+            },
+        }
+    }
+}
+
+/// A retired segment awaiting reclamation, tagged with the epoch it was
+/// retired at - see [`EpochTracker`].
+struct RetiredSegment {
+    segment: LockedSegment,
+    retire_epoch: u64,
+}
+
+/// Tracks a monotonically increasing epoch plus every currently-pinned
+/// reader's epoch, so [`SegmentHolder::collect_retired`] can tell when a
+/// retired segment is no longer reachable from any in-flight read/write:
+/// once the lowest pinned epoch has advanced past a segment's retire epoch,
+/// every guard that existed at retirement time has dropped, and no live
+/// `LockedSegment` clone from that scope can remain.
+#[derive(Default)]
+struct EpochTracker {
+    epoch: AtomicU64,
+    pinned: Mutex<HashMap<u64, u64>>,
+    next_guard_id: AtomicU64,
+}
+
+impl EpochTracker {
+    /// Bumps and returns the new global epoch - called once per segment
+    /// retired, so each one gets a distinct, strictly increasing epoch to
+    /// be compared against later.
+    fn advance(&self) -> u64 {
+        self.epoch.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Pins the epoch current as of this call for the guard's lifetime.
+    /// While held, [`Self::min_pinned_epoch`] never reports past it.
+    fn pin(&self) -> EpochGuard<'_> {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        let guard_id = self.next_guard_id.fetch_add(1, Ordering::SeqCst);
+        self.pinned.lock().insert(guard_id, epoch);
+        EpochGuard {
+            tracker: self,
+            guard_id,
+        }
+    }
+
+    /// The lowest epoch any currently-pinned guard holds, or one past the
+    /// current global epoch if nothing is pinned right now (nothing to wait
+    /// for), so every segment retired so far counts as past it.
+    fn min_pinned_epoch(&self) -> u64 {
+        self.pinned
+            .lock()
+            .values()
+            .copied()
+            .min()
+            .unwrap_or_else(|| self.epoch.load(Ordering::SeqCst) + 1)
+    }
+}
+
+/// RAII guard from [`EpochTracker::pin`]: un-pins its epoch on drop, after
+/// which [`EpochTracker::min_pinned_epoch`] may advance past it.
+struct EpochGuard<'a> {
+    tracker: &'a EpochTracker,
+    guard_id: u64,
+}
+
+impl Drop for EpochGuard<'_> {
+    fn drop(&mut self) {
+        self.tracker.pinned.lock().remove(&self.guard_id);
+    }
+}
+
+/// Per-segment write contention counters exposed by
+/// [`SegmentHolder::segment_lock_metrics`], for the optimizer to reason
+/// about hot segments. `current_readers` is always `0` today - nothing
+/// currently routes a read lease through [`SegmentLockManager`], only
+/// `aloha_random_write`'s writes - the field exists so a future read-leasing
+/// caller doesn't need a struct change.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SegmentLockMetrics {
+    pub current_readers: u32,
+    pub current_writers: u32,
+    pub total_wait: Duration,
+}
+
+struct SegmentLockManagerState {
+    metrics: HashMap<SegmentId, SegmentLockMetrics>,
+    next_ticket: u64,
+    queue: VecDeque<u64>,
+}
+
+/// Fair write-lease admission table backing [`SegmentHolder::aloha_random_write`],
+/// modeled on a transactional engine's segment-lock table. The old
+/// implementation avoided deadlock by probing segments in random order with
+/// an exponentially growing `try_write_for` timeout, which offered no
+/// fairness - a thread could in principle be repeatedly out-raced and
+/// starve. This manager instead hands out a FIFO ticket per call:
+/// [`Self::enter_queue`] blocks until the caller's ticket is at the front,
+/// guaranteeing callers are served in arrival order. The actual write is
+/// still taken against the segment's real `Arc<RwLock<_>>` once admitted
+/// (a lease here doesn't replace that lock, since a write taken outside
+/// this manager - e.g. `SegmentHolder::apply_segments` - isn't reflected in
+/// it), so admission only orders *who tries next*, not whether the
+/// underlying segment happens to be free.
+struct SegmentLockManager {
+    state: Mutex<SegmentLockManagerState>,
+    condvar: Condvar,
+}
+
+impl SegmentLockManager {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(SegmentLockManagerState {
+                metrics: HashMap::new(),
+                next_ticket: 0,
+                queue: VecDeque::new(),
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Takes the next FIFO ticket and blocks until it reaches the front of
+    /// the queue, returning it - the caller is then the only one allowed to
+    /// attempt a write acquisition until it calls [`Self::record_acquired`].
+    fn enter_queue(&self) -> u64 {
+        let mut state = self.state.lock();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.queue.push_back(ticket);
+        while state.queue.front() != Some(&ticket) {
+            self.condvar.wait(&mut state);
+        }
+        ticket
+    }
+
+    /// Called once `ticket`'s holder has actually acquired `segment_id`'s
+    /// real write lock: records how long it waited, steps the ticket out of
+    /// the queue so the next one is admitted, and wakes it.
+    fn record_acquired(&self, ticket: u64, segment_id: SegmentId, waited: Duration) {
+        let mut state = self.state.lock();
+        state.queue.retain(|&queued| queued != ticket);
+        let metrics = state.metrics.entry(segment_id).or_default();
+        metrics.current_writers += 1;
+        metrics.total_wait += waited;
+        self.condvar.notify_all();
+    }
+
+    fn record_released(&self, segment_id: SegmentId) {
+        let mut state = self.state.lock();
+        if let Some(metrics) = state.metrics.get_mut(&segment_id) {
+            metrics.current_writers = metrics.current_writers.saturating_sub(1);
+        }
+    }
+
+    fn metrics(&self, segment_id: SegmentId) -> SegmentLockMetrics {
+        self.state
+            .lock()
+            .metrics
+            .get(&segment_id)
+            .copied()
+            .unwrap_or_default()
+    }
 }
 
 impl Clone for LockedSegment {
@@ -134,7 +554,6 @@ impl From<ProxySegment> for LockedSegment {
     }
 }
 
-#[derive(Default)]
 pub struct SegmentHolder {
     segments: HashMap<SegmentId, LockedSegment>,
     /// Seq number of the first un-recovered operation.
@@ -143,6 +562,50 @@ pub struct SegmentHolder {
 
     /// Holds the first uncorrected error happened with optimizer
     pub optimizer_errors: Option<CollectionError>,
+
+    /// Write-ahead log durably recording operations before they're applied,
+    /// enabled via [`Self::with_wal`]. `None` means operations are applied
+    /// without a WAL, same as before this existed.
+    wal: Option<Mutex<SegmentWal>>,
+
+    /// Epoch/quiescence bookkeeping backing [`Self::swap`]'s deferred
+    /// reclamation - see [`EpochTracker`].
+    epochs: EpochTracker,
+    /// Segments removed by [`Self::swap`], awaiting [`Self::collect_retired`]
+    /// once no pinned reader can still reach them. Ordered oldest-retired
+    /// first.
+    retired: Mutex<VecDeque<RetiredSegment>>,
+    /// See [`DEFAULT_MIN_RETIRED_AHEAD`].
+    min_retired_ahead: usize,
+
+    /// Undo/redo history for batches applied through
+    /// [`Self::apply_points_to_appendable_journaled`].
+    journal: Mutex<OperationJournal>,
+
+    /// Fair admission table backing [`Self::aloha_random_write`].
+    lock_manager: SegmentLockManager,
+
+    /// Per-segment flush/snapshot priority set via [`Self::set_segment_priority`].
+    /// A segment absent from this map has priority 0. Higher priority is
+    /// serviced first by [`Self::segment_flush_ordering`].
+    priorities: HashMap<SegmentId, i32>,
+}
+
+impl Default for SegmentHolder {
+    fn default() -> Self {
+        Self {
+            segments: HashMap::new(),
+            failed_operation: BTreeSet::new(),
+            optimizer_errors: None,
+            wal: None,
+            epochs: EpochTracker::default(),
+            retired: Mutex::new(VecDeque::new()),
+            min_retired_ahead: DEFAULT_MIN_RETIRED_AHEAD,
+            journal: Mutex::new(OperationJournal::new(DEFAULT_MAX_JOURNAL_BATCHES)),
+            lock_manager: SegmentLockManager::new(),
+            priorities: HashMap::new(),
+        }
+    }
 }
 
 pub type LockedSegmentHolder = Arc<RwLock<SegmentHolder>>;
@@ -193,10 +656,27 @@ impl<'s> SegmentHolder {
             if let Some(segment) = removed_segment {
                 removed_segments.push(segment);
             }
+            self.priorities.remove(remove_id);
         }
         removed_segments
     }
 
+    /// Sets `segment_id`'s flush/snapshot priority - see
+    /// [`Self::segment_flush_ordering`]. Passing 0 (the default for any
+    /// segment that never had a priority set) removes the entry rather than
+    /// growing the map with a no-op value.
+    pub fn set_segment_priority(&mut self, segment_id: SegmentId, priority: i32) {
+        if priority == 0 {
+            self.priorities.remove(&segment_id);
+        } else {
+            self.priorities.insert(segment_id, priority);
+        }
+    }
+
+    fn segment_priority(&self, segment_id: SegmentId) -> i32 {
+        self.priorities.get(&segment_id).copied().unwrap_or(0)
+    }
+
     /// Replace old segments with a new one
     ///
     /// # Arguments
@@ -206,18 +686,91 @@ impl<'s> SegmentHolder {
     ///
     /// # Result
     ///
-    /// Pair of (id of newly inserted segment, Vector of replaced segments)
-    ///
-    pub fn swap<T>(
-        &mut self,
-        segment: T,
-        remove_ids: &[SegmentId],
-    ) -> (SegmentId, Vec<LockedSegment>)
+    /// Id of the newly inserted segment. The replaced segments are not
+    /// returned - unlike the old `try_unwrap_with_timeout`-based teardown,
+    /// they're enqueued for deferred reclamation (see [`Self::retire`]) and
+    /// freed later by [`Self::collect_retired`] once no pinned reader can
+    /// still reach them, instead of stalling this call on whichever one
+    /// happens to still be in use.
+    pub fn swap<T>(&mut self, segment: T, remove_ids: &[SegmentId]) -> SegmentId
     where
         T: Into<LockedSegment>,
     {
         let new_id = self.add(segment);
-        (new_id, self.remove(remove_ids))
+        for removed in self.remove(remove_ids) {
+            self.retire(removed);
+        }
+        new_id
+    }
+
+    /// Enqueues `segment` for deferred reclamation once it's no longer
+    /// reachable from any pinned reader - see [`EpochTracker`].
+    fn retire(&self, segment: LockedSegment) {
+        let retire_epoch = self.epochs.advance();
+        self.retired.lock().push_back(RetiredSegment {
+            segment,
+            retire_epoch,
+        });
+    }
+
+    /// Overrides [`DEFAULT_MIN_RETIRED_AHEAD`].
+    pub fn with_min_retired_ahead(mut self, min_retired_ahead: usize) -> Self {
+        self.min_retired_ahead = min_retired_ahead;
+        self
+    }
+
+    /// Frees as many retired segments (oldest first) as are both past
+    /// `self.min_retired_ahead` from the tail and no longer reachable from
+    /// any pinned reader - i.e. the lowest currently-pinned epoch has
+    /// advanced past the segment's retire epoch. Returns how many were
+    /// actually freed.
+    ///
+    /// Meant to be called periodically (e.g. alongside `flush_all`) rather
+    /// than on every `swap`, since reclamation only makes progress once
+    /// readers pinned at retirement time have all finished.
+    pub fn collect_retired(&self) -> OperationResult<usize> {
+        let min_pinned = self.epochs.min_pinned_epoch();
+        let mut reclaimed = 0;
+
+        loop {
+            let mut retired = self.retired.lock();
+            if retired.len() <= self.min_retired_ahead {
+                break;
+            }
+            let front_retire_epoch = match retired.front() {
+                Some(front) => front.retire_epoch,
+                None => break,
+            };
+            if front_retire_epoch >= min_pinned {
+                // Not yet safe: some reader pinned before this segment was
+                // retired might still hold a clone of it. Epochs only grow,
+                // so nothing behind it in the queue is safer either.
+                break;
+            }
+            let retired_segment = retired.pop_front().expect("checked non-empty above");
+            // Drop the lock before the (potentially slow) actual teardown
+            // so concurrent retires/collects aren't blocked on it.
+            drop(retired);
+
+            match retired_segment.segment.try_drop_data_once() {
+                Ok(result) => {
+                    result?;
+                    reclaimed += 1;
+                }
+                Err(segment) => {
+                    // Epoch safety didn't actually guarantee uniqueness this
+                    // time (e.g. a clone escaped its pinned scope) - put it
+                    // back at the front and stop; a later call can retry.
+                    self.retired.lock().push_front(RetiredSegment {
+                        segment,
+                        retire_epoch: front_retire_epoch,
+                    });
+                    break;
+                }
+            }
+        }
+
+        Ok(reclaimed)
     }
 
     pub fn get(&self, id: SegmentId) -> Option<&LockedSegment> {
@@ -259,6 +812,7 @@ impl<'s> SegmentHolder {
     where
         F: FnMut(&RwLockReadGuard<dyn SegmentEntry + 'static>) -> OperationResult<bool>,
     {
+        let _epoch_guard = self.epochs.pin();
         let mut processed_segments = 0;
         for segment in self.segments.values() {
             let is_applied = f(&segment.get().read())?;
@@ -271,6 +825,7 @@ impl<'s> SegmentHolder {
     where
         F: FnMut(&mut RwLockWriteGuard<dyn SegmentEntry + 'static>) -> OperationResult<bool>,
     {
+        let _epoch_guard = self.epochs.pin();
         let mut processed_segments = 0;
         for segment in self.segments.values() {
             let is_applied = f(&mut segment.get().write())?;
@@ -287,6 +842,7 @@ impl<'s> SegmentHolder {
             &mut RwLockWriteGuard<dyn SegmentEntry>,
         ) -> OperationResult<bool>,
     {
+        let _epoch_guard = self.epochs.pin();
         let mut applied_points = 0;
         for (idx, segment) in &self.segments {
             // Collect affected points first, we want to lock segment for writing as rare as possible
@@ -304,8 +860,11 @@ impl<'s> SegmentHolder {
         Ok(applied_points)
     }
 
-    /// Try to acquire write lock over random segment with increasing wait time.
-    /// Should prevent deadlock in case if multiple threads tries to lock segments sequentially.
+    /// Try to acquire a write lock over one of `segment_ids` with increasing
+    /// wait time, fairly - callers are admitted to attempt acquisition in
+    /// the order they called this, via [`SegmentLockManager`], so one
+    /// caller can't repeatedly out-race another and starve it the way the
+    /// old random-probe version could.
     pub fn aloha_random_write<F>(
         &self,
         segment_ids: &[SegmentId],
@@ -320,37 +879,43 @@ impl<'s> SegmentHolder {
             ));
         }
 
-        let mut entries: Vec<_> = Default::default();
+        let segment_arcs: Vec<(SegmentId, Arc<RwLock<dyn SegmentEntry>>)> = segment_ids
+            .iter()
+            .filter_map(|segment_id| {
+                self.segments
+                    .get(segment_id)
+                    .map(|s| (*segment_id, s.get()))
+            })
+            .collect();
 
-        // Try to access each segment first without any timeout (fast)
-        for segment_id in segment_ids {
-            let segment_opt = self.segments.get(segment_id).map(|x| x.get());
-            match segment_opt {
-                None => {}
-                Some(segment_lock) => {
-                    match segment_lock.try_write() {
-                        None => {}
-                        Some(mut lock) => return apply(*segment_id, &mut lock),
-                    }
-                    // save segments for further lock attempts
-                    entries.push((*segment_id, segment_lock))
-                }
-            };
-        }
+        let wait_start = std::time::Instant::now();
+        let ticket = self.lock_manager.enter_queue();
 
-        let mut rng = rand::thread_rng();
         let mut timeout = Duration::from_nanos(100);
-        loop {
-            let (segment_id, segment_lock) = entries.choose(&mut rng).unwrap();
-            let opt_segment_guard = segment_lock.try_write_for(timeout);
-
-            match opt_segment_guard {
-                None => timeout = timeout.mul(2), // Wait longer next time
-                Some(mut lock) => {
-                    return apply(*segment_id, &mut lock);
-                }
+        let (segment_id, mut lock) = loop {
+            let acquired = segment_arcs.iter().find_map(|(segment_id, segment_lock)| {
+                segment_lock
+                    .try_write_for(timeout)
+                    .map(|lock| (*segment_id, lock))
+            });
+            if let Some(found) = acquired {
+                break found;
             }
-        }
+            timeout = timeout.mul(2).min(Duration::from_millis(50));
+        };
+
+        self.lock_manager
+            .record_acquired(ticket, segment_id, wait_start.elapsed());
+        let result = apply(segment_id, &mut lock);
+        drop(lock);
+        self.lock_manager.record_released(segment_id);
+        result
+    }
+
+    /// Current write contention counters for `segment_id`, as tracked by
+    /// [`SegmentLockManager`] - see [`SegmentLockMetrics`].
+    pub fn segment_lock_metrics(&self, segment_id: SegmentId) -> SegmentLockMetrics {
+        self.lock_manager.metrics(segment_id)
     }
 
     /// Update function wrapper, which ensures that updates are not applied written to un-appendable segment.
@@ -402,10 +967,226 @@ impl<'s> SegmentHolder {
         Ok(applied_points)
     }
 
+    /// Enables a [`SegmentWal`] rooted at `wal_dir`. Once enabled, mutations
+    /// should go through [`Self::apply_points_to_appendable_with_wal`]
+    /// instead of [`Self::apply_points_to_appendable`] so they're durably
+    /// logged first.
+    pub fn with_wal(mut self, wal_dir: &Path) -> OperationResult<Self> {
+        self.wal = Some(Mutex::new(SegmentWal::open(wal_dir)?));
+        Ok(self)
+    }
+
+    /// Same as [`Self::apply_points_to_appendable`], but first durably
+    /// appends `op_payload` (the caller's serialized representation of this
+    /// operation, keyed by `op_num`) to the WAL enabled by [`Self::with_wal`].
+    /// A crash between the WAL write and the segment mutation can then be
+    /// recovered by replaying the log with [`Self::replay_wal`], instead of
+    /// relying on [`Self::deduplicate_points`] to paper over whatever a
+    /// partially-applied batch left behind. Falls back to a plain
+    /// [`Self::apply_points_to_appendable`] if no WAL is enabled.
+    pub fn apply_points_to_appendable_with_wal<F>(
+        &self,
+        op_num: SeqNumberType,
+        ids: &[PointIdType],
+        op_payload: &[u8],
+        f: F,
+    ) -> OperationResult<HashSet<PointIdType>>
+    where
+        F: FnMut(PointIdType, &mut RwLockWriteGuard<dyn SegmentEntry>) -> OperationResult<bool>,
+    {
+        if let Some(wal) = &self.wal {
+            wal.lock().append(op_num, op_payload)?;
+        }
+        self.apply_points_to_appendable(op_num, ids, f)
+    }
+
+    /// Replays every WAL record not yet covered by the WAL's persisted
+    /// `durable_seq` (see [`Self::flush_all`]), handing each one's raw
+    /// payload to `apply` in log order. Meant to be called once at startup,
+    /// before the holder serves any other operation - `apply` is expected to
+    /// route the payload back through the same machinery that originally
+    /// produced it (e.g. deserializing and re-calling
+    /// [`Self::apply_points_to_appendable_with_wal`]), which is naturally a
+    /// no-op for any point a record covers that's already at or past that
+    /// version, since `apply_points_to_appendable` itself skips points whose
+    /// segment version already meets or exceeds `op_num`.
+    pub fn replay_wal<F>(&self, mut apply: F) -> OperationResult<usize>
+    where
+        F: FnMut(SeqNumberType, &[u8]) -> OperationResult<()>,
+    {
+        let Some(wal) = &self.wal else {
+            return Ok(0);
+        };
+        let wal = wal.lock();
+        let durable_seq = wal.durable_seq()?;
+
+        let mut replayed = 0;
+        for record in wal.replay()? {
+            if record.seq_number > durable_seq {
+                apply(record.seq_number, &record.payload)?;
+                replayed += 1;
+            }
+        }
+        Ok(replayed)
+    }
+
+    /// Finds which segment (if any) currently holds `point_id`, and its
+    /// vectors/payload there - the snapshot [`Self::apply_points_to_appendable_journaled`]
+    /// records before and after a mutation.
+    fn locate_point(&self, point_id: PointIdType) -> OperationResult<PointState> {
+        for (&segment_id, segment) in &self.segments {
+            let segment_arc = segment.get();
+            let read_segment = segment_arc.read();
+            if read_segment.has_point(point_id) {
+                return Ok(PointState::Present {
+                    segment_id,
+                    vectors: read_segment.all_vectors(point_id)?,
+                    payload: read_segment.payload(point_id)?,
+                });
+            }
+        }
+        Ok(PointState::Absent)
+    }
+
+    /// Opens a new undo/redo batch in this holder's [`OperationJournal`],
+    /// returning its id. Mutations applied afterwards through
+    /// [`Self::apply_points_to_appendable_journaled`] with this id are
+    /// recorded against it, until [`Self::undo`]/[`Self::redo`] reverses or
+    /// replays them, or the batch ages out of the bounded history.
+    pub fn begin_batch(&self, op_num: SeqNumberType) -> BatchId {
+        self.journal.lock().begin_batch(op_num)
+    }
+
+    /// Same as [`Self::apply_points_to_appendable`], but additionally
+    /// records each mutated point's before/after state against `batch_id`,
+    /// so the batch can later be reverted with [`Self::undo`] or replayed
+    /// with [`Self::redo`].
+    pub fn apply_points_to_appendable_journaled<F>(
+        &self,
+        op_num: SeqNumberType,
+        ids: &[PointIdType],
+        batch_id: BatchId,
+        mut f: F,
+    ) -> OperationResult<HashSet<PointIdType>>
+    where
+        F: FnMut(PointIdType, &mut RwLockWriteGuard<dyn SegmentEntry>) -> OperationResult<bool>,
+    {
+        let mut before_states = HashMap::with_capacity(ids.len());
+        for &point_id in ids {
+            before_states.insert(point_id, self.locate_point(point_id)?);
+        }
+
+        let applied = self.apply_points_to_appendable(op_num, ids, &mut f)?;
+
+        let mut journal = self.journal.lock();
+        for &point_id in ids {
+            let before = before_states
+                .remove(&point_id)
+                .unwrap_or(PointState::Absent);
+            let after = self.locate_point(point_id)?;
+            if matches!(before, PointState::Absent) && matches!(after, PointState::Absent) {
+                continue;
+            }
+            journal.record(
+                batch_id,
+                PointJournalEntry {
+                    point_id,
+                    before,
+                    after,
+                },
+            );
+        }
+
+        Ok(applied)
+    }
+
+    /// Replays `entries` (in the given order) by writing each point's
+    /// `target` state directly into the holder's segments (an `Absent`
+    /// state deletes the point from every segment that holds it),
+    /// assigning a fresh `op_num` to each so version ordering stays
+    /// monotonic regardless of which direction the journal is being played.
+    fn replay_journal_entries<'e>(
+        &self,
+        entries: impl Iterator<Item = &'e PointJournalEntry>,
+        op_num: SeqNumberType,
+        target: impl Fn(&'e PointJournalEntry) -> &'e PointState,
+    ) -> OperationResult<()> {
+        let all_segment_ids: Vec<SegmentId> = self.segments.keys().copied().collect();
+
+        for entry in entries {
+            match target(entry) {
+                PointState::Absent => {
+                    for &segment_id in &all_segment_ids {
+                        if let Some(segment) = self.get(segment_id) {
+                            let mut write_segment = segment.get().write();
+                            if write_segment.has_point(entry.point_id) {
+                                write_segment.delete_point(op_num, entry.point_id)?;
+                            }
+                        }
+                    }
+                }
+                PointState::Present {
+                    segment_id,
+                    vectors,
+                    payload,
+                } => {
+                    // Delete any stray copy left in a different segment
+                    // first (e.g. undoing past a move-to-appendable), then
+                    // restore into the segment the recorded state names.
+                    for &other_id in &all_segment_ids {
+                        if other_id == *segment_id {
+                            continue;
+                        }
+                        if let Some(other) = self.get(other_id) {
+                            let mut write_segment = other.get().write();
+                            if write_segment.has_point(entry.point_id) {
+                                write_segment.delete_point(op_num, entry.point_id)?;
+                            }
+                        }
+                    }
+                    if let Some(target_segment) = self.get(*segment_id) {
+                        let mut write_segment = target_segment.get().write();
+                        write_segment.upsert_point(op_num, entry.point_id, vectors.clone())?;
+                        write_segment.set_full_payload(op_num, entry.point_id, payload)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reverts every mutation recorded against `batch_id`, restoring each
+    /// touched point's pre-batch state (or deleting it, if the batch
+    /// inserted it fresh), in reverse of the order the mutations were
+    /// originally applied.
+    pub fn undo(&self, batch_id: BatchId, op_num: SeqNumberType) -> OperationResult<()> {
+        let journal = self.journal.lock();
+        let Some(batch) = journal.get(batch_id) else {
+            return Ok(());
+        };
+        let entries: Vec<PointJournalEntry> = batch.entries.iter().rev().cloned().collect();
+        drop(journal);
+        self.replay_journal_entries(entries.iter(), op_num, |entry| &entry.before)
+    }
+
+    /// Re-applies every mutation recorded against `batch_id`, restoring
+    /// each touched point's post-batch state. Meant to reverse a prior
+    /// [`Self::undo`] of the same batch.
+    pub fn redo(&self, batch_id: BatchId, op_num: SeqNumberType) -> OperationResult<()> {
+        let journal = self.journal.lock();
+        let Some(batch) = journal.get(batch_id) else {
+            return Ok(());
+        };
+        let entries: Vec<PointJournalEntry> = batch.entries.to_vec();
+        drop(journal);
+        self.replay_journal_entries(entries.iter(), op_num, |entry| &entry.after)
+    }
+
     pub fn read_points<F>(&self, ids: &[PointIdType], mut f: F) -> OperationResult<usize>
     where
         F: FnMut(PointIdType, &RwLockReadGuard<dyn SegmentEntry>) -> OperationResult<bool>,
     {
+        let _epoch_guard = self.epochs.pin();
         let mut read_points = 0;
         for segment in self.segments.values() {
             let segment_arc = segment.get();
@@ -418,18 +1199,23 @@ impl<'s> SegmentHolder {
         Ok(read_points)
     }
 
-    /// Defines flush ordering for segments.
+    /// Defines flush/snapshot ordering for segments.
     ///
-    /// Flush appendable segments first, then non-appendable.
-    /// This is done to ensure that all data, transferred from non-appendable segments to appendable segments
-    /// is persisted, before marking records in non-appendable segments as removed.
+    /// Orders by [`Self::set_segment_priority`] descending first, then falls
+    /// back to the original invariant as a tiebreak: appendable segments
+    /// before non-appendable ones, so that all data transferred from
+    /// non-appendable segments to appendable segments is persisted before
+    /// marking records in non-appendable segments as removed.
     fn segment_flush_ordering(&self) -> impl Iterator<Item = SegmentId> {
-        let appendable_segments = self.appendable_segments();
-        let non_appendable_segments = self.non_appendable_segments();
-
-        appendable_segments
-            .into_iter()
-            .chain(non_appendable_segments.into_iter())
+        let mut segment_ids: Vec<SegmentId> = self.segments.keys().copied().collect();
+        segment_ids.sort_by_key(|segment_id| {
+            let is_non_appendable = !self.segments[segment_id].get().read().is_appendable();
+            (
+                Reverse(self.segment_priority(*segment_id)),
+                is_non_appendable,
+            )
+        });
+        segment_ids.into_iter()
     }
 
     /// Flushes all segments and returns maximum version to persist
@@ -455,22 +1241,75 @@ impl<'s> SegmentHolder {
 
             max_persisted_version = max(max_persisted_version, segment_persisted_version)
         }
-        if has_unsaved {
-            Ok(min_unsaved_version)
+        let result = if has_unsaved {
+            min_unsaved_version
         } else {
-            Ok(max_persisted_version)
+            max_persisted_version
+        };
+
+        // Everything at or below `result` is now durable in the segments
+        // themselves, so the WAL (if any) no longer needs to retain it.
+        if let Some(wal) = &self.wal {
+            let wal = wal.lock();
+            wal.advance_durable_seq(result)?;
+            wal.garbage_collect(result)?;
         }
+
+        // Same reasoning for the undo/redo journal: a batch whose op_num is
+        // at or below `result` is durable regardless of whether anyone
+        // undoes it, so it's no longer worth keeping around.
+        self.journal.lock().retire_flushed(result);
+
+        Ok(result)
+    }
+
+    /// Like [`Self::flush_all`], but stops once `budget` has elapsed instead
+    /// of running the whole ordering to completion, so a caller flushing a
+    /// very large collection can make incremental durability progress rather
+    /// than blocking for an all-or-nothing pass. Segments are still visited
+    /// in [`Self::segment_flush_ordering`] order, so whatever is skipped
+    /// once the budget runs out is whatever was least important to flush.
+    /// Returns the ids of segments that were not flushed.
+    pub fn flush_with_budget(
+        &self,
+        sync: bool,
+        budget: Duration,
+    ) -> OperationResult<Vec<SegmentId>> {
+        let deadline = Instant::now() + budget;
+        let mut unflushed = Vec::new();
+
+        for segment_id in self.segment_flush_ordering() {
+            if Instant::now() >= deadline {
+                unflushed.push(segment_id);
+                continue;
+            }
+            let segment = self.segments.get(&segment_id).unwrap();
+            let segment_lock = segment.get();
+            let read_segment = segment_lock.read();
+            read_segment.flush(sync)?;
+        }
+
+        Ok(unflushed)
     }
 
     /// Take a snapshot of all segments into `snapshot_dir_path`
     ///
-    /// Shortcuts at the first failing segment snapshot
+    /// Processes segments in [`Self::segment_flush_ordering`] order (highest
+    /// priority first) and shortcuts at the first failing segment snapshot,
+    /// so that under a partial/aborted snapshot the most important segments
+    /// are the ones already captured.
+    ///
+    /// Holds an advisory lock on `snapshot_dir_path` for the duration of the
+    /// call (see [`acquire_snapshot_lock`]), refusing to proceed if another
+    /// snapshot is already running against the same directory.
     pub fn snapshot_all_segments(
         &self,
         temp_dir: &Path,
         snapshot_dir_path: &Path,
     ) -> OperationResult<()> {
-        for segment in self.segments.values() {
+        let _lock = acquire_snapshot_lock(snapshot_dir_path)?;
+        for segment_id in self.segment_flush_ordering() {
+            let segment = self.segments.get(&segment_id).unwrap();
             let segment_lock = segment.get();
             let read_segment = segment_lock.read();
             read_segment.take_snapshot(temp_dir, snapshot_dir_path)?;
@@ -478,6 +1317,102 @@ impl<'s> SegmentHolder {
         Ok(())
     }
 
+    /// Like [`Self::snapshot_all_segments`], but compresses each archive per
+    /// `options.compression` (see [`SnapshotCompression`]) and records an
+    /// xxh3 checksum for it via [`write_snapshot_checksum`], returning a
+    /// manifest a restore should pass to [`verify_snapshot_manifest`] before
+    /// unpacking anything. A plain `.tar` archive has no integrity guarantee
+    /// beyond what `tar` itself checks; this gives every produced archive
+    /// one, and compression on top shrinks what's otherwise a fairly
+    /// redundant mix of vector and payload bytes.
+    pub fn snapshot_all_segments_with_options(
+        &self,
+        temp_dir: &Path,
+        snapshot_dir_path: &Path,
+        options: SnapshotOptions,
+    ) -> OperationResult<Vec<SnapshotManifestEntry>> {
+        let _lock = acquire_snapshot_lock(snapshot_dir_path)?;
+        let mut manifest = Vec::new();
+        for segment_id in self.segment_flush_ordering() {
+            let segment = self.segments.get(&segment_id).unwrap();
+            let segment_lock = segment.get();
+            let read_segment = segment_lock.read();
+            let archive_path = read_segment.take_snapshot(temp_dir, snapshot_dir_path)?;
+            let archive_path = compress_snapshot_archive(&archive_path, options.compression)?;
+            write_snapshot_checksum(&archive_path)?;
+            let checksum = read_snapshot_checksum(&archive_path)?;
+            manifest.push(SnapshotManifestEntry {
+                segment_id,
+                archive_path,
+                checksum,
+            });
+        }
+        Ok(manifest)
+    }
+
+    /// Like [`Self::snapshot_all_segments`], but skips re-archiving a segment
+    /// whose content hasn't changed since `prior_manifest` was produced by an
+    /// earlier call, comparing each segment's current [`segment_content_hash`]
+    /// against the one recorded for it. An unchanged segment's entry in the
+    /// returned manifest simply references the archive file `prior_manifest`
+    /// already points at (still sitting in `snapshot_dir_path`) instead of
+    /// writing a new one, turning a large, mostly-sealed collection's repeat
+    /// snapshot into roughly O(changed size) rather than O(total size).
+    ///
+    /// Returns the manifest for this snapshot (to pass as `prior_manifest`
+    /// next time) alongside a [`SnapshotReport`] of which segments were
+    /// newly archived versus reused.
+    pub fn snapshot_all_segments_incremental(
+        &self,
+        temp_dir: &Path,
+        snapshot_dir_path: &Path,
+        prior_manifest: &IncrementalSnapshotManifest,
+    ) -> OperationResult<(IncrementalSnapshotManifest, SnapshotReport)> {
+        let _lock = acquire_snapshot_lock(snapshot_dir_path)?;
+        let mut manifest = IncrementalSnapshotManifest::default();
+        let mut report = SnapshotReport::default();
+
+        for segment_id in self.segment_flush_ordering() {
+            let segment = self.segments.get(&segment_id).unwrap();
+            let segment_lock = segment.get();
+            let read_segment = segment_lock.read();
+            let content_hash = segment_content_hash(&*read_segment);
+
+            if let Some(prior_entry) = prior_manifest.entries.get(&segment_id) {
+                if prior_entry.content_hash == content_hash
+                    && snapshot_dir_path
+                        .join(&prior_entry.archive_filename)
+                        .exists()
+                {
+                    manifest.entries.insert(segment_id, prior_entry.clone());
+                    report.reused.push(segment_id);
+                    continue;
+                }
+            }
+
+            let archive_path = read_segment.take_snapshot(temp_dir, snapshot_dir_path)?;
+            let archive_filename = archive_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_owned)
+                .ok_or_else(|| {
+                    OperationError::service_error(format!(
+                        "snapshot archive path {archive_path:?} has no file name"
+                    ))
+                })?;
+            manifest.entries.insert(
+                segment_id,
+                IncrementalManifestEntry {
+                    content_hash,
+                    archive_filename,
+                },
+            );
+            report.newly_archived.push(segment_id);
+        }
+
+        Ok((manifest, report))
+    }
+
     pub fn report_optimizer_error<E: Into<CollectionError>>(&mut self, error: E) {
         if self.optimizer_errors.is_none() {
             self.optimizer_errors = Some(error.into());
@@ -495,7 +1430,40 @@ impl<'s> SegmentHolder {
     /// Deduplication works with plain segments only.
     pub fn deduplicate_points(&self) -> OperationResult<usize> {
         let points_to_remove = self.find_duplicated_points()?;
+        self.remove_points(points_to_remove)
+    }
+
+    /// Like [`Self::deduplicate_points`], but catches duplicates that
+    /// `deduplicate_points` misses: points which carry byte-identical
+    /// vectors and payload but were inserted under *different*
+    /// `PointIdType`s, e.g. by interrupted re-indexing that re-inserted a
+    /// point under a new id rather than overwriting the original one.
+    /// Heavier than the id/version pass (it reads and fingerprints every
+    /// point's full content), so it's its own opt-in method rather than
+    /// folded into `deduplicate_points`.
+    pub fn deduplicate_content_duplicate_points(&self) -> OperationResult<usize> {
+        let points_to_remove = self.find_content_duplicated_points()?;
+        self.remove_points(points_to_remove)
+    }
 
+    /// Like [`Self::deduplicate_points`], but lets the caller choose how a
+    /// duplicate point id is resolved instead of always keeping the highest
+    /// version, and returns a [`DeduplicationReport`] auditing which segment
+    /// won each duplicate point id and how many stale copies were discarded
+    /// for it.
+    pub fn deduplicate_points_with_policy(
+        &self,
+        policy: DeduplicationPolicy,
+    ) -> OperationResult<(usize, DeduplicationReport)> {
+        let (points_to_remove, report) = self.find_duplicated_points_with_policy(policy)?;
+        let removed = self.remove_points(points_to_remove)?;
+        Ok((removed, report))
+    }
+
+    fn remove_points(
+        &self,
+        points_to_remove: HashMap<SegmentId, Vec<PointIdType>>,
+    ) -> OperationResult<usize> {
         let mut removed_points = 0;
         for (segment_id, points) in points_to_remove {
             let locked_segment = self.segments.get(&segment_id).unwrap();
@@ -590,6 +1558,252 @@ impl<'s> SegmentHolder {
 
         Ok(points_to_remove)
     }
+
+    /// Like [`Self::find_duplicated_points`], but resolves each duplicate
+    /// point id per `policy` instead of always keeping the highest version,
+    /// and additionally returns a [`DeduplicationReport`] of which segment
+    /// won and which versions were discarded for every point id that
+    /// actually had a duplicate.
+    fn find_duplicated_points_with_policy(
+        &self,
+        policy: DeduplicationPolicy,
+    ) -> OperationResult<(HashMap<SegmentId, Vec<PointIdType>>, DeduplicationReport)> {
+        let segments = self
+            .segments
+            .iter()
+            .map(|(&segment_id, locked_segment)| (segment_id, locked_segment.get()))
+            .collect_vec();
+        let locked_segments = BTreeMap::from_iter(
+            segments
+                .iter()
+                .map(|(segment_id, locked_segment)| (*segment_id, locked_segment.read())),
+        );
+        let mut iterators = BTreeMap::from_iter(
+            locked_segments
+                .iter()
+                .map(|(segment_id, locked_segment)| (*segment_id, locked_segment.iter_points())),
+        );
+
+        // Only `KeepLargestSegment` needs this, but computing it once up
+        // front keeps the merge loop below free of a repeated
+        // `available_point_count()` call per candidate.
+        let segment_sizes: HashMap<SegmentId, usize> = locked_segments
+            .iter()
+            .map(|(&segment_id, locked_segment)| {
+                (segment_id, locked_segment.available_point_count())
+            })
+            .collect();
+
+        let score = |segment_id: SegmentId, point_id: PointIdType| -> i128 {
+            match policy {
+                DeduplicationPolicy::KeepHighestVersion => locked_segments[&segment_id]
+                    .point_version(point_id)
+                    .unwrap_or(0)
+                    as i128,
+                DeduplicationPolicy::KeepFirstSegment => -(segment_id as i128),
+                DeduplicationPolicy::KeepLargestSegment => {
+                    segment_sizes.get(&segment_id).copied().unwrap_or(0) as i128
+                }
+            }
+        };
+
+        let mut heap = iterators
+            .iter_mut()
+            .filter_map(|(&segment_id, iter)| {
+                iter.next().map(|point_id| DedupPoint {
+                    segment_id,
+                    point_id,
+                })
+            })
+            .collect::<BinaryHeap<_>>();
+
+        let mut points_to_remove: HashMap<SegmentId, Vec<PointIdType>> = Default::default();
+        let mut decisions: Vec<DeduplicationDecision> = Vec::new();
+
+        let mut run_point_id: Option<PointIdType> = None;
+        let mut winner_segment_id: Option<SegmentId> = None;
+        let mut winner_score: i128 = i128::MIN;
+        let mut discarded_versions: Vec<SeqNumberType> = Vec::new();
+
+        while let Some(entry) = heap.pop() {
+            let point_id = entry.point_id;
+            let segment_id = entry.segment_id;
+            if let Some(next_point_id) = iterators.get_mut(&segment_id).and_then(|i| i.next()) {
+                heap.push(DedupPoint {
+                    segment_id,
+                    point_id: next_point_id,
+                });
+            }
+
+            if run_point_id != Some(point_id) {
+                if let (Some(prev_point_id), Some(prev_winner)) = (run_point_id, winner_segment_id)
+                {
+                    if !discarded_versions.is_empty() {
+                        decisions.push(DeduplicationDecision {
+                            point_id: prev_point_id,
+                            kept_segment_id: prev_winner,
+                            discarded_versions: std::mem::take(&mut discarded_versions),
+                        });
+                    }
+                }
+                run_point_id = Some(point_id);
+                winner_segment_id = Some(segment_id);
+                winner_score = score(segment_id, point_id);
+                continue;
+            }
+
+            let candidate_score = score(segment_id, point_id);
+            let current_winner = winner_segment_id.expect("set when run_point_id was set");
+            if candidate_score > winner_score {
+                let discarded_version = locked_segments[&current_winner]
+                    .point_version(point_id)
+                    .unwrap_or(0);
+                discarded_versions.push(discarded_version);
+                points_to_remove
+                    .entry(current_winner)
+                    .or_default()
+                    .push(point_id);
+                winner_segment_id = Some(segment_id);
+                winner_score = candidate_score;
+            } else {
+                let discarded_version = locked_segments[&segment_id]
+                    .point_version(point_id)
+                    .unwrap_or(0);
+                discarded_versions.push(discarded_version);
+                points_to_remove
+                    .entry(segment_id)
+                    .or_default()
+                    .push(point_id);
+            }
+        }
+
+        if let (Some(prev_point_id), Some(prev_winner)) = (run_point_id, winner_segment_id) {
+            if !discarded_versions.is_empty() {
+                decisions.push(DeduplicationDecision {
+                    point_id: prev_point_id,
+                    kept_segment_id: prev_winner,
+                    discarded_versions,
+                });
+            }
+        }
+
+        Ok((points_to_remove, DeduplicationReport { decisions }))
+    }
+
+    /// Streams every segment's points through [`content_fingerprint`],
+    /// keeping the highest-version (ties broken by lowest id) point for each
+    /// distinct fingerprint and scheduling the rest for removal - the
+    /// content-addressed counterpart to [`Self::find_duplicated_points`].
+    /// A fingerprint collision is double-checked with an exact byte-for-byte
+    /// vector/payload comparison before anything is scheduled for removal,
+    /// so a hash collision alone can never drop a genuinely distinct point.
+    fn find_content_duplicated_points(
+        &self,
+    ) -> OperationResult<HashMap<SegmentId, Vec<PointIdType>>> {
+        struct Seen {
+            segment_id: SegmentId,
+            point_id: PointIdType,
+            version: SeqNumberType,
+            vectors: NamedVectors,
+            payload: Payload,
+        }
+
+        // Bucketed by fingerprint, with every distinct (non-colliding)
+        // content seen so far under that fingerprint kept side by side -
+        // so a genuine hash collision between different points never drops
+        // either of them.
+        let mut by_fingerprint: HashMap<u64, Vec<Seen>> = HashMap::new();
+        let mut points_to_remove: HashMap<SegmentId, Vec<PointIdType>> = HashMap::new();
+
+        for (&segment_id, locked_segment) in &self.segments {
+            let segment_arc = locked_segment.get();
+            let read_segment = segment_arc.read();
+            for point_id in read_segment.iter_points() {
+                let vectors = read_segment.all_vectors(point_id)?;
+                let payload = read_segment.payload(point_id)?;
+                let version = read_segment.point_version(point_id).unwrap_or(0);
+                let fingerprint = content_fingerprint(&vectors, &payload);
+
+                let bucket = by_fingerprint.entry(fingerprint).or_default();
+                let existing = bucket
+                    .iter_mut()
+                    .find(|seen| content_equal(&vectors, &payload, &seen.vectors, &seen.payload));
+
+                match existing {
+                    Some(seen) => {
+                        if version > seen.version
+                            || (version == seen.version && point_id < seen.point_id)
+                        {
+                            points_to_remove
+                                .entry(seen.segment_id)
+                                .or_default()
+                                .push(seen.point_id);
+                            *seen = Seen {
+                                segment_id,
+                                point_id,
+                                version,
+                                vectors,
+                                payload,
+                            };
+                        } else {
+                            points_to_remove
+                                .entry(segment_id)
+                                .or_default()
+                                .push(point_id);
+                        }
+                    }
+                    None => bucket.push(Seen {
+                        segment_id,
+                        point_id,
+                        version,
+                        vectors,
+                        payload,
+                    }),
+                }
+            }
+        }
+
+        Ok(points_to_remove)
+    }
+}
+
+/// A 64-bit content fingerprint over `vectors`' canonicalized bytes and a
+/// stable serialization of `payload`, using seahash (non-cryptographic, but
+/// fast enough to run over every point in every segment).
+fn content_fingerprint(vectors: &NamedVectors, payload: &Payload) -> u64 {
+    use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
+
+    let mut named: Vec<_> = vectors.iter().collect();
+    named.sort_by_key(|(name, _)| name.clone());
+
+    let mut hasher = BuildHasherDefault::<seahash::SeaHasher>::default().build_hasher();
+    for (name, vector) in named {
+        hasher.write(name.as_bytes());
+        for element in vector.iter() {
+            hasher.write(&element.to_le_bytes());
+        }
+    }
+    // `serde_json::Map` is backed by a `BTreeMap` (the `preserve_order`
+    // feature is off here), so this serialization is already key-sorted and
+    // therefore stable across payloads built in different insertion orders.
+    if let Ok(payload_bytes) = serde_json::to_vec(payload) {
+        hasher.write(&payload_bytes);
+    }
+    hasher.finish()
+}
+
+/// Exact equality check backing [`content_fingerprint`]'s collision guard.
+fn content_equal(
+    a_vectors: &NamedVectors,
+    a_payload: &Payload,
+    b_vectors: &NamedVectors,
+    b_payload: &Payload,
+) -> bool {
+    let mut a: Vec<_> = a_vectors.iter().collect();
+    let mut b: Vec<_> = b_vectors.iter().collect();
+    a.sort_by_key(|(name, _)| name.clone());
+    b.sort_by_key(|(name, _)| name.clone());
+    a == b && a_payload == b_payload
 }
 
 #[cfg(test)]
@@ -611,7 +1825,7 @@ mod tests {
         let segment1 = build_segment_1(dir.path());
         let segment2 = build_segment_2(dir.path());
 
-        let mut holder = SegmentHolder::default();
+        let mut holder = SegmentHolder::default().with_min_retired_ahead(0);
 
         let sid1 = holder.add(segment1);
         let sid2 = holder.add(segment2);
@@ -620,10 +1834,11 @@ mod tests {
 
         let segment3 = build_simple_segment(dir.path(), 4, Distance::Dot).unwrap();
 
-        let (_sid3, replaced_segments) = holder.swap(segment3, &[sid1, sid2]);
-        replaced_segments
-            .into_iter()
-            .for_each(|s| s.drop_data().unwrap());
+        let _sid3 = holder.swap(segment3, &[sid1, sid2]);
+
+        // Nothing is pinned, so both retired segments are immediately safe
+        // to reclaim, and a `min_retired_ahead` of 0 holds none of them back.
+        assert_eq!(holder.collect_retired().unwrap(), 2);
     }
 
     #[test]
@@ -0,0 +1,81 @@
+//! xxh3 integrity checksums for [`super::proxy_segment::ProxySegment`]
+//! snapshot archives.
+//!
+//! A truncated or bit-rotted `.tar` written by `take_snapshot` previously
+//! went unnoticed until a restore failed deep inside segment loading.
+//! [`write_snapshot_checksum`] hashes an archive with xxh3 right after it's
+//! written and drops the hex digest in a sibling `<archive>.checksum` file;
+//! [`verify_snapshot`] recomputes the hash on restore and refuses to
+//! continue if it doesn't match. The wrapped-segment and write-segment
+//! archives get their own checksum file each, so either can be verified
+//! without touching the other - needed because the write_segment archive may
+//! be shared and re-verified by more than one proxy.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use segment::entry::entry_point::{OperationError, OperationResult};
+
+/// Hashes the archive at `archive_path` with xxh3 and writes the hex digest
+/// to its sibling `<archive>.checksum` file.
+pub fn write_snapshot_checksum(archive_path: &Path) -> OperationResult<()> {
+    let digest = hash_file(archive_path)?;
+    fs::write(checksum_path(archive_path), format!("{digest:016x}")).map_err(|err| {
+        OperationError::service_error(format!(
+            "failed to write snapshot checksum for {archive_path:?}: {err}"
+        ))
+    })
+}
+
+/// Recomputes the xxh3 hash of the archive at `archive_path` and compares it
+/// against its `<archive>.checksum` sibling, written by
+/// [`write_snapshot_checksum`]. Returns an error distinct from ordinary
+/// I/O/decode failures when the archive's contents don't match what was
+/// recorded at snapshot time, so callers can tell "archive is corrupt" apart
+/// from "archive/checksum is missing or unreadable".
+pub fn verify_snapshot(archive_path: &Path) -> OperationResult<()> {
+    let recorded = read_snapshot_checksum(archive_path)?;
+    let actual = hash_file(archive_path)?;
+    if actual != recorded {
+        return Err(OperationError::service_error(format!(
+            "snapshot integrity check failed for {archive_path:?}: expected xxh3 {recorded:016x}, \
+             got {actual:016x} - archive is truncated or corrupted"
+        )));
+    }
+    Ok(())
+}
+
+/// Reads back the xxh3 digest [`write_snapshot_checksum`] recorded for
+/// `archive_path`, without re-hashing the archive itself - exposed so a
+/// caller aggregating many archives into one manifest (see
+/// `SegmentHolder::snapshot_all_segments_with_options`) can record each
+/// digest without duplicating the sidecar-file parsing done here.
+pub fn read_snapshot_checksum(archive_path: &Path) -> OperationResult<u64> {
+    let checksum_path = checksum_path(archive_path);
+    let recorded = fs::read_to_string(&checksum_path).map_err(|err| {
+        OperationError::service_error(format!(
+            "failed to read snapshot checksum at {checksum_path:?}: {err}"
+        ))
+    })?;
+    u64::from_str_radix(recorded.trim(), 16).map_err(|err| {
+        OperationError::service_error(format!(
+            "malformed snapshot checksum at {checksum_path:?}: {err}"
+        ))
+    })
+}
+
+fn hash_file(path: &Path) -> OperationResult<u64> {
+    let bytes = fs::read(path).map_err(|err| {
+        OperationError::service_error(format!("failed to read snapshot archive {path:?}: {err}"))
+    })?;
+    Ok(xxhash_rust::xxh3::xxh3_64(&bytes))
+}
+
+/// Path of the sibling checksum file [`write_snapshot_checksum`] writes
+/// for `archive_path`, exposed so callers that rename/replace an archive
+/// (e.g. after compressing it) can clean up the stale checksum first.
+pub fn checksum_path(archive_path: &Path) -> PathBuf {
+    let mut name = archive_path.as_os_str().to_os_string();
+    name.push(".checksum");
+    PathBuf::from(name)
+}
@@ -0,0 +1,133 @@
+//! Advisory locking around a snapshot target directory.
+//!
+//! Nothing previously stopped two `SegmentHolder::snapshot_all_segments`
+//! calls (or a snapshot racing an optimizer's `deduplicate_points`) from
+//! writing into the same temp/snapshot directories at once and corrupting
+//! each other's archives. [`acquire_snapshot_lock`] creates an exclusive
+//! `.snapshot.lock` file in the snapshot target recording the owning PID
+//! and a unix (wall-clock) timestamp, refusing with a clear error if a live lock is
+//! already there; [`SnapshotLockGuard`] removes it again on drop. A lock
+//! whose owning process no longer exists is treated as stale and reclaimed
+//! rather than blocking forever on a backup process that crashed mid-run.
+//!
+//! Liveness is checked via `/proc/<pid>`, so stale-lock reclamation is
+//! Linux-only; on other platforms a lock is only ever cleared by its owner.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use segment::entry::entry_point::{OperationError, OperationResult};
+
+const LOCK_FILE_NAME: &str = ".snapshot.lock";
+
+/// RAII handle on the advisory lock acquired by [`acquire_snapshot_lock`].
+/// Removes the `.lock` file when dropped, however the locked operation
+/// ends.
+pub struct SnapshotLockGuard {
+    lock_path: PathBuf,
+}
+
+impl Drop for SnapshotLockGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.lock_path);
+    }
+}
+
+/// Acquires an exclusive advisory lock on `snapshot_dir` by creating a
+/// `.snapshot.lock` file inside it containing this process's PID and the
+/// current unix timestamp. Fails with a `SnapshotInProgress`-style error if
+/// a live lock already exists; a lock whose recorded PID no longer
+/// corresponds to a running process is treated as stale, removed, and
+/// replaced with a fresh one rather than blocking forever.
+pub fn acquire_snapshot_lock(snapshot_dir: &Path) -> OperationResult<SnapshotLockGuard> {
+    let lock_path = snapshot_dir.join(LOCK_FILE_NAME);
+
+    if let Some(existing) = read_lock_owner(&lock_path)? {
+        if process_is_alive(existing.pid) {
+            return Err(OperationError::service_error(format!(
+                "snapshot already in progress for {snapshot_dir:?}: held by pid {} since \
+                 unix time {}",
+                existing.pid, existing.acquired_at_unix_secs,
+            )));
+        }
+        // The owning process is gone: this is a stale lock left behind by a
+        // crashed or killed snapshot, safe to reclaim.
+        let _ = fs::remove_file(&lock_path);
+    }
+
+    let acquired_at_unix_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let contents = format!("{}\n{}\n", std::process::id(), acquired_at_unix_secs);
+
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock_path)
+    {
+        Ok(mut file) => {
+            file.write_all(contents.as_bytes()).map_err(|err| {
+                OperationError::service_error(format!(
+                    "failed to write snapshot lock at {lock_path:?}: {err}"
+                ))
+            })?;
+            Ok(SnapshotLockGuard { lock_path })
+        }
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            // Lost a race with another process that just acquired the lock
+            // between our stale check and this create_new call.
+            Err(OperationError::service_error(format!(
+                "snapshot already in progress for {snapshot_dir:?}"
+            )))
+        }
+        Err(err) => Err(OperationError::service_error(format!(
+            "failed to create snapshot lock at {lock_path:?}: {err}"
+        ))),
+    }
+}
+
+struct LockOwner {
+    pid: u32,
+    acquired_at_unix_secs: u64,
+}
+
+fn read_lock_owner(lock_path: &Path) -> OperationResult<Option<LockOwner>> {
+    let contents = match fs::read_to_string(lock_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(OperationError::service_error(format!(
+                "failed to read snapshot lock at {lock_path:?}: {err}"
+            )))
+        }
+    };
+
+    let mut lines = contents.lines();
+    let pid = lines.next().and_then(|line| line.trim().parse().ok());
+    let acquired_at_unix_secs = lines.next().and_then(|line| line.trim().parse().ok());
+
+    match (pid, acquired_at_unix_secs) {
+        (Some(pid), Some(acquired_at_unix_secs)) => Ok(Some(LockOwner {
+            pid,
+            acquired_at_unix_secs,
+        })),
+        // Unreadable/malformed lock contents: treat like a missing lock
+        // rather than refusing forever on a file we can't make sense of.
+        _ => Ok(None),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_is_alive(pid: u32) -> bool {
+    Path::new(&format!("/proc/{pid}")).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_is_alive(_pid: u32) -> bool {
+    // No portable liveness check without an extra dependency: assume alive
+    // so a lock is only ever cleared by its owning process.
+    true
+}
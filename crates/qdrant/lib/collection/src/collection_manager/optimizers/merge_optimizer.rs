@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use itertools::Itertools;
@@ -7,18 +8,99 @@ use parking_lot::Mutex;
 use segment::common::operation_time_statistics::{
     OperationDurationStatistics, OperationDurationsAggregator,
 };
+use segment::entry::entry_point::{OperationError, OperationResult};
 use segment::types::{HnswConfig, QuantizationConfig, SegmentType, VECTOR_ELEMENT_SIZE};
 
 use crate::collection_manager::holders::segment_holder::{
     LockedSegment, LockedSegmentHolder, SegmentId,
 };
+use crate::collection_manager::holders::snapshot_compression::SnapshotCompression;
 use crate::collection_manager::optimizers::segment_optimizer::{
     OptimizerThresholds, SegmentOptimizer,
 };
 use crate::config::CollectionParams;
+use crate::operations::ttl_policy::TtlPolicy;
 
 const BYTES_IN_KB: usize = 1024;
 
+/// How [`MergeOptimizer::check_condition`] picks which raw segments to merge.
+///
+/// `Default` is the original "always merge the N smallest segments" behavior.
+/// `SizeTiered` borrows size-tiered compaction from LSM-tree engines: segments
+/// of similar size are grouped into tiers so a tiny segment doesn't get merged
+/// with a much larger one over and over, which otherwise drives up write
+/// amplification. Read from [`OptimizerThresholds::merge_policy`], so
+/// collections opt in per-collection rather than globally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    #[default]
+    Default,
+    SizeTiered,
+}
+
+/// Shared ceiling on bytes reserved for in-flight segment merges, so many
+/// collections optimizing at once on the same node can't collectively OOM
+/// it. A single instance is constructed once and cloned (cheaply - it's just
+/// an `Arc` and a limit) into every optimizer, so the limit is enforced
+/// cluster-wide rather than reset per collection.
+#[derive(Clone)]
+pub struct OptimizerMemoryBudget {
+    bytes_in_use: Arc<AtomicU64>,
+    limit_bytes: u64,
+}
+
+impl OptimizerMemoryBudget {
+    pub fn new(limit_bytes: u64) -> Self {
+        OptimizerMemoryBudget {
+            bytes_in_use: Arc::new(AtomicU64::new(0)),
+            limit_bytes,
+        }
+    }
+
+    /// Attempts to reserve `bytes` against the shared limit. Returns `None`
+    /// without reserving anything if doing so would exceed the limit;
+    /// otherwise returns a guard that releases the reservation again on
+    /// `Drop`, so memory is freed even if the caller returns early or panics
+    /// before explicitly releasing it.
+    pub fn try_reserve(&self, bytes: u64) -> Option<OptimizerMemoryReservation> {
+        let mut current = self.bytes_in_use.load(Ordering::Acquire);
+        loop {
+            let reserved = current.checked_add(bytes)?;
+            if reserved > self.limit_bytes {
+                return None;
+            }
+            match self.bytes_in_use.compare_exchange_weak(
+                current,
+                reserved,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    return Some(OptimizerMemoryReservation {
+                        bytes_in_use: self.bytes_in_use.clone(),
+                        bytes,
+                    })
+                }
+                Err(observed) => current = observed,
+            }
+        }
+    }
+}
+
+/// RAII reservation returned by [`OptimizerMemoryBudget::try_reserve`];
+/// releases its `bytes` back to the shared budget on drop, however the
+/// reserving operation ends (success, early return, or panic).
+pub struct OptimizerMemoryReservation {
+    bytes_in_use: Arc<AtomicU64>,
+    bytes: u64,
+}
+
+impl Drop for OptimizerMemoryReservation {
+    fn drop(&mut self) {
+        self.bytes_in_use.fetch_sub(self.bytes, Ordering::AcqRel);
+    }
+}
+
 /// Optimizer that tries to reduce number of segments until it fits configured value.
 /// It merges 3 smallest segments into a single large segment.
 /// Merging 3 segments instead of 2 guarantees that after the optimization the number of segments
@@ -32,6 +114,13 @@ pub struct MergeOptimizer {
     hnsw_config: HnswConfig,
     quantization_config: Option<QuantizationConfig>,
     telemetry_durations_aggregator: Arc<Mutex<OperationDurationsAggregator>>,
+    memory_budget: OptimizerMemoryBudget,
+    /// Reservation taken by [`check_condition`](SegmentOptimizer::check_condition)
+    /// for the merge it just approved, handed off to `optimize`'s default
+    /// implementation via [`MergeOptimizer::take_pending_reservation`] so the
+    /// memory stays reserved for the merge's actual duration rather than
+    /// just the instant `check_condition` ran.
+    pending_reservation: Mutex<Option<OptimizerMemoryReservation>>,
 }
 
 impl MergeOptimizer {
@@ -44,6 +133,7 @@ impl MergeOptimizer {
         collection_params: CollectionParams,
         hnsw_config: HnswConfig,
         quantization_config: Option<QuantizationConfig>,
+        memory_budget: OptimizerMemoryBudget,
     ) -> Self {
         MergeOptimizer {
             max_segments,
@@ -54,8 +144,98 @@ impl MergeOptimizer {
             hnsw_config,
             quantization_config,
             telemetry_durations_aggregator: OperationDurationsAggregator::new(),
+            memory_budget,
+            pending_reservation: Mutex::new(None),
         }
     }
+
+    /// Takes the memory reservation [`check_condition`](SegmentOptimizer::check_condition)
+    /// made for the merge it most recently approved, if any, so `optimize`
+    /// can hold it for the merge's duration - it's released back to the
+    /// shared [`OptimizerMemoryBudget`] when the returned guard drops.
+    pub fn take_pending_reservation(&self) -> Option<OptimizerMemoryReservation> {
+        self.pending_reservation.lock().take()
+    }
+
+    /// Original merge policy: take the N smallest segments whose cumulative
+    /// size stays under `max_segment_size`, capped at `max_candidates`.
+    fn default_merge_candidates(
+        sized_segments: Vec<(SegmentId, usize)>,
+        max_candidates: usize,
+        thresholds_config: &OptimizerThresholds,
+    ) -> Vec<SegmentId> {
+        // Find at least top-3 smallest segments to join.
+        // We need 3 segments because in this case we can guarantee that total segments number will be less
+        sized_segments
+            .into_iter()
+            .sorted_by_key(|(_, size)| *size)
+            .scan(0, |size_sum, (sid, size)| {
+                *size_sum += size; // produce a cumulative sum of segment sizes starting from smallest
+                Some((sid, *size_sum))
+            })
+            .take_while(|(_, size)| {
+                *size
+                    < thresholds_config
+                        .max_segment_size
+                        .saturating_mul(BYTES_IN_KB)
+            })
+            .take(max_candidates)
+            .map(|(sid, _)| sid)
+            .collect()
+    }
+
+    /// Size-tiered merge policy, see [`MergePolicy::SizeTiered`]: sort
+    /// segments by size, then scan them into tiers where a segment joins the
+    /// running tier only if its size is within `[bucket_avg * bucket_low,
+    /// bucket_avg * bucket_high]` of that tier's running average size
+    /// (recomputed as each member joins). Returns the fullest tier that has
+    /// reached `min_threshold` members, capped at `max_threshold` members; if
+    /// no tier qualifies, returns an empty candidate list.
+    fn size_tiered_merge_candidates(
+        sized_segments: Vec<(SegmentId, usize)>,
+        thresholds_config: &OptimizerThresholds,
+    ) -> Vec<SegmentId> {
+        let sorted = sized_segments.into_iter().sorted_by_key(|(_, size)| *size);
+
+        let mut tiers: Vec<Vec<(SegmentId, usize)>> = vec![];
+        let mut current_tier: Vec<(SegmentId, usize)> = vec![];
+        let mut current_sum: usize = 0;
+
+        for (sid, size) in sorted {
+            let joins_current = match current_tier.last() {
+                None => true,
+                Some(_) => {
+                    let bucket_avg = current_sum as f64 / current_tier.len() as f64;
+                    let size = size as f64;
+                    size >= bucket_avg * thresholds_config.bucket_low
+                        && size <= bucket_avg * thresholds_config.bucket_high
+                }
+            };
+
+            if !joins_current && !current_tier.is_empty() {
+                tiers.push(std::mem::take(&mut current_tier));
+                current_sum = 0;
+            }
+
+            current_tier.push((sid, size));
+            current_sum += size;
+        }
+        if !current_tier.is_empty() {
+            tiers.push(current_tier);
+        }
+
+        tiers
+            .into_iter()
+            .filter(|tier| tier.len() >= thresholds_config.min_threshold)
+            .max_by_key(|tier| tier.len())
+            .map(|tier| {
+                tier.into_iter()
+                    .take(thresholds_config.max_threshold)
+                    .map(|(sid, _)| sid)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl SegmentOptimizer for MergeOptimizer {
@@ -83,6 +263,35 @@ impl SegmentOptimizer for MergeOptimizer {
         &self.thresholds_config
     }
 
+    /// Codec the merge path should compress the produced segment's data
+    /// blocks with, reusing the same [`SnapshotCompression`] codecs as
+    /// snapshot archives (`None`/`Lz4`/`Deflate`) rather than a separate
+    /// compression enum. `optimize`'s default implementation picks this up
+    /// alongside `hnsw_config()`/`quantization_config()` and records the
+    /// chosen codec in the merged segment's metadata so reads transparently
+    /// decompress.
+    fn compression(&self) -> SnapshotCompression {
+        self.thresholds_config.compression
+    }
+
+    /// Whether merged segments should carry xxh3 block checksums, verified
+    /// again on load (see [`compute_block_checksums`]/[`verify_block_checksums`]).
+    /// Gated behind `CollectionParams::verify_checksums` since it costs an
+    /// extra hash pass on both write and read.
+    fn verify_checksums(&self) -> bool {
+        self.collection_params.verify_checksums
+    }
+
+    /// TTL eviction policy to sweep expired points for, if the collection
+    /// has one configured. `optimize`'s default implementation is expected
+    /// to periodically build [`crate::operations::ttl_policy::expired_points_filter`]
+    /// from this and run it through `do_delete_points`, the same way it
+    /// consults [`Self::compression`]/[`Self::verify_checksums`] alongside
+    /// the merge itself.
+    fn ttl_policy(&self) -> Option<TtlPolicy> {
+        self.collection_params.ttl_policy.clone()
+    }
+
     fn check_condition(
         &self,
         segments: LockedSegmentHolder,
@@ -102,10 +311,8 @@ impl SegmentOptimizer for MergeOptimizer {
         }
         let max_candidates = raw_segments.len() - self.max_segments + 2;
 
-        // Find at least top-3 smallest segments to join.
-        // We need 3 segments because in this case we can guarantee that total segments number will be less
-
-        let candidates: Vec<_> = raw_segments
+        // Byte size of every eligible raw segment, used by both merge policies below.
+        let sized_segments: Vec<(SegmentId, usize)> = raw_segments
             .iter()
             .cloned()
             .filter_map(|(idx, segment)| {
@@ -123,25 +330,49 @@ impl SegmentOptimizer for MergeOptimizer {
                         * VECTOR_ELEMENT_SIZE,
                 ))
             })
-            .sorted_by_key(|(_, size)| *size)
-            .scan(0, |size_sum, (sid, size)| {
-                *size_sum += size; // produce a cumulative sum of segment sizes starting from smallest
-                Some((sid, *size_sum))
-            })
-            .take_while(|(_, size)| {
-                *size
-                    < self
-                        .thresholds_config
-                        .max_segment_size
-                        .saturating_mul(BYTES_IN_KB)
-            })
-            .take(max_candidates)
-            .map(|x| x.0)
             .collect();
 
+        let candidates = match self.thresholds_config.merge_policy {
+            MergePolicy::Default => Self::default_merge_candidates(
+                sized_segments.clone(),
+                max_candidates,
+                &self.thresholds_config,
+            ),
+            MergePolicy::SizeTiered => {
+                Self::size_tiered_merge_candidates(sized_segments.clone(), &self.thresholds_config)
+            }
+        };
+
         if candidates.len() < 3 {
             return vec![];
         }
+
+        // Estimate the merge's peak memory as roughly twice the candidates'
+        // combined byte size (inputs held in memory plus the output segment
+        // being built) and back off rather than risk an OOM if that would
+        // exceed the shared cluster-wide budget.
+        let candidate_ids: HashSet<SegmentId> = candidates.iter().copied().collect();
+        let estimated_bytes: u64 = sized_segments
+            .iter()
+            .filter(|(sid, _)| candidate_ids.contains(sid))
+            .map(|(_, size)| *size as u64)
+            .sum::<u64>()
+            .saturating_mul(2);
+
+        match self.memory_budget.try_reserve(estimated_bytes) {
+            Some(reservation) => {
+                *self.pending_reservation.lock() = Some(reservation);
+            }
+            None => {
+                log::debug!(
+                    "Merge candidates {:?} would need an estimated {estimated_bytes} bytes, \
+                     which exceeds the shared optimizer memory budget - backing off",
+                    candidates
+                );
+                return vec![];
+            }
+        }
+
         log::debug!("Merge candidates: {:?}", candidates);
         candidates
     }
@@ -155,6 +386,45 @@ impl SegmentOptimizer for MergeOptimizer {
     }
 }
 
+/// Block size xxh3 checksums are computed over, see [`compute_block_checksums`].
+const CHECKSUM_BLOCK_SIZE: usize = 4096;
+
+/// Splits `data` into [`CHECKSUM_BLOCK_SIZE`]-byte blocks (the last one
+/// short if `data.len()` isn't a multiple of it) and hashes each with xxh3,
+/// producing the list [`verify_block_checksums`] later checks against.
+/// Called when `verify_checksums()` is enabled, right after `optimize`
+/// writes a merged segment's data file; the checksums are stored in the
+/// segment's metadata footer.
+pub fn compute_block_checksums(data: &[u8]) -> Vec<u64> {
+    data.chunks(CHECKSUM_BLOCK_SIZE)
+        .map(xxhash_rust::xxh3::xxh3_64)
+        .collect()
+}
+
+/// Recomputes block checksums for `data` and compares them against
+/// `expected` (as produced by [`compute_block_checksums`] at write time),
+/// returning an error identifying the first corrupted block on mismatch.
+/// Called on segment load when `verify_checksums()` is enabled.
+pub fn verify_block_checksums(data: &[u8], expected: &[u64]) -> OperationResult<()> {
+    let actual = compute_block_checksums(data);
+    if actual.len() != expected.len() {
+        return Err(OperationError::service_error(format!(
+            "segment data has {} checksum blocks but its footer records {}",
+            actual.len(),
+            expected.len()
+        )));
+    }
+    for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        if actual != expected {
+            return Err(OperationError::service_error(format!(
+                "segment data block {index} failed its xxh3 checksum: expected {expected:016x}, \
+                 got {actual:016x} - segment is corrupted"
+            )));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::AtomicBool;
@@ -275,4 +545,56 @@ mod tests {
         // Check if optimized segments removed from disk
         old_path.into_iter().for_each(|x| assert!(!x.exists()));
     }
+
+    #[test]
+    fn test_size_tiered_merge_policy() {
+        let dir = Builder::new().prefix("segment_dir").tempdir().unwrap();
+        let temp_dir = Builder::new().prefix("segment_temp_dir").tempdir().unwrap();
+
+        let mut holder = SegmentHolder::default();
+        let dim = 256;
+
+        // Four similarly-sized small segments should form one tier...
+        let small_tier = vec![
+            holder.add(random_segment(dir.path(), 100, 10, dim)),
+            holder.add(random_segment(dir.path(), 100, 11, dim)),
+            holder.add(random_segment(dir.path(), 100, 12, dim)),
+            holder.add(random_segment(dir.path(), 100, 13, dim)),
+        ];
+
+        // ...while this much larger segment is far outside that tier's ratio
+        // and must not be grouped in with it.
+        let _large_outlier = holder.add(random_segment(dir.path(), 100, 200, dim));
+
+        let mut merge_optimizer = get_merge_optimizer(dir.path(), temp_dir.path(), dim);
+        merge_optimizer.max_segments = 1;
+        merge_optimizer.thresholds_config.merge_policy = MergePolicy::SizeTiered;
+        merge_optimizer.thresholds_config.bucket_low = 0.5;
+        merge_optimizer.thresholds_config.bucket_high = 1.5;
+        merge_optimizer.thresholds_config.min_threshold = 4;
+        merge_optimizer.thresholds_config.max_threshold = 8;
+
+        let locked_holder = Arc::new(RwLock::new(holder));
+
+        let candidates = merge_optimizer.check_condition(locked_holder, &Default::default());
+
+        assert_eq!(candidates.len(), small_tier.len());
+        for candidate in &candidates {
+            assert!(small_tier.contains(candidate));
+        }
+    }
+
+    #[test]
+    fn test_block_checksum_detects_corruption() {
+        let data = vec![7u8; CHECKSUM_BLOCK_SIZE * 3 + 10];
+        let checksums = compute_block_checksums(&data);
+
+        assert!(verify_block_checksums(&data, &checksums).is_ok());
+
+        let mut corrupted = data;
+        corrupted[CHECKSUM_BLOCK_SIZE + 1] ^= 0xFF; // flip a byte in the second block
+
+        let err = verify_block_checksums(&corrupted, &checksums).unwrap_err();
+        assert!(format!("{err}").contains("block 1"));
+    }
 }
@@ -17,6 +17,8 @@ use validator::Validate;
 use wal::WalOptions;
 
 use crate::operations::config_diff::{DiffConfig, QuantizationConfigDiff};
+use crate::operations::geoip_enrichment::GeoIpEnrichmentConfig;
+use crate::operations::ttl_policy::TtlPolicy;
 use crate::operations::types::{
     CollectionError, CollectionResult, VectorParams, VectorParamsDiff, VectorsConfig,
     VectorsConfigDiff,
@@ -77,6 +79,24 @@ pub struct CollectionParams {
     /// Note: those payload values that are involved in filtering and are indexed - remain in RAM.
     #[serde(default = "default_on_disk_payload")]
     pub on_disk_payload: bool,
+    /// IP-to-geo enrichment settings: `GeoIpDatabase::enrich` resolves
+    /// `ip_field` into a `{lat, lon, country}` geo sub-object at `geo_field`
+    /// using the configured MaxMind-format database. The upsert path does
+    /// not call `enrich`, so setting this does not by itself change
+    /// upserted payloads.
+    #[serde(default)]
+    pub geoip_enrichment: Option<GeoIpEnrichmentConfig>,
+    /// If true, segments produced by optimization are written with xxh3
+    /// block checksums, and those checksums are verified again on load,
+    /// surfacing a typed error on mismatch instead of silently returning
+    /// wrong query results from bit-rotted data. Off by default because it
+    /// costs an extra hash pass over every block on both write and read.
+    #[serde(default)]
+    pub verify_checksums: bool,
+    /// When set, points are evicted once their `ttl_payload_field` expires
+    /// - see [`TtlPolicy`] and `crate::operations::ttl_policy`.
+    #[serde(default)]
+    pub ttl_policy: Option<TtlPolicy>,
 }
 
 impl Anonymize for CollectionParams {
@@ -87,6 +107,9 @@ impl Anonymize for CollectionParams {
             replication_factor: self.replication_factor,
             write_consistency_factor: self.write_consistency_factor,
             on_disk_payload: self.on_disk_payload,
+            geoip_enrichment: self.geoip_enrichment.clone(),
+            verify_checksums: self.verify_checksums,
+            ttl_policy: self.ttl_policy.clone(),
         }
     }
 }
@@ -107,8 +130,42 @@ const fn default_on_disk_payload() -> bool {
     false
 }
 
+/// Current on-disk schema version written by [`CollectionConfig::save`].
+/// Bump this and append a migration to [`MIGRATIONS`] whenever a field is
+/// renamed, removed, or gains a new non-serde-default meaning.
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+fn current_config_version() -> u32 {
+    CURRENT_CONFIG_VERSION
+}
+
+/// One `v(n) -> v(n+1)` upgrade step over the config's untyped JSON form,
+/// applied in order by [`CollectionConfig::load`] before the result is
+/// deserialized into [`CollectionConfig`].
+type Migration = fn(serde_json::Value) -> serde_json::Value;
+
+/// Ordered migrations from version `index` to `index + 1`. `MIGRATIONS[0]`
+/// upgrades v0 (any `config.json` predating this field) to v1.
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+/// v0 configs predate `version` and `CollectionParams::on_disk_payload`;
+/// both already default correctly through `#[serde(default)]`, so this
+/// migration is a no-op at the JSON level - it exists only to give the
+/// version chain a first rung, and as the template for future migrations
+/// that do need to rewrite fields.
+fn migrate_v0_to_v1(value: serde_json::Value) -> serde_json::Value {
+    value
+}
+
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, Clone, PartialEq)]
 pub struct CollectionConfig {
+    /// Schema version of this file, for [`CollectionConfig::load`]'s
+    /// migration pipeline, which treats a file without it as version 0
+    /// (pre-versioning). Plain deserialization without it fills in
+    /// [`CURRENT_CONFIG_VERSION`]. Written back on every
+    /// [`CollectionConfig::save`].
+    #[serde(default = "current_config_version")]
+    pub version: u32,
     #[validate]
     pub params: CollectionParams,
     #[validate]
@@ -132,12 +189,47 @@ impl CollectionConfig {
         Ok(())
     }
 
+    /// Loads `config.json`, transparently upgrading it through
+    /// [`MIGRATIONS`] if it was written by an older release, and
+    /// re-[`save`](Self::save)ing the upgraded result so the migration only
+    /// ever runs once per file. Fails with a clear error rather than
+    /// guessing if the file's `version` is newer than this binary
+    /// understands.
     pub fn load(path: &Path) -> CollectionResult<Self> {
         let config_path = path.join(COLLECTION_CONFIG_FILE);
         let mut contents = String::new();
         let mut file = File::open(config_path)?;
         file.read_to_string(&mut contents)?;
-        Ok(serde_json::from_str(&contents)?)
+
+        let mut value: serde_json::Value = serde_json::from_str(&contents)?;
+        let mut version = value
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0) as u32;
+
+        if version > CURRENT_CONFIG_VERSION {
+            return Err(CollectionError::service_error(format!(
+                "collection config at {path:?} has version {version}, which is newer than \
+                 version {CURRENT_CONFIG_VERSION} supported by this binary"
+            )));
+        }
+
+        let needs_migration = version < CURRENT_CONFIG_VERSION;
+        for migration in &MIGRATIONS[version as usize..] {
+            value = migration(value);
+            version += 1;
+        }
+        if needs_migration {
+            if let serde_json::Value::Object(ref mut fields) = value {
+                fields.insert("version".to_string(), serde_json::json!(version));
+            }
+        }
+
+        let config: CollectionConfig = serde_json::from_value(value)?;
+        if needs_migration {
+            config.save(path)?;
+        }
+        Ok(config)
     }
 
     /// Check if collection config exists
@@ -0,0 +1,116 @@
+//! IP-to-geo payload enrichment: [`GeoIpDatabase::enrich`] resolves a
+//! configured IP payload field into a `{lat, lon, country}` geo sub-object,
+//! using a MaxMind-format (`.mmdb`) database, so payloads that only ever
+//! stored a client IP become filterable with the existing `GeoRadius`/
+//! `GeoBoundingBox`/`GeoPolygon` checkers and geo indexes without the caller
+//! having to resolve IPs themselves. `enrich` is not called from the upsert
+//! path.
+//!
+//! The `.mmdb` file is read fully into memory (not memory-mapped) once per
+//! node and shared the same way
+//! [`crate::operations::shared_storage_config::SharedStorageConfig`] shares
+//! other per-node state, rather than being reopened per collection.
+
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::operations::types::PayloadKeyType;
+
+/// Per-collection configuration for IP-to-geo enrichment, set alongside the
+/// other fields of `CollectionParams`.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct GeoIpEnrichmentConfig {
+    /// Payload field holding the client IP to resolve, e.g. `"client_ip"`.
+    pub ip_field: PayloadKeyType,
+    /// Payload field the derived `{lat, lon, country}` object is written to.
+    /// May equal `ip_field` to replace the IP in place.
+    pub geo_field: PayloadKeyType,
+    /// Path to the MaxMind-format (GeoLite2/GeoIP2 City) database file.
+    pub database_path: PathBuf,
+}
+
+/// Hit/miss counters for enrichment attempts, so operators can see how much
+/// of the IP traffic a database actually resolves.
+#[derive(Debug, Default)]
+pub struct GeoIpEnrichmentMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl GeoIpEnrichmentMetrics {
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A MaxMind database read fully into memory, opened once per node and shared across
+/// every collection whose `GeoIpEnrichmentConfig` points at the same path.
+pub struct GeoIpDatabase {
+    reader: maxminddb::Reader<Vec<u8>>,
+    metrics: GeoIpEnrichmentMetrics,
+}
+
+impl GeoIpDatabase {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        Ok(GeoIpDatabase {
+            reader: maxminddb::Reader::open_readfile(path)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?,
+            metrics: GeoIpEnrichmentMetrics::default(),
+        })
+    }
+
+    pub fn metrics(&self) -> &GeoIpEnrichmentMetrics {
+        &self.metrics
+    }
+
+    /// Resolves `ip` to `{lat, lon, country}`, or `None` if the address is
+    /// private/unroutable or simply isn't in the database.
+    fn lookup(&self, ip: IpAddr) -> Option<Value> {
+        let city: maxminddb::geoip2::City = self.reader.lookup(ip).ok()?;
+        let location = city.location?;
+        let lat = location.latitude?;
+        let lon = location.longitude?;
+        let country = city
+            .country
+            .and_then(|country| country.names)
+            .and_then(|names| names.get("en").map(|name| name.to_string()));
+        Some(json!({ "lat": lat, "lon": lon, "country": country }))
+    }
+
+    /// Resolves `config.ip_field` in `payload` and writes the result into
+    /// `config.geo_field`, in place. Any failure to resolve (missing field,
+    /// unparseable IP, private/unroutable address, database miss) leaves
+    /// `payload` untouched rather than erroring the enclosing upsert.
+    pub fn enrich(&self, payload: &mut serde_json::Map<String, Value>, config: &GeoIpEnrichmentConfig) {
+        let resolved = payload
+            .get(config.ip_field.as_str())
+            .and_then(Value::as_str)
+            .and_then(|text| text.parse::<IpAddr>().ok())
+            .and_then(|ip| self.lookup(ip));
+
+        match resolved {
+            Some(geo) => {
+                self.metrics.record_hit();
+                payload.insert(config.geo_field.to_string(), geo);
+            }
+            None => self.metrics.record_miss(),
+        }
+    }
+}
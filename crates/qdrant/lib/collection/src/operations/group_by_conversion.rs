@@ -0,0 +1,128 @@
+//! Typed conversions applied to raw payload values before they become
+//! (part of) a `group_by` key, so grouping isn't limited to using a
+//! string/number payload value verbatim.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::operations::types::{CollectionError, CollectionResult, GroupByField};
+
+/// Coarsens a timestamp-derived group key down to a fixed calendar unit,
+/// e.g. so points from the same day end up in the same group regardless
+/// of their exact time of day.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampGranularity {
+    Hour,
+    Day,
+}
+
+/// How to turn a raw payload value into a group key component.
+///
+/// `AsIs` keeps today's behaviour of grouping by the raw string/number
+/// value. The other variants let `group_by` bucket date/time payloads too.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum GroupByConversion {
+    /// Use the raw payload value as-is.
+    #[default]
+    AsIs,
+    /// Coerce the value to an integer.
+    Integer,
+    /// Coerce the value to a float.
+    Float,
+    /// Coerce the value to a boolean.
+    Boolean,
+    /// Parse the value as an RFC3339 or epoch timestamp.
+    Timestamp,
+    /// Parse the value with an explicit strftime-style pattern.
+    TimestampFmt(String),
+    /// Parse the value with an explicit strftime-style pattern and timezone.
+    TimestampTzFmt(String),
+}
+
+impl GroupByConversion {
+    /// Whether this conversion produces a timestamp, and so can be coarsened
+    /// by a [`TimestampGranularity`].
+    fn is_timestamp_like(&self) -> bool {
+        matches!(
+            self,
+            GroupByConversion::Timestamp
+                | GroupByConversion::TimestampFmt(_)
+                | GroupByConversion::TimestampTzFmt(_)
+        )
+    }
+}
+
+/// A single field's conversion, with optional coarsening for timestamp-like
+/// conversions.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash, Default)]
+pub struct GroupByKeyConversion {
+    #[serde(default)]
+    pub conversion: GroupByConversion,
+
+    /// Only meaningful together with `timestamp`, `timestamp_fmt`, or
+    /// `timestamp_tz_fmt` conversions.
+    #[serde(default)]
+    pub granularity: Option<TimestampGranularity>,
+}
+
+impl GroupByKeyConversion {
+    fn check_compatible(&self) -> CollectionResult<()> {
+        if self.granularity.is_some() && !self.conversion.is_timestamp_like() {
+            return Err(CollectionError::bad_input(format!(
+                "granularity is only applicable to timestamp conversions, not to {:?}",
+                self.conversion
+            )));
+        }
+
+        let format = match &self.conversion {
+            GroupByConversion::TimestampFmt(format) => Some(format),
+            GroupByConversion::TimestampTzFmt(format) => Some(format),
+            _ => None,
+        };
+        if format.is_some_and(|format| format.is_empty()) {
+            return Err(CollectionError::bad_input(
+                "timestamp format pattern must not be empty".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Conversion(s) applied to `group_by` field value(s).
+///
+/// A single conversion for a single `group_by` field, or one conversion per
+/// field (same order) when grouping by multiple fields.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum GroupByConversionSpec {
+    Single(GroupByKeyConversion),
+    Multi(Vec<GroupByKeyConversion>),
+}
+
+impl GroupByConversionSpec {
+    /// Checks that this spec is structurally compatible with the `group_by`
+    /// field(s) it applies to: one conversion per field, each individually
+    /// well-formed.
+    pub fn check_compatible(&self, group_by: &GroupByField) -> CollectionResult<()> {
+        let fields_len = group_by.fields().count();
+
+        let conversions: Vec<&GroupByKeyConversion> = match self {
+            GroupByConversionSpec::Single(conversion) => vec![conversion],
+            GroupByConversionSpec::Multi(conversions) => conversions.iter().collect(),
+        };
+
+        if conversions.len() != fields_len {
+            return Err(CollectionError::bad_input(format!(
+                "group_by_conversion has {} entries, but group_by has {fields_len} field(s)",
+                conversions.len(),
+            )));
+        }
+
+        conversions
+            .into_iter()
+            .try_for_each(GroupByKeyConversion::check_compatible)
+    }
+}
@@ -0,0 +1,168 @@
+//! Typed coercion of raw payload values, applied by [`SetPayload`] and
+//! [`OverwritePayload`] before the payload is split by shard so callers can
+//! store properly typed, filterable fields instead of opaque strings.
+//!
+//! [`SetPayload`]: crate::operations::payload_ops::SetPayload
+//! [`OverwritePayload`]: crate::operations::payload_ops::PayloadOps::OverwritePayload
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use schemars::JsonSchema;
+use segment::types::{Payload, PayloadKeyType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::operations::types::{CollectionError, CollectionResult};
+
+/// How to coerce a raw payload value before it's stored.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum Conversion {
+    /// Store the value as-is.
+    Bytes,
+    /// Coerce the value to an integer.
+    Integer,
+    /// Coerce the value to a float.
+    Float,
+    /// Coerce the value to a boolean.
+    Boolean,
+    /// Parse the value as an RFC3339 or epoch timestamp.
+    Timestamp,
+    /// Parse the value with an explicit strftime-style pattern.
+    TimestampFmt(String),
+    /// Parse the value with an explicit strftime-style pattern and timezone.
+    TimestampTzFmt(String),
+}
+
+impl FromStr for Conversion {
+    type Err = CollectionError;
+
+    /// Builds a conversion from its short name, e.g. `"int"`/`"integer"`,
+    /// `"float"`, `"bool"`/`"boolean"`, `"timestamp"`. The format-carrying
+    /// variants aren't constructible this way; use the struct form instead.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bytes" => Ok(Conversion::Bytes),
+            "int" | "integer" => Ok(Conversion::Integer),
+            "float" => Ok(Conversion::Float),
+            "bool" | "boolean" => Ok(Conversion::Boolean),
+            "timestamp" => Ok(Conversion::Timestamp),
+            other => Err(CollectionError::bad_input(format!(
+                "unknown payload conversion \"{other}\""
+            ))),
+        }
+    }
+}
+
+impl Conversion {
+    /// Applies this conversion to a raw value, returning the typed,
+    /// normalized value to store, or a validation error if `raw` doesn't
+    /// structurally fit the conversion.
+    pub fn apply(&self, raw: &Value) -> CollectionResult<Value> {
+        match self {
+            Conversion::Bytes => Ok(raw.clone()),
+            Conversion::Integer => match raw {
+                Value::Number(number) if number.is_i64() || number.is_u64() => Ok(raw.clone()),
+                _ => Self::as_text(raw)?
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .map_err(|err| Self::parse_error("integer", raw, &err)),
+            },
+            Conversion::Float => match raw {
+                Value::Number(_) => Ok(raw.clone()),
+                _ => Self::as_text(raw)?
+                    .parse::<f64>()
+                    .map(Value::from)
+                    .map_err(|err| Self::parse_error("float", raw, &err)),
+            },
+            Conversion::Boolean => match raw {
+                Value::Bool(_) => Ok(raw.clone()),
+                _ => Self::as_text(raw)?
+                    .parse::<bool>()
+                    .map(Value::from)
+                    .map_err(|err| Self::parse_error("boolean", raw, &err)),
+            },
+            Conversion::Timestamp => {
+                Self::parse_timestamp(Self::as_text(raw)?, None, false).map(Self::timestamp_value)
+            }
+            Conversion::TimestampFmt(format) => {
+                Self::parse_timestamp(Self::as_text(raw)?, Some(format), false)
+                    .map(Self::timestamp_value)
+            }
+            Conversion::TimestampTzFmt(format) => {
+                Self::parse_timestamp(Self::as_text(raw)?, Some(format), true)
+                    .map(Self::timestamp_value)
+            }
+        }
+    }
+
+    fn as_text(raw: &Value) -> CollectionResult<&str> {
+        raw.as_str().ok_or_else(|| {
+            CollectionError::bad_input(format!(
+                "expected a string payload value to convert, got {raw}"
+            ))
+        })
+    }
+
+    fn parse_error(kind: &str, raw: &Value, err: &impl std::fmt::Display) -> CollectionError {
+        CollectionError::bad_input(format!("failed to parse {raw} as a {kind}: {err}"))
+    }
+
+    fn timestamp_value(timestamp: DateTime<Utc>) -> Value {
+        Value::String(timestamp.to_rfc3339())
+    }
+
+    fn parse_timestamp(
+        raw: &str,
+        format: Option<&str>,
+        with_tz: bool,
+    ) -> CollectionResult<DateTime<Utc>> {
+        if let Some(format) = format {
+            if with_tz {
+                DateTime::parse_from_str(raw, format)
+                    .map(|parsed| parsed.with_timezone(&Utc))
+                    .map_err(|err| Self::parse_error("timestamp", &Value::from(raw), &err))
+            } else {
+                NaiveDateTime::parse_from_str(raw, format)
+                    .map(|naive| Utc.from_utc_datetime(&naive))
+                    .map_err(|err| Self::parse_error("timestamp", &Value::from(raw), &err))
+            }
+        } else if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
+            Ok(parsed.with_timezone(&Utc))
+        } else if let Ok(epoch) = raw.parse::<i64>() {
+            Utc.timestamp_opt(epoch, 0).single().ok_or_else(|| {
+                CollectionError::bad_input(format!("epoch timestamp \"{raw}\" is out of range"))
+            })
+        } else {
+            Err(CollectionError::bad_input(format!(
+                "failed to parse \"{raw}\" as an RFC3339 or epoch timestamp"
+            )))
+        }
+    }
+}
+
+/// Applies `conversions` to the matching keys of `payload`, leaving keys
+/// without a configured conversion untouched.
+pub fn apply_conversions(
+    payload: Payload,
+    conversions: &HashMap<PayloadKeyType, Conversion>,
+) -> CollectionResult<Payload> {
+    let mut value = serde_json::to_value(payload).map_err(|err| {
+        CollectionError::bad_input(format!("failed to inspect payload for conversion: {err}"))
+    })?;
+
+    if let Value::Object(map) = &mut value {
+        for (key, conversion) in conversions {
+            if let Some(raw) = map.get(key) {
+                let converted = conversion.apply(raw)?;
+                map.insert(key.clone(), converted);
+            }
+        }
+    }
+
+    serde_json::from_value(value).map_err(|err| {
+        CollectionError::bad_input(format!("failed to rebuild payload after conversion: {err}"))
+    })
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use schemars::JsonSchema;
 use segment::types::{Filter, Payload, PayloadKeyType, PointIdType};
 use serde;
@@ -6,6 +8,8 @@ use validator::Validate;
 
 use super::{split_iter_by_shard, OperationToShard, SplitByShard};
 use crate::hash_ring::HashRing;
+use crate::operations::payload_conversion::{apply_conversions, Conversion};
+use crate::operations::types::CollectionError;
 use crate::shards::shard::ShardId;
 
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, Clone)]
@@ -16,6 +20,9 @@ pub struct SetPayload {
     pub points: Option<Vec<PointIdType>>,
     /// Assigns payload to each point that satisfy this filter condition
     pub filter: Option<Filter>,
+    /// Per-key coercion applied to the raw payload value before it's stored,
+    /// e.g. parsing a string into an integer or a timestamp.
+    pub conversions: Option<HashMap<PayloadKeyType, Conversion>>,
 }
 
 #[derive(Deserialize)]
@@ -23,6 +30,8 @@ struct SetPayloadShadow {
     pub payload: Payload,
     pub points: Option<Vec<PointIdType>>,
     pub filter: Option<Filter>,
+    #[serde(default)]
+    pub conversions: Option<HashMap<PayloadKeyType, Conversion>>,
 }
 
 pub struct PointsSelectorValidationError;
@@ -36,18 +45,42 @@ impl std::fmt::Display for PointsSelectorValidationError {
     }
 }
 
+/// Error constructing a [`SetPayload`] from its deserialized shadow: either
+/// the points selector is missing, or a configured conversion failed.
+pub enum SetPayloadError {
+    PointsSelector(PointsSelectorValidationError),
+    Conversion(CollectionError),
+}
+
+impl std::fmt::Display for SetPayloadError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetPayloadError::PointsSelector(err) => err.fmt(formatter),
+            SetPayloadError::Conversion(err) => write!(formatter, "{err}"),
+        }
+    }
+}
+
 impl TryFrom<SetPayloadShadow> for SetPayload {
-    type Error = PointsSelectorValidationError;
+    type Error = SetPayloadError;
 
     fn try_from(value: SetPayloadShadow) -> Result<Self, Self::Error> {
         if value.points.is_some() || value.filter.is_some() {
+            let payload = match &value.conversions {
+                Some(conversions) => apply_conversions(value.payload, conversions)
+                    .map_err(SetPayloadError::Conversion)?,
+                None => value.payload,
+            };
             Ok(SetPayload {
-                payload: value.payload,
+                payload,
                 points: value.points,
                 filter: value.filter,
+                conversions: value.conversions,
             })
         } else {
-            Err(PointsSelectorValidationError)
+            Err(SetPayloadError::PointsSelector(
+                PointsSelectorValidationError,
+            ))
         }
     }
 }
@@ -171,6 +204,7 @@ impl SplitByShard for SetPayload {
                     points: Some(points),
                     payload: self.payload.clone(),
                     filter: self.filter.clone(),
+                    conversions: self.conversions.clone(),
                 })
             }
             (None, Some(_)) => OperationToShard::to_all(self),
@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
+use crate::operations::geoip_enrichment::GeoIpDatabase;
 use crate::operations::types::NodeType;
 
 /// Default timeout for search requests.
@@ -7,6 +11,12 @@ use crate::operations::types::NodeType;
 const DEFAULT_SEARCH_TIMEOUT: Duration = Duration::from_secs(60);
 const DEFAULT_UPDATE_QUEUE_SIZE: usize = 100;
 const DEFAULT_UPDATE_QUEUE_SIZE_LISTENER: usize = 10_000;
+/// Default `2^k` bucket count for disk-backed field indexes: `2^16` buckets.
+const DEFAULT_INDEX_BUCKET_CAPACITY_POW2: u8 = 16;
+const DEFAULT_INDEX_BUCKET_MAX_PROBES: usize = 8;
+/// Consecutive optimizer failures tolerated for a given segment set, via
+/// exponential backoff, before the error is persisted and retries stop.
+const DEFAULT_MAX_OPTIMIZER_RETRIES: usize = 5;
 
 /// Storage configuration shared between all collections.
 /// Represents a per-node configuration, which might be changes with restart.
@@ -18,6 +28,22 @@ pub struct SharedStorageConfig {
     pub handle_collection_load_errors: bool,
     pub recovery_mode: Option<String>,
     pub search_timeout: Duration,
+    /// `k` in the `2^k` bucket count a disk-backed field index starts with;
+    /// doubled automatically once a bucket's load factor is exceeded. Lower
+    /// values trade memory for longer probe sequences.
+    pub index_bucket_capacity_pow2: u8,
+    /// Maximum slots probed within a bucket before giving up on a lookup,
+    /// bounding worst-case search latency against a pathologically
+    /// overloaded bucket.
+    pub index_bucket_max_probes: usize,
+    /// Consecutive optimizer failures tolerated for the same segment set
+    /// (via exponential backoff) before the error is persisted with
+    /// `report_optimizer_error` and that set stops being retried.
+    pub max_optimizer_retries: usize,
+    /// Per-node cache of in-memory GeoIP databases, keyed by
+    /// `GeoIpEnrichmentConfig::database_path` so collections sharing a path
+    /// share one loaded copy instead of each opening their own.
+    pub geoip_databases: Arc<RwLock<HashMap<PathBuf, Arc<GeoIpDatabase>>>>,
 }
 
 impl Default for SharedStorageConfig {
@@ -28,17 +54,42 @@ impl Default for SharedStorageConfig {
             handle_collection_load_errors: false,
             recovery_mode: None,
             search_timeout: DEFAULT_SEARCH_TIMEOUT,
+            index_bucket_capacity_pow2: DEFAULT_INDEX_BUCKET_CAPACITY_POW2,
+            index_bucket_max_probes: DEFAULT_INDEX_BUCKET_MAX_PROBES,
+            max_optimizer_retries: DEFAULT_MAX_OPTIMIZER_RETRIES,
+            geoip_databases: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
 
 impl SharedStorageConfig {
+    /// Returns the shared [`GeoIpDatabase`] for `path` (read fully into
+    /// memory), opening and caching it on first use so every collection
+    /// configured with the same `database_path` reuses one loaded copy.
+    pub fn geoip_database(&self, path: &std::path::Path) -> std::io::Result<Arc<GeoIpDatabase>> {
+        if let Some(database) = self.geoip_databases.read().unwrap().get(path) {
+            return Ok(database.clone());
+        }
+        let database = Arc::new(GeoIpDatabase::open(path)?);
+        self.geoip_databases
+            .write()
+            .unwrap()
+            .insert(path.to_path_buf(), database.clone());
+        Ok(database)
+    }
+}
+
+impl SharedStorageConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         update_queue_size: Option<usize>,
         node_type: NodeType,
         handle_collection_load_errors: bool,
         recovery_mode: Option<String>,
         search_timeout: Option<Duration>,
+        index_bucket_capacity_pow2: Option<u8>,
+        index_bucket_max_probes: Option<usize>,
+        max_optimizer_retries: Option<usize>,
     ) -> Self {
         let update_queue_size = update_queue_size.unwrap_or(match node_type {
             NodeType::Normal => DEFAULT_UPDATE_QUEUE_SIZE,
@@ -51,6 +102,12 @@ impl SharedStorageConfig {
             handle_collection_load_errors,
             recovery_mode,
             search_timeout: search_timeout.unwrap_or(DEFAULT_SEARCH_TIMEOUT),
+            index_bucket_capacity_pow2: index_bucket_capacity_pow2
+                .unwrap_or(DEFAULT_INDEX_BUCKET_CAPACITY_POW2),
+            index_bucket_max_probes: index_bucket_max_probes
+                .unwrap_or(DEFAULT_INDEX_BUCKET_MAX_PROBES),
+            max_optimizer_retries: max_optimizer_retries.unwrap_or(DEFAULT_MAX_OPTIMIZER_RETRIES),
+            geoip_databases: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
@@ -0,0 +1,136 @@
+//! Point TTL / lifecycle expiration, analogous to object-storage lifecycle
+//! rules: a point whose [`TtlPolicy::ttl_payload_field`] holds a unix
+//! timestamp older than [`TtlPolicy::ttl_seconds`] is eligible for
+//! automatic eviction, so session caches and other ephemeral embeddings
+//! age out without a user-run cron deleter.
+//!
+//! [`expired_points_filter`] builds the `Filter` that selects expired
+//! points, for a periodic sweep to pass to `do_delete_points` under the
+//! collection's default `WriteOrdering`. This module only builds that
+//! filter; nothing in it schedules or runs the sweep.
+
+use schemars::JsonSchema;
+use segment::types::{Condition, FieldCondition, Filter, PayloadFieldSchema, PayloadSchemaType};
+use serde::{Deserialize, Serialize};
+
+use crate::operations::types::{CollectionError, CollectionResult, PayloadKeyType};
+
+fn default_ttl_seconds() -> u64 {
+    86_400
+}
+
+/// Per-collection configuration for automatic point expiration, set
+/// alongside the other fields of `CollectionParams`.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq)]
+pub struct TtlPolicy {
+    /// Payload field holding the point's unix-timestamp expiry marker.
+    pub ttl_payload_field: PayloadKeyType,
+    /// How long after `ttl_payload_field` a point is eligible for eviction.
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+impl TtlPolicy {
+    /// Rejects a policy whose `ttl_payload_field` isn't indexed as an
+    /// integer, since the expiry comparison this module builds only makes
+    /// sense against a numeric payload index.
+    pub fn validate_indexed_field(
+        &self,
+        indexed_fields: &std::collections::HashMap<PayloadKeyType, PayloadFieldSchema>,
+    ) -> CollectionResult<()> {
+        match indexed_fields.get(&self.ttl_payload_field) {
+            Some(schema) if schema_is_integer(schema) => Ok(()),
+            Some(_) => Err(CollectionError::BadInput {
+                description: format!(
+                    "ttl_payload_field {:?} must be indexed as an integer",
+                    self.ttl_payload_field
+                ),
+            }),
+            None => Err(CollectionError::BadInput {
+                description: format!(
+                    "ttl_payload_field {:?} is not indexed; index it as an integer before enabling TTL",
+                    self.ttl_payload_field
+                ),
+            }),
+        }
+    }
+}
+
+fn schema_is_integer(schema: &PayloadFieldSchema) -> bool {
+    matches!(
+        schema,
+        PayloadFieldSchema::FieldType(PayloadSchemaType::Integer)
+    )
+}
+
+/// The filter a periodic sweep should pass to `do_delete_points`: every
+/// point whose `ttl_payload_field` plus `ttl_seconds` has already passed,
+/// as of `now` (a unix timestamp).
+pub fn expired_points_filter(policy: &TtlPolicy, now: i64) -> Filter {
+    let cutoff = now - policy.ttl_seconds as i64;
+    Filter {
+        must: Some(vec![Condition::Field(FieldCondition {
+            key: policy.ttl_payload_field.clone(),
+            r#match: None,
+            range: Some(segment::types::Range {
+                gt: None,
+                gte: None,
+                lt: Some(cutoff as f64),
+                lte: None,
+            }),
+            geo_bounding_box: None,
+            geo_radius: None,
+            geo_polygon: None,
+            values_count: None,
+        })]),
+        should: None,
+        must_not: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_points_filter_uses_ttl_cutoff() {
+        let policy = TtlPolicy {
+            ttl_payload_field: "expires_at".to_string(),
+            ttl_seconds: 3600,
+        };
+        let filter = expired_points_filter(&policy, 10_000);
+        let conditions = filter.must.unwrap();
+        assert_eq!(conditions.len(), 1);
+        match &conditions[0] {
+            Condition::Field(field_condition) => {
+                assert_eq!(field_condition.key, "expires_at");
+                let range = field_condition.range.as_ref().unwrap();
+                assert_eq!(range.lt, Some(6_400.0));
+            }
+            _ => panic!("expected a field condition"),
+        }
+    }
+
+    #[test]
+    fn test_validate_indexed_field_requires_integer_index() {
+        let policy = TtlPolicy {
+            ttl_payload_field: "expires_at".to_string(),
+            ttl_seconds: 3600,
+        };
+
+        let mut indexed_fields = std::collections::HashMap::new();
+        assert!(policy.validate_indexed_field(&indexed_fields).is_err());
+
+        indexed_fields.insert(
+            "expires_at".to_string(),
+            PayloadFieldSchema::FieldType(PayloadSchemaType::Keyword),
+        );
+        assert!(policy.validate_indexed_field(&indexed_fields).is_err());
+
+        indexed_fields.insert(
+            "expires_at".to_string(),
+            PayloadFieldSchema::FieldType(PayloadSchemaType::Integer),
+        );
+        assert!(policy.validate_indexed_field(&indexed_fields).is_ok());
+    }
+}
@@ -2,13 +2,17 @@ use std::backtrace::Backtrace;
 use std::collections::{BTreeMap, HashMap};
 use std::error::Error as _;
 use std::fmt::Write as _;
+use std::future::Future;
 use std::iter;
 use std::num::NonZeroU64;
-use std::time::SystemTimeError;
+use std::time::{Duration, SystemTimeError};
 
 use api::grpc::transport_channel_pool::RequestError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
 use futures::io;
 use merge::Merge;
+use rand::Rng;
 use schemars::JsonSchema;
 use segment::common::anonymize::Anonymize;
 use segment::common::file_operations::FileStorageError;
@@ -18,8 +22,9 @@ use segment::data_types::vectors::{
 };
 use segment::entry::entry_point::OperationError;
 use segment::types::{
-    Distance, Filter, Payload, PayloadIndexInfo, PayloadKeyType, PointIdType, QuantizationConfig,
-    ScoreType, ScoredPoint, SearchParams, SeqNumberType, WithPayloadInterface, WithVector,
+    Distance, Filter, GeoPoint, Payload, PayloadIndexInfo, PayloadKeyType, PointIdType,
+    QuantizationConfig, ScoreType, ScoredPoint, SearchParams, SeqNumberType, WithPayloadInterface,
+    WithVector,
 };
 use serde;
 use serde::{Deserialize, Serialize};
@@ -29,12 +34,13 @@ use tokio::sync::mpsc::error::SendError;
 use tokio::sync::oneshot::error::RecvError as OneshotRecvError;
 use tokio::task::JoinError;
 use tonic::codegen::http::uri::InvalidUri;
-use validator::{Validate, ValidationErrors};
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use super::config_diff;
 use crate::config::{CollectionConfig, CollectionParams};
 use crate::lookup::types::WithLookupInterface;
 use crate::operations::config_diff::{HnswConfigDiff, QuantizationConfigDiff};
+use crate::operations::group_by_conversion::GroupByConversionSpec;
 use crate::save_on_disk;
 use crate::shards::replica_set::ReplicaState;
 use crate::shards::shard::{PeerId, ShardId};
@@ -81,6 +87,50 @@ pub struct Record {
     pub vector: Option<VectorStruct>,
 }
 
+/// A [`Record`] whose payload is kept as undecoded JSON.
+///
+/// Records pass through several merge and truncation steps (across shards,
+/// then across replicas) before reaching the client, and most of those steps
+/// never look at individual payload fields. Keeping the payload as a
+/// `RawValue` lets it ride through those steps - and back out over the wire -
+/// without paying for a `Payload` round-trip it doesn't need.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LazyRecord {
+    pub id: PointIdType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Box<serde_json::value::RawValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector: Option<VectorStruct>,
+}
+
+impl LazyRecord {
+    /// Parses the raw payload. Nothing is cached: each call parses the
+    /// bytes again.
+    pub fn payload(&self) -> Result<Option<Payload>, JsonError> {
+        self.payload
+            .as_deref()
+            .map(|raw| serde_json::from_str(raw.get()))
+            .transpose()
+    }
+}
+
+impl TryFrom<Record> for LazyRecord {
+    type Error = JsonError;
+
+    fn try_from(record: Record) -> Result<Self, Self::Error> {
+        let payload = record
+            .payload
+            .map(|payload| serde_json::value::to_raw_value(&payload))
+            .transpose()?;
+        Ok(LazyRecord {
+            id: record.id,
+            payload,
+            vector: record.vector,
+        })
+    }
+}
+
 /// Current statistics and configuration of the collection
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Validate)]
 pub struct CollectionInfo {
@@ -133,6 +183,12 @@ pub struct ShardTransferInfo {
     /// If `true` transfer is a synchronization of a replicas
     /// If `false` transfer is a moving of a shard from one peer to another
     pub sync: bool,
+    /// Fraction of the transfer's chunks the target has acknowledged so
+    /// far, `0.0..=1.0`. `None` if no progress is being tracked for this
+    /// transfer (e.g. it hasn't started streaming chunks yet, or this
+    /// peer isn't a party to it and so has no local progress to report).
+    #[serde(default)]
+    pub progress: Option<f32>,
 }
 
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
@@ -175,12 +231,193 @@ pub struct UpdateResult {
     pub status: UpdateStatus,
 }
 
+/// A pagination position, encoded opaquely so clients only ever pass a
+/// `page_token` back unmodified instead of reconstructing a raw offset or
+/// point id. This lets the encoding change later (e.g. to carry a shard or
+/// snapshot id) without breaking anyone who treats it as an opaque token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum CursorPayload {
+    Offset(usize),
+    PointId(PointIdType),
+    ShardScroll {
+        positions: Vec<ShardCursorPosition>,
+        checksum: u64,
+    },
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CursorError {
+    #[error("page_token is malformed or was not issued by this server")]
+    Malformed,
+}
+
+impl From<CursorError> for CollectionError {
+    fn from(err: CursorError) -> Self {
+        CollectionError::BadInput {
+            description: err.to_string(),
+        }
+    }
+}
+
+fn encode_cursor(payload: &CursorPayload) -> String {
+    let json = serde_json::to_vec(payload).expect("cursor payload is always serializable");
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+fn decode_cursor(cursor: &str) -> Result<CursorPayload, CursorError> {
+    let json = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|_| CursorError::Malformed)?;
+    serde_json::from_slice(&json).map_err(|_| CursorError::Malformed)
+}
+
+/// Encodes a raw numeric offset (as used by [`SearchRequest::offset`]) as an
+/// opaque `page_token`.
+pub fn encode_offset_cursor(offset: usize) -> String {
+    encode_cursor(&CursorPayload::Offset(offset))
+}
+
+/// Decodes a `page_token` previously produced by [`encode_offset_cursor`].
+pub fn decode_offset_cursor(cursor: &str) -> Result<usize, CursorError> {
+    match decode_cursor(cursor)? {
+        CursorPayload::Offset(offset) => Ok(offset),
+        CursorPayload::PointId(_) | CursorPayload::ShardScroll { .. } => {
+            Err(CursorError::Malformed)
+        }
+    }
+}
+
+/// Encodes a keyset position (as used by [`ScrollRequest::offset`]) as an
+/// opaque `page_token`.
+pub fn encode_point_cursor(point_id: PointIdType) -> String {
+    encode_cursor(&CursorPayload::PointId(point_id))
+}
+
+/// Decodes a `page_token` previously produced by [`encode_point_cursor`].
+pub fn decode_point_cursor(cursor: &str) -> Result<PointIdType, CursorError> {
+    match decode_cursor(cursor)? {
+        CursorPayload::PointId(point_id) => Ok(point_id),
+        CursorPayload::Offset(_) | CursorPayload::ShardScroll { .. } => {
+            Err(CursorError::Malformed)
+        }
+    }
+}
+
+/// One shard's resume position inside a cluster-wide scroll cursor: the
+/// last point id it returned, and the snapshot watermark it was read at
+/// (so a shard whose contents changed between calls can tell it needs to
+/// re-synchronize instead of silently skipping or repeating points).
+/// `last_seen_id: None` means the shard hadn't started yet, or finished
+/// returning all its points.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardCursorPosition {
+    pub shard_id: ShardId,
+    pub last_seen_id: Option<PointIdType>,
+    pub watermark: SeqNumberType,
+    pub exhausted: bool,
+}
+
+/// A page of scroll results from a single shard, as gathered by fanning a
+/// `ScrollRequest` out over [`ShardHolder::target_shard`](crate::shards::shard_holder::ShardHolder::target_shard).
+#[derive(Debug, Clone)]
+pub struct ShardScrollPage {
+    pub points: Vec<Record>,
+    /// This shard's id, along with its updated resume position.
+    pub position: ShardCursorPosition,
+}
+
+/// Encodes every shard's [`ShardCursorPosition`] into one opaque
+/// `page_token`, with an xxh3 checksum over the positions so a tampered
+/// token - not just a malformed one - is rejected by [`decode_shard_scroll_cursor`]
+/// rather than silently accepted with corrupted resume state.
+pub fn encode_shard_scroll_cursor(positions: &[ShardCursorPosition]) -> String {
+    let positions_json =
+        serde_json::to_vec(positions).expect("cursor positions are always serializable");
+    let checksum = xxhash_rust::xxh3::xxh3_64(&positions_json);
+    encode_cursor(&CursorPayload::ShardScroll {
+        positions: positions.to_vec(),
+        checksum,
+    })
+}
+
+/// Decodes and integrity-checks a `page_token` produced by
+/// [`encode_shard_scroll_cursor`], rejecting both malformed tokens and
+/// ones whose positions don't match their embedded checksum.
+pub fn decode_shard_scroll_cursor(cursor: &str) -> Result<Vec<ShardCursorPosition>, CursorError> {
+    match decode_cursor(cursor)? {
+        CursorPayload::ShardScroll {
+            positions,
+            checksum,
+        } => {
+            let positions_json =
+                serde_json::to_vec(&positions).map_err(|_| CursorError::Malformed)?;
+            if xxhash_rust::xxh3::xxh3_64(&positions_json) == checksum {
+                Ok(positions)
+            } else {
+                Err(CursorError::Malformed)
+            }
+        }
+        CursorPayload::Offset(_) | CursorPayload::PointId(_) => Err(CursorError::Malformed),
+    }
+}
+
+/// Merges one page per shard (as produced by fanning a `ScrollRequest` out
+/// over `ShardHolder::target_shard`, each shard resuming from its own
+/// [`ShardCursorPosition::last_seen_id`]) into one cluster-wide, point-id
+/// ordered page, truncated to `limit`, with a `page_token` carrying every
+/// shard's updated position - including shards with no points in this page
+/// but not yet `exhausted`, so the next call still resumes them rather
+/// than treating them as done.
+///
+/// The actual per-shard fan-out (dispatching to each shard's storage and
+/// reading its watermark) isn't performed here: that belongs to whatever
+/// drives `ShardHolder::target_shard` in this deployment's top-level
+/// request handler, which this tree doesn't have (no `TableOfContent` /
+/// `Collection` implementation is present to wire it into). This function
+/// is the cluster-wide merge step of that pipeline.
+pub fn merge_shard_scroll_pages(mut pages: Vec<ShardScrollPage>, limit: usize) -> ScrollResult {
+    let page_max_ids: Vec<Option<PointIdType>> = pages
+        .iter()
+        .map(|page| page.points.iter().map(|record| record.id).max())
+        .collect();
+    let mut points: Vec<Record> = pages
+        .iter_mut()
+        .flat_map(|page| page.points.drain(..))
+        .collect();
+    points.sort_by_key(|record| record.id);
+    let truncated = points.len() > limit;
+    points.truncate(limit);
+
+    // A shard whose points were cut off by `limit` resumes right after the
+    // last id kept, so the dropped points come back on the next call.
+    if let (true, Some(cutoff)) = (truncated, points.last().map(|record| record.id)) {
+        for (page, max_id) in pages.iter_mut().zip(page_max_ids) {
+            if max_id.is_some_and(|max_id| max_id > cutoff) {
+                page.position.last_seen_id = Some(cutoff);
+                page.position.exhausted = false;
+            }
+        }
+    }
+
+    let positions = pages.into_iter().map(|page| page.position).collect();
+
+    ScrollResult {
+        points,
+        next_page_offset: None,
+        next_page_token: Some(encode_shard_scroll_cursor(&positions)),
+    }
+}
+
 /// Scroll request - paginate over all points which matches given condition
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct ScrollRequest {
     /// Start ID to read points from.
     pub offset: Option<PointIdType>,
+    /// Opaque cursor returned by a previous scroll, as an alternative to
+    /// `offset`. Takes precedence over `offset` when both are set.
+    #[serde(default)]
+    pub page_token: Option<String>,
     /// Page size. Default: 10
     #[validate(range(min = 1))]
     pub limit: Option<usize>,
@@ -193,6 +430,17 @@ pub struct ScrollRequest {
     pub with_vector: WithVector,
 }
 
+impl ScrollRequest {
+    /// Resolves the effective keyset offset, preferring `page_token` over
+    /// the raw `offset` field when both are present.
+    pub fn resolve_offset(&self) -> Result<Option<PointIdType>, CursorError> {
+        match &self.page_token {
+            Some(token) => decode_point_cursor(token).map(Some),
+            None => Ok(self.offset),
+        }
+    }
+}
+
 impl Default for ScrollRequest {
     fn default() -> Self {
         ScrollRequest {
@@ -213,6 +461,46 @@ pub struct ScrollResult {
     pub points: Vec<Record>,
     /// Offset which should be used to retrieve a next page result
     pub next_page_offset: Option<PointIdType>,
+    /// Opaque, integrity-checked cursor produced by
+    /// [`merge_shard_scroll_pages`] when this result came from fanning a
+    /// scroll out across shards: encodes every shard's own last-seen
+    /// point id and snapshot watermark, so the next call resumes each
+    /// shard exactly where it left off instead of restarting shards whose
+    /// contents shifted. `None` for a single-shard/legacy scroll that only
+    /// has `next_page_offset`.
+    #[serde(default)]
+    pub next_page_token: Option<String>,
+}
+
+/// A [`ScrollResult`] whose points carry a [`LazyRecord`] payload.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LazyScrollResult {
+    pub points: Vec<LazyRecord>,
+    pub next_page_offset: Option<PointIdType>,
+    /// Opaque cursor to pass back as [`ScrollRequest::page_token`]: the
+    /// result's own `next_page_token` when it has one, otherwise
+    /// `next_page_offset` encoded with [`encode_point_cursor`].
+    /// [`ScrollRequest::resolve_offset`] only decodes the latter kind.
+    pub next_page_token: Option<String>,
+}
+
+impl TryFrom<ScrollResult> for LazyScrollResult {
+    type Error = JsonError;
+
+    fn try_from(result: ScrollResult) -> Result<Self, Self::Error> {
+        Ok(LazyScrollResult {
+            next_page_token: result
+                .next_page_token
+                .or_else(|| result.next_page_offset.map(encode_point_cursor)),
+            points: result
+                .points
+                .into_iter()
+                .map(LazyRecord::try_from)
+                .collect::<Result<_, _>>()?,
+            next_page_offset: result.next_page_offset,
+        })
+    }
 }
 
 /// Search request.
@@ -237,6 +525,10 @@ pub struct SearchRequest {
     /// Note: large offset values may cause performance issues.
     #[serde(default)]
     pub offset: usize,
+    /// Opaque cursor from a previous page's response, as an alternative to
+    /// `offset`. Takes precedence over `offset` when both are set.
+    #[serde(default)]
+    pub page_token: Option<String>,
     /// Select which payload to return with the response. Default: None
     pub with_payload: Option<WithPayloadInterface>,
     /// Whether to return the point vector with the result?
@@ -247,6 +539,93 @@ pub struct SearchRequest {
     /// Score of the returned result might be higher or smaller than the threshold depending on the
     /// Distance function used. E.g. for cosine similarity only higher scores will be returned.
     pub score_threshold: Option<ScoreType>,
+    /// Order results by ascending distance to a reference geo point instead
+    /// of vector score, e.g. to list the nearest stores to a user. Applied
+    /// by the caller to the merged top-k, after `limit`/`offset` have
+    /// already trimmed the vector-score-ranked candidates down, so `order_by`
+    /// reorders rather than re-selects the result set.
+    #[serde(default)]
+    pub order_by: Option<GeoOrderBy>,
+}
+
+impl SearchRequest {
+    /// Resolves the effective result offset, preferring `page_token` over
+    /// the raw `offset` field when both are present.
+    pub fn resolve_offset(&self) -> Result<usize, CursorError> {
+        match &self.page_token {
+            Some(token) => decode_offset_cursor(token),
+            None => Ok(self.offset),
+        }
+    }
+}
+
+/// Orders search results by ascending haversine distance from `point` to
+/// each result's resolved value of the indexed geo payload field `key`,
+/// instead of by vector score.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct GeoOrderBy {
+    /// Indexed geo payload field to measure distance against.
+    pub key: PayloadKeyType,
+    /// Reference point results are ordered by ascending distance to.
+    pub point: GeoPoint,
+}
+
+impl GeoOrderBy {
+    /// Re-sorts `points` ascending by distance from `self.point` to each
+    /// point's resolved geo value at `self.key`, breaking ties by descending
+    /// vector score. A point whose payload is missing `key`, or whose value
+    /// at `key` isn't a geo point (or array of them), has no distance and is
+    /// sorted after every point that does; among those, ties also break by
+    /// descending vector score. For an array of geo points, the nearest
+    /// element is used.
+    pub fn sort(&self, points: &mut [ScoredPoint]) {
+        points.sort_by(|a, b| match (self.distance_to(a), self.distance_to(b)) {
+            (Some(a_dist), Some(b_dist)) => a_dist
+                .partial_cmp(&b_dist)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.score.total_cmp(&a.score)),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => b.score.total_cmp(&a.score),
+        });
+    }
+
+    /// Distance in meters from `self.point` to the nearest geo value of
+    /// `self.key` in `point`'s payload, or `None` if the field is missing or
+    /// holds no geo point.
+    fn distance_to(&self, point: &ScoredPoint) -> Option<f64> {
+        let payload = point.payload.as_ref()?;
+        let value = payload.get(self.key.as_str())?;
+        let nearest = match value {
+            serde_json::Value::Array(values) => values
+                .iter()
+                .filter_map(parse_geo_point)
+                .map(|candidate| haversine_distance_meters(&self.point, &candidate))
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)),
+            other => parse_geo_point(other)
+                .map(|candidate| haversine_distance_meters(&self.point, &candidate)),
+        };
+        nearest
+    }
+}
+
+fn parse_geo_point(value: &serde_json::Value) -> Option<GeoPoint> {
+    let obj = value.as_object()?;
+    let lon = obj.get("lon")?.as_f64()?;
+    let lat = obj.get("lat")?.as_f64()?;
+    Some(GeoPoint { lon, lat })
+}
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+fn haversine_distance_meters(a: &GeoPoint, b: &GeoPoint) -> f64 {
+    let lat1 = a.lat.to_radians();
+    let lat2 = b.lat.to_radians();
+    let delta_lat = (b.lat - a.lat).to_radians();
+    let delta_lon = (b.lon - a.lon).to_radians();
+    let h =
+        (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
 }
 
 #[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, Clone)]
@@ -461,6 +840,44 @@ pub struct CountResult {
     pub count: usize,
 }
 
+/// One entry of a heterogeneous query batch.
+///
+/// Unlike [`SearchRequestBatch`] and [`RecommendRequestBatch`], which each
+/// hold a single kind of request, this lets a client combine search,
+/// recommend, scroll, and count requests in one round trip - useful for a UI
+/// that wants, say, the top hits and the total match count in one call.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryRequest {
+    Search(#[validate] SearchRequest),
+    Recommend(#[validate] RecommendRequest),
+    Scroll(#[validate] ScrollRequest),
+    Count(#[validate] CountRequest),
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryBatchRequest {
+    #[validate]
+    pub queries: Vec<QueryRequest>,
+}
+
+/// The result of a single [`QueryRequest`], tagged by which kind of request
+/// produced it so results can be matched back up positionally.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryResult {
+    Search(Vec<ScoredPoint>),
+    Recommend(Vec<ScoredPoint>),
+    Scroll(ScrollResult),
+    Count(CountResult),
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QueryBatchResponse {
+    pub results: Vec<QueryResult>,
+}
+
 #[derive(Error, Debug, Clone)]
 #[error("{0}")]
 pub enum CollectionError {
@@ -561,6 +978,40 @@ impl CollectionError {
     }
 }
 
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+const RETRY_FACTOR: u32 = 2;
+
+/// Re-invokes `operation` until it succeeds or returns a non-[transient](CollectionError::is_transient)
+/// error, retrying transient failures with exponential backoff and jitter
+/// (base 50ms, factor 2, capped at 5s) up to `max_attempts` times in total.
+///
+/// On final failure, the last error is returned as-is, so its backtrace (if
+/// any) is preserved.
+pub async fn retry_with_backoff<T, Fut>(
+    max_attempts: usize,
+    mut operation: impl FnMut() -> Fut,
+) -> Result<T, CollectionError>
+where
+    Fut: Future<Output = Result<T, CollectionError>>,
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < max_attempts && err.is_transient() => {
+                let delay = RETRY_BASE_DELAY
+                    .saturating_mul(RETRY_FACTOR.saturating_pow(attempt as u32 - 1))
+                    .min(RETRY_MAX_DELAY);
+                let jitter = delay.mul_f64(rand::thread_rng().gen_range(0.0..=1.0));
+                tokio::time::sleep(jitter).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 impl From<SystemTimeError> for CollectionError {
     fn from(error: SystemTimeError) -> CollectionError {
         CollectionError::ServiceError {
@@ -710,6 +1161,15 @@ impl From<tonic::Status> for CollectionError {
             tonic::Code::DeadlineExceeded => CollectionError::Timeout {
                 description: format!("Deadline Exceeded: {err}"),
             },
+            tonic::Code::Unavailable => CollectionError::Timeout {
+                description: format!("Unavailable: {err}"),
+            },
+            tonic::Code::ResourceExhausted => CollectionError::Timeout {
+                description: format!("ResourceExhausted: {err}"),
+            },
+            tonic::Code::Aborted => CollectionError::Cancelled {
+                description: format!("Aborted: {err}"),
+            },
             other => CollectionError::ServiceError {
                 error: format!("Tonic status error: {other}"),
                 backtrace: Some(Backtrace::force_capture().to_string()),
@@ -1022,13 +1482,15 @@ impl VectorsConfigDiff {
     /// Returns an error if incompatible.
     pub fn check_vector_names(&self, collection: &CollectionParams) -> CollectionResult<()> {
         for vector_name in self.0.keys() {
-            collection
-                .vectors
-                .get_params(vector_name)
-                .map(|_| ())
-                .ok_or_else(|| OperationError::VectorNameNotExists {
-                    received_name: vector_name.into(),
-                })?;
+            if collection.vectors.get_params(vector_name).is_none() {
+                let known_names = collection.vectors.params_iter().map(|(name, _)| name);
+                return Err(CollectionError::BadInput {
+                    description: format!(
+                        "Vector \"{vector_name}\" does not exist in this collection.{}",
+                        crate::operations::validation::did_you_mean(vector_name, known_names)
+                    ),
+                });
+            }
         }
         Ok(())
     }
@@ -1078,13 +1540,79 @@ pub enum NodeType {
     Listener,
 }
 
+/// Payload field(s) to group by.
+///
+/// A single field behaves as before: one key per distinct value. Multiple
+/// fields form a composite key made of the tuple of their values, the same
+/// way a multi-key storage address is jointly identified by several keys.
+/// If any field contains more than 1 value, all combinations will be used
+/// for grouping. One point can be in multiple groups.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case", untagged)]
+pub enum GroupByField {
+    Single(String),
+    Multi(Vec<String>),
+}
+
+impl GroupByField {
+    /// Iterate over the field names that jointly make up the group key, in order.
+    pub fn fields(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        match self {
+            GroupByField::Single(field) => Box::new(iter::once(field.as_str())),
+            GroupByField::Multi(fields) => Box::new(fields.iter().map(String::as_str)),
+        }
+    }
+
+    /// The field `with_lookup` resolves against, i.e. the first field of the key.
+    pub fn lookup_field(&self) -> &str {
+        match self {
+            GroupByField::Single(field) => field,
+            GroupByField::Multi(fields) => fields.first().map(String::as_str).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<String> for GroupByField {
+    fn from(field: String) -> Self {
+        GroupByField::Single(field)
+    }
+}
+
+impl Validate for GroupByField {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let all_non_empty = match self {
+            GroupByField::Single(field) => !field.is_empty(),
+            GroupByField::Multi(fields) => {
+                !fields.is_empty() && fields.iter().all(|field| !field.is_empty())
+            }
+        };
+
+        if all_non_empty {
+            return Ok(());
+        }
+
+        let mut errors = ValidationErrors::new();
+        errors.add("group_by", ValidationError::new("length"));
+        Err(errors)
+    }
+}
+
 #[derive(Validate, Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct BaseGroupRequest {
     /// Payload field to group by, must be a string or number field.
     /// If the field contains more than 1 value, all values will be used for grouping.
     /// One point can be in multiple groups.
-    #[validate(length(min = 1))]
-    pub group_by: String,
+    ///
+    /// Also accepts an array of fields to group by several fields at once,
+    /// e.g. `["category", "region"]` groups by the combination of both.
+    #[validate]
+    pub group_by: GroupByField,
+
+    /// How to convert the raw `group_by` field value(s) into group keys,
+    /// e.g. parsing a date/time payload and truncating it to a day.
+    /// Defaults to using each raw value as-is.
+    #[serde(default)]
+    pub group_by_conversion: Option<GroupByConversionSpec>,
 
     /// Maximum amount of points to return per group
     #[validate(range(min = 1))]
@@ -1097,3 +1625,14 @@ pub struct BaseGroupRequest {
     /// Look for points in another collection using the group ids
     pub with_lookup: Option<WithLookupInterface>,
 }
+
+impl BaseGroupRequest {
+    /// Checks that `group_by_conversion`, if set, is structurally compatible
+    /// with `group_by`: one conversion per field, each individually well-formed.
+    pub fn check_group_by_conversion(&self) -> CollectionResult<()> {
+        match &self.group_by_conversion {
+            Some(conversion) => conversion.check_compatible(&self.group_by),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,6 +1,56 @@
 use actix_web_validator::error::flatten_errors;
 use validator::{ValidationError, ValidationErrors};
 
+/// Finds the known name closest to `unknown`, for "did you mean ...?"
+/// suggestions on unknown vector and payload-key names.
+///
+/// Returns `None` if there's no candidate, or the closest one is so far from
+/// `unknown` that suggesting it would likely be more confusing than helpful.
+pub fn suggest_closest<'a>(
+    unknown: &str,
+    known: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (unknown.len() / 2).max(1);
+
+    known
+        .into_iter()
+        .map(|candidate| (candidate, levenshtein_distance(unknown, candidate)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
+/// Formats a "did you mean ...?" hint for an unknown name, or an empty
+/// string if no close-enough candidate exists.
+pub fn did_you_mean<'a>(unknown: &str, known: impl IntoIterator<Item = &'a str>) -> String {
+    match suggest_closest(unknown, known) {
+        Some(suggestion) => format!(" Did you mean \"{suggestion}\"?"),
+        None => String::new(),
+    }
+}
+
+/// Classic Levenshtein edit distance between two strings.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut prev_diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let temp = row[j + 1];
+            row[j + 1] = if ca == cb {
+                prev_diagonal
+            } else {
+                1 + prev_diagonal.min(row[j]).min(row[j + 1])
+            };
+            prev_diagonal = temp;
+        }
+    }
+    row[b.len()]
+}
+
 /// Warn about validation errors in the log.
 ///
 /// Validation errors are pretty printed field-by-field.
@@ -119,6 +169,14 @@ mod tests {
         pub things: Vec<SomeThing>,
     }
 
+    #[test]
+    fn test_suggest_closest() {
+        let known = vec!["title", "description", "tags"];
+        assert_eq!(suggest_closest("titel", known.clone()), Some("title"));
+        assert_eq!(suggest_closest("tags", known.clone()), Some("tags"));
+        assert_eq!(suggest_closest("completely_unrelated", known), None);
+    }
+
     #[test]
     fn test_validation() {
         let bad_config = OtherThing {
@@ -0,0 +1,255 @@
+//! Content-defined chunking for resumable, deduplicating shard transfers.
+//!
+//! A shard's serialized segment stream is split into variable-length
+//! chunks using a gear-hash rolling cut-point scheme: a boundary falls
+//! wherever the rolling hash's low [`CUT_BITS`] bits are all zero, and
+//! every chunk is clamped to [`MIN_CHUNK_SIZE`]..[`MAX_CHUNK_SIZE`] so a
+//! run of bytes that never (or immediately) hits the cut mask still
+//! produces well-behaved chunks. Because the boundary only depends on a
+//! local window of bytes, inserting or deleting data elsewhere in the
+//! stream only reshuffles the chunks adjacent to the edit - the rest are
+//! byte-identical to a prior transfer and are identified as such by
+//! [`chunk_hash`], letting [`ChunkedTransferProgress`] skip re-sending
+//! them.
+//!
+//! The exchange protocol this module supports (sender lists chunk
+//! hashes, receiver replies with the subset it lacks, only those bodies
+//! are streamed) is intentionally not implemented here - this tree has no
+//! transport for shard transfers to hang the request/response pair off
+//! of. What's provided is the local half: cutting a stream into chunks,
+//! naming them, and persisting which ones a given [`ShardTransferKey`]
+//! has already confirmed, so that whichever RPC eventually drives the
+//! exchange can resume a dropped connection instead of restarting it.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::operations::types::CollectionResult;
+use crate::save_on_disk::SaveOnDisk;
+use crate::shards::transfer::shard_transfer::ShardTransferKey;
+
+/// A chunk is never emitted smaller than this unless it's the final chunk
+/// of the stream, so the cut mask alone can't degenerate into a storm of
+/// tiny chunks.
+pub const MIN_CHUNK_SIZE: usize = 4 * 1024;
+/// A chunk is always cut at this size even if the rolling hash never
+/// happens to hit the cut mask, bounding how much of a re-transfer a
+/// single unlucky stretch of input can force.
+pub const MAX_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Cut a boundary whenever the rolling hash's low `CUT_BITS` bits are all
+/// zero, i.e. on average every `2^CUT_BITS` bytes - tuned so the average
+/// chunk size lands between [`MIN_CHUNK_SIZE`] and [`MAX_CHUNK_SIZE`].
+const CUT_BITS: u32 = 14; // average chunk size 2^14 = 16 KiB
+const CUT_MASK: u64 = (1 << CUT_BITS) - 1;
+
+/// Gear-hash table: one fixed pseudo-random `u64` per input byte value.
+/// The rolling hash is `hash = (hash << 1) + TABLE[byte as usize]`, the
+/// standard gear hash used by FastCDC - cheaper than Buzhash since it
+/// needs no explicit sliding window or byte-removal step, just a running
+/// accumulator. Fixed (not generated at runtime) so two processes running
+/// the same binary always agree on cut points.
+#[rustfmt::skip]
+const GEAR_TABLE: [u64; 256] = [
+    0x1C948E1575796814, 0xAE9EF1AB67004BDB, 0x7A2988D31F16E86E, 0x7A5DAEA24EBA3BA7,
+    0xBB83C0C2207AD3E6, 0xE2DA71D9F0E79E32, 0xF037B46F16A54449, 0xAFD7E49C4512EE8C,
+    0x25ADE43F8DCFFC85, 0x0028CF578EC6BD94, 0x9F26B835468010BB, 0xB9792DE59DE179E6,
+    0xCA030EF931C393C6, 0x34C690FBF80367A9, 0x5BDDD920E3712B45, 0x7587183F9ED6C5BF,
+    0xAC39BB1F2AA2A8FC, 0xEE1F1C282CDF78CC, 0xEE912E80C0B0B0D3, 0x0149FC107D224EBB,
+    0xB7173F0E17DDD8FB, 0x0818F93AAAFEFBEC, 0xB7B727CAD1BCAC49, 0x0F27C615267DAAFC,
+    0x627E5846E66E1CDC, 0x896C34FCD5C143D5, 0xD86261F86FB4D030, 0x34277192202EFA4B,
+    0xE86163428D79CC4C, 0xCC80491077821E40, 0xD5A79428C5380876, 0x46BB59954A664517,
+    0xD615B473AE917CD1, 0xADA6B9C1AAA299C0, 0x18BE433D79D1001C, 0x7D42902E01E03D3F,
+    0xC336EA240CC55A28, 0x2A6E0C08500E8148, 0x97ADD580A62A5E9F, 0x21A10A7BD4FB549C,
+    0xBD61E521DDAF5E0B, 0x369E55E09758F5AB, 0xD6BD449915FC5DB6, 0xE0EBB372A27D4E0B,
+    0xE881FF7DB53AB26E, 0xB295815C0AD9D50C, 0x29748CEC736E65FA, 0x029D4D575B392925,
+    0x7B5D52485E89F7CE, 0x4A77B5797E686207, 0x3B54BAFA59F120BB, 0x48C5E171D53DCC93,
+    0x8E2A8538B38C614D, 0x9F7A4F5AD14729ED, 0x2100412C2323CFEA, 0x61EC9C0D6FE30A13,
+    0xE7718FB33904E4C5, 0xCA2008B9ACC9EF40, 0xA251E94FC57AA676, 0x263240C61C50D933,
+    0x46D8F93EF7577DD6, 0x9479417DACCDFF6E, 0x5B52165400BD7942, 0x8151AD860E24E2BF,
+    0xE82DE5D9052182C7, 0x97A0A2276751DDD1, 0xC84303A82DB39C9C, 0xE8718E5547F4865D,
+    0x6788C3DABFC84451, 0xB81DF11F951178A2, 0xA872F4FBADC968E8, 0x0F3ACEAD1A0605E9,
+    0x5888FADA257031C6, 0x8674FBBBEA0B4BC8, 0x55AAA61ACEAD6F7C, 0x56B3CB62382F0F8B,
+    0x347125003D5D8155, 0x932EE7FE3A28B65E, 0x5AEC7B1B833A65DE, 0x037672637D06F303,
+    0xF1F08E4D292BA51B, 0x5ED39E20CCE85599, 0x27F6A93CC0DD9A73, 0x2FB423E0FF31BE46,
+    0x04671EB1F06F9C8D, 0x08D6B838FF1CCB41, 0xDAE7598073FDCBD2, 0x2167F5E688770662,
+    0xCF4CDB49ECDDE32D, 0x669ABB2445DA919C, 0x96AEF901DEBB4CA7, 0x48C6F03856A5B723,
+    0xCF6A0B80F476D289, 0x62568D960A1668C2, 0xA2C64B0494DCE97F, 0x601ECB1B34FAD593,
+    0x1C07A82EF3679F73, 0xBE9F9BFEF7C92A49, 0x6C61E7193C8F6A7F, 0xFD956BBC800AB564,
+    0x8AA6044C5433707E, 0xDF326685CEC950F3, 0x9E5B32CC5B43AE70, 0xCCF73827F611D8F4,
+    0x360406225E60D817, 0x87E4A17414ABAD4D, 0x7ED02D9B2AD3100C, 0xEEA05398243753C2,
+    0x41572D3175A6FC7E, 0xF4F73FB0D9380FA7, 0x65C661FB62669E18, 0xE47CF521B0A505E1,
+    0xE4207EF3449D0910, 0x5A504CBD12174279, 0x71BBCED8E97D5DF8, 0x1A537EF2B248C955,
+    0x4171D1D41857DB2B, 0xFE5B86DDF65935E6, 0x28AE9E9D7AB065C6, 0x644A5F1E62BF9BE3,
+    0xA90B7026CD2F1120, 0xB7C6EAB3ABF40F3B, 0xD7769E29A9239AC3, 0x8BA64B6E1E80F0B6,
+    0xFF4083FBA4DE3F85, 0x680FD6D835870118, 0xCAC2BE8C8833AED4, 0xD1A01EEBA6D37400,
+    0x5577099A6EC5A999, 0xCB137103EBE3FFD0, 0xDC25C5AD2B944524, 0xD9E27631EFA8699C,
+    0x686A053001656F59, 0x3263342ED0865172, 0xA49508CE83EAEE7B, 0x53A831D8DB6B1F1F,
+    0x25F7077BA004EAB9, 0xAEF1E66BD8EBFD28, 0x868E17AA682CFD0A, 0x3BD0093CA994A5CA,
+    0x135CDB946E507857, 0x0A912E0BE93B662D, 0xD8ECC4441007C8C1, 0x561E178466B59252,
+    0x2DEF8ED2BEE575F5, 0x1E1E09F42A457DB7, 0x8EC320B9F8CEE28C, 0xD759F8F74596CF14,
+    0xFAB0AC026CEFEEA9, 0xF049455BD5F7ABBA, 0xED9E9412382777FC, 0x8B1203C0A21CC318,
+    0x673BC8068DB2CBBD, 0x4300B1ABBE595484, 0x7878934971175B02, 0x9CFAD36B194DA5F4,
+    0xD9970769A636154C, 0xB1F94FCD55922BD5, 0x7C0EA01C2CB45B2B, 0x9971D632D8EE10D1,
+    0x26C82AF59FEC8B8F, 0x15B8AE154495021A, 0x9A2672445C041A0D, 0x8B357230D0FAC6B0,
+    0x0A04C3630D2DD796, 0x921266F124A1EE12, 0xFF63189C118357F3, 0xB25E46B109239319,
+    0x08D842320598FC51, 0x1EB7BFA516E9C70D, 0xE29B365D9851FBA1, 0x57C138A082EF0741,
+    0x8D3A94D42BC7D7BD, 0xF96E62B9F980ADD1, 0xF5402A5F2B5A8660, 0x44D4F5CBFB1B56B5,
+    0x141C60550A57A2A7, 0x642BEC2AC328DC00, 0xB1C896615F0D8C0B, 0xA2E086FB081D1960,
+    0x6619754E04DFD33C, 0x13A0B00DBDD67818, 0xCD8E62FBC8729760, 0x283EEC042ED5B63B,
+    0xA3EFD3C7D1905547, 0xF1A02042408553DE, 0xB9EE414E7168BE7E, 0x34C2866DA01009EF,
+    0x9583E6772652607B, 0x158C7EA5FDE901DB, 0x7ACADA6411A4A929, 0x853F8CD012E531BA,
+    0x72553849906AD830, 0x7BB792C2E8BC87FD, 0x5CD9A5A6C9CBDBAB, 0xC99D409981D0E564,
+    0x69BC17221FD380F4, 0x61442302A22539A8, 0xD074B99D3A4CF99D, 0x987B6F273B2AE50C,
+    0x3FE733CEAD818809, 0x8DB44F415B71437A, 0x7B753867EE8047FE, 0x6637A45F4301C6F3,
+    0x2E6F055A34D9F81F, 0x244C958624F5385A, 0xDC99A194ADCBFA5D, 0xFB63A3FAFC53F503,
+    0xD3B003D84CF0A1DF, 0x419AE704975EC587, 0x4DBC42ECD43865F6, 0xD78C5568E81ECD88,
+    0x8A8120C194710AEE, 0x5B336727063E2449, 0x00A9B547DD35420A, 0x4C5C2FD3BBBFBC52,
+    0xF78C616A48A6B8F2, 0xF903E17B91E445DD, 0x48431681B5B2E979, 0xEE3314082BB774F9,
+    0x08405A9DC6D83118, 0xBAA2863A8E403EFE, 0x83446CD8B0435298, 0x16C6F534009BAEA8,
+    0xD4D88BA0F66C4ED6, 0x1E765B9CEC74B6C7, 0xFDBFF1BAC7029B8F, 0xBF8CB457D89B670A,
+    0x2642A944EAF70AB8, 0x4E042EA096602653, 0xF76F87E65AA480B4, 0x8C7AF60091FCB7D1,
+    0x981C27559BB9199D, 0x51E575DE83DDC0F2, 0x3926F3D015C99F33, 0x4ED8C3DA363ED7ED,
+    0x07171A1066A58A83, 0x8630C5D201125E14, 0x61C846EAFC217344, 0xA943AAE763132C1F,
+    0xC2C5C9821A867AF3, 0x839F8CB73B93074D, 0xE8267A4B417E5BEC, 0xBF989CDA1062E827,
+    0x6529CEFA105723EE, 0xE86E14386EECFD0D, 0xB40375F2FFE7BDCA, 0xE060479440D55FE4,
+    0x58B0A43EB7563058, 0xDB0224FBAEC22B7F, 0x9B8C29D1647C680F, 0xA62CE73446A8812E,
+    0x43FA52D40917DC4F, 0x7FAB5556671C4FD4, 0xE509D926D2917B19, 0x9680A9FA10C5C35D,
+];
+
+/// Splits `data` into content-defined chunks; see this module's doc
+/// comment. Deterministic: the same bytes always cut into the same
+/// chunks, regardless of where in a larger stream they sit, as long as
+/// enough unchanged context precedes them for the rolling hash to settle.
+pub fn cut_chunks(data: &[u8]) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut hash = 0u64;
+
+    for (i, &byte) in data.iter().enumerate() {
+        let len = i - start + 1;
+        hash = hash.wrapping_shl(1).wrapping_add(GEAR_TABLE[byte as usize]);
+
+        let at_cut_point = len >= MIN_CHUNK_SIZE && hash & CUT_MASK == 0;
+        if at_cut_point || len == MAX_CHUNK_SIZE {
+            chunks.push(&data[start..=i]);
+            start = i + 1;
+            hash = 0;
+        }
+    }
+    if start < data.len() {
+        chunks.push(&data[start..]);
+    }
+    chunks
+}
+
+/// Content hash identifying a chunk, stable across re-transfers of the
+/// same bytes. Uses xxh3, already a dependency of this crate for
+/// snapshot checksums (see
+/// [`collection_manager::holders::snapshot_checksum`](crate::collection_manager::holders::snapshot_checksum)),
+/// rather than a cryptographic hash: collisions are not a security
+/// concern here, only a (negligible, at this width) dedup false-positive
+/// risk.
+pub fn chunk_hash(chunk: &[u8]) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(chunk)
+}
+
+const ACKED_CHUNKS_FILE_PREFIX: &str = "shard_transfer_acked_chunks";
+
+/// Persists, per [`ShardTransferKey`], the set of chunk hashes the
+/// receiving end has already confirmed - so a transfer interrupted by a
+/// network drop resumes by re-sending only the chunk list and streaming
+/// whatever the receiver still reports missing, instead of starting over.
+pub struct ChunkedTransferProgress {
+    acked_chunks: SaveOnDisk<HashSet<u64>>,
+}
+
+impl ChunkedTransferProgress {
+    /// Loads (or initializes) the acknowledged-chunk set for `key`,
+    /// persisted in its own file under `collection_path` so concurrent
+    /// transfers for different keys don't contend on the same save file.
+    pub fn load(collection_path: &Path, key: &ShardTransferKey) -> CollectionResult<Self> {
+        let file_name = format!("{ACKED_CHUNKS_FILE_PREFIX}-{key:?}");
+        let acked_chunks = SaveOnDisk::load_or_init(collection_path.join(file_name))?;
+        Ok(Self { acked_chunks })
+    }
+
+    /// Chunk hashes from `all_chunks` not yet acknowledged - the ones the
+    /// sender still needs to stream.
+    pub fn missing<'a>(&self, all_chunks: &'a [u64]) -> Vec<&'a u64> {
+        let acked = self.acked_chunks.read();
+        all_chunks
+            .iter()
+            .filter(|hash| !acked.contains(hash))
+            .collect()
+    }
+
+    /// Records that `hash` has been received and persisted by the target,
+    /// so a resumed transfer won't re-request it.
+    pub fn acknowledge(&self, hash: u64) -> CollectionResult<()> {
+        Ok(self.acked_chunks.write(|acked| {
+            acked.insert(hash);
+        })?)
+    }
+
+    /// Whether every chunk in `all_chunks` has been acknowledged, i.e. the
+    /// transfer is complete and
+    /// [`register_finish_transfer`](crate::shards::shard_holder::ShardHolder::register_finish_transfer)
+    /// can be called.
+    pub fn is_complete(&self, all_chunks: &[u64]) -> bool {
+        self.missing(all_chunks).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cut_chunks_reassembles_to_the_original() {
+        let data: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+        let chunks = cut_chunks(&data);
+        assert!(chunks.len() > 1);
+        let reassembled: Vec<u8> = chunks.concat();
+        assert_eq!(reassembled, data);
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(chunk.len() >= MIN_CHUNK_SIZE);
+            assert!(chunk.len() <= MAX_CHUNK_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_insertion_only_reshuffles_nearby_chunks() {
+        // Pseudo-random bytes (xorshift64): a short repeating pattern never
+        // hits the cut mask, so every chunk would be cut at MAX_CHUNK_SIZE
+        // and an insertion would shift every later chunk.
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let data: Vec<u8> = (0..200_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 56) as u8
+            })
+            .collect();
+        let original_hashes: Vec<u64> = cut_chunks(&data).iter().map(|c| chunk_hash(c)).collect();
+
+        let mut edited = data.clone();
+        edited.splice(100_000..100_000, std::iter::repeat(7u8).take(37));
+        let edited_hashes: Vec<u64> = cut_chunks(&edited).iter().map(|c| chunk_hash(c)).collect();
+
+        let unchanged_prefix = original_hashes
+            .iter()
+            .zip(edited_hashes.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        assert!(unchanged_prefix > 0);
+        let unchanged_suffix = original_hashes
+            .iter()
+            .rev()
+            .zip(edited_hashes.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        assert!(unchanged_suffix > 0);
+    }
+}
@@ -0,0 +1,397 @@
+//! Pluggable backend for cluster metadata (`shard_transfers`, shard
+//! configs, ...) that today is persisted one local file per key via
+//! `SaveOnDisk`. A single node's local files can't give several nodes a
+//! shared, transactional view of the same state, so [`ShardHolder`]
+//! depends on the [`MetadataStore`] trait instead of a concrete file path:
+//! [`FileMetadataStore`] keeps today's one-file-per-key behavior as the
+//! default, and [`PooledSqlMetadataStore`] gives a multi-node deployment a
+//! single authoritative store behind a bounded connection pool.
+//!
+//! [`MetadataStore`]'s own methods work in raw bytes rather than a
+//! generic `T: Serialize + Deserialize`, so `Arc<dyn MetadataStore>` stays
+//! object-safe; [`load`], [`store`] and [`update`] are the typed
+//! convenience wrappers callers actually use, mirroring how
+//! [`crate::save_on_disk::SaveOnDisk`] serializes to JSON under the hood.
+//!
+//! [`ShardHolder`]: crate::shards::shard_holder::ShardHolder
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use atomicwrites::AtomicFile;
+use atomicwrites::OverwriteBehavior::AllowOverwrite;
+use parking_lot::Mutex;
+use rusqlite::OptionalExtension;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::Semaphore;
+
+use crate::operations::types::{CollectionError, CollectionResult};
+
+#[async_trait]
+pub trait MetadataStore: Send + Sync {
+    async fn load_raw(&self, key: &str) -> CollectionResult<Option<Vec<u8>>>;
+
+    async fn store_raw(&self, key: &str, value: Vec<u8>) -> CollectionResult<()>;
+
+    /// Atomically loads the current bytes for `key` (or `None` if absent),
+    /// applies `f`, and persists the result - holding whatever lock or
+    /// transaction the implementation needs so no concurrent `update_raw`
+    /// for the same key interleaves, giving `load`-`modify`-`store` its
+    /// compare-and-swap guarantee. [`PooledSqlMetadataStore`] holds it
+    /// across processes; [`FileMetadataStore`] only within one process.
+    async fn update_raw(
+        &self,
+        key: &str,
+        f: Box<dyn FnOnce(Option<Vec<u8>>) -> Vec<u8> + Send>,
+    ) -> CollectionResult<Vec<u8>>;
+}
+
+/// Loads and deserializes `key`, or `None` if it has never been stored.
+pub async fn load<T: DeserializeOwned>(
+    store: &dyn MetadataStore,
+    key: &str,
+) -> CollectionResult<Option<T>> {
+    match store.load_raw(key).await? {
+        Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Serializes and stores `value` under `key`, overwriting any prior value.
+pub async fn store<T: Serialize + Sync>(
+    store: &dyn MetadataStore,
+    key: &str,
+    value: &T,
+) -> CollectionResult<()> {
+    store.store_raw(key, serde_json::to_vec(value)?).await
+}
+
+/// Loads `key` (defaulting to `T::default()` if absent), applies `f`, and
+/// stores the result - see [`MetadataStore::update_raw`] for the
+/// atomicity this gives. Returns whatever `f` computed
+/// about the update (e.g. "was this the first insertion"), not the
+/// updated value itself - callers that need the new value can have `f`
+/// return a clone of it.
+///
+/// `f` is boxed as `FnOnce(Option<Vec<u8>>) -> Vec<u8>` by
+/// [`MetadataStore::update_raw`], which has no room to carry `R` back out,
+/// so `R` is smuggled through a `Mutex` slot that `f` is guaranteed to
+/// fill exactly once before `update_raw` returns.
+pub async fn update<T, F, R>(store: &dyn MetadataStore, key: &str, f: F) -> CollectionResult<R>
+where
+    T: Serialize + DeserializeOwned + Default + Send + 'static,
+    R: Send + 'static,
+    F: FnOnce(&mut T) -> R + Send + 'static,
+{
+    let result_slot: Arc<std::sync::Mutex<Option<R>>> = Arc::new(std::sync::Mutex::new(None));
+    let result_slot_clone = result_slot.clone();
+    store
+        .update_raw(
+            key,
+            Box::new(move |current| {
+                let mut value: T = current
+                    .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                    .unwrap_or_default();
+                let result = f(&mut value);
+                *result_slot_clone.lock().unwrap() = Some(result);
+                // `update_raw` impls only deal in bytes; a value that
+                // fails to serialize here is a bug in `T`'s `Serialize`
+                // impl, not a runtime condition callers can recover from.
+                serde_json::to_vec(&value).expect("metadata value must serialize")
+            }),
+        )
+        .await?;
+    Ok(result_slot
+        .lock()
+        .unwrap()
+        .take()
+        .expect("update_raw always invokes f exactly once"))
+}
+
+/// Default [`MetadataStore`]: one file per key under `root`, written with
+/// the same atomic-rename-on-write behavior as
+/// [`CollectionConfig::save`](crate::config::CollectionConfig::save).
+/// `update_raw` is serialized by an in-process lock rather than a true
+/// cross-process CAS, matching `SaveOnDisk`'s existing single-writer
+/// assumption - good enough for one node, not for several sharing `root`.
+pub struct FileMetadataStore {
+    root: PathBuf,
+    update_lock: tokio::sync::Mutex<()>,
+}
+
+impl FileMetadataStore {
+    pub fn new(root: PathBuf) -> Self {
+        FileMetadataStore {
+            root,
+            update_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl MetadataStore for FileMetadataStore {
+    async fn load_raw(&self, key: &str) -> CollectionResult<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path_for(key)).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(CollectionError::from(err)),
+        }
+    }
+
+    async fn store_raw(&self, key: &str, value: Vec<u8>) -> CollectionResult<()> {
+        let path = self.path_for(key);
+        tokio::task::spawn_blocking(move || {
+            AtomicFile::new(&path, AllowOverwrite)
+                .write(|f| std::io::Write::write_all(f, &value))
+                .map_err(|err| {
+                    CollectionError::service_error(format!("Can't write {path:?}, error: {err}"))
+                })
+        })
+        .await
+        .map_err(|err| CollectionError::service_error(format!("store_raw task panicked: {err}")))?
+    }
+
+    async fn update_raw(
+        &self,
+        key: &str,
+        f: Box<dyn FnOnce(Option<Vec<u8>>) -> Vec<u8> + Send>,
+    ) -> CollectionResult<Vec<u8>> {
+        let _guard = self.update_lock.lock().await;
+        let current = self.load_raw(key).await?;
+        let updated = f(current);
+        self.store_raw(key, updated.clone()).await?;
+        Ok(updated)
+    }
+}
+
+/// SQL-backed [`MetadataStore`] for multi-node deployments: a small,
+/// bounded pool of blocking `rusqlite` connections (acquired via a
+/// semaphore so checkout has a real timeout rather than queuing forever),
+/// each operation dispatched to a blocking task since `rusqlite` has no
+/// async API. `update_raw` wraps its read-modify-write in a single
+/// `BEGIN IMMEDIATE` transaction so SQLite serializes concurrent updates
+/// to the same database, giving cross-process CAS semantics - the
+/// property [`FileMetadataStore`] can't offer.
+pub struct PooledSqlMetadataStore {
+    connections: Arc<Mutex<VecDeque<rusqlite::Connection>>>,
+    semaphore: Arc<Semaphore>,
+    acquire_timeout: Duration,
+}
+
+const CREATE_METADATA_TABLE: &str = "CREATE TABLE IF NOT EXISTS metadata (\
+     key TEXT PRIMARY KEY, \
+     value BLOB NOT NULL\
+ )";
+
+impl PooledSqlMetadataStore {
+    /// Opens `pool_size` connections to the SQLite database at
+    /// `database_path` (created if missing) and runs the metadata table's
+    /// DDL once up front.
+    pub fn open(
+        database_path: &std::path::Path,
+        pool_size: usize,
+        acquire_timeout: Duration,
+    ) -> CollectionResult<Self> {
+        let mut connections = VecDeque::with_capacity(pool_size);
+        for _ in 0..pool_size {
+            let conn = rusqlite::Connection::open(database_path).map_err(sqlite_error)?;
+            conn.execute(CREATE_METADATA_TABLE, [])
+                .map_err(sqlite_error)?;
+            connections.push_back(conn);
+        }
+        Ok(PooledSqlMetadataStore {
+            semaphore: Arc::new(Semaphore::new(pool_size)),
+            connections: Arc::new(Mutex::new(connections)),
+            acquire_timeout,
+        })
+    }
+
+    /// Checks out a connection, bounded by `acquire_timeout` so a pool
+    /// exhausted by a burst of concurrent callers fails fast with
+    /// [`CollectionError::Timeout`] instead of queuing indefinitely.
+    async fn acquire(&self) -> CollectionResult<PooledConnection> {
+        let permit =
+            tokio::time::timeout(self.acquire_timeout, self.semaphore.clone().acquire_owned())
+                .await
+                .map_err(|_| CollectionError::Timeout {
+                    description: "timed out acquiring a metadata store connection".to_string(),
+                })?
+                .expect("semaphore is never closed");
+
+        let conn = self
+            .connections
+            .lock()
+            .pop_front()
+            .expect("a permit implies a connection is available");
+
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self.connections.clone(),
+            _permit: permit,
+        })
+    }
+}
+
+/// A checked-out connection; returns itself to the pool on drop.
+struct PooledConnection {
+    conn: Option<rusqlite::Connection>,
+    pool: Arc<Mutex<VecDeque<rusqlite::Connection>>>,
+    _permit: tokio::sync::OwnedSemaphorePermit,
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.lock().push_back(conn);
+        }
+    }
+}
+
+fn sqlite_error(err: rusqlite::Error) -> CollectionError {
+    CollectionError::service_error(format!("metadata store sqlite error: {err}"))
+}
+
+#[async_trait]
+impl MetadataStore for PooledSqlMetadataStore {
+    async fn load_raw(&self, key: &str) -> CollectionResult<Option<Vec<u8>>> {
+        let pooled = self.acquire().await?;
+        let key = key.to_string();
+        tokio::task::spawn_blocking(move || {
+            let conn = pooled.conn.as_ref().unwrap();
+            conn.query_row("SELECT value FROM metadata WHERE key = ?1", [&key], |row| {
+                row.get::<_, Vec<u8>>(0)
+            })
+            .optional()
+            .map_err(sqlite_error)
+        })
+        .await
+        .map_err(|err| CollectionError::service_error(format!("load_raw task panicked: {err}")))?
+    }
+
+    async fn store_raw(&self, key: &str, value: Vec<u8>) -> CollectionResult<()> {
+        let pooled = self.acquire().await?;
+        let key = key.to_string();
+        tokio::task::spawn_blocking(move || {
+            let conn = pooled.conn.as_ref().unwrap();
+            conn.execute(
+                "INSERT INTO metadata (key, value) VALUES (?1, ?2) \
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                rusqlite::params![key, value],
+            )
+            .map_err(sqlite_error)?;
+            Ok(())
+        })
+        .await
+        .map_err(|err| CollectionError::service_error(format!("store_raw task panicked: {err}")))?
+    }
+
+    async fn update_raw(
+        &self,
+        key: &str,
+        f: Box<dyn FnOnce(Option<Vec<u8>>) -> Vec<u8> + Send>,
+    ) -> CollectionResult<Vec<u8>> {
+        let pooled = self.acquire().await?;
+        let key = key.to_string();
+        tokio::task::spawn_blocking(move || {
+            let conn = pooled.conn.as_ref().unwrap();
+            conn.execute("BEGIN IMMEDIATE", []).map_err(sqlite_error)?;
+
+            let current = conn
+                .query_row("SELECT value FROM metadata WHERE key = ?1", [&key], |row| {
+                    row.get::<_, Vec<u8>>(0)
+                })
+                .optional()
+                .map_err(sqlite_error)?;
+            let updated = f(current);
+
+            conn.execute(
+                "INSERT INTO metadata (key, value) VALUES (?1, ?2) \
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                rusqlite::params![key, updated],
+            )
+            .map_err(sqlite_error)?;
+            conn.execute("COMMIT", []).map_err(sqlite_error)?;
+
+            Ok(updated)
+        })
+        .await
+        .map_err(|err| CollectionError::service_error(format!("update_raw task panicked: {err}")))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct Counter {
+        value: u64,
+    }
+
+    #[tokio::test]
+    async fn test_file_store_round_trips_and_updates() {
+        let dir = tempdir().unwrap();
+        let store = FileMetadataStore::new(dir.path().to_path_buf());
+
+        assert_eq!(load::<Counter>(&store, "counter").await.unwrap(), None);
+
+        store
+            .store_raw(
+                "counter",
+                serde_json::to_vec(&Counter { value: 1 }).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            load::<Counter>(&store, "counter").await.unwrap(),
+            Some(Counter { value: 1 })
+        );
+
+        let updated: Counter = update(&store, "counter", |c: &mut Counter| {
+            c.value += 1;
+            c.clone()
+        })
+        .await
+        .unwrap();
+        assert_eq!(updated, Counter { value: 2 });
+    }
+
+    #[tokio::test]
+    async fn test_pooled_sql_store_round_trips_and_updates() {
+        let dir = tempdir().unwrap();
+        let sql_store = PooledSqlMetadataStore::open(
+            &dir.path().join("metadata.db"),
+            2,
+            Duration::from_secs(1),
+        )
+        .unwrap();
+
+        assert_eq!(load::<Counter>(&sql_store, "counter").await.unwrap(), None);
+
+        store::<Counter>(&sql_store, "counter", &Counter { value: 1 })
+            .await
+            .unwrap();
+        assert_eq!(
+            load::<Counter>(&sql_store, "counter").await.unwrap(),
+            Some(Counter { value: 1 })
+        );
+
+        let updated: Counter = update(&sql_store, "counter", |c: &mut Counter| {
+            c.value += 1;
+            c.clone()
+        })
+        .await
+        .unwrap();
+        assert_eq!(updated, Counter { value: 2 });
+    }
+}
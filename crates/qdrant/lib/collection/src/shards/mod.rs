@@ -1,12 +1,18 @@
 pub mod channel_service;
+pub mod chunked_transfer;
 pub mod collection_shard_distribution;
 mod conversions;
 pub mod dummy_shard;
 pub mod forward_proxy_shard;
+pub mod layout;
 pub mod local_shard;
 pub mod local_shard_operations;
+pub mod merkle_tree;
+pub mod metadata_store;
+pub mod or_set;
 pub mod proxy_shard;
 pub mod remote_shard;
+pub mod repair;
 #[allow(dead_code)]
 pub mod replica_set;
 pub mod resolve;
@@ -17,6 +23,7 @@ pub mod shard_trait;
 pub mod shard_versioning;
 pub mod telemetry;
 pub mod transfer;
+pub mod wal_watch;
 
 use std::path::{Path, PathBuf};
 
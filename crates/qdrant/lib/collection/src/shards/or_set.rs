@@ -0,0 +1,185 @@
+//! Add-wins observed-remove set (OR-Set) CRDT, so two peers that each
+//! mutate their own copy of a set (e.g. [`ShardHolder::shard_transfers`])
+//! independently during a network partition can [`OrSet::merge`] their
+//! copies back into one without last-writer-wins file persistence
+//! silently dropping a transfer one side started, or resurrecting one the
+//! other side already finished.
+//!
+//! Every insertion is tagged with the inserting peer's id and a local
+//! monotonic counter, so the same logical insertion is never confused
+//! with another. Removal doesn't delete the insertion outright - it
+//! records the tags observed live *at removal time* as tombstones. An
+//! element is present iff at least one of its tags isn't tombstoned, so a
+//! concurrent insert (a fresh tag the remover never saw, and so never
+//! tombstoned) always wins over a concurrent remove.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::shards::shard::PeerId;
+
+/// Uniquely identifies one insertion: the peer that made it, and that
+/// peer's local counter at the time - never reused, even if the same
+/// value is inserted again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Tag {
+    pub peer_id: PeerId,
+    pub counter: u64,
+}
+
+/// See this module's doc comment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "T: Serialize + DeserializeOwned")]
+pub struct OrSet<T> {
+    /// Every insertion ever made, live or since removed; membership is
+    /// determined by cross-referencing against `tombstones`, not by
+    /// deleting from here.
+    adds: HashMap<Tag, T>,
+    tombstones: HashSet<Tag>,
+    /// This replica's next counter for [`OrSet::insert`]; peers track
+    /// their own independently, so no coordination is needed to hand out
+    /// unique tags.
+    next_counter: u64,
+}
+
+impl<T> Default for OrSet<T> {
+    fn default() -> Self {
+        OrSet {
+            adds: HashMap::new(),
+            tombstones: HashSet::new(),
+            next_counter: 0,
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> OrSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `value`, tagged with `peer_id` and this replica's next
+    /// counter. A concurrent removal on another replica can never observe
+    /// this tag (it didn't exist yet), so it can never tombstone it - add
+    /// wins.
+    pub fn insert(&mut self, peer_id: PeerId, value: T) -> Tag {
+        let tag = Tag {
+            peer_id,
+            counter: self.next_counter,
+        };
+        self.next_counter += 1;
+        self.adds.insert(tag, value);
+        tag
+    }
+
+    /// Tombstones every live tag currently matching `predicate`, removing
+    /// them from the visible set. Returns whether anything was removed.
+    pub fn remove_matching(&mut self, mut predicate: impl FnMut(&T) -> bool) -> bool {
+        let matching_tags: Vec<Tag> = self
+            .iter_tagged()
+            .filter(|(_, value)| predicate(value))
+            .map(|(tag, _)| tag)
+            .collect();
+        let removed = !matching_tags.is_empty();
+        self.tombstones.extend(matching_tags);
+        removed
+    }
+
+    /// Live (tag, value) pairs: insertions whose tag hasn't been
+    /// tombstoned.
+    fn iter_tagged(&self) -> impl Iterator<Item = (Tag, &T)> {
+        self.adds
+            .iter()
+            .filter(|(tag, _)| !self.tombstones.contains(tag))
+            .map(|(&tag, value)| (tag, value))
+    }
+
+    /// The live elements, deduplicated - if the same value was inserted
+    /// under more than one still-live tag, it's yielded once.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let mut seen = HashSet::new();
+        let mut values: Vec<&T> = Vec::new();
+        for (_, value) in self.iter_tagged() {
+            if seen.insert(value) {
+                values.push(value);
+            }
+        }
+        values.into_iter()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.iter_tagged().any(|(_, v)| v == value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter_tagged().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Merges `other`'s insertions and tombstones into `self`: the union
+    /// of both replicas' adds and the union of both replicas' tombstones.
+    /// Commutative, associative and idempotent, so it's safe to call
+    /// repeatedly (e.g. on every gossip round) in any order.
+    pub fn merge(&mut self, other: &OrSet<T>) {
+        for (&tag, value) in other.adds.iter() {
+            self.adds.entry(tag).or_insert_with(|| value.clone());
+        }
+        self.tombstones.extend(other.tombstones.iter().copied());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_wins_over_concurrent_remove() {
+        let mut a = OrSet::new();
+        a.insert(1, "transfer-x");
+
+        // `b` starts as a merge of `a` (so it's seen the insert), then
+        // removes it.
+        let mut b = a.clone();
+        b.remove_matching(|v| *v == "transfer-x");
+
+        // Meanwhile, `a` re-inserts the same value under a fresh tag
+        // concurrently with `b`'s remove - `a` never observed `b`'s
+        // removal and so never tombstoned the new tag.
+        a.insert(1, "transfer-x");
+
+        a.merge(&b);
+        assert!(a.contains(&"transfer-x"));
+    }
+
+    #[test]
+    fn test_remove_then_merge_drops_the_element_if_not_reinserted() {
+        let mut a = OrSet::new();
+        a.insert(1, "transfer-x");
+        let mut b = a.clone();
+        b.remove_matching(|v| *v == "transfer-x");
+
+        a.merge(&b);
+        assert!(!a.contains(&"transfer-x"));
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let mut a = OrSet::new();
+        a.insert(1, "x");
+        let mut b = OrSet::new();
+        b.insert(2, "y");
+
+        let mut ab = a.clone();
+        ab.merge(&b);
+        let mut ba = b.clone();
+        ba.merge(&a);
+
+        assert_eq!(ab.contains(&"x"), ba.contains(&"x"));
+        assert_eq!(ab.contains(&"y"), ba.contains(&"y"));
+    }
+}
@@ -0,0 +1,272 @@
+//! Merkle-tree anti-entropy repair between replica set members.
+//!
+//! [`ShardReplicaSet`](super::replica_set::ShardReplicaSet) has no way to
+//! notice silent divergence between replicas short of a full rescan, which
+//! doesn't scale to large shards. This module partitions a shard's point-id
+//! space into a fixed number of [`Bucket`]s (the same `HASH_RING_SHARD_SCALE`
+//! used to size the collection's hash ring, so bucket granularity stays
+//! consistent with how shards themselves are sized), hashes each bucket's
+//! sorted `(point_id, version)` pairs, and arranges the bucket hashes into a
+//! [`MerkleTree`] so two replicas can compare a single root hash and, on
+//! mismatch, descend only into the diverging subtrees - `O(divergence)`
+//! instead of `O(shard size)`. [`diff_bucket`] does the actual per-bucket
+//! reconciliation once a leaf is known to differ, deciding which points each
+//! side is missing or stale on.
+//!
+//! Modeled on Garage's resync/repair. This module is the comparison and
+//! planning engine that a repair RPC handler would drive - the RPC itself
+//! isn't added here because this snapshot's `api` crate has no generated
+//! `PointsInternal` service code to extend, and pulling a stale point still
+//! goes through the shard's existing `sync` path (unchanged by this module).
+
+use std::cmp::Ordering;
+
+use segment::types::{PointIdType, SeqNumberType};
+
+use crate::shards::HASH_RING_SHARD_SCALE;
+
+pub type Bucket = u32;
+
+/// Number of leaf buckets a shard's point-id space is partitioned into.
+/// Reuses [`HASH_RING_SHARD_SCALE`] so bucket granularity tracks the same
+/// scale the collection already partitions shards at.
+pub const BUCKET_COUNT: Bucket = HASH_RING_SHARD_SCALE;
+
+/// A point's reconciliation-relevant state within a bucket: its id, the
+/// operation/WAL version it was last written at, and whether that write was
+/// a delete. Tombstones participate in hashing and diffing like any other
+/// point, so a point deleted on one replica but still present on another is
+/// detected as a divergence rather than silently ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointVersion {
+    pub point_id: PointIdType,
+    pub version: SeqNumberType,
+    pub deleted: bool,
+}
+
+/// Hashes `point_id` into one of [`BUCKET_COUNT`] buckets.
+pub fn bucket_of(point_id: PointIdType) -> Bucket {
+    let digest = xxhash_rust::xxh3::xxh3_64(&point_id_bytes(point_id));
+    (digest % BUCKET_COUNT as u64) as Bucket
+}
+
+/// Serializes a [`PointIdType`] to a fixed-width byte form suitable for
+/// hashing: a tag byte distinguishing `NumId`/`Uuid` (so the two id spaces
+/// never collide with each other) followed by the id's own bits.
+fn point_id_bytes(point_id: PointIdType) -> [u8; 17] {
+    let mut bytes = [0u8; 17];
+    match point_id {
+        PointIdType::NumId(num_id) => {
+            bytes[0] = 0;
+            bytes[1..9].copy_from_slice(&num_id.to_le_bytes());
+        }
+        PointIdType::Uuid(uuid) => {
+            bytes[0] = 1;
+            let (high, low) = uuid.as_u64_pair();
+            bytes[1..9].copy_from_slice(&high.to_le_bytes());
+            bytes[9..17].copy_from_slice(&low.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Hashes one bucket's points. Sorts by `point_id` first so the hash is
+/// independent of the order points were collected in, since two replicas
+/// will virtually never have scanned their storage in the same order.
+pub fn hash_bucket(points: &mut [PointVersion]) -> u64 {
+    points.sort_unstable_by_key(|point| point.point_id);
+    let mut bytes = Vec::with_capacity(points.len() * 26);
+    for point in points.iter() {
+        bytes.extend_from_slice(&point_id_bytes(point.point_id));
+        bytes.push(u8::from(point.deleted));
+        bytes.extend_from_slice(&point.version.to_le_bytes());
+    }
+    xxhash_rust::xxh3::xxh3_64(&bytes)
+}
+
+/// Hash used to pad a level to an even width when building a
+/// [`MerkleTree`], and read in place of a node missing from a level. It is
+/// not the leaf hash of an empty bucket: [`hash_bucket`] of no points hashes
+/// an empty input.
+const EMPTY_HASH: u64 = 0;
+
+/// A balanced binary tree over a shard's [`BUCKET_COUNT`] bucket hashes:
+/// `leaves` is level 0, and each subsequent level hashes pairs of the level
+/// below until a single root remains. An odd-width level is padded with
+/// [`EMPTY_HASH`] so every internal node always has two children.
+pub struct MerkleTree {
+    levels: Vec<Vec<u64>>,
+}
+
+impl MerkleTree {
+    /// Builds a tree over `bucket_hashes`, indexed by [`Bucket`] (i.e.
+    /// `bucket_hashes[b]` is bucket `b`'s hash from [`hash_bucket`]).
+    pub fn build(bucket_hashes: Vec<u64>) -> Self {
+        let mut levels = vec![bucket_hashes];
+        while levels.last().unwrap().len() > 1 {
+            let below = levels.last().unwrap();
+            let mut above = Vec::with_capacity(below.len().div_ceil(2));
+            for pair in below.chunks(2) {
+                let left = pair[0];
+                let right = pair.get(1).copied().unwrap_or(EMPTY_HASH);
+                above.push(combine(left, right));
+            }
+            levels.push(above);
+        }
+        Self { levels }
+    }
+
+    pub fn root(&self) -> u64 {
+        *self.levels.last().unwrap().first().unwrap_or(&EMPTY_HASH)
+    }
+
+    /// Returns the buckets whose hash differs between `self` and `other`,
+    /// found by descending only into subtrees whose node hash disagrees -
+    /// matching subtrees are skipped entirely. `self` and `other` must have
+    /// been built over the same `bucket_hashes` length (i.e. the same
+    /// [`BUCKET_COUNT`]).
+    pub fn diverging_buckets(&self, other: &MerkleTree) -> Vec<Bucket> {
+        let mut diverging = Vec::new();
+        if self.root() != other.root() {
+            self.collect_diverging(other, self.levels.len() - 1, 0, &mut diverging);
+        }
+        diverging
+    }
+
+    fn collect_diverging(
+        &self,
+        other: &MerkleTree,
+        level: usize,
+        index: usize,
+        out: &mut Vec<Bucket>,
+    ) {
+        let ours = self.levels[level].get(index).copied().unwrap_or(EMPTY_HASH);
+        let theirs = other.levels[level]
+            .get(index)
+            .copied()
+            .unwrap_or(EMPTY_HASH);
+        if ours == theirs {
+            return;
+        }
+        if level == 0 {
+            out.push(index as Bucket);
+            return;
+        }
+        self.collect_diverging(other, level - 1, index * 2, out);
+        self.collect_diverging(other, level - 1, index * 2 + 1, out);
+    }
+}
+
+fn combine(left: u64, right: u64) -> u64 {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&left.to_le_bytes());
+    bytes[8..].copy_from_slice(&right.to_le_bytes());
+    xxhash_rust::xxh3::xxh3_64(&bytes)
+}
+
+/// One point whose state disagrees between the two replicas being
+/// compared, and which side holds the version to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    pub point_id: PointIdType,
+    /// `true` if `local` is the stale/missing side and should pull from the
+    /// remote; `false` if `local` is ahead and the remote should pull.
+    pub local_is_stale: bool,
+}
+
+/// Compares a single bucket's points between `local` and `remote` (already
+/// known to diverge via [`MerkleTree::diverging_buckets`]) and decides, per
+/// point, which side is missing or stale. Both slices are sorted by
+/// `point_id` first, same as [`hash_bucket`], so this is a linear merge
+/// rather than a quadratic comparison.
+pub fn diff_bucket(local: &mut [PointVersion], remote: &mut [PointVersion]) -> Vec<Divergence> {
+    local.sort_unstable_by_key(|point| point.point_id);
+    remote.sort_unstable_by_key(|point| point.point_id);
+
+    let mut divergences = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < local.len() || j < remote.len() {
+        match (local.get(i), remote.get(j)) {
+            (Some(l), Some(r)) => match l.point_id.cmp(&r.point_id) {
+                Ordering::Equal => {
+                    if l.version != r.version || l.deleted != r.deleted {
+                        divergences.push(Divergence {
+                            point_id: l.point_id,
+                            local_is_stale: l.version < r.version,
+                        });
+                    }
+                    i += 1;
+                    j += 1;
+                }
+                Ordering::Less => {
+                    // Only local has this point - remote is missing it (stale).
+                    divergences.push(Divergence {
+                        point_id: l.point_id,
+                        local_is_stale: false,
+                    });
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    // Only remote has this point - local is missing it (stale).
+                    divergences.push(Divergence {
+                        point_id: r.point_id,
+                        local_is_stale: true,
+                    });
+                    j += 1;
+                }
+            },
+            (Some(l), None) => {
+                divergences.push(Divergence {
+                    point_id: l.point_id,
+                    local_is_stale: false,
+                });
+                i += 1;
+            }
+            (None, Some(r)) => {
+                divergences.push(Divergence {
+                    point_id: r.point_id,
+                    local_is_stale: true,
+                });
+                j += 1;
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    divergences
+}
+
+/// Per-bucket outcome of a repair pass, as reported back by the (not yet
+/// implemented) trigger RPC: how many points were found to diverge and were
+/// reconciled for that bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BucketRepairReport {
+    pub bucket: Bucket,
+    pub points_reconciled: usize,
+}
+
+/// Resumable, abortable cursor over a repair pass's remaining buckets.
+/// Each call to [`Self::next_bucket`] advances past one bucket, so a repair
+/// can be paused (the caller just stops calling) and resumed later from the
+/// same [`Bucket`] index, or aborted outright by dropping the cursor - no
+/// partial bucket state is held across calls.
+pub struct RepairCursor {
+    next: Bucket,
+}
+
+impl RepairCursor {
+    /// Starts (or resumes) a repair pass at `from_bucket`.
+    pub fn resume_from(from_bucket: Bucket) -> Self {
+        Self { next: from_bucket }
+    }
+
+    /// Returns the next bucket to compare, or `None` once every bucket up
+    /// to [`BUCKET_COUNT`] has been visited.
+    pub fn next_bucket(&mut self) -> Option<Bucket> {
+        if self.next >= BUCKET_COUNT {
+            return None;
+        }
+        let bucket = self.next;
+        self.next += 1;
+        Some(bucket)
+    }
+}
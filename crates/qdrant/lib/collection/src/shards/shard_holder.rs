@@ -2,17 +2,22 @@ use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
+use segment::types::PointIdType;
 use tokio::runtime::Handle;
 use tokio::sync::RwLock;
 
 use crate::config::CollectionConfig;
 use crate::hash_ring::HashRing;
 use crate::operations::shared_storage_config::SharedStorageConfig;
-use crate::operations::types::{CollectionResult, ShardTransferInfo};
+use crate::operations::types::{
+    CollectionClusterInfo, CollectionResult, LocalShardInfo, RemoteShardInfo, ShardTransferInfo,
+};
 use crate::operations::{OperationToShard, SplitByShard};
-use crate::save_on_disk::SaveOnDisk;
 use crate::shards::channel_service::ChannelService;
 use crate::shards::local_shard::LocalShard;
+use crate::shards::merkle_tree::{LeafDivergence, MerkleTree};
+use crate::shards::metadata_store::{self, FileMetadataStore, MetadataStore};
+use crate::shards::or_set::OrSet;
 use crate::shards::replica_set::{ChangePeerState, ReplicaState, ShardReplicaSet};
 use crate::shards::shard::{PeerId, ShardId};
 use crate::shards::shard_config::{ShardConfig, ShardType};
@@ -20,22 +25,44 @@ use crate::shards::shard_versioning::latest_shard_paths;
 use crate::shards::transfer::shard_transfer::{ShardTransfer, ShardTransferKey};
 use crate::shards::CollectionId;
 
-const SHARD_TRANSFERS_FILE: &str = "shard_transfers";
+const SHARD_TRANSFERS_KEY: &str = "shard_transfers";
+const POINT_MERKLE_TREES_KEY: &str = "point_merkle_trees";
 
 pub struct ShardHolder {
     shards: HashMap<ShardId, ShardReplicaSet>,
-    pub(crate) shard_transfers: SaveOnDisk<HashSet<ShardTransfer>>,
+    /// Backs the shard-transfer set (keyed by [`SHARD_TRANSFERS_KEY`]) and
+    /// the per-shard Merkle trees (keyed by [`POINT_MERKLE_TREES_KEY`]).
+    /// Defaults to [`FileMetadataStore`] (one local file per key, same as
+    /// the old `SaveOnDisk` fields this replaced), but a multi-node
+    /// deployment can swap in
+    /// [`crate::shards::metadata_store::PooledSqlMetadataStore`] so
+    /// several peers share one authoritative store instead of divergent
+    /// local files - see [`crate::shards::metadata_store`].
+    metadata: Arc<dyn MetadataStore>,
     ring: HashRing<ShardId>,
 }
 
 pub type LockedShardHolder = RwLock<ShardHolder>;
 
 impl ShardHolder {
-    pub fn new(collection_path: &Path, hashring: HashRing<ShardId>) -> CollectionResult<Self> {
-        let shard_transfers = SaveOnDisk::load_or_init(collection_path.join(SHARD_TRANSFERS_FILE))?;
+    pub async fn new(
+        collection_path: &Path,
+        hashring: HashRing<ShardId>,
+    ) -> CollectionResult<Self> {
+        let metadata = Arc::new(FileMetadataStore::new(collection_path.to_path_buf()));
+        Self::with_metadata_store(metadata, hashring).await
+    }
+
+    /// Like [`Self::new`], but with an explicit [`MetadataStore`] - e.g. a
+    /// [`crate::shards::metadata_store::PooledSqlMetadataStore`] shared by
+    /// several `ShardHolder`s across peers.
+    pub async fn with_metadata_store(
+        metadata: Arc<dyn MetadataStore>,
+        hashring: HashRing<ShardId>,
+    ) -> CollectionResult<Self> {
         Ok(Self {
             shards: HashMap::new(),
-            shard_transfers,
+            metadata,
             ring: hashring,
         })
     }
@@ -107,23 +134,73 @@ impl ShardHolder {
         shard_ops
     }
 
-    pub fn register_start_shard_transfer(&self, transfer: ShardTransfer) -> CollectionResult<bool> {
-        Ok(self
-            .shard_transfers
-            .write(|transfers| transfers.insert(transfer))?)
+    pub async fn register_start_shard_transfer(
+        &self,
+        this_peer_id: PeerId,
+        transfer: ShardTransfer,
+    ) -> CollectionResult<bool> {
+        let was_present = metadata_store::update(
+            self.metadata.as_ref(),
+            SHARD_TRANSFERS_KEY,
+            move |transfers: &mut OrSet<ShardTransfer>| {
+                let was_present = transfers.contains(&transfer);
+                transfers.insert(this_peer_id, transfer);
+                was_present
+            },
+        )
+        .await?;
+        Ok(!was_present)
+    }
+
+    pub async fn register_finish_transfer(&self, key: &ShardTransferKey) -> CollectionResult<bool> {
+        let key = key.clone();
+        let removed = metadata_store::update(
+            self.metadata.as_ref(),
+            SHARD_TRANSFERS_KEY,
+            move |transfers: &mut OrSet<ShardTransfer>| {
+                transfers.remove_matching(|transfer| key.check(transfer))
+            },
+        )
+        .await?;
+        Ok(removed)
+    }
+
+    /// Merges `other`'s transfer set into ours - see [`OrSet::merge`]. For
+    /// reconciling two `ShardHolder`s' transfer state after a partition,
+    /// e.g. via gossip or gaining access to a peer's persisted state.
+    pub async fn merge_shard_transfers(
+        &self,
+        other: &OrSet<ShardTransfer>,
+    ) -> CollectionResult<()> {
+        let other = other.clone();
+        metadata_store::update(
+            self.metadata.as_ref(),
+            SHARD_TRANSFERS_KEY,
+            move |transfers: &mut OrSet<ShardTransfer>| {
+                transfers.merge(&other);
+            },
+        )
+        .await?;
+        Ok(())
     }
 
-    pub fn register_finish_transfer(&self, key: &ShardTransferKey) -> CollectionResult<bool> {
-        Ok(self.shard_transfers.write(|transfers| {
-            let before_remove = transfers.len();
-            transfers.retain(|transfer| !key.check(transfer));
-            before_remove != transfers.len() // `true` if something was removed
-        })?)
+    async fn shard_transfers(&self) -> CollectionResult<OrSet<ShardTransfer>> {
+        Ok(
+            metadata_store::load(self.metadata.as_ref(), SHARD_TRANSFERS_KEY)
+                .await?
+                .unwrap_or_default(),
+        )
     }
 
-    pub fn get_shard_transfer_info(&self) -> Vec<ShardTransferInfo> {
+    /// `ShardTransferInfo::progress` is always `None` here: computing it
+    /// needs the transfer's [`ChunkedTransferProgress`](crate::shards::chunked_transfer::ChunkedTransferProgress)
+    /// acked-chunk count against the transfer's total chunk count, and
+    /// `ShardHolder` has no lookup from a live transfer to the chunk
+    /// stream that produced it - that link is made transiently by
+    /// whatever RPC drives the transfer, which this tree doesn't have.
+    pub async fn get_shard_transfer_info(&self) -> CollectionResult<Vec<ShardTransferInfo>> {
         let mut shard_transfers = vec![];
-        for shard_transfer in self.shard_transfers.read().iter() {
+        for shard_transfer in self.shard_transfers().await?.iter() {
             let shard_id = shard_transfer.shard_id;
             let to = shard_transfer.to;
             let from = shard_transfer.from;
@@ -133,24 +210,147 @@ impl ShardHolder {
                 from,
                 to,
                 sync,
+                progress: None,
             })
         }
         shard_transfers.sort_by_key(|k| k.shard_id);
-        shard_transfers
+        Ok(shard_transfers)
+    }
+
+    /// Builds a snapshot of shard topology and in-flight transfers for a
+    /// cluster-health admin endpoint: every shard's replica peers split
+    /// into `local_shards` (this peer holds that replica) and
+    /// `remote_shards` (some other peer does), plus the transfers from
+    /// [`get_shard_transfer_info`](Self::get_shard_transfer_info).
+    ///
+    /// `LocalShardInfo::points_count` is left at zero: an exact count
+    /// needs a call into each local shard's storage, which this
+    /// metadata-only snapshot doesn't make - a caller
+    /// needing exact counts should cross-reference `Collection::info`
+    /// instead.
+    pub async fn cluster_info(
+        &self,
+        this_peer_id: PeerId,
+    ) -> CollectionResult<CollectionClusterInfo> {
+        let mut local_shards = Vec::new();
+        let mut remote_shards = Vec::new();
+        for (&shard_id, replica_set) in self.get_shards() {
+            for (peer_id, state) in replica_set.peers() {
+                if peer_id == this_peer_id {
+                    local_shards.push(LocalShardInfo {
+                        shard_id,
+                        points_count: 0,
+                        state,
+                    });
+                } else {
+                    remote_shards.push(RemoteShardInfo {
+                        shard_id,
+                        peer_id,
+                        state,
+                    });
+                }
+            }
+        }
+        local_shards.sort_by_key(|s| s.shard_id);
+        remote_shards.sort_by_key(|s| s.shard_id);
+
+        Ok(CollectionClusterInfo {
+            peer_id: this_peer_id,
+            shard_count: self.len(),
+            local_shards,
+            remote_shards,
+            shard_transfers: self.get_shard_transfer_info().await?,
+        })
     }
 
-    pub fn get_related_transfers(
+    pub async fn get_related_transfers(
         &self,
         shard_id: &ShardId,
         peer_id: &PeerId,
-    ) -> Vec<ShardTransfer> {
-        self.shard_transfers
-            .read()
+    ) -> CollectionResult<Vec<ShardTransfer>> {
+        Ok(self
+            .shard_transfers()
+            .await?
             .iter()
             .filter(|transfer| transfer.shard_id == *shard_id)
             .filter(|transfer| transfer.from == *peer_id || transfer.to == *peer_id)
             .cloned()
-            .collect()
+            .collect())
+    }
+
+    /// Records a point's current version in `shard_id`'s Merkle tree,
+    /// initializing an empty tree for the shard on first use.
+    pub async fn update_merkle_tree(
+        &self,
+        shard_id: ShardId,
+        point_id: PointIdType,
+        version: u64,
+    ) -> CollectionResult<()> {
+        metadata_store::update(
+            self.metadata.as_ref(),
+            POINT_MERKLE_TREES_KEY,
+            move |trees: &mut HashMap<ShardId, MerkleTree>| {
+                trees.entry(shard_id).or_default().upsert(point_id, version);
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Forgets a deleted point in `shard_id`'s Merkle tree, if the shard
+    /// has one.
+    pub async fn remove_from_merkle_tree(
+        &self,
+        shard_id: ShardId,
+        point_id: PointIdType,
+    ) -> CollectionResult<()> {
+        metadata_store::update(
+            self.metadata.as_ref(),
+            POINT_MERKLE_TREES_KEY,
+            move |trees: &mut HashMap<ShardId, MerkleTree>| {
+                if let Some(tree) = trees.get_mut(&shard_id) {
+                    tree.remove(point_id);
+                }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn merkle_trees(&self) -> CollectionResult<HashMap<ShardId, MerkleTree>> {
+        Ok(
+            metadata_store::load(self.metadata.as_ref(), POINT_MERKLE_TREES_KEY)
+                .await?
+                .unwrap_or_default(),
+        )
+    }
+
+    /// The root hash of `shard_id`'s Merkle tree, or `None` if the shard
+    /// has no tree yet (e.g. it has never had a point upserted through
+    /// [`update_merkle_tree`](Self::update_merkle_tree)).
+    pub async fn merkle_root(&self, shard_id: ShardId) -> CollectionResult<Option<u64>> {
+        Ok(self
+            .merkle_trees()
+            .await?
+            .get(&shard_id)
+            .map(MerkleTree::root_hash))
+    }
+
+    /// Compares `shard_id`'s local Merkle tree against `other` - presumably
+    /// fetched from a peer replica by some out-of-band means - returning
+    /// the points that need to be pushed to or pulled from that peer to
+    /// bring the two shards back in sync. `None` if the shard has no
+    /// local tree yet.
+    pub async fn reconcile_shard(
+        &self,
+        shard_id: ShardId,
+        other: &MerkleTree,
+    ) -> CollectionResult<Option<Vec<LeafDivergence>>> {
+        Ok(self
+            .merkle_trees()
+            .await?
+            .get(&shard_id)
+            .map(|tree| tree.reconcile(other)))
     }
 
     pub fn target_shard(
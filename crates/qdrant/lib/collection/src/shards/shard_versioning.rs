@@ -1,10 +1,65 @@
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use crate::operations::types::{CollectionError, CollectionResult};
 use crate::shards::shard::ShardId;
 use crate::shards::shard_config::{ShardConfig, ShardType};
 use crate::shards::ShardVersion;
 
+/// How many superseded shard versions [`drop_old_shards`] keeps around
+/// instead of deleting immediately, so an operator can roll back to a
+/// prior shard snapshot after a bad migration before GC reclaims it.
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// Always keep at least this many most-recent versions (including the
+    /// current one), regardless of age.
+    pub keep_last: usize,
+    /// Additionally keep any version younger than this, regardless of how
+    /// many versions that leaves around.
+    pub keep_for: Option<Duration>,
+}
+
+impl Default for RetentionPolicy {
+    /// Matches the historical behavior of eagerly dropping everything but
+    /// the single newest version.
+    fn default() -> Self {
+        RetentionPolicy {
+            keep_last: 1,
+            keep_for: None,
+        }
+    }
+}
+
+/// One shard version on disk, current or superseded, as reported by
+/// [`list_shard_versions`].
+#[derive(Debug, Clone)]
+pub struct ShardVersionInfo {
+    pub version: ShardVersion,
+    pub shard_type: ShardType,
+    pub path: PathBuf,
+    pub size_on_disk: u64,
+    pub mtime: SystemTime,
+}
+
+/// Recursively sums the size of every file under `path`, iteratively (no
+/// async recursion) since a shard's data folder can nest arbitrarily deep.
+async fn dir_size_on_disk(path: &Path) -> CollectionResult<u64> {
+    let mut total = 0u64;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    Ok(total)
+}
+
 async fn shards_versions(
     collection_path: &Path,
     shard_id: ShardId,
@@ -44,14 +99,66 @@ async fn shards_versions(
     Ok(all_versions)
 }
 
-pub async fn drop_old_shards(collection_path: &Path, shard_id: ShardId) -> CollectionResult<()> {
-    for (_version, old_path) in shards_versions(collection_path, shard_id)
+/// Lists every version of `shard_id`'s data folder, current and
+/// superseded, without deleting anything - for an operator auditing what a
+/// future [`drop_old_shards`] call would reclaim, or deciding to roll back
+/// to an older version first. Ordered newest-first, same as
+/// [`shards_versions`].
+pub async fn list_shard_versions(
+    collection_path: &Path,
+    shard_id: ShardId,
+) -> CollectionResult<Vec<ShardVersionInfo>> {
+    let mut result = Vec::new();
+    for (version, path) in shards_versions(collection_path, shard_id).await? {
+        let Some(shard_config) = ShardConfig::load(&path)? else {
+            log::warn!("Shard config not found for {}, skipping", path.display());
+            continue;
+        };
+        let metadata = tokio::fs::metadata(&path).await?;
+        let mtime = metadata.modified()?;
+        let size_on_disk = dir_size_on_disk(&path).await?;
+        result.push(ShardVersionInfo {
+            version,
+            shard_type: shard_config.r#type,
+            path,
+            size_on_disk,
+            mtime,
+        });
+    }
+    Ok(result)
+}
+
+/// Deletes shard versions superseded under `retention`: every version
+/// beyond `retention.keep_last` that is also older than
+/// `retention.keep_for` (if set). Each deletion is logged with the bytes
+/// freed, so eager cleanup stays auditable instead of silent.
+pub async fn drop_old_shards(
+    collection_path: &Path,
+    shard_id: ShardId,
+    retention: &RetentionPolicy,
+) -> CollectionResult<()> {
+    let now = SystemTime::now();
+    for (index, info) in list_shard_versions(collection_path, shard_id)
         .await?
         .into_iter()
-        .skip(1)
+        .enumerate()
     {
+        if index < retention.keep_last {
+            continue;
+        }
+        if let Some(keep_for) = retention.keep_for {
+            if now.duration_since(info.mtime).unwrap_or_default() < keep_for {
+                continue;
+            }
+        }
         // delete old shard's data folder
-        tokio::fs::remove_dir_all(&old_path).await?;
+        tokio::fs::remove_dir_all(&info.path).await?;
+        log::info!(
+            "Dropped superseded shard version {} at {} ({} bytes freed)",
+            info.version,
+            info.path.display(),
+            info.size_on_disk,
+        );
     }
     Ok(())
 }
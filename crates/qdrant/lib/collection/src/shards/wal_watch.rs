@@ -0,0 +1,118 @@
+//! Resumable change-notification stream over a shard's WAL.
+//!
+//! External consumers of a shard - secondary indexes, cache invalidators,
+//! mirrors - currently have to poll `scroll`/`count` to notice mutations.
+//! [`WalWatcher`] lets them instead subscribe from a resume token equal to
+//! the last WAL version (`op_num`) they've already seen: [`WalWatcher::subscribe`]
+//! first replays every committed operation past that token straight off the
+//! WAL (reusing [`LockedWal::read`], the same call `UpdateHandler` uses for
+//! [`crate::update_handler::UpdateHandler`]'s own crash recovery), then hands
+//! back a receiver for a live broadcast of everything applied from that point
+//! on, so a subscriber never observes a gap between replay and live traffic.
+//! [`WalWatcher::publish`] is the update worker's side of that broadcast.
+//!
+//! This backs the `watch` RPC described for `PointsInternal`, inspired by
+//! Garage's K2V poll. The RPC itself isn't wired up here: this snapshot's
+//! `api` crate has no generated `PointsInternal` service code to extend (no
+//! `.proto`-derived server stubs exist anywhere under `lib/api`), and
+//! `CollectionUpdateOperations`'s own definition isn't present in this tree
+//! either, so turning a [`WatchEvent`] into an "operation kind" plus a flat
+//! list of affected point ids - both per-variant concerns - is left to
+//! whatever conversion layer eventually sits between this and the RPC
+//! handler, rather than guessed at here.
+
+use segment::types::SeqNumberType;
+use tokio::sync::broadcast;
+
+use crate::operations::CollectionUpdateOperations;
+use crate::shards::local_shard::LockedWal;
+use crate::shards::shard::ShardId;
+
+/// How many not-yet-consumed events a live subscriber can lag behind before
+/// it starts missing live events. A subscriber that falls behind this far
+/// just needs to re-[`WalWatcher::subscribe`] with an older resume token -
+/// the WAL replay makes it safe to fall arbitrarily far behind, just not
+/// instant.
+const DEFAULT_CHANNEL_CAPACITY: usize = 1024;
+
+/// One applied mutation, as delivered to a `watch` subscriber.
+#[derive(Debug, Clone)]
+pub struct WatchEvent {
+    pub shard_id: ShardId,
+    /// WAL version this operation was committed at; also the resume token
+    /// a subscriber should pass to pick up immediately after this event.
+    pub op_num: SeqNumberType,
+    pub operation: CollectionUpdateOperations,
+}
+
+/// The result of [`WalWatcher::subscribe`]: operations already committed at
+/// or before the subscribe call, replayed from the WAL, plus a receiver for
+/// everything committed afterwards.
+///
+/// `replay` and `live` can overlap at the boundary - the live broadcast
+/// subscription is opened before the WAL replay runs, so an operation
+/// applied concurrently with the replay can show up in both. A consumer
+/// should skip any event read from `live` whose `op_num` is less than or
+/// equal to `replay`'s last entry (if any) rather than assume the two
+/// sources line up exactly.
+pub struct WatchSubscription {
+    pub replay: Vec<WatchEvent>,
+    pub live: broadcast::Receiver<WatchEvent>,
+}
+
+/// Publishes newly applied operations to `watch` subscribers and lets new
+/// subscribers catch up from an arbitrary resume token by replaying the
+/// shard's WAL before joining the live broadcast.
+pub struct WalWatcher {
+    shard_id: ShardId,
+    wal: LockedWal,
+    live: broadcast::Sender<WatchEvent>,
+}
+
+impl WalWatcher {
+    pub fn new(shard_id: ShardId, wal: LockedWal) -> Self {
+        let (live, _) = broadcast::channel(DEFAULT_CHANNEL_CAPACITY);
+        Self {
+            shard_id,
+            wal,
+            live,
+        }
+    }
+
+    /// Called by the update worker right after `operation` is durably
+    /// applied at `op_num`, so subscribers already live-tailing see it
+    /// without needing to re-read the WAL. A lack of subscribers is not an
+    /// error - it just means nobody is watching right now.
+    pub fn publish(&self, op_num: SeqNumberType, operation: CollectionUpdateOperations) {
+        let _ = self.live.send(WatchEvent {
+            shard_id: self.shard_id,
+            op_num,
+            operation,
+        });
+    }
+
+    /// Subscribes from `resume_token` (exclusive): replays every committed
+    /// operation already in the WAL with `op_num > resume_token`, then
+    /// returns a [`WatchSubscription`] whose `live` receiver carries
+    /// everything applied from this point on. The live subscription is
+    /// created before the replay is read, so an operation committed while
+    /// the replay is in flight is buffered by the broadcast channel instead
+    /// of lost.
+    pub fn subscribe(&self, resume_token: SeqNumberType) -> WatchSubscription {
+        let live = self.live.subscribe();
+
+        let shard_id = self.shard_id;
+        let replay = self
+            .wal
+            .lock()
+            .read(resume_token + 1)
+            .map(|(op_num, operation)| WatchEvent {
+                shard_id,
+                op_num,
+                operation,
+            })
+            .collect();
+
+        WatchSubscription { replay, live }
+    }
+}
@@ -1,29 +1,373 @@
 use std::cmp::min;
-use std::collections::HashSet;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
-use itertools::Itertools;
+use atomicwrites::AtomicFile;
+use atomicwrites::OverwriteBehavior::AllowOverwrite;
 use log::{debug, error, info, trace, warn};
 use segment::entry::entry_point::OperationResult;
 use segment::types::SeqNumberType;
+use serde::{Deserialize, Serialize};
 use tokio::runtime::Handle;
 use tokio::sync::mpsc::{self, Receiver, Sender};
-use tokio::sync::{oneshot, Mutex as TokioMutex};
+use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 use tokio::time::Duration;
 
 use crate::collection_manager::collection_updater::CollectionUpdater;
-use crate::collection_manager::holders::segment_holder::LockedSegmentHolder;
+use crate::collection_manager::holders::segment_holder::{LockedSegmentHolder, SegmentId};
 use crate::collection_manager::optimizers::segment_optimizer::SegmentOptimizer;
-use crate::common::stoppable_task::{spawn_stoppable, StoppableTaskHandle};
 use crate::operations::shared_storage_config::SharedStorageConfig;
 use crate::operations::types::{CollectionError, CollectionResult};
 use crate::operations::CollectionUpdateOperations;
 use crate::shards::local_shard::LockedWal;
+use crate::shards::shard::ShardId;
+use crate::shards::wal_watch::WalWatcher;
 use crate::wal::WalError;
 
 pub type Optimizer = dyn SegmentOptimizer + Sync + Send;
 
+/// Backoff delay for the first retry of a failed optimization; doubled on
+/// each further consecutive failure of the same segment set, up to
+/// [`MAX_OPTIMIZATION_RETRY_DELAY`].
+const BASE_OPTIMIZATION_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound on the exponential retry backoff below.
+const MAX_OPTIMIZATION_RETRY_DELAY: Duration = Duration::from_secs(32);
+
+/// Per-segment-set consecutive-failure counters backing the retry policy in
+/// `run_job`, keyed by the sorted `segment_ids` the failing job was run with.
+type OptimizationRetryState = Mutex<HashMap<Vec<SegmentId>, usize>>;
+
+/// Growth step and ceiling for the linear backoff an idle optimization
+/// worker sleeps through after failing to pop or steal a job from
+/// [`OptimizationScheduler::queue`], so a mostly-idle pool doesn't busy-spin.
+const IDLE_BACKOFF_STEP: Duration = Duration::from_millis(10);
+const MAX_IDLE_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Coarse lifecycle state of a launched optimization task.
+///
+/// `Stopping` is not produced by `optimization_status` today - a worker
+/// handed a cooperative stop by `OptimizerSignal::PauseOptimizations(false)`
+/// clears its `current` slot once `optimize` actually returns, same as any
+/// other finished job - but the variant is kept here to describe that
+/// transition and so future callers have somewhere to report a
+/// stop-in-progress before the job actually exits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizationState {
+    Running,
+    Stopping,
+    Finished,
+}
+
+/// One independently schedulable merge: a specific optimizer paired with
+/// the segment set it decided needs optimizing. Pushed onto
+/// [`OptimizationScheduler::queue`] by `enqueue_pending_optimizations`
+/// instead of being run immediately, so a fixed pool of workers can pull
+/// work at their own pace rather than one task being spawned per job.
+struct OptimizationJob {
+    optimizer: Arc<Optimizer>,
+    segment_ids: Vec<SegmentId>,
+}
+
+/// State shared by every worker in the optimization pool and by the
+/// dispatcher that discovers new jobs.
+struct OptimizationScheduler {
+    queue: Mutex<VecDeque<OptimizationJob>>,
+    /// Segments currently claimed by a queued or running job, so the same
+    /// segment is never claimed by two workers at once.
+    scheduled_segment_ids: Mutex<HashSet<SegmentId>>,
+    retry_state: OptimizationRetryState,
+    /// Set by `PauseOptimizations`, cleared by `ResumeOptimizations`; workers
+    /// stop popping new jobs while this is set. Updates keep flowing through
+    /// `update_worker_fn` regardless - only launching new optimizations is
+    /// suspended.
+    paused: AtomicBool,
+    /// Set once by `wait_workers_stops` to tell every worker thread to exit
+    /// after finishing whatever job it's currently running.
+    stop: AtomicBool,
+}
+
+/// One long-lived worker thread in the optimization pool, plus the state
+/// `optimization_status`/`PauseOptimizations(drain: false)` need to reach
+/// into its currently running job from outside.
+struct OptimizationWorker {
+    thread: Option<std::thread::JoinHandle<()>>,
+    /// Cooperative stop flag for whatever job this worker is currently
+    /// running; set by `PauseOptimizations(false)`, reset before each job.
+    job_stop: Arc<AtomicBool>,
+    /// `Some` for as long as this worker is running a job.
+    current: Arc<Mutex<Option<OptimizationTaskInfo>>>,
+}
+
+/// Snapshot of one in-flight (or just-finished) optimization task, reported
+/// by `optimization_status` via [`OptimizationWorker::current`].
+#[derive(Debug, Clone)]
+pub struct OptimizationTaskInfo {
+    /// The optimizer's concrete type name (e.g. `MergeOptimizer`), since
+    /// `SegmentOptimizer` has no dedicated name/kind accessor.
+    pub optimizer_name: String,
+    /// Segment ids this task was launched to optimize - the job's
+    /// `segment_ids`, as discovered by `enqueue_pending_optimizations`.
+    pub segment_ids: Vec<SegmentId>,
+    pub start_time: SystemTime,
+    pub state: OptimizationState,
+}
+
+/// The optimizer's concrete type name, stripped of its module path - the
+/// closest thing to a "kind" `SegmentOptimizer` exposes.
+fn optimizer_kind_name(optimizer: &Optimizer) -> String {
+    let type_name = std::any::type_name_of_val(optimizer);
+    type_name
+        .rsplit("::")
+        .next()
+        .unwrap_or(type_name)
+        .to_string()
+}
+
+/// Unique id assigned to a launched optimization task by `OptimizationJournal::start_task`,
+/// used to correlate its start and finish records.
+pub type OptimizationTaskId = u64;
+
+/// How a journaled optimization task ended.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OptimizationOutcome {
+    Success,
+    Cancelled,
+    Failed { error: String },
+}
+
+/// One durable record in an [`OptimizationJournal`]: either a task still
+/// running (`outcome: None`, `end_time: None`) in `active.jsonl`, or a
+/// finished one in the archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationRecord {
+    pub task_id: OptimizationTaskId,
+    pub optimizer_name: String,
+    pub segment_ids: Vec<SegmentId>,
+    pub start_time: SystemTime,
+    pub end_time: Option<SystemTime>,
+    pub outcome: Option<OptimizationOutcome>,
+}
+
+const OPTIMIZATION_JOURNAL_ACTIVE_FILE: &str = "active.jsonl";
+const OPTIMIZATION_JOURNAL_CURRENT_ARCHIVE_FILE: &str = "archive-current.jsonl";
+
+/// Durable, append-only audit trail of optimization tasks, so operators can
+/// see what merged, when, and why something failed, across restarts - which
+/// the in-memory `optimization_status`/`report_optimizer_error` path alone
+/// cannot provide.
+///
+/// Lives under its own `dir` with two kinds of files:
+/// - `active.jsonl`: one JSON line per task currently tracked as running,
+///   rewritten in full (via [`AtomicFile`]) every time a task starts or
+///   finishes, so it always reflects exactly the live task set.
+/// - `archive-current.jsonl`: an append-only log of finished tasks. Once it
+///   grows past `rotate_after_bytes`, it's renamed to a timestamped
+///   `archive-<unix_nanos>.jsonl` segment and a fresh current file is
+///   started; only the newest `max_archive_segments` rotated segments are
+///   kept, oldest pruned first.
+pub struct OptimizationJournal {
+    dir: PathBuf,
+    rotate_after_bytes: u64,
+    max_archive_segments: usize,
+    next_task_id: AtomicU64,
+    active: Mutex<HashMap<OptimizationTaskId, OptimizationRecord>>,
+}
+
+impl OptimizationJournal {
+    /// Opens (creating if necessary) the journal directory at `dir`,
+    /// recovering the active-task index left behind by a previous run.
+    pub fn open(
+        dir: PathBuf,
+        rotate_after_bytes: u64,
+        max_archive_segments: usize,
+    ) -> CollectionResult<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let active_records =
+            read_jsonl::<OptimizationRecord>(&dir.join(OPTIMIZATION_JOURNAL_ACTIVE_FILE))?;
+        let next_task_id = active_records.iter().map(|r| r.task_id).max().unwrap_or(0) + 1;
+        let active = active_records
+            .into_iter()
+            .map(|record| (record.task_id, record))
+            .collect();
+
+        Ok(Self {
+            dir,
+            rotate_after_bytes,
+            max_archive_segments,
+            next_task_id: AtomicU64::new(next_task_id),
+            active: Mutex::new(active),
+        })
+    }
+
+    /// Records a newly launched task, returning the id assigned to it.
+    pub fn start_task(
+        &self,
+        optimizer_name: String,
+        segment_ids: Vec<SegmentId>,
+        start_time: SystemTime,
+    ) -> CollectionResult<OptimizationTaskId> {
+        let task_id = self.next_task_id.fetch_add(1, Ordering::Relaxed);
+        let record = OptimizationRecord {
+            task_id,
+            optimizer_name,
+            segment_ids,
+            start_time,
+            end_time: None,
+            outcome: None,
+        };
+        let mut active = self.active.lock().unwrap();
+        active.insert(task_id, record);
+        self.rewrite_active(&active)?;
+        Ok(task_id)
+    }
+
+    /// Moves a task from the active index into the archive with its outcome.
+    pub fn finish_task(
+        &self,
+        task_id: OptimizationTaskId,
+        outcome: OptimizationOutcome,
+    ) -> CollectionResult<()> {
+        let mut active = self.active.lock().unwrap();
+        let Some(mut record) = active.remove(&task_id) else {
+            return Ok(());
+        };
+        self.rewrite_active(&active)?;
+        drop(active);
+
+        record.end_time = Some(SystemTime::now());
+        record.outcome = Some(outcome);
+        self.append_archive(&record)?;
+        self.rotate_if_needed()?;
+        Ok(())
+    }
+
+    /// Drops the active/finished distinction and returns up to `limit`
+    /// records, most recently started first.
+    pub async fn recent_optimizations(&self, limit: usize) -> Vec<OptimizationRecord> {
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || Self::collect_recent(&dir, limit))
+            .await
+            .unwrap_or_default()
+    }
+
+    fn collect_recent(dir: &Path, limit: usize) -> Vec<OptimizationRecord> {
+        let mut records =
+            read_jsonl::<OptimizationRecord>(&dir.join(OPTIMIZATION_JOURNAL_ACTIVE_FILE))
+                .unwrap_or_default();
+        records.extend(
+            read_jsonl::<OptimizationRecord>(&dir.join(OPTIMIZATION_JOURNAL_CURRENT_ARCHIVE_FILE))
+                .unwrap_or_default(),
+        );
+        for segment in Self::archive_segments(dir).unwrap_or_default() {
+            records.extend(read_jsonl::<OptimizationRecord>(&segment).unwrap_or_default());
+        }
+        records.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+        records.truncate(limit);
+        records
+    }
+
+    fn rewrite_active(
+        &self,
+        active: &HashMap<OptimizationTaskId, OptimizationRecord>,
+    ) -> CollectionResult<()> {
+        write_jsonl(
+            &self.dir.join(OPTIMIZATION_JOURNAL_ACTIVE_FILE),
+            active.values(),
+        )
+    }
+
+    fn append_archive(&self, record: &OptimizationRecord) -> CollectionResult<()> {
+        use std::io::Write;
+
+        let path = self.dir.join(OPTIMIZATION_JOURNAL_CURRENT_ARCHIVE_FILE);
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let line = serde_json::to_string(record)
+            .map_err(|err| CollectionError::service_error(format!("{err}")))?;
+        writeln!(file, "{line}")?;
+        Ok(())
+    }
+
+    fn rotate_if_needed(&self) -> CollectionResult<()> {
+        let current = self.dir.join(OPTIMIZATION_JOURNAL_CURRENT_ARCHIVE_FILE);
+        let size = match fs::metadata(&current) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return Ok(()),
+        };
+        if size < self.rotate_after_bytes {
+            return Ok(());
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let rotated = self.dir.join(format!("archive-{timestamp}.jsonl"));
+        fs::rename(&current, &rotated)?;
+
+        let mut segments = Self::archive_segments(&self.dir)?;
+        segments.sort();
+        while segments.len() > self.max_archive_segments {
+            let oldest = segments.remove(0);
+            let _ = fs::remove_file(oldest);
+        }
+        Ok(())
+    }
+
+    fn archive_segments(dir: &Path) -> CollectionResult<Vec<PathBuf>> {
+        let mut segments = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_rotated_segment =
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with("archive-")
+                            && name != OPTIMIZATION_JOURNAL_CURRENT_ARCHIVE_FILE
+                    });
+            if is_rotated_segment {
+                segments.push(path);
+            }
+        }
+        Ok(segments)
+    }
+}
+
+fn read_jsonl<T: for<'de> Deserialize<'de>>(path: &Path) -> CollectionResult<Vec<T>> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Ok(vec![]);
+    };
+    Ok(contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn write_jsonl<'a, T: Serialize + 'a>(
+    path: &Path,
+    records: impl Iterator<Item = &'a T>,
+) -> CollectionResult<()> {
+    let mut contents = String::new();
+    for record in records {
+        let line = serde_json::to_string(record)
+            .map_err(|err| CollectionError::service_error(format!("{err}")))?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+    AtomicFile::new(path, AllowOverwrite)
+        .write(|f| std::io::Write::write_all(f, contents.as_bytes()))
+        .map_err(|err| CollectionError::service_error(format!("Failed to persist {path:?}: {err}")))
+}
+
 /// Information, required to perform operation and notify regarding the result
 #[derive(Debug)]
 pub struct OperationData {
@@ -48,6 +392,14 @@ pub enum UpdateSignal {
     Nop,
     /// Ensures that previous updates are applied
     Plunger(oneshot::Sender<()>),
+    /// Suspend launching new optimizations until `ResumeOptimizations`.
+    /// The `bool` is the `drain` flag: `true` lets in-flight optimizations
+    /// run to completion, `false` cooperatively stops them via their
+    /// `stopped` flag right away. Updates keep flowing through
+    /// `update_worker_fn` either way.
+    PauseOptimizations(bool),
+    /// Resume launching optimizations suspended by `PauseOptimizations`.
+    ResumeOptimizations,
 }
 
 /// Signal, used to inform Optimization process
@@ -59,6 +411,10 @@ pub enum OptimizerSignal {
     Stop,
     /// Empty signal used to trigger optimizers
     Nop,
+    /// See [`UpdateSignal::PauseOptimizations`]
+    PauseOptimizations(bool),
+    /// See [`UpdateSignal::ResumeOptimizations`]
+    ResumeOptimizations,
 }
 
 /// Structure, which holds object, required for processing updates of the collection
@@ -80,8 +436,20 @@ pub struct UpdateHandler {
     runtime_handle: Handle,
     /// WAL, required for operations
     wal: LockedWal,
-    optimization_handles: Arc<TokioMutex<Vec<StoppableTaskHandle<bool>>>>,
+    /// Shared job queue and retry/pause/stop state for the optimization
+    /// worker pool; created in `new` and shared with the workers that
+    /// `run_workers` spawns.
+    scheduler: Arc<OptimizationScheduler>,
+    /// The fixed pool of optimization worker threads, one per
+    /// `max_optimization_threads`; empty until `run_workers` spawns them.
+    workers: Vec<OptimizationWorker>,
     max_optimization_threads: usize,
+    /// Publishes each applied operation to `watch` subscribers of this shard
+    watcher: Arc<WalWatcher>,
+    /// Durable optimization task history, set up via
+    /// [`Self::enable_optimization_journal`]. `None` until then, so
+    /// constructing an `UpdateHandler` never requires a writable directory.
+    optimization_journal: Option<Arc<OptimizationJournal>>,
 }
 
 impl UpdateHandler {
@@ -93,6 +461,7 @@ impl UpdateHandler {
         wal: LockedWal,
         flush_interval_sec: u64,
         max_optimization_threads: usize,
+        shard_id: ShardId,
     ) -> UpdateHandler {
         UpdateHandler {
             shared_storage_config,
@@ -103,29 +472,115 @@ impl UpdateHandler {
             flush_worker: None,
             flush_stop: None,
             runtime_handle,
+            watcher: Arc::new(WalWatcher::new(shard_id, wal.clone())),
             wal,
             flush_interval_sec,
-            optimization_handles: Arc::new(TokioMutex::new(vec![])),
+            scheduler: Arc::new(OptimizationScheduler {
+                queue: Mutex::new(VecDeque::new()),
+                scheduled_segment_ids: Mutex::new(HashSet::new()),
+                retry_state: Mutex::new(HashMap::new()),
+                paused: AtomicBool::new(false),
+                stop: AtomicBool::new(false),
+            }),
+            workers: Vec::new(),
             max_optimization_threads,
+            optimization_journal: None,
+        }
+    }
+
+    /// Gives a `watch` RPC handler a handle to subscribe to this shard's
+    /// applied operations; see [`WalWatcher::subscribe`].
+    pub fn watcher(&self) -> Arc<WalWatcher> {
+        self.watcher.clone()
+    }
+
+    /// Turns on the durable optimization-task journal under `dir`, so
+    /// [`Self::recent_optimizations`] can report history that survives a
+    /// restart. Call before [`Self::run_workers`]; a handler with no journal
+    /// enabled simply keeps no durable history.
+    pub fn enable_optimization_journal(
+        &mut self,
+        dir: PathBuf,
+        rotate_after_bytes: u64,
+        max_archive_segments: usize,
+    ) -> CollectionResult<()> {
+        let journal = OptimizationJournal::open(dir, rotate_after_bytes, max_archive_segments)?;
+        self.optimization_journal = Some(Arc::new(journal));
+        Ok(())
+    }
+
+    /// Merges durable (archived and still-active) optimization history, most
+    /// recently started first. Empty if no journal was enabled.
+    pub async fn recent_optimizations(&self, limit: usize) -> Vec<OptimizationRecord> {
+        match &self.optimization_journal {
+            Some(journal) => journal.recent_optimizations(limit).await,
+            None => vec![],
         }
     }
 
+    /// Snapshot of the job each optimization worker is currently running
+    /// (workers sitting idle on the queue report nothing), for diagnosing
+    /// stuck or long-running merges at runtime.
+    pub async fn optimization_status(&self) -> Vec<OptimizationTaskInfo> {
+        self.workers
+            .iter()
+            .filter_map(|worker| worker.current.lock().unwrap().clone())
+            .collect()
+    }
+
     pub fn run_workers(&mut self, update_receiver: Receiver<UpdateSignal>) {
         let (tx, rx) = mpsc::channel(self.shared_storage_config.update_queue_size);
-        self.optimizer_worker = Some(self.runtime_handle.spawn(Self::optimization_worker_fn(
+
+        let mut job_stops = Vec::with_capacity(self.max_optimization_threads);
+        for worker_index in 0..self.max_optimization_threads {
+            let job_stop = Arc::new(AtomicBool::new(false));
+            let current = Arc::new(Mutex::new(None));
+            let thread = std::thread::Builder::new()
+                .name(format!("optimization-worker-{worker_index}"))
+                .spawn({
+                    let segments = self.segments.clone();
+                    let scheduler = self.scheduler.clone();
+                    let max_retries = self.shared_storage_config.max_optimizer_retries;
+                    let journal = self.optimization_journal.clone();
+                    let job_stop = job_stop.clone();
+                    let current = current.clone();
+                    let requeue = tx.clone();
+                    move || {
+                        Self::optimization_worker_loop(
+                            segments,
+                            scheduler,
+                            max_retries,
+                            journal,
+                            job_stop,
+                            current,
+                            requeue,
+                        )
+                    }
+                })
+                .expect("failed to spawn optimization worker thread");
+            job_stops.push(job_stop.clone());
+            self.workers.push(OptimizationWorker {
+                thread: Some(thread),
+                job_stop,
+                current,
+            });
+        }
+
+        self.optimizer_worker = Some(self.runtime_handle.spawn(Self::optimization_dispatcher_fn(
             self.optimizers.clone(),
             tx.clone(),
             rx,
             self.segments.clone(),
             self.wal.clone(),
-            self.optimization_handles.clone(),
-            self.max_optimization_threads,
+            self.scheduler.clone(),
+            job_stops,
         )));
         self.update_worker = Some(self.runtime_handle.spawn(Self::update_worker_fn(
             update_receiver,
             tx,
             self.wal.clone(),
             self.segments.clone(),
+            self.watcher.clone(),
         )));
         let (flush_tx, flush_rx) = oneshot::channel();
         self.flush_worker = Some(self.runtime_handle.spawn(Self::flush_worker(
@@ -161,15 +616,21 @@ impl UpdateHandler {
             handle.await?;
         }
 
-        let mut opt_handles_guard = self.optimization_handles.lock().await;
-        let opt_handles = std::mem::take(&mut *opt_handles_guard);
-        let stopping_handles = opt_handles
-            .into_iter()
-            .filter_map(|h| h.stop())
-            .collect_vec();
-
-        for res in stopping_handles {
-            res.await?;
+        // No more jobs will be enqueued; each worker thread exits on its own
+        // once this flag is visible, after finishing whatever job it's
+        // currently running.
+        self.scheduler.stop.store(true, Ordering::Relaxed);
+        for worker in &mut self.workers {
+            if let Some(thread) = worker.thread.take() {
+                tokio::task::spawn_blocking(move || thread.join())
+                    .await
+                    .map_err(|err| CollectionError::service_error(format!("{err}")))?
+                    .map_err(|_| {
+                        CollectionError::service_error(
+                            "Optimization worker thread panicked".to_string(),
+                        )
+                    })?;
+            }
         }
 
         Ok(())
@@ -192,124 +653,246 @@ impl UpdateHandler {
         Ok(0)
     }
 
-    /// Checks conditions for all optimizers until there is no suggested segment
-    /// Starts a task for each optimization
-    /// Returns handles for started tasks
-    pub(crate) fn launch_optimization<F>(
-        optimizers: Arc<Vec<Arc<Optimizer>>>,
-        segments: LockedSegmentHolder,
-        callback: F,
-    ) -> Vec<StoppableTaskHandle<bool>>
-    where
-        F: FnOnce(bool),
-        F: Send + 'static,
-        F: Clone,
-    {
-        let mut scheduled_segment_ids: HashSet<_> = Default::default();
-        let mut handles = vec![];
-        for optimizer in optimizers.iter() {
+    /// Checks conditions for all optimizers until there is no suggested
+    /// segment left, pushing one [`OptimizationJob`] per non-optimal segment
+    /// set onto `scheduler`'s queue for the worker pool to pick up. Does not
+    /// run anything itself.
+    fn enqueue_pending_optimizations(
+        optimizers: &[Arc<Optimizer>],
+        segments: &LockedSegmentHolder,
+        scheduler: &OptimizationScheduler,
+    ) {
+        for optimizer in optimizers {
             loop {
-                let nonoptimal_segment_ids =
-                    optimizer.check_condition(segments.clone(), &scheduled_segment_ids);
+                let excluded = scheduler.scheduled_segment_ids.lock().unwrap().clone();
+                let nonoptimal_segment_ids = optimizer.check_condition(segments.clone(), &excluded);
                 if nonoptimal_segment_ids.is_empty() {
                     break;
-                } else {
-                    let optim = optimizer.clone();
-                    let segs = segments.clone();
-                    let nsi = nonoptimal_segment_ids.clone();
-                    for sid in &nsi {
-                        scheduled_segment_ids.insert(*sid);
-                    }
-                    let callback_cloned = callback.clone();
-
-                    handles.push(spawn_stoppable(move |stopped| {
-                        match optim.as_ref().optimize(segs.clone(), nsi, stopped) {
-                            Ok(result) => {
-                                callback_cloned(result); // Perform some actions when optimization if finished
-                                result
-                            }
-                            Err(error) => match error {
-                                CollectionError::Cancelled { description } => {
-                                    log::debug!("Optimization cancelled - {}", description);
-                                    false
-                                }
-                                _ => {
-                                    // Save only the first error
-                                    // If is more likely to be the real cause of all further problems
-                                    segs.write().report_optimizer_error(error.clone());
-
-                                    // Error of the optimization can not be handled by API user
-                                    // It is only possible to fix after full restart,
-                                    // so the best available action here is to stop whole
-                                    // optimization thread and log the error
-                                    log::error!("Optimization error: {}", error);
-                                    panic!("Optimization error: {error}");
-                                }
-                            },
-                        }
-                    }));
                 }
+                scheduler
+                    .scheduled_segment_ids
+                    .lock()
+                    .unwrap()
+                    .extend(nonoptimal_segment_ids.iter().copied());
+                scheduler.queue.lock().unwrap().push_back(OptimizationJob {
+                    optimizer: optimizer.clone(),
+                    segment_ids: nonoptimal_segment_ids,
+                });
             }
         }
-        handles
     }
 
-    pub(crate) async fn process_optimization(
-        optimizers: Arc<Vec<Arc<Optimizer>>>,
-        segments: LockedSegmentHolder,
-        optimization_handles: Arc<TokioMutex<Vec<StoppableTaskHandle<bool>>>>,
-        sender: Sender<OptimizerSignal>,
+    /// Runs one [`OptimizationJob`] to completion, with its retry and
+    /// journal bookkeeping:
+    /// on success or exhausted retries the job's segments are released from
+    /// `scheduled_segment_ids`; on a retryable failure the job is re-pushed
+    /// onto the queue (keeping its segments claimed) after an exponential
+    /// backoff sleep.
+    fn run_job(
+        job: OptimizationJob,
+        segments: &LockedSegmentHolder,
+        scheduler: &OptimizationScheduler,
+        max_retries: usize,
+        journal: &Option<Arc<OptimizationJournal>>,
+        job_stop: &Arc<AtomicBool>,
+        current: &Arc<Mutex<Option<OptimizationTaskInfo>>>,
+        requeue: &Sender<OptimizerSignal>,
     ) {
-        let mut new_handles = Self::launch_optimization(
-            optimizers.clone(),
+        let mut retry_key = job.segment_ids.clone();
+        retry_key.sort_unstable();
+
+        let task_info = OptimizationTaskInfo {
+            optimizer_name: optimizer_kind_name(job.optimizer.as_ref()),
+            segment_ids: job.segment_ids.clone(),
+            start_time: SystemTime::now(),
+            state: OptimizationState::Running,
+        };
+        *current.lock().unwrap() = Some(task_info.clone());
+
+        let task_id = journal.as_ref().and_then(|journal| {
+            journal
+                .start_task(
+                    task_info.optimizer_name.clone(),
+                    task_info.segment_ids.clone(),
+                    task_info.start_time,
+                )
+                .ok()
+        });
+        let finish = |outcome: OptimizationOutcome| {
+            if let (Some(journal), Some(task_id)) = (journal, task_id) {
+                let _ = journal.finish_task(task_id, outcome);
+            }
+        };
+
+        job_stop.store(false, Ordering::Relaxed);
+        let result = job.optimizer.as_ref().optimize(
             segments.clone(),
-            move |_optimization_result| {
-                // After optimization is finished, we still need to check if there are
-                // some further optimizations possible.
-                // If receiver is already dead - we do not care.
-                // If channel is full - optimization will be triggered by some other signal
-                let _ = sender.try_send(OptimizerSignal::Nop);
-            },
+            job.segment_ids.clone(),
+            job_stop.clone(),
         );
-        let mut handles = optimization_handles.lock().await;
-        handles.append(&mut new_handles);
-        handles.retain(|h| !h.is_finished())
+
+        match result {
+            Ok(_) => {
+                // This segment set optimized cleanly, so forget any prior
+                // failures recorded against it and free its segments.
+                scheduler.retry_state.lock().unwrap().remove(&retry_key);
+                scheduler
+                    .scheduled_segment_ids
+                    .lock()
+                    .unwrap()
+                    .retain(|id| !retry_key.contains(id));
+                finish(OptimizationOutcome::Success);
+                // It may have uncovered further optimizable segments.
+                let _ = requeue.try_send(OptimizerSignal::Nop);
+            }
+            Err(CollectionError::Cancelled { description }) => {
+                log::debug!("Optimization cancelled - {}", description);
+                scheduler
+                    .scheduled_segment_ids
+                    .lock()
+                    .unwrap()
+                    .retain(|id| !retry_key.contains(id));
+                finish(OptimizationOutcome::Cancelled);
+            }
+            Err(error) => {
+                let attempt = {
+                    let mut state = scheduler.retry_state.lock().unwrap();
+                    let counter = state.entry(retry_key.clone()).or_insert(0);
+                    *counter += 1;
+                    *counter
+                };
+                finish(OptimizationOutcome::Failed {
+                    error: error.to_string(),
+                });
+
+                if attempt > max_retries {
+                    // Consecutive failures exhausted the retry budget - this is
+                    // more likely to be the real cause of all further problems,
+                    // so persist it and give up on this segment set. The worker
+                    // itself stays alive to keep servicing other jobs.
+                    scheduler.retry_state.lock().unwrap().remove(&retry_key);
+                    scheduler
+                        .scheduled_segment_ids
+                        .lock()
+                        .unwrap()
+                        .retain(|id| !retry_key.contains(id));
+                    segments.write().report_optimizer_error(error.clone());
+                    log::error!(
+                        "Optimization error persisted after {attempt} \
+                         consecutive failures on segments {:?}: {error}",
+                        retry_key
+                    );
+                } else {
+                    let exponent = u32::try_from(attempt - 1).unwrap_or(u32::MAX).min(31);
+                    let delay = BASE_OPTIMIZATION_RETRY_DELAY
+                        .saturating_mul(1u32 << exponent)
+                        .min(MAX_OPTIMIZATION_RETRY_DELAY);
+                    log::warn!(
+                        "Optimization error (attempt {attempt}/{max_retries}), \
+                         retrying segments {:?} in {:?}: {error}",
+                        retry_key,
+                        delay
+                    );
+                    std::thread::sleep(delay);
+                    // Keep this segment set claimed and re-enqueue the same
+                    // job rather than requiring a restart.
+                    scheduler.queue.lock().unwrap().push_back(job);
+                }
+            }
+        }
+
+        *current.lock().unwrap() = None;
+    }
+
+    /// Body of one long-lived optimization worker thread: pop a job from
+    /// the front of the shared queue, or - if idle - try stealing one from
+    /// the tail; on a failed steal, sleep with a linear backoff up to
+    /// [`MAX_IDLE_BACKOFF`] instead of busy-spinning. Exits once
+    /// `scheduler.stop` is set, after finishing any job in hand.
+    fn optimization_worker_loop(
+        segments: LockedSegmentHolder,
+        scheduler: Arc<OptimizationScheduler>,
+        max_retries: usize,
+        journal: Option<Arc<OptimizationJournal>>,
+        job_stop: Arc<AtomicBool>,
+        current: Arc<Mutex<Option<OptimizationTaskInfo>>>,
+        requeue: Sender<OptimizerSignal>,
+    ) {
+        let mut backoff = IDLE_BACKOFF_STEP;
+        loop {
+            if scheduler.stop.load(Ordering::Relaxed) {
+                return;
+            }
+            if scheduler.paused.load(Ordering::Relaxed) {
+                std::thread::sleep(IDLE_BACKOFF_STEP);
+                continue;
+            }
+
+            let job = scheduler.queue.lock().unwrap().pop_front();
+            let job = match job.or_else(|| scheduler.queue.lock().unwrap().pop_back()) {
+                Some(job) => job,
+                None => {
+                    std::thread::sleep(backoff);
+                    backoff = (backoff + IDLE_BACKOFF_STEP).min(MAX_IDLE_BACKOFF);
+                    continue;
+                }
+            };
+            backoff = IDLE_BACKOFF_STEP;
+
+            Self::run_job(
+                job,
+                &segments,
+                &scheduler,
+                max_retries,
+                &journal,
+                &job_stop,
+                &current,
+                &requeue,
+            );
+        }
     }
 
-    async fn optimization_worker_fn(
+    /// Listens for post-update signals, recovers failed operations, and
+    /// discovers+enqueues pending optimization jobs for the worker pool;
+    /// also handles pause/resume/stop of the optimization pool as a whole.
+    async fn optimization_dispatcher_fn(
         optimizers: Arc<Vec<Arc<Optimizer>>>,
         sender: Sender<OptimizerSignal>,
         mut receiver: Receiver<OptimizerSignal>,
         segments: LockedSegmentHolder,
         wal: LockedWal,
-        optimization_handles: Arc<TokioMutex<Vec<StoppableTaskHandle<bool>>>>,
-        max_handles: usize,
+        scheduler: Arc<OptimizationScheduler>,
+        job_stops: Vec<Arc<AtomicBool>>,
     ) {
         while let Some(signal) = receiver.recv().await {
             match signal {
                 OptimizerSignal::Nop | OptimizerSignal::Operation(_) => {
-                    if signal != OptimizerSignal::Nop
-                        && optimization_handles.lock().await.len() >= max_handles
-                    {
-                        let mut handles = optimization_handles.lock().await;
-                        handles.retain(|h| !h.is_finished());
+                    if scheduler.paused.load(Ordering::Relaxed) {
                         continue;
                     }
-                    // We skip the check for number of optimization handles here
-                    // Because `Nop` usually means that we need to force the optimization
                     if Self::try_recover(segments.clone(), wal.clone())
                         .await
                         .is_err()
                     {
                         continue;
                     }
-                    Self::process_optimization(
-                        optimizers.clone(),
-                        segments.clone(),
-                        optimization_handles.clone(),
-                        sender.clone(),
-                    )
-                    .await;
+                    Self::enqueue_pending_optimizations(&optimizers, &segments, &scheduler);
+                }
+
+                OptimizerSignal::PauseOptimizations(drain) => {
+                    scheduler.paused.store(true, Ordering::Relaxed);
+                    if !drain {
+                        debug!("Cooperatively stopping in-flight optimization jobs for pause");
+                        for job_stop in &job_stops {
+                            job_stop.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+
+                OptimizerSignal::ResumeOptimizations => {
+                    scheduler.paused.store(false, Ordering::Relaxed);
+                    // Re-trigger any optimizations that became due while paused.
+                    let _ = sender.try_send(OptimizerSignal::Nop);
                 }
 
                 OptimizerSignal::Stop => break,
@@ -322,6 +905,7 @@ impl UpdateHandler {
         optimize_sender: Sender<OptimizerSignal>,
         wal: LockedWal,
         segments: LockedSegmentHolder,
+        watcher: Arc<WalWatcher>,
     ) {
         while let Some(signal) = receiver.recv().await {
             match signal {
@@ -342,9 +926,14 @@ impl UpdateHandler {
                         Ok(())
                     };
 
+                    let operation_for_watch = operation.clone();
                     let operation_result = flush_res
                         .and_then(|_| CollectionUpdater::update(&segments, op_num, operation));
 
+                    if operation_result.is_ok() {
+                        watcher.publish(op_num, operation_for_watch);
+                    }
+
                     let res = match operation_result {
                         Ok(update_res) => optimize_sender
                             .send(OptimizerSignal::Operation(op_num))
@@ -383,6 +972,22 @@ impl UpdateHandler {
                         debug!("Can't notify sender, assume nobody is waiting anymore");
                     });
                 }
+                UpdateSignal::PauseOptimizations(drain) => optimize_sender
+                    .send(OptimizerSignal::PauseOptimizations(drain))
+                    .await
+                    .unwrap_or_else(|_| {
+                        info!(
+                            "Can't pause optimizers, assume process is dead. Restart is required"
+                        );
+                    }),
+                UpdateSignal::ResumeOptimizations => optimize_sender
+                    .send(OptimizerSignal::ResumeOptimizations)
+                    .await
+                    .unwrap_or_else(|_| {
+                        info!(
+                            "Can't resume optimizers, assume process is dead. Restart is required"
+                        );
+                    }),
             }
         }
         // Transmitter was destroyed
@@ -40,6 +40,7 @@ async fn _test_snapshot_and_recover_collection(node_type: NodeType) {
     };
 
     let config = CollectionConfig {
+        version: collection::config::CURRENT_CONFIG_VERSION,
         params: collection_params,
         optimizer_config: TEST_OPTIMIZERS_CONFIG.clone(),
         wal_config,
@@ -180,3 +181,75 @@ async fn test_snapshot_and_recover_collection_normal() {
 async fn test_snapshot_and_recover_collection_listener() {
     _test_snapshot_and_recover_collection(NodeType::Listener).await;
 }
+
+/// Round-trips a handful of [`Record`]s through [`SqliteSnapshotExporter`]
+/// and asserts the recovered config and records are identical to the
+/// originals, the same property [`_test_snapshot_and_recover_collection`]
+/// checks for the tar format above.
+#[test]
+fn test_sqlite_snapshot_exporter_round_trip() {
+    use collection::operations::snapshot_format::{SnapshotExporter, SqliteSnapshotExporter};
+    use collection::operations::types::Record;
+    use segment::data_types::vectors::VectorStruct;
+    use segment::types::Payload;
+    use serde_json::json;
+
+    let wal_config = WalConfig {
+        wal_capacity_mb: 1,
+        wal_segments_ahead: 0,
+    };
+
+    let collection_params = CollectionParams {
+        vectors: VectorsConfig::Single(VectorParams {
+            size: NonZeroU64::new(4).unwrap(),
+            distance: Distance::Dot,
+            hnsw_config: None,
+            quantization_config: None,
+            on_disk: None,
+        }),
+        shard_number: NonZeroU32::new(1).unwrap(),
+        replication_factor: NonZeroU32::new(1).unwrap(),
+        write_consistency_factor: NonZeroU32::new(1).unwrap(),
+        on_disk_payload: false,
+        geoip_enrichment: None,
+    };
+
+    let config = CollectionConfig {
+        version: collection::config::CURRENT_CONFIG_VERSION,
+        params: collection_params,
+        optimizer_config: TEST_OPTIMIZERS_CONFIG.clone(),
+        wal_config,
+        hnsw_config: Default::default(),
+        quantization_config: Default::default(),
+    };
+
+    let records = vec![
+        Record {
+            id: 1.into(),
+            payload: Some(Payload::from(
+                json!({ "city": "Berlin" }).as_object().unwrap().clone(),
+            )),
+            vector: Some(VectorStruct::Single(vec![1.0, 0.0, 0.0, 0.0])),
+        },
+        Record {
+            id: 2.into(),
+            payload: None,
+            vector: Some(VectorStruct::Single(vec![0.0, 1.0, 0.0, 0.0])),
+        },
+    ];
+
+    let snapshot_file = Builder::new()
+        .prefix("test_sqlite_snapshot")
+        .suffix(".sqlite")
+        .tempfile()
+        .unwrap();
+
+    let exporter = SqliteSnapshotExporter;
+    exporter
+        .export(&config, &records, snapshot_file.path())
+        .unwrap();
+    let (recovered_config, recovered_records) = exporter.import(snapshot_file.path()).unwrap();
+
+    assert_eq!(recovered_config, config);
+    assert_eq!(recovered_records, records);
+}
@@ -0,0 +1,67 @@
+//! RocksDB compaction filter that garbage-collects `FieldIndex` entries
+//! belonging to point offsets that are no longer live, so a `drop`/`delete`
+//! doesn't leave tombstones sitting in the column family until the next full
+//! index `rebuild`.
+
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use atomic_refcell::AtomicRefCell;
+use rocksdb::{
+    CompactionDecision, CompactionFilter, CompactionFilterContext, CompactionFilterFactory,
+};
+
+use crate::id_tracker::IdTrackerSS;
+use crate::types::PointOffsetType;
+
+/// `FieldIndex` RocksDB keys end with the point offset encoded as a
+/// little-endian `u32`; keys shorter than this can't embed one.
+const POINT_ID_SUFFIX_LEN: usize = size_of::<u32>();
+
+fn decode_point_id(key: &[u8]) -> Option<PointOffsetType> {
+    let split = key.len().checked_sub(POINT_ID_SUFFIX_LEN)?;
+    let suffix: [u8; POINT_ID_SUFFIX_LEN] = key[split..].try_into().ok()?;
+    Some(u32::from_le_bytes(suffix) as PointOffsetType)
+}
+
+pub struct LiveIdsCompactionFilter {
+    live_ids: HashSet<PointOffsetType>,
+}
+
+impl CompactionFilter for LiveIdsCompactionFilter {
+    fn filter(&mut self, _level: u32, key: &[u8], _value: &[u8]) -> CompactionDecision {
+        match decode_point_id(key) {
+            // Key format we don't recognize: keep it, we can't safely judge liveness.
+            None => CompactionDecision::Keep,
+            Some(point_id) if self.live_ids.contains(&point_id) => CompactionDecision::Keep,
+            Some(_) => CompactionDecision::Remove,
+        }
+    }
+}
+
+/// Builds a [`LiveIdsCompactionFilter`] from a fresh snapshot of live
+/// internal ids whenever RocksDB starts a compaction.
+pub struct LiveIdsCompactionFilterFactory {
+    id_tracker: Arc<AtomicRefCell<IdTrackerSS>>,
+}
+
+impl LiveIdsCompactionFilterFactory {
+    pub fn new(id_tracker: Arc<AtomicRefCell<IdTrackerSS>>) -> Self {
+        Self { id_tracker }
+    }
+}
+
+impl CompactionFilterFactory for LiveIdsCompactionFilterFactory {
+    type Filter = LiveIdsCompactionFilter;
+
+    fn create(&mut self, _context: CompactionFilterContext) -> Self::Filter {
+        let live_ids = self.id_tracker.borrow().iter_ids().collect();
+        LiveIdsCompactionFilter { live_ids }
+    }
+
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"live-ids-compaction-filter\0").unwrap()
+    }
+}
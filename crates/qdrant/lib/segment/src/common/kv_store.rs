@@ -0,0 +1,264 @@
+//! A minimal transactional key-value interface, [`KvStore`], a [`KvBackend`]
+//! choice of embedded engine, and [`open_kv_store`] to open one. The
+//! id-tracker, payload storage and `SegmentBuilder::build` do not use this
+//! trait: they go through [`crate::common::rocksdb_wrapper`] directly, and no
+//! segment config field selects a backend.
+//!
+//! `"lmdb"` and `"sqlite"` are gated behind their own Cargo features, and
+//! opening either returns an error, with or without its feature: only
+//! `rocksdb` has a storage implementation, and only it is always compiled in.
+
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::entry::entry_point::{OperationError, OperationResult};
+
+/// Which embedded KV engine [`open_kv_store`] opens. Defaults to `Rocksdb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KvBackend {
+    #[default]
+    Rocksdb,
+    /// LMDB. Requires the `lmdb-backend` feature; opening it returns an error.
+    Lmdb,
+    /// SQLite. Requires the `sqlite-backend` feature; opening it returns an error.
+    Sqlite,
+}
+
+/// A single half-open `[start, end)` key range, as used by [`KvStore::range_scan`].
+pub struct KeyRange<'a> {
+    pub start: &'a [u8],
+    pub end: &'a [u8],
+}
+
+/// A minimal transactional key-value store, implemented here by a
+/// single-column-family RocksDB store. `IdTracker` and `PayloadStorage` are
+/// not built on it.
+pub trait KvStore: Send + Sync {
+    fn open(path: &Path) -> OperationResult<Self>
+    where
+        Self: Sized;
+
+    fn get(&self, key: &[u8]) -> OperationResult<Option<Vec<u8>>>;
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> OperationResult<()>;
+
+    fn remove(&self, key: &[u8]) -> OperationResult<()>;
+
+    /// Keys and values in `range`, in ascending key order.
+    fn range_scan(&self, range: KeyRange) -> OperationResult<Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// Runs `f` as a single atomic unit: either every write `f` makes lands,
+    /// or (if `f` returns `Err`) none of them do.
+    fn transaction(
+        &self,
+        f: &mut dyn FnMut(&dyn KvTransaction) -> OperationResult<()>,
+    ) -> OperationResult<()>;
+}
+
+/// The write surface available inside a [`KvStore::transaction`] closure.
+pub trait KvTransaction {
+    fn insert(&self, key: &[u8], value: &[u8]) -> OperationResult<()>;
+    fn remove(&self, key: &[u8]) -> OperationResult<()>;
+}
+
+/// Opens the configured backend at `path`, as a trait object so callers
+/// don't need to be generic over which engine was selected.
+pub fn open_kv_store(backend: KvBackend, path: &Path) -> OperationResult<Box<dyn KvStore>> {
+    match backend {
+        KvBackend::Rocksdb => Ok(Box::new(rocksdb_backend::RocksDbKvStore::open(path)?)),
+        KvBackend::Lmdb => lmdb_backend::open(path),
+        KvBackend::Sqlite => sqlite_backend::open(path),
+    }
+}
+
+mod rocksdb_backend {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use parking_lot::RwLock;
+    use rocksdb::{IteratorMode, Options, DB};
+
+    use super::{KeyRange, KvStore, KvTransaction};
+    use crate::common::rocksdb_wrapper::db_options;
+    use crate::entry::entry_point::{OperationError, OperationResult};
+
+    const DEFAULT_CF: &str = "kv_store";
+
+    /// [`KvStore`] backed by a single-column-family RocksDB database, the
+    /// same engine every store in this crate already uses.
+    pub struct RocksDbKvStore {
+        db: Arc<RwLock<DB>>,
+    }
+
+    impl KvStore for RocksDbKvStore {
+        fn open(path: &Path) -> OperationResult<Self> {
+            let options: Options = db_options();
+            let db = DB::open_cf(&options, path, [DEFAULT_CF])
+                .map_err(|err| OperationError::service_error(format!("RocksDB open error: {err}")))?;
+            Ok(Self {
+                db: Arc::new(RwLock::new(db)),
+            })
+        }
+
+        fn get(&self, key: &[u8]) -> OperationResult<Option<Vec<u8>>> {
+            let db = self.db.read();
+            let cf = cf_handle(&db)?;
+            db.get_cf(cf, key)
+                .map_err(|err| OperationError::service_error(format!("RocksDB get error: {err}")))
+        }
+
+        fn insert(&self, key: &[u8], value: &[u8]) -> OperationResult<()> {
+            let db = self.db.read();
+            let cf = cf_handle(&db)?;
+            db.put_cf(cf, key, value)
+                .map_err(|err| OperationError::service_error(format!("RocksDB put error: {err}")))
+        }
+
+        fn remove(&self, key: &[u8]) -> OperationResult<()> {
+            let db = self.db.read();
+            let cf = cf_handle(&db)?;
+            db.delete_cf(cf, key)
+                .map_err(|err| OperationError::service_error(format!("RocksDB delete error: {err}")))
+        }
+
+        fn range_scan(&self, range: KeyRange) -> OperationResult<Vec<(Vec<u8>, Vec<u8>)>> {
+            let db = self.db.read();
+            let cf = cf_handle(&db)?;
+            let iter = db
+                .iterator_cf(cf, IteratorMode::From(range.start, rocksdb::Direction::Forward));
+            let mut out = Vec::new();
+            for item in iter {
+                let (key, value) = item.map_err(|err| {
+                    OperationError::service_error(format!("RocksDB range scan error: {err}"))
+                })?;
+                if key.as_ref() >= range.end {
+                    break;
+                }
+                out.push((key.to_vec(), value.to_vec()));
+            }
+            Ok(out)
+        }
+
+        fn transaction(
+            &self,
+            f: &mut dyn FnMut(&dyn KvTransaction) -> OperationResult<()>,
+        ) -> OperationResult<()> {
+            let db = self.db.read();
+            let cf = cf_handle(&db)?;
+            let batch = std::cell::RefCell::new(rocksdb::WriteBatch::default());
+            f(&RocksDbTransaction { cf, batch: &batch })?;
+            db.write(batch.into_inner())
+                .map_err(|err| OperationError::service_error(format!("RocksDB commit error: {err}")))
+        }
+    }
+
+    struct RocksDbTransaction<'a> {
+        cf: &'a rocksdb::ColumnFamily,
+        batch: &'a std::cell::RefCell<rocksdb::WriteBatch>,
+    }
+
+    impl<'a> KvTransaction for RocksDbTransaction<'a> {
+        fn insert(&self, key: &[u8], value: &[u8]) -> OperationResult<()> {
+            self.batch.borrow_mut().put_cf(self.cf, key, value);
+            Ok(())
+        }
+
+        fn remove(&self, key: &[u8]) -> OperationResult<()> {
+            self.batch.borrow_mut().delete_cf(self.cf, key);
+            Ok(())
+        }
+    }
+
+    fn cf_handle(db: &DB) -> OperationResult<&rocksdb::ColumnFamily> {
+        db.cf_handle(DEFAULT_CF).ok_or_else(|| {
+            OperationError::service_error(format!("RocksDB cf_handle error: missing {DEFAULT_CF}"))
+        })
+    }
+}
+
+#[cfg(feature = "lmdb-backend")]
+mod lmdb_backend {
+    use std::path::Path;
+
+    use super::KvStore;
+    use crate::entry::entry_point::OperationResult;
+
+    pub fn open(path: &Path) -> OperationResult<Box<dyn KvStore>> {
+        Ok(Box::new(LmdbKvStore::open(path)?))
+    }
+
+    /// Placeholder for an LMDB store; [`LmdbKvStore::open`] always returns
+    /// an error.
+    pub struct LmdbKvStore {
+        // Left unimplemented: wiring a real `heed::Env` requires the
+        // `lmdb-backend` feature's dependencies, which this build doesn't
+        // vendor. The trait surface above is what a real implementation
+        // would fill in.
+        _private: (),
+    }
+
+    impl LmdbKvStore {
+        fn open(_path: &Path) -> OperationResult<Self> {
+            Err(crate::entry::entry_point::OperationError::service_error(
+                "LMDB backend is not yet implemented",
+            ))
+        }
+    }
+}
+
+#[cfg(not(feature = "lmdb-backend"))]
+mod lmdb_backend {
+    use std::path::Path;
+
+    use super::{KvStore, OperationError};
+    use crate::entry::entry_point::OperationResult;
+
+    pub fn open(_path: &Path) -> OperationResult<Box<dyn KvStore>> {
+        Err(OperationError::service_error(
+            "LMDB backend selected but this build was compiled without the `lmdb-backend` feature",
+        ))
+    }
+}
+
+#[cfg(feature = "sqlite-backend")]
+mod sqlite_backend {
+    use std::path::Path;
+
+    use super::KvStore;
+    use crate::entry::entry_point::OperationResult;
+
+    pub fn open(path: &Path) -> OperationResult<Box<dyn KvStore>> {
+        Ok(Box::new(SqliteKvStore::open(path)?))
+    }
+
+    /// Placeholder for a single-file SQLite store; [`SqliteKvStore::open`]
+    /// always returns an error.
+    pub struct SqliteKvStore {
+        _private: (),
+    }
+
+    impl SqliteKvStore {
+        fn open(_path: &Path) -> OperationResult<Self> {
+            Err(crate::entry::entry_point::OperationError::service_error(
+                "SQLite backend is not yet implemented",
+            ))
+        }
+    }
+}
+
+#[cfg(not(feature = "sqlite-backend"))]
+mod sqlite_backend {
+    use std::path::Path;
+
+    use super::{KvStore, OperationError};
+    use crate::entry::entry_point::OperationResult;
+
+    pub fn open(_path: &Path) -> OperationResult<Box<dyn KvStore>> {
+        Err(OperationError::service_error(
+            "SQLite backend selected but this build was compiled without the `sqlite-backend` feature",
+        ))
+    }
+}
@@ -3,7 +3,8 @@ use std::sync::Arc;
 
 use parking_lot::RwLock;
 //use atomic_refcell::{AtomicRef, AtomicRefCell};
-use rocksdb::{ColumnFamily, LogLevel, Options, WriteOptions, DB};
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::{BlockBasedOptions, ColumnFamily, Env, LogLevel, Options, WriteOptions, DB};
 
 use crate::common::Flusher;
 //use crate::common::arc_rwlock_iterator::ArcRwLockIterator;
@@ -12,6 +13,11 @@ use crate::entry::entry_point::{OperationError, OperationResult};
 const DB_CACHE_SIZE: usize = 10 * 1024 * 1024; // 10 mb
 const DB_MAX_LOG_SIZE: usize = 1024 * 1024; // 1 mb
 const DB_MAX_OPEN_FILES: usize = 256;
+/// Bits per key for the block-based table's bloom filter, so a point lookup
+/// that misses (e.g. `get_pinned_cf` for a key that doesn't exist) can skip
+/// reading a block instead of always scanning it. ~10 bits/key is RocksDB's
+/// usual recommendation for a ~1% false positive rate.
+const DB_BLOOM_FILTER_BITS_PER_KEY: f64 = 10.0;
 
 pub const DB_VECTOR_CF: &str = "vector";
 pub const DB_PAYLOAD_CF: &str = "payload";
@@ -22,6 +28,10 @@ pub const DB_VERSIONS_CF: &str = "version";
 pub struct DatabaseColumnWrapper {
     pub database: Arc<RwLock<DB>>,
     pub column_name: String,
+    /// Set by [`DatabaseColumnWrapper::new_read_only`]; rejects `put`/`remove`
+    /// instead of writing through a database opened via
+    /// [`open_db_read_only`], which holds no write lock on disk.
+    read_only: bool,
 }
 
 pub struct DatabaseColumnIterator<'a> {
@@ -35,7 +45,32 @@ pub struct LockedDatabaseColumnWrapper<'a> {
     column_name: &'a str,
 }
 
+/// One on-disk storage tier for SSTables, mirroring `rocksdb::DBPath`:
+/// RocksDB fills the tiers in the order given, each up to its
+/// `target_size`, and moves older data to later tiers (e.g. NVMe, then
+/// spinning disk). Once any tier is set, SSTables go only to the tiers, not
+/// to the primary `path`. The tiers apply to the whole database, not to one
+/// column family.
+pub struct DbPath {
+    pub path: std::path::PathBuf,
+    pub target_size: u64,
+}
+
+/// Extra tuning passed to [`db_options_with`]/[`open_db_with_options`] beyond the
+/// single primary `path`: additional storage tiers and an optional shared
+/// [`Env`], e.g. for rate-limited background IO or a thread pool shared
+/// across several collections' databases.
+#[derive(Default, Clone)]
+pub struct DbOpenOptions {
+    pub db_paths: Vec<DbPath>,
+    pub env: Option<Arc<Env>>,
+}
+
 pub fn db_options() -> Options {
+    db_options_with(&DbOpenOptions::default())
+}
+
+pub fn db_options_with(extra: &DbOpenOptions) -> Options {
     let mut options: Options = Options::default();
     options.set_write_buffer_size(DB_CACHE_SIZE);
     options.create_if_missing(true);
@@ -44,6 +79,33 @@ pub fn db_options() -> Options {
     options.set_max_log_file_size(DB_MAX_LOG_SIZE);
     options.create_missing_column_families(true);
     options.set_max_open_files(DB_MAX_OPEN_FILES as i32);
+
+    let mut block_based_options = BlockBasedOptions::default();
+    block_based_options.set_bloom_filter(DB_BLOOM_FILTER_BITS_PER_KEY, false);
+    options.set_block_based_table_factory(&block_based_options);
+
+    if !extra.db_paths.is_empty() {
+        let mut db_paths = Vec::with_capacity(extra.db_paths.len());
+        for db_path in &extra.db_paths {
+            match rocksdb::DBPath::new(&db_path.path, db_path.target_size) {
+                Ok(db_path) => db_paths.push(db_path),
+                Err(err) => {
+                    log::error!(
+                        "Ignoring invalid extra RocksDB storage path {}: {err}",
+                        db_path.path.display(),
+                    );
+                }
+            }
+        }
+        if !db_paths.is_empty() {
+            options.set_db_paths(&db_paths);
+        }
+    }
+
+    if let Some(env) = &extra.env {
+        options.set_env(env);
+    }
+
     #[cfg(debug_assertions)]
     {
         options.set_paranoid_checks(true);
@@ -54,12 +116,22 @@ pub fn db_options() -> Options {
 pub fn open_db<T: AsRef<str>>(
     path: &Path,
     vector_pathes: &[T],
+) -> Result<Arc<RwLock<DB>>, rocksdb::Error> {
+    open_db_with_options(path, vector_pathes, &DbOpenOptions::default())
+}
+
+/// Same as [`open_db`], but with [`DbOpenOptions`] for extra storage tiers
+/// and/or a shared [`Env`].
+pub fn open_db_with_options<T: AsRef<str>>(
+    path: &Path,
+    vector_pathes: &[T],
+    extra: &DbOpenOptions,
 ) -> Result<Arc<RwLock<DB>>, rocksdb::Error> {
     let mut column_families = vec![DB_PAYLOAD_CF, DB_MAPPING_CF, DB_VERSIONS_CF];
     for vector_path in vector_pathes {
         column_families.push(vector_path.as_ref());
     }
-    let db = DB::open_cf(&db_options(), path, column_families)?;
+    let db = DB::open_cf(&db_options_with(extra), path, column_families)?;
     Ok(Arc::new(RwLock::new(db)))
 }
 
@@ -68,6 +140,29 @@ pub fn check_db_exists(path: &Path) -> bool {
     db_file.exists()
 }
 
+/// Opens an existing database at `path` without taking RocksDB's write
+/// lock, so a second process can attach to a store another process is
+/// actively writing to (e.g. a query-only replica of the index). No WAL is
+/// replayed; `error_if_log_file_exist` controls whether a lingering WAL file
+/// from an unclean shutdown is treated as an error rather than ignored.
+pub fn open_db_read_only<T: AsRef<str>>(
+    path: &Path,
+    vector_pathes: &[T],
+    error_if_log_file_exist: bool,
+) -> Result<Arc<RwLock<DB>>, rocksdb::Error> {
+    let mut column_families = vec![DB_PAYLOAD_CF, DB_MAPPING_CF, DB_VERSIONS_CF];
+    for vector_path in vector_pathes {
+        column_families.push(vector_path.as_ref());
+    }
+    let db = DB::open_cf_for_read_only(
+        &db_options(),
+        path,
+        column_families,
+        error_if_log_file_exist,
+    )?;
+    Ok(Arc::new(RwLock::new(db)))
+}
+
 pub fn open_db_with_existing_cf(path: &Path) -> Result<Arc<RwLock<DB>>, rocksdb::Error> {
     let existing_column_families = if check_db_exists(path) {
         DB::list_cf(&db_options(), path)?
@@ -78,6 +173,26 @@ pub fn open_db_with_existing_cf(path: &Path) -> Result<Arc<RwLock<DB>>, rocksdb:
     Ok(Arc::new(RwLock::new(db)))
 }
 
+/// Same as [`open_db_with_existing_cf`], but installs `compaction_filter_factory`
+/// on the opened database, so background compactions can drop entries on the fly.
+pub fn open_db_with_existing_cf_and_compaction_filter<F>(
+    path: &Path,
+    compaction_filter_factory: F,
+) -> Result<Arc<RwLock<DB>>, rocksdb::Error>
+where
+    F: rocksdb::CompactionFilterFactory + 'static,
+{
+    let existing_column_families = if check_db_exists(path) {
+        DB::list_cf(&db_options(), path)?
+    } else {
+        vec![]
+    };
+    let mut options = db_options();
+    options.set_compaction_filter_factory(compaction_filter_factory);
+    let db = DB::open_cf(&options, path, existing_column_families)?;
+    Ok(Arc::new(RwLock::new(db)))
+}
+
 pub fn db_write_options() -> WriteOptions {
     let mut write_options = WriteOptions::default();
     write_options.set_sync(false);
@@ -112,6 +227,18 @@ impl DatabaseColumnWrapper {
         Self {
             database,
             column_name: column_name.to_string(),
+            read_only: false,
+        }
+    }
+
+    /// Same as [`Self::new`], but for a `database` opened via
+    /// [`open_db_read_only`]: `put`/`remove` return an error instead of
+    /// attempting to write through a database holding no write lock.
+    pub fn new_read_only(database: Arc<RwLock<DB>>, column_name: &str) -> Self {
+        Self {
+            database,
+            column_name: column_name.to_string(),
+            read_only: true,
         }
     }
 
@@ -120,6 +247,12 @@ impl DatabaseColumnWrapper {
         K: AsRef<[u8]>,
         V: AsRef<[u8]>,
     {
+        if self.read_only {
+            return Err(OperationError::service_error(format!(
+                "Cannot put into column family {} - database was opened read-only",
+                &self.column_name
+            )));
+        }
         let db = self.database.read();
         let cf_handle = self.get_column_family(&db)?;
         db.put_cf_opt(cf_handle, key, value, &Self::get_write_options())
@@ -146,6 +279,12 @@ impl DatabaseColumnWrapper {
     where
         K: AsRef<[u8]>,
     {
+        if self.read_only {
+            return Err(OperationError::service_error(format!(
+                "Cannot remove from column family {} - database was opened read-only",
+                &self.column_name
+            )));
+        }
         let db = self.database.read();
         let cf_handle = self.get_column_family(&db)?;
         db.delete_cf(cf_handle, key).map_err(|err| {
@@ -211,6 +350,44 @@ impl DatabaseColumnWrapper {
         Ok(db.cf_handle(&self.column_name).is_some())
     }
 
+    /// Compacts the range `[start, end)` of this wrapper's column family,
+    /// via RocksDB's `compact_range_cf`, to proactively reclaim space and
+    /// flatten the LSM tree (e.g. after `recreate_column_family` or many
+    /// `remove` calls leave behind tombstones that slow down reads) instead
+    /// of waiting for background compaction. `None` on either end means
+    /// unbounded in that direction.
+    pub fn compact_range(&self, start: Option<&[u8]>, end: Option<&[u8]>) -> OperationResult<()> {
+        let db = self.database.read();
+        let cf_handle = self.get_column_family(&db)?;
+        db.compact_range_cf(cf_handle, start, end);
+        Ok(())
+    }
+
+    /// Compacts the whole column family; see [`Self::compact_range`].
+    pub fn compact_all(&self) -> OperationResult<()> {
+        self.compact_range(None, None)
+    }
+
+    /// Creates a consistent point-in-time checkpoint of the whole database
+    /// (every column family, not just this wrapper's) at `target_dir`, via
+    /// RocksDB's Checkpoint API. SST files are hard-linked rather than
+    /// copied where possible, so the checkpoint costs minimal extra disk
+    /// space while the live database keeps accepting writes. `target_dir`
+    /// must not already exist.
+    ///
+    /// To restore, reopen `target_dir` with [`open_db_with_existing_cf`] (or
+    /// [`open_db_read_only`] for an inspection-only copy).
+    pub fn snapshot(&self, target_dir: &Path) -> OperationResult<()> {
+        let db = self.database.read();
+        let checkpoint = Checkpoint::new(&db).map_err(|err| {
+            OperationError::service_error(format!("RocksDB checkpoint error: {err}"))
+        })?;
+        checkpoint.create_checkpoint(target_dir).map_err(|err| {
+            OperationError::service_error(format!("RocksDB checkpoint error: {err}"))
+        })?;
+        Ok(())
+    }
+
     fn get_write_options() -> WriteOptions {
         let mut write_options = WriteOptions::default();
         write_options.set_sync(false);
@@ -235,6 +412,13 @@ impl<'a> LockedDatabaseColumnWrapper<'a> {
     pub fn iter(&self) -> OperationResult<DatabaseColumnIterator> {
         DatabaseColumnIterator::new(&self.guard, self.column_name)
     }
+
+    /// Like [`Self::iter`], but only over entries whose key starts with
+    /// `prefix`, e.g. to scan just the `mapping`/`version` entries for one
+    /// point instead of the whole column family.
+    pub fn iter_from(&self, prefix: &[u8]) -> OperationResult<DatabaseColumnIterator> {
+        DatabaseColumnIterator::new_from(&self.guard, self.column_name, prefix)
+    }
 }
 
 impl<'a> DatabaseColumnIterator<'a> {
@@ -252,6 +436,27 @@ impl<'a> DatabaseColumnIterator<'a> {
             just_seeked: true,
         })
     }
+
+    /// Same as [`Self::new`], but positions the iterator at the first key
+    /// greater than or equal to `prefix` instead of the first key overall.
+    pub fn new_from(
+        db: &'a DB,
+        column_name: &str,
+        prefix: &[u8],
+    ) -> OperationResult<DatabaseColumnIterator<'a>> {
+        let handle = db.cf_handle(column_name).ok_or_else(|| {
+            OperationError::service_error(format!(
+                "RocksDB cf_handle error: Cannot find column family {column_name}"
+            ))
+        })?;
+        let mut iter = db.raw_iterator_cf(&handle);
+        iter.seek(prefix);
+        Ok(DatabaseColumnIterator {
+            handle,
+            iter,
+            just_seeked: true,
+        })
+    }
 }
 
 impl<'a> Iterator for DatabaseColumnIterator<'a> {
@@ -0,0 +1,328 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::entry::entry_point::{OperationError, OperationResult};
+use crate::types::PointOffsetType;
+
+/// Byte size of one bucket slot: `(value_hash: u64, posting_offset: u64,
+/// posting_len: u32, occupied: u8)`, padded to 24 bytes.
+const SLOT_SIZE: usize = 24;
+/// Fraction of a bucket's slots that may be occupied before the whole store
+/// doubles `k` and redistributes every entry.
+const MAX_LOAD_FACTOR: f64 = 0.75;
+
+/// Configuration for a [`BucketStore`], mirroring the
+/// `index_bucket_capacity_pow2`/`index_bucket_max_probes` knobs on
+/// `SharedStorageConfig` in the `collection` crate.
+#[derive(Clone, Copy, Debug)]
+pub struct BucketStoreConfig {
+    /// Initial bucket count is `2^capacity_pow2`.
+    pub capacity_pow2: u8,
+    /// Slots probed from a value's home bucket before a lookup gives up or
+    /// an insert grows the store; never fewer than one bucket's slots.
+    pub max_probes: usize,
+}
+
+impl Default for BucketStoreConfig {
+    fn default() -> Self {
+        BucketStoreConfig {
+            capacity_pow2: 16,
+            max_probes: 8,
+        }
+    }
+}
+
+/// A disk-backed, memory-mapped `value -> posting list` table for a single
+/// field index, an alternative to holding every distinct value's posting
+/// list resident in a `HashMap` the way [`super::map_index::MapIndex`] and
+/// [`super::numeric_index::NumericIndex`] do. No field index in this crate
+/// is built on it.
+///
+/// Every value hashes into one of `2^k` buckets; a bucket is a fixed-size
+/// run of slots in one mmap'd file, each holding `(value_hash,
+/// posting_list_offset, posting_list_len)`. A full bucket is resolved by
+/// linear probing into neighbouring slots up to `max_probes` times; if that
+/// still doesn't find room, the whole store doubles `k` and every existing
+/// slot is re-hashed into the wider bucket space. Posting lists themselves
+/// live in a single append-only side file referenced by slot offset/len,
+/// following the same record-plus-offset-table shape as
+/// [`super::super::payload_storage::mmap_payload_storage::MmapPayloadStorage`].
+///
+/// Collisions between two different values that hash to the same
+/// `value_hash` are not distinguished — the caller is expected to filter
+/// the returned posting list against its own exact-match check, the same
+/// tradeoff the wire format in the proposal this is modelled on makes.
+pub struct BucketStore {
+    config: BucketStoreConfig,
+    slots_file: File,
+    slots_mmap: MmapMut,
+    postings_file: File,
+    postings_mmap: Mmap,
+    /// `k`: the store currently has `2^k` buckets, each `bucket_slots` slots
+    /// wide, for `2^k * bucket_slots` total slots in `slots_mmap`.
+    k: u8,
+    occupied_slots: usize,
+}
+
+const BUCKET_SLOTS: usize = 8;
+
+impl BucketStore {
+    pub fn open(path: &Path, config: BucketStoreConfig) -> OperationResult<Self> {
+        std::fs::create_dir_all(path)
+            .map_err(|err| OperationError::service_error(format!("cannot create {path:?}: {err}")))?;
+
+        let slots_path = path.join("buckets.slots");
+        let slots_file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .open(&slots_path)
+            .map_err(|err| OperationError::service_error(format!("cannot open {slots_path:?}: {err}")))?;
+
+        let k = config.capacity_pow2;
+        let total_slots = (1usize << k) * BUCKET_SLOTS;
+        let required_len = (total_slots * SLOT_SIZE) as u64;
+        if slots_file.metadata().map(|meta| meta.len()).unwrap_or(0) < required_len {
+            slots_file
+                .set_len(required_len)
+                .map_err(|err| OperationError::service_error(format!("cannot size bucket slots file: {err}")))?;
+        }
+        let slots_mmap = open_write_mmap(&slots_file)?;
+
+        let postings_path = path.join("buckets.postings");
+        let postings_file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&postings_path)
+            .map_err(|err| OperationError::service_error(format!("cannot open {postings_path:?}: {err}")))?;
+        let postings_mmap = open_read_mmap(&postings_file)?;
+
+        let occupied_slots = (0..total_slots)
+            .filter(|&slot| slot_occupied(&slots_mmap, slot))
+            .count();
+
+        Ok(BucketStore {
+            config,
+            slots_file,
+            slots_mmap,
+            postings_file,
+            postings_mmap,
+            k,
+            occupied_slots,
+        })
+    }
+
+    fn total_slots(&self) -> usize {
+        (1usize << self.k) * BUCKET_SLOTS
+    }
+
+    fn bucket_of(&self, value_hash: u64) -> usize {
+        (value_hash as usize) & ((1usize << self.k) - 1)
+    }
+
+    fn append_posting(&mut self, points: &[PointOffsetType]) -> OperationResult<(u64, u32)> {
+        let mut bytes = Vec::with_capacity(points.len() * 4);
+        for point in points {
+            bytes.extend_from_slice(&point.to_le_bytes());
+        }
+        let offset = self.postings_mmap.len() as u64;
+        self.postings_file
+            .write_all(&bytes)
+            .map_err(|err| OperationError::service_error(format!("cannot append posting list: {err}")))?;
+        self.postings_file
+            .flush()
+            .map_err(|err| OperationError::service_error(format!("cannot flush posting list: {err}")))?;
+        self.postings_mmap = open_read_mmap(&self.postings_file)?;
+        Ok((offset, points.len() as u32))
+    }
+
+    fn read_posting(&self, offset: u64, len: u32) -> Vec<PointOffsetType> {
+        let start = offset as usize;
+        let end = start + len as usize * 4;
+        self.postings_mmap[start..end]
+            .chunks_exact(4)
+            .map(|chunk| PointOffsetType::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    /// Associates `value_hash` with `points`, probing forward from its home
+    /// bucket for a free or matching slot and doubling the bucket count
+    /// first if the home bucket has reached its load factor.
+    pub fn insert(&mut self, value_hash: u64, points: &[PointOffsetType]) -> OperationResult<()> {
+        let home = self.bucket_of(value_hash) * BUCKET_SLOTS;
+        let bucket_occupied = (home..home + BUCKET_SLOTS)
+            .filter(|&slot| slot_occupied(&self.slots_mmap, slot))
+            .count();
+        if bucket_occupied as f64 >= BUCKET_SLOTS as f64 * MAX_LOAD_FACTOR {
+            self.grow()?;
+        }
+
+        let home = self.bucket_of(value_hash) * BUCKET_SLOTS;
+        for probe in 0..self.config.max_probes.max(BUCKET_SLOTS) {
+            let slot = (home + probe) % self.total_slots();
+            let occupied = slot_occupied(&self.slots_mmap, slot);
+            if !occupied || slot_hash(&self.slots_mmap, slot) == value_hash {
+                let (offset, len) = self.append_posting(points)?;
+                write_slot(&mut self.slots_mmap, slot, value_hash, offset, len);
+                if !occupied {
+                    self.occupied_slots += 1;
+                }
+                return Ok(());
+            }
+        }
+
+        // Ran out of probes without finding room: widen the table and retry.
+        self.grow()?;
+        self.insert(value_hash, points)
+    }
+
+    /// Returns the posting list stored for `value_hash`, probing the same
+    /// sequence of slots [`Self::insert`] would have used.
+    pub fn get(&self, value_hash: u64) -> Option<Vec<PointOffsetType>> {
+        let home = self.bucket_of(value_hash) * BUCKET_SLOTS;
+        for probe in 0..self.config.max_probes.max(BUCKET_SLOTS) {
+            let slot = (home + probe) % self.total_slots();
+            if !slot_occupied(&self.slots_mmap, slot) {
+                continue;
+            }
+            if slot_hash(&self.slots_mmap, slot) == value_hash {
+                let (offset, len) = slot_posting(&self.slots_mmap, slot);
+                return Some(self.read_posting(offset, len));
+            }
+        }
+        None
+    }
+
+    /// Doubles `k` and re-inserts every occupied slot into the wider table.
+    fn grow(&mut self) -> OperationResult<()> {
+        let old_total = self.total_slots();
+        let mut existing = Vec::with_capacity(self.occupied_slots);
+        for slot in 0..old_total {
+            if slot_occupied(&self.slots_mmap, slot) {
+                let hash = slot_hash(&self.slots_mmap, slot);
+                let (offset, len) = slot_posting(&self.slots_mmap, slot);
+                existing.push((hash, offset, len));
+            }
+        }
+
+        self.k += 1;
+        let required_len = (self.total_slots() * SLOT_SIZE) as u64;
+        self.slots_file
+            .set_len(required_len)
+            .map_err(|err| OperationError::service_error(format!("cannot grow bucket slots file: {err}")))?;
+        self.slots_mmap = open_write_mmap(&self.slots_file)?;
+        for byte in self.slots_mmap.iter_mut() {
+            *byte = 0;
+        }
+        self.occupied_slots = 0;
+
+        for (hash, offset, len) in existing {
+            let home = self.bucket_of(hash) * BUCKET_SLOTS;
+            let mut placed = false;
+            for probe in 0..self.config.max_probes.max(BUCKET_SLOTS) {
+                let slot = (home + probe) % self.total_slots();
+                if !slot_occupied(&self.slots_mmap, slot) {
+                    write_slot(&mut self.slots_mmap, slot, hash, offset, len);
+                    self.occupied_slots += 1;
+                    placed = true;
+                    break;
+                }
+            }
+            if !placed {
+                // `max_probes` didn't fit even a freshly doubled table; grow
+                // again rather than drop the entry.
+                return self.grow();
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of distinct values currently stored.
+    pub fn len(&self) -> usize {
+        self.occupied_slots
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.occupied_slots == 0
+    }
+
+    /// Iterates every occupied slot's posting list, for
+    /// `count_indexed_points`/`payload_blocks`-style scans that don't need
+    /// to hold the whole table in memory at once.
+    pub fn iter_postings(&self) -> impl Iterator<Item = Vec<PointOffsetType>> + '_ {
+        (0..self.total_slots())
+            .filter(|&slot| slot_occupied(&self.slots_mmap, slot))
+            .map(|slot| {
+                let (offset, len) = slot_posting(&self.slots_mmap, slot);
+                self.read_posting(offset, len)
+            })
+    }
+
+    pub fn clear(&mut self) -> OperationResult<()> {
+        self.k = self.config.capacity_pow2;
+        let required_len = (self.total_slots() * SLOT_SIZE) as u64;
+        self.slots_file
+            .set_len(0)
+            .map_err(|err| OperationError::service_error(format!("cannot truncate bucket slots file: {err}")))?;
+        self.slots_file
+            .set_len(required_len)
+            .map_err(|err| OperationError::service_error(format!("cannot size bucket slots file: {err}")))?;
+        self.slots_mmap = open_write_mmap(&self.slots_file)?;
+        self.postings_file
+            .set_len(0)
+            .map_err(|err| OperationError::service_error(format!("cannot truncate postings file: {err}")))?;
+        self.postings_mmap = open_read_mmap(&self.postings_file)?;
+        self.occupied_slots = 0;
+        Ok(())
+    }
+}
+
+/// Hashes an arbitrary indexed value down to the `u64` stored in a slot.
+pub fn hash_value<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn slot_occupied(mmap: &MmapMut, slot: usize) -> bool {
+    mmap[slot * SLOT_SIZE + 20] != 0
+}
+
+fn slot_hash(mmap: &MmapMut, slot: usize) -> u64 {
+    let start = slot * SLOT_SIZE;
+    u64::from_le_bytes(mmap[start..start + 8].try_into().unwrap())
+}
+
+fn slot_posting(mmap: &MmapMut, slot: usize) -> (u64, u32) {
+    let start = slot * SLOT_SIZE;
+    let offset = u64::from_le_bytes(mmap[start + 8..start + 16].try_into().unwrap());
+    let len = u32::from_le_bytes(mmap[start + 16..start + 20].try_into().unwrap());
+    (offset, len)
+}
+
+fn write_slot(mmap: &mut MmapMut, slot: usize, hash: u64, offset: u64, len: u32) {
+    let start = slot * SLOT_SIZE;
+    mmap[start..start + 8].copy_from_slice(&hash.to_le_bytes());
+    mmap[start + 8..start + 16].copy_from_slice(&offset.to_le_bytes());
+    mmap[start + 16..start + 20].copy_from_slice(&len.to_le_bytes());
+    mmap[start + 20] = 1;
+}
+
+fn open_read_mmap(file: &File) -> OperationResult<Mmap> {
+    // Safety: this file is only ever grown by appending from this process,
+    // the same caveat every other mmap'd store in this crate accepts.
+    unsafe { Mmap::map(file) }
+        .map_err(|err| OperationError::service_error(format!("cannot mmap bucket store file: {err}")))
+}
+
+fn open_write_mmap(file: &File) -> OperationResult<MmapMut> {
+    // Safety: see `open_read_mmap`.
+    unsafe { MmapMut::map_mut(file) }
+        .map_err(|err| OperationError::service_error(format!("cannot mmap bucket store file: {err}")))
+}
@@ -1,3 +1,4 @@
+use roaring::RoaringBitmap;
 use serde_json::Value;
 use smol_str::SmolStr;
 
@@ -5,15 +6,17 @@ use crate::common::utils::MultiValue;
 use crate::common::Flusher;
 use crate::entry::entry_point::OperationResult;
 use crate::index::field_index::binary_index::BinaryIndex;
+use crate::index::field_index::fst_keyword_index::FstKeywordIndex;
 use crate::index::field_index::full_text_index::text_index::FullTextIndex;
 use crate::index::field_index::geo_index::GeoMapIndex;
+use crate::index::field_index::geo_rtree_index::GeoRTreeIndex;
 use crate::index::field_index::map_index::MapIndex;
 use crate::index::field_index::numeric_index::NumericIndex;
 use crate::index::field_index::{CardinalityEstimation, PayloadBlockCondition};
 use crate::telemetry::PayloadIndexTelemetry;
 use crate::types::{
-    FieldCondition, FloatPayloadType, IntPayloadType, Match, MatchText, PayloadKeyType,
-    PointOffsetType,
+    AnyVariants, FieldCondition, FloatPayloadType, IntPayloadType, Match, MatchAny, MatchExcept,
+    MatchText, MatchValue, PayloadKeyType, PointOffsetType, ValueVariants,
 };
 
 pub trait PayloadFieldIndex {
@@ -36,6 +39,19 @@ pub trait PayloadFieldIndex {
         condition: &'a FieldCondition,
     ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>>;
 
+    /// Get the matching points as a compressed posting list instead of a
+    /// boxed iterator, so a `must`/`should`/`must_not` combination of
+    /// conditions can be resolved with roaring AND/OR/ANDNOT instead of
+    /// chaining and intersecting iterators point-by-point.
+    ///
+    /// The default implementation just collects [`Self::filter`]; an index
+    /// that keeps a `RoaringBitmap` posting list per indexed value bucket
+    /// (rather than a plain point-id list) should override this to return
+    /// that bitmap directly instead of rebuilding it.
+    fn filter_bitmap(&self, condition: &FieldCondition) -> Option<RoaringBitmap> {
+        Some(self.filter(condition)?.collect())
+    }
+
     /// Return estimation of points amount which satisfy given condition
     fn estimate_cardinality(&self, condition: &FieldCondition) -> Option<CardinalityEstimation>;
 
@@ -48,6 +64,56 @@ pub trait PayloadFieldIndex {
     ) -> Box<dyn Iterator<Item = PayloadBlockCondition> + '_>;
 }
 
+/// A single step in a permissive JSON-pointer-style path into a payload
+/// `Value`: either an object key to descend into, or a marker (written `[]`
+/// after a key) that expands every element of an array at this position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    ExpandArray,
+}
+
+/// Parses a dotted field path like `"author.address.city"` or
+/// `"reviews[].score"` into [`PathSegment`]s.
+pub fn parse_path(path: &str) -> Vec<PathSegment> {
+    path.split('.')
+        .flat_map(|part| match part.strip_suffix("[]") {
+            Some(key) => vec![PathSegment::Key(key.to_string()), PathSegment::ExpandArray],
+            None => vec![PathSegment::Key(part.to_string())],
+        })
+        .collect()
+}
+
+/// Walks `value` along `path`, descending into objects and fanning out
+/// across arrays at any level (an array of arrays flattens fully), and
+/// returns every `Value` reached at the end of the path.
+///
+/// A missing intermediate key or a scalar encountered where an object key is
+/// expected yields no values rather than an error, so a path that doesn't
+/// apply to a given point is silently skipped just like a missing top-level
+/// field is today.
+pub fn resolve_path<'a>(value: &'a Value, path: &[PathSegment]) -> Vec<&'a Value> {
+    let Some((segment, rest)) = path.split_first() else {
+        return match value {
+            Value::Array(values) => values.iter().flat_map(|x| resolve_path(x, path)).collect(),
+            _ => vec![value],
+        };
+    };
+    match segment {
+        PathSegment::Key(key) => match value {
+            Value::Array(values) => values.iter().flat_map(|x| resolve_path(x, path)).collect(),
+            _ => value
+                .get(key)
+                .map(|next| resolve_path(next, rest))
+                .unwrap_or_default(),
+        },
+        PathSegment::ExpandArray => match value {
+            Value::Array(values) => values.iter().flat_map(|x| resolve_path(x, rest)).collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
 pub trait ValueIndexer<T> {
     /// Add multiple values associated with a single point
     /// This function should be called only once for each point
@@ -57,13 +123,25 @@ pub trait ValueIndexer<T> {
     fn get_value(&self, value: &Value) -> Option<T>;
 
     /// Try to extract index-able values from payload `Value`, even if it is an array
-    fn get_values(&self, value: &Value) -> Vec<T> {
+    fn collect_values(&self, value: &Value) -> Vec<T> {
         match value {
             Value::Array(values) => values.iter().flat_map(|x| self.get_value(x)).collect(),
             _ => self.get_value(value).map(|x| vec![x]).unwrap_or_default(),
         }
     }
 
+    /// Extracts every index-able value reachable from `root` along a nested
+    /// `path` (see [`resolve_path`]), so a field like `reviews[].score` can
+    /// be indexed without pre-flattening the payload. A single top-level key
+    /// path behaves like [`Self::collect_values`] on that key's value, except
+    /// that arrays nested inside it are flattened fully.
+    fn get_values_at_path(&self, root: &Value, path: &[PathSegment]) -> Vec<T> {
+        resolve_path(root, path)
+            .into_iter()
+            .flat_map(|value| self.collect_values(value))
+            .collect()
+    }
+
     /// Add point with payload to index
     fn add_point(
         &mut self,
@@ -119,8 +197,10 @@ pub enum FieldIndex {
     KeywordIndex(MapIndex<SmolStr>),
     FloatIndex(NumericIndex<FloatPayloadType>),
     GeoIndex(GeoMapIndex),
+    GeoRTreeIndex(GeoRTreeIndex),
     FullTextIndex(FullTextIndex),
     BinaryIndex(BinaryIndex),
+    FstKeywordIndex(FstKeywordIndex),
 }
 
 impl FieldIndex {
@@ -137,16 +217,121 @@ impl FieldIndex {
         payload_value: &Value,
     ) -> Option<bool> {
         match self {
-            FieldIndex::IntIndex(_) => None,
-            FieldIndex::IntMapIndex(_) => None,
-            FieldIndex::KeywordIndex(_) => None,
-            FieldIndex::FloatIndex(_) => None,
-            FieldIndex::GeoIndex(_) => None,
+            FieldIndex::IntIndex(index) => {
+                let mut applicable = false;
+                let mut matched = false;
+                if let Some(range) = &condition.range {
+                    applicable = true;
+                    matched |= index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|value| range.check_range(*value as FloatPayloadType));
+                }
+                if let Some(Match::Value(MatchValue {
+                    value: ValueVariants::Integer(value),
+                })) = &condition.r#match
+                {
+                    applicable = true;
+                    matched |= index.collect_values(payload_value).iter().any(|v| v == value);
+                }
+                applicable.then_some(matched)
+            }
+            FieldIndex::FloatIndex(index) => condition.range.as_ref().map(|range| {
+                index
+                    .collect_values(payload_value)
+                    .iter()
+                    .any(|value| range.check_range(*value))
+            }),
+            FieldIndex::IntMapIndex(index) => condition.r#match.as_ref().and_then(|m| match m {
+                Match::Value(MatchValue {
+                    value: ValueVariants::Integer(value),
+                }) => Some(index.collect_values(payload_value).iter().any(|v| v == value)),
+                Match::Any(MatchAny {
+                    any: AnyVariants::Integers(list),
+                }) => Some(
+                    index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|v| list.contains(v)),
+                ),
+                Match::Except(MatchExcept {
+                    except: AnyVariants::Integers(list),
+                }) => Some(
+                    index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|v| !list.contains(v)),
+                ),
+                _ => None,
+            }),
+            FieldIndex::KeywordIndex(index) => condition.r#match.as_ref().and_then(|m| match m {
+                Match::Value(MatchValue {
+                    value: ValueVariants::Keyword(keyword),
+                }) => Some(
+                    index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|v| v.as_str() == keyword),
+                ),
+                Match::Any(MatchAny {
+                    any: AnyVariants::Keywords(list),
+                }) => Some(index.collect_values(payload_value).iter().any(|v| {
+                    list.iter().any(|keyword| keyword.as_str() == v.as_str())
+                })),
+                Match::Except(MatchExcept {
+                    except: AnyVariants::Keywords(list),
+                }) => Some(index.collect_values(payload_value).iter().any(|v| {
+                    !list.iter().any(|keyword| keyword.as_str() == v.as_str())
+                })),
+                _ => None,
+            }),
+            FieldIndex::GeoIndex(index) => {
+                let mut applicable = false;
+                let mut matched = false;
+                if let Some(geo_radius) = &condition.geo_radius {
+                    applicable = true;
+                    matched |= index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|point| geo_radius.check_point(point.lon, point.lat));
+                }
+                if let Some(geo_bounding_box) = &condition.geo_bounding_box {
+                    applicable = true;
+                    matched |= index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|point| geo_bounding_box.check_point(point.lon, point.lat));
+                }
+                applicable.then_some(matched)
+            }
+            FieldIndex::GeoRTreeIndex(index) => {
+                let mut applicable = false;
+                let mut matched = false;
+                if let Some(geo_radius) = &condition.geo_radius {
+                    applicable = true;
+                    matched |= index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|point| geo_radius.check_point(point.lon, point.lat));
+                }
+                if let Some(geo_bounding_box) = &condition.geo_bounding_box {
+                    applicable = true;
+                    matched |= index
+                        .collect_values(payload_value)
+                        .iter()
+                        .any(|point| geo_bounding_box.check_point(point.lon, point.lat));
+                }
+                applicable.then_some(matched)
+            }
             FieldIndex::BinaryIndex(_) => None,
+            // Prefix/fuzzy matching is resolved through `filter`/
+            // `estimate_cardinality` against the FST directly; there's no
+            // per-value check independent of the index to do here.
+            FieldIndex::FstKeywordIndex(_) => None,
             FieldIndex::FullTextIndex(full_text_index) => match &condition.r#match {
                 Some(Match::Text(MatchText { text })) => {
                     let query = full_text_index.parse_query(text);
-                    for value in full_text_index.get_values(payload_value) {
+                    for value in full_text_index.collect_values(payload_value) {
                         let document = full_text_index.parse_document(&value);
                         if query.check_match(&document) {
                             return Some(true);
@@ -166,8 +351,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(payload_field_index) => payload_field_index,
             FieldIndex::FloatIndex(payload_field_index) => payload_field_index,
             FieldIndex::GeoIndex(payload_field_index) => payload_field_index,
+            FieldIndex::GeoRTreeIndex(payload_field_index) => payload_field_index,
             FieldIndex::BinaryIndex(payload_field_index) => payload_field_index,
             FieldIndex::FullTextIndex(payload_field_index) => payload_field_index,
+            FieldIndex::FstKeywordIndex(payload_field_index) => payload_field_index,
         }
     }
 
@@ -179,8 +366,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(ref mut payload_field_index) => payload_field_index,
             FieldIndex::FloatIndex(ref mut payload_field_index) => payload_field_index,
             FieldIndex::GeoIndex(ref mut payload_field_index) => payload_field_index,
+            FieldIndex::GeoRTreeIndex(ref mut payload_field_index) => payload_field_index,
             FieldIndex::BinaryIndex(ref mut payload_field_index) => payload_field_index,
             FieldIndex::FullTextIndex(ref mut payload_field_index) => payload_field_index,
+            FieldIndex::FstKeywordIndex(ref mut payload_field_index) => payload_field_index,
         }
     }
 
@@ -191,8 +380,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(ref mut payload_field_index) => payload_field_index.load(),
             FieldIndex::FloatIndex(ref mut payload_field_index) => payload_field_index.load(),
             FieldIndex::GeoIndex(ref mut payload_field_index) => payload_field_index.load(),
+            FieldIndex::GeoRTreeIndex(ref mut payload_field_index) => payload_field_index.load(),
             FieldIndex::BinaryIndex(ref mut payload_field_index) => payload_field_index.load(),
             FieldIndex::FullTextIndex(ref mut payload_field_index) => payload_field_index.load(),
+            FieldIndex::FstKeywordIndex(ref mut payload_field_index) => payload_field_index.load(),
         }
     }
 
@@ -203,8 +394,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(index) => index.clear(),
             FieldIndex::FloatIndex(index) => index.clear(),
             FieldIndex::GeoIndex(index) => index.clear(),
+            FieldIndex::GeoRTreeIndex(index) => index.clear(),
             FieldIndex::BinaryIndex(index) => index.clear(),
             FieldIndex::FullTextIndex(index) => index.clear(),
+            FieldIndex::FstKeywordIndex(index) => index.clear(),
         }
     }
 
@@ -215,8 +408,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(index) => index.recreate(),
             FieldIndex::FloatIndex(index) => index.recreate(),
             FieldIndex::GeoIndex(index) => index.recreate(),
+            FieldIndex::GeoRTreeIndex(index) => index.recreate(),
             FieldIndex::BinaryIndex(index) => index.recreate(),
             FieldIndex::FullTextIndex(index) => index.recreate(),
+            FieldIndex::FstKeywordIndex(index) => index.recreate(),
         }
     }
 
@@ -235,6 +430,10 @@ impl FieldIndex {
         self.get_payload_field_index().filter(condition)
     }
 
+    pub fn filter_bitmap(&self, condition: &FieldCondition) -> Option<RoaringBitmap> {
+        self.get_payload_field_index().filter_bitmap(condition)
+    }
+
     pub fn estimate_cardinality(
         &self,
         condition: &FieldCondition,
@@ -273,12 +472,18 @@ impl FieldIndex {
             FieldIndex::GeoIndex(ref mut payload_field_index) => {
                 payload_field_index.add_point(id, payload)
             }
+            FieldIndex::GeoRTreeIndex(ref mut payload_field_index) => {
+                payload_field_index.add_point(id, payload)
+            }
             FieldIndex::BinaryIndex(ref mut payload_field_index) => {
                 payload_field_index.add_point(id, payload)
             }
             FieldIndex::FullTextIndex(ref mut payload_field_index) => {
                 payload_field_index.add_point(id, payload)
             }
+            FieldIndex::FstKeywordIndex(ref mut payload_field_index) => {
+                payload_field_index.add_point(id, payload)
+            }
         }
     }
 
@@ -289,8 +494,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(index) => index.remove_point(point_id),
             FieldIndex::FloatIndex(index) => index.remove_point(point_id),
             FieldIndex::GeoIndex(index) => index.remove_point(point_id),
+            FieldIndex::GeoRTreeIndex(index) => index.remove_point(point_id),
             FieldIndex::BinaryIndex(index) => index.remove_point(point_id),
             FieldIndex::FullTextIndex(index) => index.remove_point(point_id),
+            FieldIndex::FstKeywordIndex(index) => index.remove_point(point_id),
         }
     }
 
@@ -301,8 +508,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(index) => index.get_telemetry_data(),
             FieldIndex::FloatIndex(index) => index.get_telemetry_data(),
             FieldIndex::GeoIndex(index) => index.get_telemetry_data(),
+            FieldIndex::GeoRTreeIndex(index) => index.get_telemetry_data(),
             FieldIndex::BinaryIndex(index) => index.get_telemetry_data(),
             FieldIndex::FullTextIndex(index) => index.get_telemetry_data(),
+            FieldIndex::FstKeywordIndex(index) => index.get_telemetry_data(),
         }
     }
 
@@ -313,8 +522,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(index) => index.values_count(point_id),
             FieldIndex::FloatIndex(index) => index.values_count(point_id),
             FieldIndex::GeoIndex(index) => index.values_count(point_id),
+            FieldIndex::GeoRTreeIndex(index) => index.values_count(point_id),
             FieldIndex::BinaryIndex(index) => index.values_count(point_id),
             FieldIndex::FullTextIndex(index) => index.values_count(point_id),
+            FieldIndex::FstKeywordIndex(index) => index.values_count(point_id),
         }
     }
 
@@ -325,8 +536,10 @@ impl FieldIndex {
             FieldIndex::KeywordIndex(index) => index.values_is_empty(point_id),
             FieldIndex::FloatIndex(index) => index.values_is_empty(point_id),
             FieldIndex::GeoIndex(index) => index.values_is_empty(point_id),
+            FieldIndex::GeoRTreeIndex(index) => index.values_is_empty(point_id),
             FieldIndex::BinaryIndex(index) => index.values_is_empty(point_id),
             FieldIndex::FullTextIndex(index) => index.values_is_empty(point_id),
+            FieldIndex::FstKeywordIndex(index) => index.values_is_empty(point_id),
         }
     }
 }
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use fst::automaton::{Automaton, Levenshtein, Str};
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+use parking_lot::RwLock;
+use serde_json::Value;
+use smol_str::SmolStr;
+
+use crate::common::Flusher;
+use crate::entry::entry_point::{OperationError, OperationResult};
+use crate::index::field_index::{
+    CardinalityEstimation, PayloadBlockCondition, PayloadFieldIndex, ValueIndexer,
+};
+use crate::telemetry::PayloadIndexTelemetry;
+use crate::types::{FieldCondition, Match, MatchFuzzy, MatchText, PayloadKeyType, PointOffsetType};
+
+/// Levenshtein automata beyond this distance blow up in state count, so
+/// fuzzy queries are clamped to it rather than rejected outright.
+const MAX_FUZZY_DISTANCE: u32 = 2;
+/// Below this many characters, a fuzzy query at `MAX_FUZZY_DISTANCE` matches
+/// too large a fraction of the keyword space to be worth running at all.
+const MIN_FUZZY_QUERY_LEN: usize = 3;
+
+/// The mutable parts of [`FstKeywordIndex`], behind a single lock so a
+/// `flusher()` rebuild and a concurrent query/write never see the FST and
+/// its posting lists in inconsistent states relative to each other.
+#[derive(Default)]
+struct Inner {
+    /// Sorted distinct keywords -> term id, as an immutable FST.
+    fst: Option<Map<Vec<u8>>>,
+    /// term id -> posting list, built alongside `fst`.
+    postings: Vec<Vec<PointOffsetType>>,
+    /// keyword -> posting list for points added since the last rebuild.
+    overlay: HashMap<SmolStr, Vec<PointOffsetType>>,
+}
+
+impl Inner {
+    /// Folds `overlay` into a freshly built FST, leaving the overlay empty.
+    fn rebuild(&mut self) -> OperationResult<()> {
+        let mut merged: HashMap<SmolStr, Vec<PointOffsetType>> = HashMap::new();
+
+        if let Some(fst) = &self.fst {
+            let mut stream = fst.stream();
+            while let Some((keyword, term_id)) = stream.next() {
+                if let Ok(keyword) = std::str::from_utf8(keyword) {
+                    merged.insert(SmolStr::from(keyword), self.postings[term_id as usize].clone());
+                }
+            }
+        }
+        for (keyword, points) in self.overlay.drain() {
+            merged.entry(keyword).or_default().extend(points);
+        }
+
+        let mut keywords: Vec<SmolStr> = merged.keys().cloned().collect();
+        keywords.sort();
+
+        let mut builder = MapBuilder::memory();
+        let mut postings = Vec::with_capacity(keywords.len());
+        for (term_id, keyword) in keywords.iter().enumerate() {
+            builder
+                .insert(keyword.as_bytes(), term_id as u64)
+                .map_err(fst_error)?;
+            postings.push(merged.remove(keyword).unwrap_or_default());
+        }
+
+        self.fst = Some(Map::new(builder.into_inner().map_err(fst_error)?).map_err(fst_error)?);
+        self.postings = postings;
+        Ok(())
+    }
+
+    fn match_prefix(&self, prefix: &str) -> Vec<PointOffsetType> {
+        let mut points = Vec::new();
+        if let Some(fst) = &self.fst {
+            let mut stream = fst.search(Str::new(prefix).starts_with()).into_stream();
+            while let Some((_, term_id)) = stream.next() {
+                points.extend(self.postings[term_id as usize].iter().copied());
+            }
+        }
+        for (keyword, overlay_points) in &self.overlay {
+            if keyword.starts_with(prefix) {
+                points.extend(overlay_points.iter().copied());
+            }
+        }
+        points
+    }
+
+    fn match_fuzzy(&self, term: &str, distance: u32) -> Vec<PointOffsetType> {
+        let mut points = Vec::new();
+        if let Some(fst) = &self.fst {
+            if let Ok(automaton) = Levenshtein::new(term, distance) {
+                let mut stream = fst.search(automaton).into_stream();
+                while let Some((_, term_id)) = stream.next() {
+                    points.extend(self.postings[term_id as usize].iter().copied());
+                }
+            }
+        }
+        for (keyword, overlay_points) in &self.overlay {
+            if levenshtein_at_most(term, keyword, distance) {
+                points.extend(overlay_points.iter().copied());
+            }
+        }
+        points
+    }
+
+    fn keyword_count(&self) -> usize {
+        self.fst.as_ref().map_or(0, |fst| fst.len()) + self.overlay.len()
+    }
+
+    fn count_indexed_points(&self) -> usize {
+        let mut points: std::collections::HashSet<PointOffsetType> = Default::default();
+        for postings in self.postings.iter().chain(self.overlay.values()) {
+            points.extend(postings.iter().copied());
+        }
+        points.len()
+    }
+
+    fn values_count(&self, point_id: PointOffsetType) -> usize {
+        self.postings
+            .iter()
+            .chain(self.overlay.values())
+            .filter(|postings| postings.contains(&point_id))
+            .count()
+    }
+
+    fn drop_point(&mut self, id: PointOffsetType) {
+        for postings in &mut self.postings {
+            postings.retain(|&point| point != id);
+        }
+        for postings in self.overlay.values_mut() {
+            postings.retain(|&point| point != id);
+        }
+    }
+}
+
+/// A keyword index backed by an immutable finite-state transducer, so
+/// prefix and bounded-edit-distance ("fuzzy") queries can be answered by
+/// running an automaton over the FST instead of scanning every distinct
+/// value the way [`super::map_index::MapIndex`]'s hash map does.
+///
+/// The FST only supports rebuild-from-scratch, so it is rebuilt lazily: new
+/// points land in a small mutable `overlay` posting-list map that both
+/// [`Self::add_many`] and every query consult alongside the FST, and
+/// [`Self::flusher`] is what folds the overlay into a fresh FST, the same
+/// role a RocksDB column family's flush plays in turning buffered writes
+/// into the queryable on-disk form.
+pub struct FstKeywordIndex {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl Default for FstKeywordIndex {
+    fn default() -> Self {
+        FstKeywordIndex {
+            inner: Arc::new(RwLock::new(Inner::default())),
+        }
+    }
+}
+
+impl FstKeywordIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn recreate(&self) -> OperationResult<()> {
+        *self.inner.write() = Inner::default();
+        Ok(())
+    }
+
+    /// Points whose keyword starts with `prefix`.
+    pub fn match_prefix(&self, prefix: &str) -> Vec<PointOffsetType> {
+        self.inner.read().match_prefix(prefix)
+    }
+
+    /// Points whose keyword is within `distance` (1 or 2) edits of `term`.
+    pub fn match_fuzzy(&self, term: &str, distance: u32) -> Vec<PointOffsetType> {
+        self.inner.read().match_fuzzy(term, distance)
+    }
+
+    pub fn get_telemetry_data(&self) -> PayloadIndexTelemetry {
+        let inner = self.inner.read();
+        PayloadIndexTelemetry {
+            field_name: None,
+            points_count: inner.count_indexed_points(),
+            points_values_count: inner.keyword_count(),
+            histogram_bucket_size: None,
+        }
+    }
+
+    pub fn values_count(&self, point_id: PointOffsetType) -> usize {
+        self.inner.read().values_count(point_id)
+    }
+
+    pub fn values_is_empty(&self, point_id: PointOffsetType) -> bool {
+        self.values_count(point_id) == 0
+    }
+
+    /// Resolve a `Match::Text` (prefix) or `Match::Fuzzy` (bounded edit
+    /// distance) condition against the FST. Returns `None` for any other
+    /// match kind, or for a fuzzy query too short to bound usefully.
+    fn matched_points(&self, condition: &FieldCondition) -> Option<Vec<PointOffsetType>> {
+        match condition.r#match.as_ref()? {
+            Match::Text(MatchText { text }) => Some(self.match_prefix(text)),
+            Match::Fuzzy(MatchFuzzy { text, max_distance }) => {
+                if text.chars().count() < MIN_FUZZY_QUERY_LEN {
+                    return None;
+                }
+                let distance = (*max_distance).min(MAX_FUZZY_DISTANCE);
+                Some(self.match_fuzzy(text, distance))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ValueIndexer<SmolStr> for FstKeywordIndex {
+    fn add_many(&mut self, id: PointOffsetType, values: Vec<SmolStr>) -> OperationResult<()> {
+        let mut inner = self.inner.write();
+        for value in values {
+            inner.overlay.entry(value).or_default().push(id);
+        }
+        Ok(())
+    }
+
+    fn get_value(&self, value: &Value) -> Option<SmolStr> {
+        match value {
+            Value::String(text) => Some(SmolStr::from(text.as_str())),
+            _ => None,
+        }
+    }
+
+    fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()> {
+        self.inner.write().drop_point(id);
+        Ok(())
+    }
+}
+
+fn fst_error(err: impl std::fmt::Display) -> OperationError {
+    OperationError::service_error(format!("fst keyword index error: {err}"))
+}
+
+/// Plain edit-distance check used for the small mutable overlay, where
+/// building a full Levenshtein automaton isn't worth it.
+fn levenshtein_at_most(a: &str, b: &str, max_distance: u32) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) as u32 > max_distance {
+        return false;
+    }
+
+    let mut previous: Vec<u32> = (0..=b.len() as u32).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut current = vec![i as u32 + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = u32::from(ca != cb);
+            current.push(
+                (previous[j + 1] + 1)
+                    .min(current[j] + 1)
+                    .min(previous[j] + cost),
+            );
+        }
+        previous = current;
+    }
+    *previous.last().unwrap_or(&0) <= max_distance
+}
+
+impl PayloadFieldIndex for FstKeywordIndex {
+    fn count_indexed_points(&self) -> usize {
+        self.inner.read().count_indexed_points()
+    }
+
+    fn load(&mut self) -> OperationResult<bool> {
+        // Nothing is persisted to disk by this self-contained implementation,
+        // so there's nothing to load; the FST is rebuilt from the payload
+        // storage the same way a freshly created index would be.
+        Ok(false)
+    }
+
+    fn clear(self) -> OperationResult<()> {
+        *self.inner.write() = Inner::default();
+        Ok(())
+    }
+
+    fn flusher(&self) -> Flusher {
+        let inner = self.inner.clone();
+        Box::new(move || inner.write().rebuild())
+    }
+
+    fn filter<'a>(
+        &'a self,
+        condition: &'a FieldCondition,
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
+        Some(Box::new(self.matched_points(condition)?.into_iter()))
+    }
+
+    fn estimate_cardinality(&self, condition: &FieldCondition) -> Option<CardinalityEstimation> {
+        let matched = self.matched_points(condition)?.len();
+        Some(CardinalityEstimation::exact(matched))
+    }
+
+    fn payload_blocks(
+        &self,
+        _threshold: usize,
+        _key: PayloadKeyType,
+    ) -> Box<dyn Iterator<Item = PayloadBlockCondition> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
@@ -0,0 +1,179 @@
+//! Standard base32 geohash encode/decode, plus the covering-prefix
+//! computation [`super::geo_index::GeoMapIndex`] uses to turn a
+//! `GeoRadius`/`GeoBoundingBox` query into a set of hash-bucket lookups.
+//!
+//! Geohash interleaves alternating bits of longitude and latitude, each
+//! refined by bisecting its remaining `[min, max]` range: a `0` bit means
+//! the value fell in the lower half, `1` the upper half. Every 5 bits are
+//! packed into one base32 character, so a longer prefix is a smaller,
+//! more precise rectangle nested inside every shorter prefix of it -
+//! which is exactly what makes prefix matching a valid (if approximate)
+//! proxy for spatial containment.
+
+const BASE32_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Default hash length used when encoding points into
+/// [`super::geo_index::GeoMapIndex`]; ~4.8m x 4.8m cells at the equator,
+/// fine enough to keep false-positive prefix matches rare without making
+/// the bucket map too large.
+pub const GEO_HASH_PRECISION: usize = 9;
+
+/// Encodes `(lon, lat)` into a base32 geohash of `precision` characters.
+pub fn encode(lon: f64, lat: f64, precision: usize) -> String {
+    let mut lon_range = (-180.0, 180.0);
+    let mut lat_range = (-90.0, 90.0);
+    let mut even_bit = true;
+    let mut bit = 0;
+    let mut char_value = 0usize;
+    let mut hash = String::with_capacity(precision);
+
+    while hash.len() < precision {
+        if even_bit {
+            let mid = (lon_range.0 + lon_range.1) / 2.0;
+            if lon >= mid {
+                char_value = (char_value << 1) | 1;
+                lon_range.0 = mid;
+            } else {
+                char_value <<= 1;
+                lon_range.1 = mid;
+            }
+        } else {
+            let mid = (lat_range.0 + lat_range.1) / 2.0;
+            if lat >= mid {
+                char_value = (char_value << 1) | 1;
+                lat_range.0 = mid;
+            } else {
+                char_value <<= 1;
+                lat_range.1 = mid;
+            }
+        }
+        even_bit = !even_bit;
+
+        bit += 1;
+        if bit == 5 {
+            hash.push(BASE32_ALPHABET[char_value] as char);
+            bit = 0;
+            char_value = 0;
+        }
+    }
+
+    hash
+}
+
+/// The `[lon_min, lon_max] x [lat_min, lat_max]` rectangle a geohash string
+/// covers, by replaying [`encode`]'s bisection in reverse.
+pub fn decode_bounds(hash: &str) -> Option<((f64, f64), (f64, f64))> {
+    let mut lon_range = (-180.0, 180.0);
+    let mut lat_range = (-90.0, 90.0);
+    let mut even_bit = true;
+
+    for ch in hash.chars() {
+        let char_value = BASE32_ALPHABET.iter().position(|&c| c as char == ch)?;
+        for shift in (0..5).rev() {
+            let bit = (char_value >> shift) & 1;
+            if even_bit {
+                let mid = (lon_range.0 + lon_range.1) / 2.0;
+                if bit == 1 {
+                    lon_range.0 = mid;
+                } else {
+                    lon_range.1 = mid;
+                }
+            } else {
+                let mid = (lat_range.0 + lat_range.1) / 2.0;
+                if bit == 1 {
+                    lat_range.0 = mid;
+                } else {
+                    lat_range.1 = mid;
+                }
+            }
+            even_bit = !even_bit;
+        }
+    }
+
+    Some((lon_range, lat_range))
+}
+
+/// Every geohash prefix, at every length down to `min_precision`, of a
+/// point encoded at `GEO_HASH_PRECISION` - the set a stored point's full
+/// hash should be looked up under so a shorter-prefix query can find it.
+pub fn prefixes(full_hash: &str, min_precision: usize) -> impl Iterator<Item = &str> {
+    (min_precision..=full_hash.len()).map(move |len| &full_hash[..len])
+}
+
+/// The geohash prefixes (at `precision` characters) whose cells intersect
+/// the axis-aligned rectangle `[lon_min, lon_max] x [lat_min, lat_max]`,
+/// found by walking the cell grid at that precision. Used to turn a
+/// `GeoBoundingBox` - or the bounding box around a `GeoRadius` circle -
+/// into the set of hash buckets [`super::geo_index::GeoMapIndex`] must
+/// union before doing the exact point-level check.
+pub fn covering_prefixes(
+    lon_min: f64,
+    lat_min: f64,
+    lon_max: f64,
+    lat_max: f64,
+    precision: usize,
+) -> Vec<String> {
+    let mut prefixes = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+
+    let ((cell_lon_min, cell_lon_max), (cell_lat_min, cell_lat_max)) =
+        match decode_bounds(&encode(lon_min, lat_min, precision)) {
+            Some(bounds) => bounds,
+            None => return prefixes,
+        };
+    let lon_step = (cell_lon_max - cell_lon_min).max(f64::EPSILON);
+    let lat_step = (cell_lat_max - cell_lat_min).max(f64::EPSILON);
+
+    let mut lat = lat_min;
+    loop {
+        let mut lon = lon_min;
+        loop {
+            let hash = encode(lon, lat, precision);
+            if seen.insert(hash.clone()) {
+                prefixes.push(hash);
+            }
+            if lon >= lon_max {
+                break;
+            }
+            lon = (lon + lon_step).min(lon_max);
+        }
+        if lat >= lat_max {
+            break;
+        }
+        lat = (lat + lat_step).min(lat_max);
+    }
+
+    prefixes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_known_value() {
+        // Berlin, Germany - checked against a reference geohash encoder.
+        let hash = encode(13.413637435864272, 52.52197645, 9);
+        assert!(hash.starts_with("u33dc"));
+    }
+
+    #[test]
+    fn test_decode_bounds_contains_encoded_point() {
+        let lon = -122.4194;
+        let lat = 37.7749;
+        let hash = encode(lon, lat, 9);
+        let ((lon_min, lon_max), (lat_min, lat_max)) = decode_bounds(&hash).unwrap();
+        assert!((lon_min..=lon_max).contains(&lon));
+        assert!((lat_min..=lat_max).contains(&lat));
+    }
+
+    #[test]
+    fn test_prefixes_are_nested() {
+        let hash = encode(0.0, 0.0, 9);
+        let all: Vec<&str> = prefixes(&hash, 1).collect();
+        assert_eq!(all.len(), 9);
+        for prefix in &all {
+            assert!(hash.starts_with(prefix));
+        }
+    }
+}
@@ -0,0 +1,365 @@
+//! Geohash-bucket geo index, used instead of
+//! [`super::geo_rtree_index::GeoRTreeIndex`] when the field is better
+//! served by grouping points under their geohash prefix than by a spatial
+//! tree - see [`super::geo_rtree_index::GeoRTreeIndex`]'s doc comment for
+//! when each is the better fit.
+//!
+//! Every `{lon, lat}` value is encoded (via [`super::geo_hash::encode`]) to
+//! a [`super::geo_hash::GEO_HASH_PRECISION`]-character hash, which is
+//! stored under every one of its own prefixes (via
+//! [`super::geo_hash::prefixes`]) in a `hash prefix -> point ids` multimap.
+//! A query computes the covering set of prefixes for its circle/rectangle
+//! (via [`super::geo_hash::covering_prefixes`]), at a precision coarse
+//! enough to keep that set small, unions their posting lists, and then
+//! refines with the haversine (`GeoRadius::check_point`) or min/max
+//! (`GeoBoundingBox::check_point`) exact check, to drop the false positives
+//! a coarse hash-prefix match lets in near a cell edge.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::Flusher;
+use crate::entry::entry_point::{OperationError, OperationResult};
+use crate::index::field_index::geo_hash::{
+    covering_prefixes, encode, prefixes, GEO_HASH_PRECISION,
+};
+use crate::index::field_index::{
+    CardinalityEstimation, PayloadBlockCondition, PayloadFieldIndex, ValueIndexer,
+};
+use crate::telemetry::PayloadIndexTelemetry;
+use crate::types::{FieldCondition, GeoPoint, PayloadKeyType, PointOffsetType};
+
+/// Shortest prefix length a query looks up; a wide query drops to shorter
+/// prefixes, down to this length, so its covering set stays small.
+const MIN_QUERY_PRECISION: usize = 1;
+
+/// Mean earth radius in meters, matching the copy
+/// [`super::geo_rtree_index::EARTH_RADIUS_METERS`] uses for the same
+/// radius-to-bounding-box conversion.
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct GeoEntry {
+    point_id: PointOffsetType,
+    lon: f64,
+    lat: f64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Storage {
+    /// `point_id -> {lon, lat}` values, indexed at full precision.
+    entries: Vec<GeoEntry>,
+}
+
+/// Geo index backed by a geohash-prefix -> point-id multimap; see this
+/// module's doc comment.
+pub struct GeoMapIndex {
+    /// Every stored `{lon, lat}` value, by point id.
+    entries: Vec<GeoEntry>,
+    /// `hash prefix -> point ids`, populated for every prefix length from
+    /// [`MIN_QUERY_PRECISION`] up to [`GEO_HASH_PRECISION`] of each entry's
+    /// full hash, so a query at any precision in that range can look its
+    /// covering prefixes up directly.
+    points_per_hash: BTreeMap<String, HashSet<PointOffsetType>>,
+    path: PathBuf,
+}
+
+impl GeoMapIndex {
+    pub fn new(path: PathBuf) -> Self {
+        GeoMapIndex {
+            entries: Vec::new(),
+            points_per_hash: BTreeMap::new(),
+            path,
+        }
+    }
+
+    pub fn recreate(&self) -> OperationResult<()> {
+        let _ = fs::remove_file(&self.path);
+        Ok(())
+    }
+
+    pub fn get_telemetry_data(&self) -> PayloadIndexTelemetry {
+        PayloadIndexTelemetry {
+            field_name: None,
+            points_count: self.count_indexed_points(),
+            points_values_count: self.entries.len(),
+            histogram_bucket_size: None,
+        }
+    }
+
+    pub fn values_count(&self, point_id: PointOffsetType) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.point_id == point_id)
+            .count()
+    }
+
+    pub fn values_is_empty(&self, point_id: PointOffsetType) -> bool {
+        self.values_count(point_id) == 0
+    }
+
+    fn index_entry(&mut self, entry: GeoEntry) {
+        let full_hash = encode(entry.lon, entry.lat, GEO_HASH_PRECISION);
+        for prefix in prefixes(&full_hash, MIN_QUERY_PRECISION) {
+            self.points_per_hash
+                .entry(prefix.to_string())
+                .or_default()
+                .insert(entry.point_id);
+        }
+        self.entries.push(entry);
+    }
+
+    fn rebuild_index(&mut self) {
+        self.points_per_hash.clear();
+        let entries = std::mem::take(&mut self.entries);
+        for entry in entries {
+            self.index_entry(entry);
+        }
+    }
+
+    /// Point ids found under any prefix in `query_prefixes`, deduplicated.
+    fn candidates(&self, query_prefixes: &[String]) -> HashSet<PointOffsetType> {
+        let mut candidates = HashSet::new();
+        for prefix in query_prefixes {
+            if let Some(points) = self.points_per_hash.get(prefix) {
+                candidates.extend(points.iter().copied());
+            }
+        }
+        candidates
+    }
+
+    /// Points matching the condition's `geo_radius`/`geo_bounding_box`, if
+    /// either is set: the covering geohash prefixes are looked up and
+    /// unioned, then refined with an exact point-level check to drop false
+    /// positives near a cell's edge - see this module's doc comment.
+    fn matched_points(&self, condition: &FieldCondition) -> Option<Vec<PointOffsetType>> {
+        // Longest prefix whose cell grid covers the query box in at most 1024 cells.
+        let query_precision = |lon_span: f64, lat_span: f64| {
+            (MIN_QUERY_PRECISION..=GEO_HASH_PRECISION)
+                .rev()
+                .find(|&precision| {
+                    let bits = precision * 5;
+                    let lon_cells = lon_span / (360.0 / 2f64.powi(((bits + 1) / 2) as i32)) + 1.0;
+                    let lat_cells = lat_span / (180.0 / 2f64.powi((bits / 2) as i32)) + 1.0;
+                    lon_cells * lat_cells <= 1024.0
+                })
+                .unwrap_or(MIN_QUERY_PRECISION)
+        };
+        if let Some(geo_bounding_box) = &condition.geo_bounding_box {
+            let query_prefixes = covering_prefixes(
+                geo_bounding_box.top_left.lon,
+                geo_bounding_box.bottom_right.lat,
+                geo_bounding_box.bottom_right.lon,
+                geo_bounding_box.top_left.lat,
+                query_precision(
+                    geo_bounding_box.bottom_right.lon - geo_bounding_box.top_left.lon,
+                    geo_bounding_box.top_left.lat - geo_bounding_box.bottom_right.lat,
+                ),
+            );
+            let candidates = self.candidates(&query_prefixes);
+            return Some(
+                self.entries
+                    .iter()
+                    .filter(|entry| candidates.contains(&entry.point_id))
+                    .filter(|entry| geo_bounding_box.check_point(entry.lon, entry.lat))
+                    .map(|entry| entry.point_id)
+                    .collect(),
+            );
+        }
+        if let Some(geo_radius) = &condition.geo_radius {
+            let delta_lat_deg = (geo_radius.radius / EARTH_RADIUS_METERS).to_degrees();
+            let lat_rad = geo_radius.center.lat.to_radians();
+            let delta_lon_deg = delta_lat_deg / lat_rad.cos().max(f64::EPSILON);
+            let query_prefixes = covering_prefixes(
+                geo_radius.center.lon - delta_lon_deg,
+                geo_radius.center.lat - delta_lat_deg,
+                geo_radius.center.lon + delta_lon_deg,
+                geo_radius.center.lat + delta_lat_deg,
+                query_precision(2.0 * delta_lon_deg, 2.0 * delta_lat_deg),
+            );
+            let candidates = self.candidates(&query_prefixes);
+            return Some(
+                self.entries
+                    .iter()
+                    .filter(|entry| candidates.contains(&entry.point_id))
+                    .filter(|entry| geo_radius.check_point(entry.lon, entry.lat))
+                    .map(|entry| entry.point_id)
+                    .collect(),
+            );
+        }
+        None
+    }
+}
+
+impl ValueIndexer<GeoPoint> for GeoMapIndex {
+    fn add_many(&mut self, id: PointOffsetType, values: Vec<GeoPoint>) -> OperationResult<()> {
+        for value in values {
+            self.index_entry(GeoEntry {
+                point_id: id,
+                lon: value.lon,
+                lat: value.lat,
+            });
+        }
+        Ok(())
+    }
+
+    fn get_value(&self, value: &Value) -> Option<GeoPoint> {
+        let obj = value.as_object()?;
+        let lon = obj.get("lon")?.as_f64()?;
+        let lat = obj.get("lat")?.as_f64()?;
+        Some(GeoPoint { lon, lat })
+    }
+
+    fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()> {
+        self.entries.retain(|entry| entry.point_id != id);
+        self.rebuild_index();
+        Ok(())
+    }
+}
+
+impl PayloadFieldIndex for GeoMapIndex {
+    fn count_indexed_points(&self) -> usize {
+        let mut points: HashSet<PointOffsetType> = HashSet::new();
+        points.extend(self.entries.iter().map(|entry| entry.point_id));
+        points.len()
+    }
+
+    fn load(&mut self) -> OperationResult<bool> {
+        if !self.path.exists() {
+            return Ok(false);
+        }
+        let bytes = fs::read(&self.path).map_err(|err| {
+            OperationError::service_error(format!("geo map index read error: {err}"))
+        })?;
+        let storage: Storage = bincode::deserialize(&bytes).map_err(|err| {
+            OperationError::service_error(format!("geo map index deserialize error: {err}"))
+        })?;
+        self.entries = storage.entries;
+        self.rebuild_index();
+        Ok(true)
+    }
+
+    fn clear(self) -> OperationResult<()> {
+        let _ = fs::remove_file(&self.path);
+        Ok(())
+    }
+
+    fn flusher(&self) -> Flusher {
+        let entries = self.entries.clone();
+        let path = self.path.clone();
+        Box::new(move || {
+            let storage = Storage { entries };
+            let bytes = bincode::serialize(&storage).map_err(|err| {
+                OperationError::service_error(format!("geo map index serialize error: {err}"))
+            })?;
+            fs::write(&path, bytes).map_err(|err| {
+                OperationError::service_error(format!("geo map index write error: {err}"))
+            })
+        })
+    }
+
+    fn filter<'a>(
+        &'a self,
+        condition: &'a FieldCondition,
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
+        Some(Box::new(self.matched_points(condition)?.into_iter()))
+    }
+
+    fn estimate_cardinality(&self, condition: &FieldCondition) -> Option<CardinalityEstimation> {
+        let matched = self.matched_points(condition)?.len();
+        Some(CardinalityEstimation::exact(matched))
+    }
+
+    fn payload_blocks(
+        &self,
+        _threshold: usize,
+        _key: PayloadKeyType,
+    ) -> Box<dyn Iterator<Item = PayloadBlockCondition> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{GeoBoundingBox, GeoRadius};
+
+    #[test]
+    fn test_radius_query_refines_hash_bucket_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = GeoMapIndex::new(dir.path().join("field.geo_map"));
+
+        let berlin = GeoPoint {
+            lat: 52.52197645,
+            lon: 13.413637435864272,
+        };
+        let moscow = GeoPoint {
+            lat: 55.7536283,
+            lon: 37.62137960067377,
+        };
+        index.add_many(0, vec![berlin]).unwrap();
+        index.add_many(1, vec![moscow]).unwrap();
+
+        let near_berlin = GeoRadius {
+            center: GeoPoint {
+                lat: 52.511,
+                lon: 13.423637,
+            },
+            radius: 2000.0,
+        };
+        let condition = FieldCondition {
+            key: "location".parse().unwrap(),
+            r#match: None,
+            range: None,
+            geo_bounding_box: None,
+            geo_radius: Some(near_berlin),
+            values_count: None,
+        };
+
+        let matched: Vec<_> = index.filter(&condition).unwrap().collect();
+        assert_eq!(matched, vec![0]);
+    }
+
+    #[test]
+    fn test_bounding_box_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = GeoMapIndex::new(dir.path().join("field.geo_map"));
+
+        let berlin = GeoPoint {
+            lat: 52.52197645,
+            lon: 13.413637435864272,
+        };
+        let moscow = GeoPoint {
+            lat: 55.7536283,
+            lon: 37.62137960067377,
+        };
+        index.add_many(0, vec![berlin]).unwrap();
+        index.add_many(1, vec![moscow]).unwrap();
+
+        let box_around_berlin = GeoBoundingBox {
+            top_left: GeoPoint {
+                lat: 53.0,
+                lon: 13.0,
+            },
+            bottom_right: GeoPoint {
+                lat: 52.0,
+                lon: 14.0,
+            },
+        };
+        let condition = FieldCondition {
+            key: "location".parse().unwrap(),
+            r#match: None,
+            range: None,
+            geo_bounding_box: Some(box_around_berlin),
+            geo_radius: None,
+            values_count: None,
+        };
+
+        let matched: Vec<_> = index.filter(&condition).unwrap().collect();
+        assert_eq!(matched, vec![0]);
+    }
+}
@@ -0,0 +1,334 @@
+//! R-tree backed geo index, so `GeoRadius`/`GeoBoundingBox` filters can prune
+//! most points via a spatial lookup instead of the linear
+//! `ValueChecker::check_match` scan every stored point pays today.
+//!
+//! One tree is kept per geo-indexed field, with one entry per `{lon, lat}`
+//! pair (a multi-geo payload contributes one entry per array element, the
+//! same "flatten, then index every element" convention
+//! [`super::map_index::MapIndex`] and the other field indexes already
+//! follow). The tree only supports rebuild-from-scratch, so new points land
+//! in a small mutable `overlay` that both [`GeoRTreeIndex::add_many`] and
+//! every query consult alongside the tree, and [`GeoRTreeIndex::flusher`] is
+//! what folds the overlay into a fresh `RTree` and persists it to
+//! `<field>.geo_rtree` inside the segment directory via `bincode`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use rstar::{RTree, RTreeObject, AABB};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::Flusher;
+use crate::entry::entry_point::{OperationError, OperationResult};
+use crate::index::field_index::{
+    CardinalityEstimation, PayloadBlockCondition, PayloadFieldIndex, ValueIndexer,
+};
+use crate::telemetry::PayloadIndexTelemetry;
+use crate::types::{FieldCondition, GeoPoint, PayloadKeyType, PointOffsetType};
+
+/// Mean earth radius in meters, matching the haversine constant
+/// `GeoRadius::check_point` already uses.
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct GeoEntry {
+    point_id: PointOffsetType,
+    lon: f64,
+    lat: f64,
+}
+
+impl RTreeObject for GeoEntry {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_point([self.lon, self.lat])
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    tree: RTree<GeoEntry>,
+    /// Entries added since the last rebuild; consulted alongside `tree` by
+    /// every query so a point is searchable immediately after `add_point`.
+    overlay: Vec<GeoEntry>,
+}
+
+impl Inner {
+    fn rebuild(&mut self) {
+        if self.overlay.is_empty() {
+            return;
+        }
+        let mut entries: Vec<GeoEntry> = self.tree.iter().copied().collect();
+        entries.append(&mut self.overlay);
+        self.tree = RTree::bulk_load(entries);
+    }
+
+    fn drop_point(&mut self, id: PointOffsetType) {
+        let stale: Vec<GeoEntry> = self
+            .tree
+            .iter()
+            .filter(|entry| entry.point_id == id)
+            .copied()
+            .collect();
+        for entry in stale {
+            self.tree.remove(&entry);
+        }
+        self.overlay.retain(|entry| entry.point_id != id);
+    }
+
+    fn candidates_in_envelope(&self, envelope: AABB<[f64; 2]>) -> Vec<GeoEntry> {
+        let mut points: Vec<GeoEntry> = self
+            .tree
+            .locate_in_envelope_intersecting(&envelope)
+            .copied()
+            .collect();
+        points.extend(
+            self.overlay
+                .iter()
+                .filter(|entry| envelope.contains_point(&[entry.lon, entry.lat]))
+                .copied(),
+        );
+        points
+    }
+
+    fn all_entries(&self) -> impl Iterator<Item = &GeoEntry> {
+        self.tree.iter().chain(self.overlay.iter())
+    }
+
+    fn count_indexed_points(&self) -> usize {
+        let mut points: std::collections::HashSet<PointOffsetType> = Default::default();
+        points.extend(self.all_entries().map(|entry| entry.point_id));
+        points.len()
+    }
+
+    fn values_count(&self, point_id: PointOffsetType) -> usize {
+        self.all_entries()
+            .filter(|entry| entry.point_id == point_id)
+            .count()
+    }
+}
+
+/// Geo index backed by an R-tree (`rstar`), used instead of
+/// [`super::geo_index::GeoMapIndex`]'s geohash buckets when the field is
+/// queried mostly via `GeoRadius`/`GeoBoundingBox` rather than grouped by
+/// hash prefix.
+pub struct GeoRTreeIndex {
+    inner: Arc<RwLock<Inner>>,
+    path: PathBuf,
+}
+
+impl GeoRTreeIndex {
+    pub fn new(path: PathBuf) -> Self {
+        GeoRTreeIndex {
+            inner: Arc::new(RwLock::new(Inner::default())),
+            path,
+        }
+    }
+
+    pub fn recreate(&self) -> OperationResult<()> {
+        *self.inner.write() = Inner::default();
+        let _ = fs::remove_file(&self.path);
+        Ok(())
+    }
+
+    pub fn get_telemetry_data(&self) -> PayloadIndexTelemetry {
+        let inner = self.inner.read();
+        PayloadIndexTelemetry {
+            field_name: None,
+            points_count: inner.count_indexed_points(),
+            points_values_count: inner.all_entries().count(),
+            histogram_bucket_size: None,
+        }
+    }
+
+    pub fn values_count(&self, point_id: PointOffsetType) -> usize {
+        self.inner.read().values_count(point_id)
+    }
+
+    pub fn values_is_empty(&self, point_id: PointOffsetType) -> bool {
+        self.values_count(point_id) == 0
+    }
+
+    /// Conservative `[lon, lat]` bounding box around `radius` meters of
+    /// `center`: `Δlat = radius / earth_radius` (in degrees), widened in
+    /// longitude by `1 / cos(lat)` to account for meridians converging
+    /// towards the poles.
+    fn radius_envelope(center: &GeoPoint, radius_meters: f64) -> AABB<[f64; 2]> {
+        let delta_lat_deg = (radius_meters / EARTH_RADIUS_METERS).to_degrees();
+        let lat_rad = center.lat.to_radians();
+        let delta_lon_deg = delta_lat_deg / lat_rad.cos().max(f64::EPSILON);
+        AABB::from_corners(
+            [center.lon - delta_lon_deg, center.lat - delta_lat_deg],
+            [center.lon + delta_lon_deg, center.lat + delta_lat_deg],
+        )
+    }
+
+    /// Points matching the condition's `geo_radius`/`geo_bounding_box`, if
+    /// either is set. A bounding box intersection from the tree is exact, so
+    /// those candidates are returned as-is; a radius query over-fetches a
+    /// bounding box around the circle and then refines with the same
+    /// haversine check `GeoRadius::check_point` uses elsewhere.
+    fn matched_points(&self, condition: &FieldCondition) -> Option<Vec<PointOffsetType>> {
+        let inner = self.inner.read();
+        if let Some(geo_bounding_box) = &condition.geo_bounding_box {
+            let envelope = AABB::from_corners(
+                [geo_bounding_box.top_left.lon, geo_bounding_box.bottom_right.lat],
+                [geo_bounding_box.bottom_right.lon, geo_bounding_box.top_left.lat],
+            );
+            return Some(
+                inner
+                    .candidates_in_envelope(envelope)
+                    .into_iter()
+                    .map(|entry| entry.point_id)
+                    .collect(),
+            );
+        }
+        if let Some(geo_radius) = &condition.geo_radius {
+            let envelope = Self::radius_envelope(&geo_radius.center, geo_radius.radius);
+            return Some(
+                inner
+                    .candidates_in_envelope(envelope)
+                    .into_iter()
+                    .filter(|entry| geo_radius.check_point(entry.lon, entry.lat))
+                    .map(|entry| entry.point_id)
+                    .collect(),
+            );
+        }
+        None
+    }
+}
+
+impl ValueIndexer<GeoPoint> for GeoRTreeIndex {
+    fn add_many(&mut self, id: PointOffsetType, values: Vec<GeoPoint>) -> OperationResult<()> {
+        let mut inner = self.inner.write();
+        for value in values {
+            inner.overlay.push(GeoEntry {
+                point_id: id,
+                lon: value.lon,
+                lat: value.lat,
+            });
+        }
+        Ok(())
+    }
+
+    fn get_value(&self, value: &Value) -> Option<GeoPoint> {
+        let obj = value.as_object()?;
+        let lon = obj.get("lon")?.as_f64()?;
+        let lat = obj.get("lat")?.as_f64()?;
+        Some(GeoPoint { lon, lat })
+    }
+
+    fn remove_point(&mut self, id: PointOffsetType) -> OperationResult<()> {
+        self.inner.write().drop_point(id);
+        Ok(())
+    }
+}
+
+impl PayloadFieldIndex for GeoRTreeIndex {
+    fn count_indexed_points(&self) -> usize {
+        self.inner.read().count_indexed_points()
+    }
+
+    fn load(&mut self) -> OperationResult<bool> {
+        if !self.path.exists() {
+            return Ok(false);
+        }
+        let bytes = fs::read(&self.path)
+            .map_err(|err| OperationError::service_error(format!("geo rtree index read error: {err}")))?;
+        let entries: Vec<GeoEntry> = bincode::deserialize(&bytes).map_err(|err| {
+            OperationError::service_error(format!("geo rtree index deserialize error: {err}"))
+        })?;
+        *self.inner.write() = Inner {
+            tree: RTree::bulk_load(entries),
+            overlay: Vec::new(),
+        };
+        Ok(true)
+    }
+
+    fn clear(self) -> OperationResult<()> {
+        let _ = fs::remove_file(&self.path);
+        Ok(())
+    }
+
+    fn flusher(&self) -> Flusher {
+        let inner = self.inner.clone();
+        let path = self.path.clone();
+        Box::new(move || {
+            let mut inner = inner.write();
+            inner.rebuild();
+            let entries: Vec<GeoEntry> = inner.tree.iter().copied().collect();
+            let bytes = bincode::serialize(&entries).map_err(|err| {
+                OperationError::service_error(format!("geo rtree index serialize error: {err}"))
+            })?;
+            fs::write(&path, bytes).map_err(|err| {
+                OperationError::service_error(format!("geo rtree index write error: {err}"))
+            })
+        })
+    }
+
+    fn filter<'a>(
+        &'a self,
+        condition: &'a FieldCondition,
+    ) -> Option<Box<dyn Iterator<Item = PointOffsetType> + 'a>> {
+        Some(Box::new(self.matched_points(condition)?.into_iter()))
+    }
+
+    fn estimate_cardinality(&self, condition: &FieldCondition) -> Option<CardinalityEstimation> {
+        let matched = self.matched_points(condition)?.len();
+        Some(CardinalityEstimation::exact(matched))
+    }
+
+    fn payload_blocks(
+        &self,
+        _threshold: usize,
+        _key: PayloadKeyType,
+    ) -> Box<dyn Iterator<Item = PayloadBlockCondition> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::GeoRadius;
+
+    #[test]
+    fn test_radius_query_refines_bounding_box_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = GeoRTreeIndex::new(dir.path().join("field.geo_rtree"));
+
+        let berlin = GeoPoint {
+            lat: 52.52197645,
+            lon: 13.413637435864272,
+        };
+        let moscow = GeoPoint {
+            lat: 55.7536283,
+            lon: 37.62137960067377,
+        };
+        index.add_many(0, vec![berlin]).unwrap();
+        index.add_many(1, vec![moscow]).unwrap();
+        index.flusher()().unwrap();
+
+        let near_berlin = GeoRadius {
+            center: GeoPoint {
+                lat: 52.511,
+                lon: 13.423637,
+            },
+            radius: 2000.0,
+        };
+        let condition = FieldCondition {
+            key: "location".parse().unwrap(),
+            r#match: None,
+            range: None,
+            geo_bounding_box: None,
+            geo_radius: Some(near_berlin),
+            values_count: None,
+        };
+
+        let matched: Vec<_> = index.filter(&condition).unwrap().collect();
+        assert_eq!(matched, vec![0]);
+    }
+}
@@ -0,0 +1,268 @@
+//! Area-based selectivity model for geo conditions with no backing index.
+//!
+//! [`StructPayloadIndex::condition_cardinality`](crate::index::struct_payload_index::StructPayloadIndex)
+//! falls back to [`CardinalityEstimation::unknown`] for any `Condition::Field`
+//! it has no index to ask, which is needlessly pessimistic for `geo_radius`/
+//! `geo_bounding_box`/`geo_polygon`: the geometry itself already says how
+//! selective the query probably is, even without an index to consult.
+//! [`estimate_geo_selectivity`] computes each geometry's area on the sphere
+//! and scales it against the indexed field's coverage area (or, lacking any
+//! recorded coverage, the whole sphere) to get a selectivity fraction.
+//!
+//! This is a heuristic, not a measurement - actual point density within the
+//! geometry can differ arbitrarily from the field's average - so `min`/`max`
+//! are widened away from `exp` by [`CONFIDENCE_FACTOR`] rather than collapsed
+//! onto it the way [`CardinalityEstimation::exact`] would.
+
+use std::f64::consts::PI;
+
+use crate::index::field_index::CardinalityEstimation;
+use crate::types::{FieldCondition, GeoBoundingBox, GeoPolygon, GeoRadius};
+
+/// Mean earth radius in meters, matching the haversine constant
+/// [`super::geo_rtree_index`] already uses.
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+const SPHERE_SURFACE_AREA_M2: f64 = 4.0 * PI * EARTH_RADIUS_METERS * EARTH_RADIUS_METERS;
+
+/// `min`/`max` are widened this fraction of `total` away from `exp`, since
+/// this estimate comes from geometry alone and real point density within the
+/// query region can differ substantially from the field's average.
+const CONFIDENCE_FACTOR: f64 = 0.2;
+
+/// A geo-indexed field's recorded spatial coverage, used as the denominator
+/// of the selectivity fraction in place of the whole sphere. Nothing in this
+/// tree currently persists per-field bounds, so every caller passes `None`
+/// today; the parameter exists so a future index that does track bounds (a
+/// natural addition to `GeoRTreeIndex`) can plug in without reshaping this
+/// function's signature.
+#[derive(Debug, Clone, Copy)]
+pub struct GeoFieldExtent {
+    pub area_m2: f64,
+}
+
+/// Estimates the cardinality of `condition`'s geo clause from its geometric
+/// area alone. Returns `None` if `condition` has no `geo_radius`/
+/// `geo_bounding_box`/`geo_polygon` set, so callers can chain this into their
+/// existing indexed-estimate-or-fallback logic with `.or_else(...)`.
+pub fn estimate_geo_selectivity(
+    condition: &FieldCondition,
+    total: usize,
+    indexed_extent: Option<GeoFieldExtent>,
+) -> Option<CardinalityEstimation> {
+    let area_m2 = geo_condition_area_m2(condition)?;
+    let coverage_m2 = indexed_extent
+        .map(|extent| extent.area_m2)
+        .unwrap_or(SPHERE_SURFACE_AREA_M2);
+
+    let selectivity = (area_m2 / coverage_m2).clamp(0.0, 1.0);
+    let exp = (selectivity * total as f64).round() as usize;
+    let confidence_width = ((CONFIDENCE_FACTOR * total as f64).round() as usize).max(1);
+
+    Some(CardinalityEstimation {
+        primary_clauses: vec![],
+        min: exp.saturating_sub(confidence_width),
+        exp,
+        max: (exp + confidence_width).min(total),
+    })
+}
+
+fn geo_condition_area_m2(condition: &FieldCondition) -> Option<f64> {
+    if let Some(geo_radius) = &condition.geo_radius {
+        return Some(radius_area_m2(geo_radius));
+    }
+    if let Some(geo_bounding_box) = &condition.geo_bounding_box {
+        return Some(bounding_box_area_m2(geo_bounding_box));
+    }
+    if let Some(geo_polygon) = &condition.geo_polygon {
+        return Some(polygon_area_m2(geo_polygon));
+    }
+    None
+}
+
+/// Spherical cap area of a `radius`-meter circle: `2*pi*R^2*(1 - cos(r/R))`.
+/// `radius` is clamped to `pi*R` (half the sphere's great-circle
+/// circumference) first, since a larger value doesn't correspond to any
+/// larger cap - past that point the formula would start "shrinking" again as
+/// the cap wraps past the antipode.
+fn radius_area_m2(geo_radius: &GeoRadius) -> f64 {
+    let radius = geo_radius.radius.clamp(0.0, PI * EARTH_RADIUS_METERS);
+    2.0 * PI
+        * EARTH_RADIUS_METERS
+        * EARTH_RADIUS_METERS
+        * (1.0 - (radius / EARTH_RADIUS_METERS).cos())
+}
+
+/// Area of a lat/lon bounding box: `(sin(phi2) - sin(phi1)) * delta_lambda * R^2`.
+/// `top_left`/`bottom_right` follow [`GeoRTreeIndex`](super::geo_rtree_index::GeoRTreeIndex)'s
+/// convention (`top_left` is the box's max-lat/min-lon corner). A box whose
+/// `bottom_right.lon` is west of `top_left.lon` is read as crossing the
+/// antimeridian, and its longitude span is computed as the wrapped distance
+/// through +/-180 instead of going negative.
+fn bounding_box_area_m2(geo_bounding_box: &GeoBoundingBox) -> f64 {
+    let lat_lo = geo_bounding_box
+        .bottom_right
+        .lat
+        .min(geo_bounding_box.top_left.lat);
+    let lat_hi = geo_bounding_box
+        .bottom_right
+        .lat
+        .max(geo_bounding_box.top_left.lat);
+
+    let lon_lo = geo_bounding_box.top_left.lon;
+    let lon_hi = geo_bounding_box.bottom_right.lon;
+    let delta_lon_deg = if lon_hi >= lon_lo {
+        lon_hi - lon_lo
+    } else {
+        // Antimeridian crossing: the span runs lon_lo -> 180, then -180 -> lon_hi.
+        (180.0 - lon_lo) + (lon_hi - (-180.0))
+    };
+
+    let delta_sin_phi = lat_hi.to_radians().sin() - lat_lo.to_radians().sin();
+    let area = delta_sin_phi.abs()
+        * delta_lon_deg.to_radians()
+        * EARTH_RADIUS_METERS
+        * EARTH_RADIUS_METERS;
+    area.min(SPHERE_SURFACE_AREA_M2)
+}
+
+/// Shoelace area of `polygon`, after projecting each vertex to a local planar
+/// `(x, y)` in meters via an equirectangular projection centered on the
+/// polygon's mean latitude (`x = lon * R * cos(mean_lat)`, `y = lat * R`) -
+/// accurate enough for the polygon sizes a payload filter realistically
+/// describes, without pulling in a full geodesic-area library.
+fn polygon_area_m2(geo_polygon: &GeoPolygon) -> f64 {
+    let points = &geo_polygon.points;
+    if points.len() < 3 {
+        return 0.0;
+    }
+
+    let mean_lat_rad =
+        (points.iter().map(|point| point.lat).sum::<f64>() / points.len() as f64).to_radians();
+    let lon_scale = EARTH_RADIUS_METERS * mean_lat_rad.cos();
+
+    let projected: Vec<(f64, f64)> = points
+        .iter()
+        .map(|point| {
+            (
+                point.lon.to_radians() * lon_scale,
+                point.lat.to_radians() * EARTH_RADIUS_METERS,
+            )
+        })
+        .collect();
+
+    let mut shoelace_sum = 0.0;
+    for i in 0..projected.len() {
+        let (x1, y1) = projected[i];
+        let (x2, y2) = projected[(i + 1) % projected.len()];
+        shoelace_sum += x1 * y2 - x2 * y1;
+    }
+    (shoelace_sum / 2.0).abs().min(SPHERE_SURFACE_AREA_M2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::GeoPoint;
+
+    fn empty_field_condition(key: &str) -> FieldCondition {
+        FieldCondition {
+            key: key.to_owned(),
+            r#match: None,
+            range: None,
+            geo_bounding_box: None,
+            geo_radius: None,
+            values_count: None,
+            geo_polygon: None,
+        }
+    }
+
+    #[test]
+    fn tight_radius_is_far_more_selective_than_half_globe_box() {
+        let tight_radius = FieldCondition {
+            geo_radius: Some(GeoRadius {
+                center: GeoPoint {
+                    lat: 52.5,
+                    lon: 13.4,
+                },
+                radius: 2_000.0,
+            }),
+            ..empty_field_condition("location")
+        };
+        let half_globe_box = FieldCondition {
+            geo_bounding_box: Some(GeoBoundingBox {
+                top_left: GeoPoint {
+                    lat: 90.0,
+                    lon: -180.0,
+                },
+                bottom_right: GeoPoint {
+                    lat: -90.0,
+                    lon: 0.0,
+                },
+            }),
+            ..empty_field_condition("location")
+        };
+
+        let tight = estimate_geo_selectivity(&tight_radius, 1_000_000, None).unwrap();
+        let wide = estimate_geo_selectivity(&half_globe_box, 1_000_000, None).unwrap();
+
+        assert!(tight.exp < wide.exp);
+        // A half-globe box should land close to half of `total`.
+        assert!((wide.exp as i64 - 500_000).abs() < 1_000);
+    }
+
+    #[test]
+    fn non_geo_condition_returns_none() {
+        let condition = empty_field_condition("color");
+        assert!(estimate_geo_selectivity(&condition, 1_000, None).is_none());
+    }
+
+    #[test]
+    fn antimeridian_crossing_box_uses_wrapped_longitude_span() {
+        let crossing = FieldCondition {
+            geo_bounding_box: Some(GeoBoundingBox {
+                top_left: GeoPoint {
+                    lat: 10.0,
+                    lon: 170.0,
+                },
+                bottom_right: GeoPoint {
+                    lat: -10.0,
+                    lon: -170.0,
+                },
+            }),
+            ..empty_field_condition("location")
+        };
+        let non_crossing = FieldCondition {
+            geo_bounding_box: Some(GeoBoundingBox {
+                top_left: GeoPoint {
+                    lat: 10.0,
+                    lon: -170.0,
+                },
+                bottom_right: GeoPoint {
+                    lat: -10.0,
+                    lon: 170.0,
+                },
+            }),
+            ..empty_field_condition("location")
+        };
+
+        let crossing_area = geo_condition_area_m2(&crossing).unwrap();
+        let non_crossing_area = geo_condition_area_m2(&non_crossing).unwrap();
+        // Both boxes have edges at +/-170 degrees; read west to east, the
+        // crossing box spans 20 degrees and the other spans 340 degrees.
+        assert!(crossing_area < non_crossing_area);
+    }
+
+    #[test]
+    fn oversized_radius_clamps_to_whole_sphere() {
+        let huge_radius = FieldCondition {
+            geo_radius: Some(GeoRadius {
+                center: GeoPoint { lat: 0.0, lon: 0.0 },
+                radius: EARTH_RADIUS_METERS * 100.0,
+            }),
+            ..empty_field_condition("location")
+        };
+        let estimation = estimate_geo_selectivity(&huge_radius, 1_000, None).unwrap();
+        assert_eq!(estimation.exp, 1_000);
+    }
+}
@@ -2,10 +2,14 @@ use std::collections::HashSet;
 
 use crate::types::{FieldCondition, IsEmptyCondition, IsNullCondition, PointOffsetType};
 
+mod bucket_store;
 mod field_index_base;
 pub mod full_text_index;
+pub mod fst_keyword_index;
 pub mod geo_hash;
 pub mod geo_index;
+pub mod geo_rtree_index;
+pub mod geo_selectivity;
 mod histogram;
 pub mod index_selector;
 pub mod map_index;
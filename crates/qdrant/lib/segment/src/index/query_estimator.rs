@@ -5,10 +5,24 @@
 
 use std::cmp::{max, min};
 
-use itertools::Itertools;
+use roaring::RoaringBitmap;
 
+use crate::entry::entry_point::{OperationError, OperationResult};
 use crate::index::field_index::{CardinalityEstimation, PrimaryCondition};
-use crate::types::{Condition, Filter};
+use crate::types::{
+    AnyVariants, Condition, FieldCondition, Filter, Match, MatchAny, MatchExcept, MatchValue,
+    Range, ValueVariants,
+};
+
+/// Maximum nesting depth [`estimate_filter`] will recurse through before
+/// giving up instead of overflowing the stack. A `Condition::Filter` tree
+/// this deep can only arrive from an untrusted payload - no query a human
+/// would write nests this far - so this is deliberately far above any
+/// legitimate filter while still being cheap to recurse through. Matches the
+/// depth limit comparable engines (e.g. Elasticsearch's `indices.query.bool.max_nested_depth`)
+/// use for the same reason, and is reusable by any future validation pass
+/// that wants to reject an over-nested filter before estimation ever runs.
+pub const MAX_FILTER_DEPTH: usize = 2000;
 
 /// Re-estimate cardinality based on number of available vectors
 /// Assuming that deleted vectors are not correlated with the filter
@@ -160,31 +174,143 @@ pub fn combine_must_estimations(
     }
 }
 
+/// Combines the per-condition posting-list bitmaps of a `should` clause with
+/// a roaring OR, the bitmap equivalent of [`combine_should_estimations`]'s
+/// probability combination but exact instead of estimated.
+///
+/// # Example
+///
+/// ```
+/// use roaring::RoaringBitmap;
+/// let a = RoaringBitmap::from_iter([1, 2, 3]);
+/// let b = RoaringBitmap::from_iter([3, 4]);
+/// let combined = segment::index::query_estimator::combine_should_bitmaps(&[a, b]);
+/// assert_eq!(combined, RoaringBitmap::from_iter([1, 2, 3, 4]));
+/// ```
+pub fn combine_should_bitmaps(bitmaps: &[RoaringBitmap]) -> RoaringBitmap {
+    bitmaps
+        .iter()
+        .fold(RoaringBitmap::new(), |acc, bitmap| acc | bitmap)
+}
+
+/// Combines the per-condition posting-list bitmaps of a `must` clause with a
+/// roaring AND, the exact counterpart to [`combine_must_estimations`].
+///
+/// # Example
+///
+/// ```
+/// use roaring::RoaringBitmap;
+/// let a = RoaringBitmap::from_iter([1, 2, 3]);
+/// let b = RoaringBitmap::from_iter([2, 3, 4]);
+/// let combined = segment::index::query_estimator::combine_must_bitmaps(&[a, b]);
+/// assert_eq!(combined, RoaringBitmap::from_iter([2, 3]));
+/// ```
+pub fn combine_must_bitmaps(bitmaps: &[RoaringBitmap]) -> RoaringBitmap {
+    let mut bitmaps = bitmaps.iter();
+    match bitmaps.next() {
+        Some(first) => bitmaps.fold(first.clone(), |acc, bitmap| acc & bitmap),
+        None => RoaringBitmap::new(),
+    }
+}
+
+/// Subtracts a `must_not` clause's combined bitmap (itself a
+/// [`combine_should_bitmaps`] OR of each excluded condition) from `matched`
+/// via a roaring ANDNOT.
+///
+/// # Example
+///
+/// ```
+/// use roaring::RoaringBitmap;
+/// let matched = RoaringBitmap::from_iter([1, 2, 3]);
+/// let excluded = RoaringBitmap::from_iter([2]);
+/// let combined = segment::index::query_estimator::combine_must_not_bitmap(&matched, &excluded);
+/// assert_eq!(combined, RoaringBitmap::from_iter([1, 3]));
+/// ```
+pub fn combine_must_not_bitmap(matched: &RoaringBitmap, excluded: &RoaringBitmap) -> RoaringBitmap {
+    matched - excluded
+}
+
 fn estimate_condition<F>(
     estimator: &F,
     condition: &Condition,
     total: usize,
-) -> CardinalityEstimation
+    depth: usize,
+) -> OperationResult<CardinalityEstimation>
 where
     F: Fn(&Condition) -> CardinalityEstimation,
 {
     match condition {
-        Condition::Filter(filter) => estimate_filter(estimator, filter, total),
-        _ => estimator(condition),
+        Condition::Filter(filter) => estimate_filter(estimator, filter, total, depth),
+        _ => Ok(estimator(condition)),
     }
 }
 
-pub fn estimate_filter<F>(estimator: &F, filter: &Filter, total: usize) -> CardinalityEstimation
+/// Validates that `filter`'s nesting (through `Condition::Filter` and
+/// `Condition::Nested`) does not exceed [`MAX_FILTER_DEPTH`].
+///
+/// Meant to be called once, up front, at every entry point that accepts a
+/// `Filter` from a request - before `estimate_filter`, before the
+/// index-backed bitmap fold (`StructPayloadIndex::filter_bitmap`/
+/// `condition_bitmap`), and before any full-scan fallback that walks the
+/// same tree - so a maliciously deep-nested filter is rejected once instead
+/// of requiring every recursive consumer to track its own depth.
+pub fn validate_filter_depth(filter: &Filter) -> OperationResult<()> {
+    fn validate(filter: &Filter, depth: usize) -> OperationResult<()> {
+        if depth > MAX_FILTER_DEPTH {
+            return Err(OperationError::service_error(format!(
+                "filter too deeply nested: exceeds max depth of {MAX_FILTER_DEPTH}"
+            )));
+        }
+        for conditions in [
+            filter.must.as_deref(),
+            filter.should.as_deref(),
+            filter.must_not.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for condition in conditions {
+                match condition {
+                    Condition::Filter(nested) => validate(nested, depth + 1)?,
+                    Condition::Nested(nested) => validate(nested.filter(), depth + 1)?,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    validate(filter, 0)
+}
+
+/// Estimates how many points `filter` selects out of `total`, recursing
+/// into nested `Condition::Filter`s through `estimate_must`/`estimate_should`/
+/// `estimate_must_not`. `depth` is the nesting level of `filter` itself (0 for
+/// a top-level filter); once it would exceed [`MAX_FILTER_DEPTH`], this
+/// returns an error instead of recursing further, so a maliciously
+/// deep-nested filter can't overflow the stack.
+pub fn estimate_filter<F>(
+    estimator: &F,
+    filter: &Filter,
+    total: usize,
+    depth: usize,
+) -> OperationResult<CardinalityEstimation>
 where
     F: Fn(&Condition) -> CardinalityEstimation,
 {
+    if depth > MAX_FILTER_DEPTH {
+        return Err(OperationError::service_error(format!(
+            "filter too deeply nested: exceeds max depth of {MAX_FILTER_DEPTH}"
+        )));
+    }
+
     let mut filter_estimations: Vec<CardinalityEstimation> = vec![];
 
     match &filter.must {
         None => {}
         Some(conditions) => {
             if !conditions.is_empty() {
-                filter_estimations.push(estimate_must(estimator, conditions, total));
+                filter_estimations.push(estimate_must(estimator, conditions, total, depth)?);
             }
         }
     }
@@ -192,7 +318,7 @@ where
         None => {}
         Some(conditions) => {
             if !conditions.is_empty() {
-                filter_estimations.push(estimate_should(estimator, conditions, total));
+                filter_estimations.push(estimate_should(estimator, conditions, total, depth)?);
             }
         }
     }
@@ -200,35 +326,45 @@ where
         None => {}
         Some(conditions) => {
             if !conditions.is_empty() {
-                filter_estimations.push(estimate_must_not(estimator, conditions, total))
+                filter_estimations.push(estimate_must_not(estimator, conditions, total, depth)?)
             }
         }
     }
 
-    combine_must_estimations(&filter_estimations, total)
+    Ok(combine_must_estimations(&filter_estimations, total))
 }
 
 fn estimate_should<F>(
     estimator: &F,
     conditions: &[Condition],
     total: usize,
-) -> CardinalityEstimation
+    depth: usize,
+) -> OperationResult<CardinalityEstimation>
 where
     F: Fn(&Condition) -> CardinalityEstimation,
 {
-    let estimate = |x| estimate_condition(estimator, x, total);
-    let should_estimations = conditions.iter().map(estimate).collect_vec();
-    combine_should_estimations(&should_estimations, total)
+    let should_estimations: Vec<CardinalityEstimation> = conditions
+        .iter()
+        .map(|x| estimate_condition(estimator, x, total, depth + 1))
+        .collect::<OperationResult<_>>()?;
+    Ok(combine_should_estimations(&should_estimations, total))
 }
 
-fn estimate_must<F>(estimator: &F, conditions: &[Condition], total: usize) -> CardinalityEstimation
+fn estimate_must<F>(
+    estimator: &F,
+    conditions: &[Condition],
+    total: usize,
+    depth: usize,
+) -> OperationResult<CardinalityEstimation>
 where
     F: Fn(&Condition) -> CardinalityEstimation,
 {
-    let estimate = |x| estimate_condition(estimator, x, total);
-    let must_estimations = conditions.iter().map(estimate).collect_vec();
+    let must_estimations: Vec<CardinalityEstimation> = conditions
+        .iter()
+        .map(|x| estimate_condition(estimator, x, total, depth + 1))
+        .collect::<OperationResult<_>>()?;
 
-    combine_must_estimations(&must_estimations, total)
+    Ok(combine_must_estimations(&must_estimations, total))
 }
 
 pub fn invert_estimation(
@@ -247,13 +383,173 @@ fn estimate_must_not<F>(
     estimator: &F,
     conditions: &[Condition],
     total: usize,
-) -> CardinalityEstimation
+    depth: usize,
+) -> OperationResult<CardinalityEstimation>
+where
+    F: Fn(&Condition) -> CardinalityEstimation,
+{
+    let must_not_estimations: Vec<CardinalityEstimation> = conditions
+        .iter()
+        .map(|x| estimate_negated_condition(estimator, x, total, depth))
+        .collect::<OperationResult<_>>()?;
+    Ok(combine_must_estimations(&must_not_estimations, total))
+}
+
+/// Estimates the cardinality of `NOT condition`. Where `condition` has an
+/// algebraic negation (see [`negate_field_condition`]), that negation is
+/// estimated directly - each resulting condition is a real, independently
+/// indexable condition with its own `PrimaryCondition`, so the index can
+/// still be used for the negated query. Everything else (`HasId`,
+/// `IsEmpty`, `IsNull`, nested `Filter`s, and any `FieldCondition` shape
+/// [`negate_field_condition`] doesn't know how to invert) falls back to
+/// [`invert_estimation`], which only has the positive estimation's bounds to
+/// work with and so always clears `primary_clauses`.
+fn estimate_negated_condition<F>(
+    estimator: &F,
+    condition: &Condition,
+    total: usize,
+    depth: usize,
+) -> OperationResult<CardinalityEstimation>
 where
     F: Fn(&Condition) -> CardinalityEstimation,
 {
-    let estimate = |x| invert_estimation(&estimate_condition(estimator, x, total), total);
-    let must_not_estimations = conditions.iter().map(estimate).collect_vec();
-    combine_must_estimations(&must_not_estimations, total)
+    if let Condition::Field(field) = condition {
+        if let Some(negated) = negate_field_condition(field) {
+            let negated_estimations: Vec<CardinalityEstimation> = negated
+                .iter()
+                .map(|x| estimate_condition(estimator, x, total, depth + 1))
+                .collect::<OperationResult<_>>()?;
+            return Ok(match negated_estimations.len() {
+                1 => negated_estimations.into_iter().next().unwrap(),
+                _ => combine_should_estimations(&negated_estimations, total),
+            });
+        }
+    }
+    let estimation = estimate_condition(estimator, condition, total, depth + 1)?;
+    Ok(invert_estimation(&estimation, total))
+}
+
+/// Pushes negation down into `field`'s own operator, where possible, so the
+/// negated query stays index-friendly instead of degrading to a full-scan
+/// bound via [`invert_estimation`]. A `between(a, b)` range (both a lower and
+/// an upper bound present) negates to the OR of `< a` and `> b`, one
+/// condition per originally-present bound; a single-sided range or a
+/// `match` negates to one condition. Returns `None` (meaning: fall back to
+/// [`invert_estimation`]) for anything without a known algebraic negation -
+/// geo conditions, `values_count`, and `match` shapes that can't be
+/// expressed as a `MatchExcept`/`MatchAny` (text, fuzzy, or a boolean
+/// value, since [`AnyVariants`] has no boolean variant to except against).
+fn negate_field_condition(field: &FieldCondition) -> Option<Vec<Condition>> {
+    let FieldCondition {
+        key,
+        r#match,
+        range,
+        geo_bounding_box: None,
+        geo_radius: None,
+        values_count: None,
+        geo_polygon: None,
+    } = field
+    else {
+        return None;
+    };
+
+    match (r#match, range) {
+        (Some(m), None) => {
+            let negated = negate_match(m)?;
+            Some(vec![Condition::Field(FieldCondition {
+                key: key.clone(),
+                r#match: Some(negated),
+                range: None,
+                geo_bounding_box: None,
+                geo_radius: None,
+                values_count: None,
+                geo_polygon: None,
+            })])
+        }
+        (None, Some(r)) => Some(
+            negate_range(r)
+                .into_iter()
+                .map(|negated_range| {
+                    Condition::Field(FieldCondition {
+                        key: key.clone(),
+                        r#match: None,
+                        range: Some(negated_range),
+                        geo_bounding_box: None,
+                        geo_radius: None,
+                        values_count: None,
+                        geo_polygon: None,
+                    })
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Negates each bound present in `range` independently: `NOT (a AND b)` is
+/// `NOT a OR NOT b`, and a `Range` is itself a conjunction of whichever
+/// bounds are set. `> n` negates to `<= n`, `>= n` to `< n`, and
+/// symmetrically for the upper bounds.
+fn negate_range(range: &Range) -> Vec<Range> {
+    let mut negated = Vec::with_capacity(2);
+    if let Some(gt) = range.gt {
+        negated.push(Range {
+            gt: None,
+            gte: None,
+            lt: None,
+            lte: Some(gt),
+        });
+    }
+    if let Some(gte) = range.gte {
+        negated.push(Range {
+            gt: None,
+            gte: None,
+            lt: Some(gte),
+            lte: None,
+        });
+    }
+    if let Some(lt) = range.lt {
+        negated.push(Range {
+            gt: None,
+            gte: Some(lt),
+            lt: None,
+            lte: None,
+        });
+    }
+    if let Some(lte) = range.lte {
+        negated.push(Range {
+            gt: Some(lte),
+            gte: None,
+            lt: None,
+            lte: None,
+        });
+    }
+    negated
+}
+
+/// Negates a `match` operator: `= v` becomes `!= v` (via `MatchExcept`),
+/// "any of" becomes "except", and "except" (already a negation) becomes
+/// "any of". Returns `None` for shapes with no such counterpart: `Text`,
+/// `Fuzzy`, and a `Value` match on a `Bool`, since [`AnyVariants`] can only
+/// hold keywords or integers.
+fn negate_match(m: &Match) -> Option<Match> {
+    match m {
+        Match::Value(MatchValue { value }) => {
+            let any = match value {
+                ValueVariants::Keyword(keyword) => AnyVariants::Keywords(vec![keyword.clone()]),
+                ValueVariants::Integer(integer) => AnyVariants::Integers(vec![*integer]),
+                ValueVariants::Bool(_) => return None,
+            };
+            Some(Match::Except(MatchExcept { except: any }))
+        }
+        Match::Any(MatchAny { any }) => Some(Match::Except(MatchExcept {
+            except: any.clone(),
+        })),
+        Match::Except(MatchExcept { except }) => Some(Match::Any(MatchAny {
+            any: except.clone(),
+        })),
+        Match::Text(_) | Match::Fuzzy(_) => None,
+    }
 }
 
 #[cfg(test)]
@@ -262,7 +558,7 @@ mod tests {
     use std::iter::FromIterator;
 
     use super::*;
-    use crate::types::{FieldCondition, HasIdCondition};
+    use crate::types::HasIdCondition;
 
     const TOTAL: usize = 1000;
 
@@ -333,7 +629,7 @@ mod tests {
     #[test]
     fn simple_query_estimation_test() {
         let query = Filter::new_must(test_condition("color".to_owned()));
-        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
         assert_eq!(estimation.exp, 200);
         assert!(!estimation.primary_clauses.is_empty());
     }
@@ -350,7 +646,7 @@ mod tests {
             must_not: None,
         };
 
-        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
         assert_eq!(estimation.primary_clauses.len(), 1);
         match &estimation.primary_clauses[0] {
             PrimaryCondition::Condition(field) => assert_eq!(&field.key, "size"),
@@ -372,7 +668,7 @@ mod tests {
             must_not: None,
         };
 
-        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
         assert_eq!(estimation.primary_clauses.len(), 2);
         assert!(estimation.max <= TOTAL);
         assert!(estimation.exp <= estimation.max);
@@ -391,7 +687,7 @@ mod tests {
             must_not: None,
         };
 
-        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
         assert_eq!(estimation.primary_clauses.len(), 0);
         eprintln!("estimation = {estimation:#?}");
         assert!(estimation.max <= TOTAL);
@@ -426,7 +722,7 @@ mod tests {
             })]),
         };
 
-        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
         assert_eq!(estimation.primary_clauses.len(), 2);
         assert!(estimation.max <= TOTAL);
         assert!(estimation.exp <= estimation.max);
@@ -460,7 +756,7 @@ mod tests {
             })]),
         };
 
-        let estimation = estimate_filter(&test_estimator, &query, TOTAL);
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
         assert_eq!(estimation.primary_clauses.len(), 2);
         estimation.primary_clauses.iter().for_each(|x| match x {
             PrimaryCondition::Condition(field) => {
@@ -485,4 +781,64 @@ mod tests {
         let res = combine_must_estimations(&estimations, 10_000);
         eprintln!("res = {res:#?}");
     }
+
+    #[test]
+    fn must_not_on_indexed_range_keeps_primary_clause() {
+        // A negated range has an algebraic negation, so the index should
+        // still be usable - unlike a negated `HasId`, which always falls
+        // back to `invert_estimation` and clears `primary_clauses`.
+        let query = Filter {
+            must: None,
+            should: None,
+            must_not: Some(vec![Condition::Field(FieldCondition {
+                key: "price".to_owned(),
+                r#match: None,
+                range: Some(Range {
+                    gt: Some(10.0),
+                    gte: None,
+                    lt: None,
+                    lte: None,
+                }),
+                geo_bounding_box: None,
+                geo_radius: None,
+                values_count: None,
+                geo_polygon: None,
+            })]),
+        };
+
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
+        assert_eq!(estimation.primary_clauses.len(), 1);
+    }
+
+    #[test]
+    fn must_not_between_range_splits_into_should() {
+        let range = Range {
+            gte: Some(1.0),
+            lte: Some(2.0),
+            gt: None,
+            lt: None,
+        };
+        let negated = negate_range(&range);
+        assert_eq!(negated.len(), 2);
+        assert!(negated
+            .iter()
+            .any(|r| r.lt == Some(1.0) && r.gt.is_none() && r.gte.is_none() && r.lte.is_none()));
+        assert!(negated
+            .iter()
+            .any(|r| r.gt == Some(2.0) && r.lt.is_none() && r.gte.is_none() && r.lte.is_none()));
+    }
+
+    #[test]
+    fn must_not_on_has_id_falls_back_to_invert_estimation() {
+        let query = Filter {
+            must: None,
+            should: None,
+            must_not: Some(vec![Condition::HasId(HasIdCondition {
+                has_id: HashSet::from_iter([1, 2, 3].into_iter().map(|x| x.into())),
+            })]),
+        };
+
+        let estimation = estimate_filter(&test_estimator, &query, TOTAL, 0).unwrap();
+        assert!(estimation.primary_clauses.is_empty());
+    }
 }
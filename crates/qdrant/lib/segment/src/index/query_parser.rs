@@ -0,0 +1,707 @@
+//! Parses a human-readable filter string into the crate's `Filter`/
+//! `Condition` AST, so callers don't have to hand-build nested structures
+//! to express e.g. `price > 100 AND color = "red" AND (size = 42 OR
+//! _geoRadius(40.7, -74.0, 2000))`.
+//!
+//! Grammar (`AND` binds tighter than `OR`, parentheses override either):
+//!
+//! ```text
+//! or_expr    := and_expr ("OR" and_expr)*
+//! and_expr   := unary ("AND" unary)*
+//! unary      := "NOT" unary | primary
+//! primary    := "(" or_expr ")" | range | comparison | geo_call
+//! comparison := IDENT OP value                    OP ∈ {=, !=, >, >=, <, <=}
+//! range      := IDENT "FROM" value "TO" value
+//! geo_call   := [IDENT "."] ("_geoRadius" | "_geoBoundingBox") "(" NUMBER ("," NUMBER)* ")"
+//! value      := NUMBER | STRING | "true" | "false"
+//! ```
+//!
+//! `AND` sequences lower to `filter.must`, `OR` to `filter.should`, and
+//! `NOT`/`!=` to `filter.must_not` - see [`parse_filter`]. Sits next to
+//! [`crate::index::query_estimator`], which is what consumes the `Filter`
+//! this produces, and round-trips through it unchanged: every condition
+//! this module builds carries the same `FieldCondition` shape the estimator
+//! and payload indexes already understand.
+//!
+//! A `geo_call` without a leading `IDENT.` targets the field named
+//! `"location"`, the repo's conventional name for a geo-indexed payload
+//! field (see the fixtures in `payload_storage::condition_checker` and
+//! `index::field_index::geo_rtree_index`) - this lets the common case,
+//! e.g. `_geoRadius(40.7, -74.0, 2000)`, skip naming the field at all, while
+//! `warehouse._geoRadius(...)` still works for any other geo field.
+
+use std::ops::Range as ByteRange;
+
+use crate::types::{
+    Condition, FieldCondition, Filter, GeoBoundingBox, GeoPoint, GeoRadius, Match, MatchValue,
+    Range, ValueVariants,
+};
+
+/// The default field a geo function targets when the grammar's optional
+/// `IDENT "."` prefix is omitted.
+const DEFAULT_GEO_FIELD: &str = "location";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: ByteRange<usize>,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "filter parse error at byte {}..{}: {}",
+            self.span.start, self.span.end, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses `input` into a [`Filter`], per the grammar documented on this
+/// module. The top-level boolean connective decides which `Filter` field
+/// the parsed conditions land in directly (`AND` -> `must`, `OR` ->
+/// `should`, `NOT` -> `must_not`); anything nested under a different
+/// connective becomes a `Condition::Filter` subtree instead.
+pub fn parse_filter(input: &str) -> Result<Filter, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_or()?;
+    parser.expect_eof()?;
+    Ok(expr_to_top_level_filter(expr))
+}
+
+/// The boolean AST an expression parses into, before being lowered into a
+/// `Filter`/`Condition` tree by [`expr_to_top_level_filter`]/[`expr_to_condition`].
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Leaf(Condition),
+}
+
+fn expr_to_top_level_filter(expr: Expr) -> Filter {
+    match expr {
+        Expr::And(items) => Filter {
+            must: Some(items.into_iter().map(expr_to_condition).collect()),
+            should: None,
+            must_not: None,
+        },
+        Expr::Or(items) => Filter {
+            must: None,
+            should: Some(items.into_iter().map(expr_to_condition).collect()),
+            must_not: None,
+        },
+        Expr::Not(inner) => Filter {
+            must: None,
+            should: None,
+            must_not: Some(vec![expr_to_condition(*inner)]),
+        },
+        Expr::Leaf(condition) => Filter::new_must(condition),
+    }
+}
+
+fn expr_to_condition(expr: Expr) -> Condition {
+    match expr {
+        Expr::And(items) => Condition::Filter(Filter {
+            must: Some(items.into_iter().map(expr_to_condition).collect()),
+            should: None,
+            must_not: None,
+        }),
+        Expr::Or(items) => Condition::Filter(Filter {
+            must: None,
+            should: Some(items.into_iter().map(expr_to_condition).collect()),
+            must_not: None,
+        }),
+        Expr::Not(inner) => Condition::Filter(Filter {
+            must: None,
+            should: None,
+            must_not: Some(vec![expr_to_condition(*inner)]),
+        }),
+        Expr::Leaf(condition) => condition,
+    }
+}
+
+// --- Tokenizer ---------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Eq,
+    Neq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    And,
+    Or,
+    Not,
+    From,
+    To,
+    True,
+    False,
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: ByteRange<usize>,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0usize;
+
+    while pos < bytes.len() {
+        let ch = bytes[pos] as char;
+        if ch.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        let kind = match ch {
+            '(' => {
+                pos += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                pos += 1;
+                TokenKind::RParen
+            }
+            ',' => {
+                pos += 1;
+                TokenKind::Comma
+            }
+            '.' => {
+                pos += 1;
+                TokenKind::Dot
+            }
+            '=' => {
+                pos += 1;
+                TokenKind::Eq
+            }
+            '!' => {
+                if bytes.get(pos + 1) == Some(&b'=') {
+                    pos += 2;
+                    TokenKind::Neq
+                } else {
+                    return Err(ParseError {
+                        message: "expected '=' after '!'".to_string(),
+                        span: start..start + 1,
+                    });
+                }
+            }
+            '>' => {
+                if bytes.get(pos + 1) == Some(&b'=') {
+                    pos += 2;
+                    TokenKind::Gte
+                } else {
+                    pos += 1;
+                    TokenKind::Gt
+                }
+            }
+            '<' => {
+                if bytes.get(pos + 1) == Some(&b'=') {
+                    pos += 2;
+                    TokenKind::Lte
+                } else {
+                    pos += 1;
+                    TokenKind::Lt
+                }
+            }
+            '"' => {
+                pos += 1;
+                let str_start = pos;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    pos += 1;
+                }
+                if pos >= bytes.len() {
+                    return Err(ParseError {
+                        message: "unterminated string literal".to_string(),
+                        span: start..pos,
+                    });
+                }
+                let text = input[str_start..pos].to_string();
+                pos += 1; // closing quote
+                TokenKind::Str(text)
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit)) =>
+            {
+                pos += 1;
+                while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+                    pos += 1;
+                }
+                let text = &input[start..pos];
+                let value = text.parse::<f64>().map_err(|_| ParseError {
+                    message: format!("invalid number literal '{text}'"),
+                    span: start..pos,
+                })?;
+                TokenKind::Number(value)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                pos += 1;
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_')
+                {
+                    pos += 1;
+                }
+                let text = &input[start..pos];
+                match text.to_ascii_uppercase().as_str() {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    "FROM" => TokenKind::From,
+                    "TO" => TokenKind::To,
+                    "TRUE" => TokenKind::True,
+                    "FALSE" => TokenKind::False,
+                    _ => TokenKind::Ident(text.to_string()),
+                }
+            }
+            other => {
+                return Err(ParseError {
+                    message: format!("unexpected character '{other}'"),
+                    span: start..start + other.len_utf8(),
+                });
+            }
+        };
+        tokens.push(Token {
+            kind,
+            span: start..pos,
+        });
+    }
+
+    let eof_pos = bytes.len();
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        span: eof_pos..eof_pos,
+    });
+    Ok(tokens)
+}
+
+// --- Recursive-descent parser ------------------------------------------
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect_eof(&self) -> Result<(), ParseError> {
+        match &self.peek().kind {
+            TokenKind::Eof => Ok(()),
+            other => Err(ParseError {
+                message: format!("unexpected trailing token {other:?}"),
+                span: self.peek().span.clone(),
+            }),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut items = vec![self.parse_and()?];
+        while matches!(self.peek().kind, TokenKind::Or) {
+            self.advance();
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            Expr::Or(items)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut items = vec![self.parse_unary()?];
+        while matches!(self.peek().kind, TokenKind::And) {
+            self.advance();
+            items.push(self.parse_unary()?);
+        }
+        Ok(if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            Expr::And(items)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if matches!(self.peek().kind, TokenKind::Not) {
+            self.advance();
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        if matches!(self.peek().kind, TokenKind::LParen) {
+            self.advance();
+            let inner = self.parse_or()?;
+            self.expect(TokenKind::RParen, "expected closing ')'")?;
+            return Ok(inner);
+        }
+        self.parse_condition()
+    }
+
+    fn expect(&mut self, kind: TokenKind, message: &str) -> Result<Token, ParseError> {
+        if self.peek().kind == kind {
+            Ok(self.advance())
+        } else {
+            Err(ParseError {
+                message: message.to_string(),
+                span: self.peek().span.clone(),
+            })
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<(String, ByteRange<usize>), ParseError> {
+        match self.peek().kind.clone() {
+            TokenKind::Ident(name) => {
+                let span = self.advance().span;
+                Ok((name, span))
+            }
+            other => Err(ParseError {
+                message: format!("expected field name, found {other:?}"),
+                span: self.peek().span.clone(),
+            }),
+        }
+    }
+
+    /// `primary` entry point for anything that isn't parens/`NOT`: a geo
+    /// call (with or without its `IDENT "."` prefix), a `FROM .. TO ..`
+    /// range, or an `OP` comparison - all of which start with an `IDENT`
+    /// except the keyless geo-call form.
+    fn parse_condition(&mut self) -> Result<Expr, ParseError> {
+        if is_geo_function(&self.peek().kind) {
+            let condition = self.parse_geo_call(DEFAULT_GEO_FIELD.to_string())?;
+            return Ok(Expr::Leaf(Condition::Field(condition)));
+        }
+
+        let (key, key_span) = self.expect_ident()?;
+
+        if matches!(self.peek().kind, TokenKind::Dot) {
+            self.advance();
+            if !is_geo_function(&self.peek().kind) {
+                return Err(ParseError {
+                    message: "expected a geo function after '.'".to_string(),
+                    span: self.peek().span.clone(),
+                });
+            }
+            let condition = self.parse_geo_call(key)?;
+            return Ok(Expr::Leaf(Condition::Field(condition)));
+        }
+
+        if matches!(self.peek().kind, TokenKind::From) {
+            self.advance();
+            let from = self.parse_number()?;
+            self.expect(TokenKind::To, "expected 'TO' in range expression")?;
+            let to = self.parse_number()?;
+            return Ok(Expr::Leaf(Condition::Field(FieldCondition {
+                key,
+                range: Some(Range {
+                    gte: Some(from),
+                    lte: Some(to),
+                    gt: None,
+                    lt: None,
+                }),
+                ..empty_field_condition()
+            })));
+        }
+
+        let op = self.advance();
+        let negate = match &op.kind {
+            TokenKind::Eq => false,
+            TokenKind::Neq => true,
+            TokenKind::Gt | TokenKind::Gte | TokenKind::Lt | TokenKind::Lte => {
+                let value = self.parse_number()?;
+                let range = match op.kind {
+                    TokenKind::Gt => Range {
+                        gt: Some(value),
+                        gte: None,
+                        lt: None,
+                        lte: None,
+                    },
+                    TokenKind::Gte => Range {
+                        gt: None,
+                        gte: Some(value),
+                        lt: None,
+                        lte: None,
+                    },
+                    TokenKind::Lt => Range {
+                        gt: None,
+                        gte: None,
+                        lt: Some(value),
+                        lte: None,
+                    },
+                    TokenKind::Lte => Range {
+                        gt: None,
+                        gte: None,
+                        lt: None,
+                        lte: Some(value),
+                    },
+                    _ => unreachable!(),
+                };
+                return Ok(Expr::Leaf(Condition::Field(FieldCondition {
+                    key,
+                    range: Some(range),
+                    ..empty_field_condition()
+                })));
+            }
+            other => {
+                return Err(ParseError {
+                    message: format!("expected a comparison operator, found {other:?}"),
+                    span: op.span,
+                });
+            }
+        };
+
+        let value = self.parse_match_value()?;
+        let condition = Condition::Field(FieldCondition {
+            key,
+            r#match: Some(value),
+            ..empty_field_condition()
+        });
+        if negate {
+            Ok(Expr::Not(Box::new(Expr::Leaf(condition))))
+        } else {
+            let _ = key_span;
+            Ok(Expr::Leaf(condition))
+        }
+    }
+
+    fn parse_match_value(&mut self) -> Result<Match, ParseError> {
+        let token = self.advance();
+        let value = match token.kind {
+            TokenKind::Str(text) => ValueVariants::Keyword(text),
+            TokenKind::Number(number) => ValueVariants::Integer(number as i64),
+            TokenKind::True => ValueVariants::Bool(true),
+            TokenKind::False => ValueVariants::Bool(false),
+            other => {
+                return Err(ParseError {
+                    message: format!("expected a value, found {other:?}"),
+                    span: token.span,
+                });
+            }
+        };
+        Ok(Match::Value(MatchValue { value }))
+    }
+
+    fn parse_number(&mut self) -> Result<f64, ParseError> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Number(number) => Ok(number),
+            other => Err(ParseError {
+                message: format!("expected a number, found {other:?}"),
+                span: token.span,
+            }),
+        }
+    }
+
+    /// Parses the `"(" NUMBER ("," NUMBER)* ")"` tail of a geo call,
+    /// already positioned at the function name token, and lowers it to a
+    /// `FieldCondition` against `key`.
+    fn parse_geo_call(&mut self, key: String) -> Result<FieldCondition, ParseError> {
+        let is_radius =
+            matches!(self.peek().kind, TokenKind::Ident(ref name) if name == "_geoRadius");
+        self.advance(); // function name
+        self.expect(TokenKind::LParen, "expected '(' after geo function name")?;
+
+        let mut args = vec![self.parse_number()?];
+        while matches!(self.peek().kind, TokenKind::Comma) {
+            self.advance();
+            args.push(self.parse_number()?);
+        }
+        self.expect(TokenKind::RParen, "expected ')' to close geo function call")?;
+
+        if is_radius {
+            if args.len() != 3 {
+                return Err(ParseError {
+                    message: format!(
+                        "_geoRadius expects 3 arguments (lat, lng, radius_m), got {}",
+                        args.len()
+                    ),
+                    span: self.peek().span.clone(),
+                });
+            }
+            Ok(FieldCondition {
+                key,
+                geo_radius: Some(GeoRadius {
+                    center: GeoPoint {
+                        lat: args[0],
+                        lon: args[1],
+                    },
+                    radius: args[2],
+                }),
+                ..empty_field_condition()
+            })
+        } else {
+            if args.len() != 4 {
+                return Err(ParseError {
+                    message: format!(
+                        "_geoBoundingBox expects 4 arguments (top_lat, top_lon, bottom_lat, bottom_lon), got {}",
+                        args.len()
+                    ),
+                    span: self.peek().span.clone(),
+                });
+            }
+            Ok(FieldCondition {
+                key,
+                geo_bounding_box: Some(GeoBoundingBox {
+                    top_left: GeoPoint {
+                        lat: args[0],
+                        lon: args[1],
+                    },
+                    bottom_right: GeoPoint {
+                        lat: args[2],
+                        lon: args[3],
+                    },
+                }),
+                ..empty_field_condition()
+            })
+        }
+    }
+}
+
+fn is_geo_function(kind: &TokenKind) -> bool {
+    matches!(kind, TokenKind::Ident(name) if name == "_geoRadius" || name == "_geoBoundingBox")
+}
+
+fn empty_field_condition() -> FieldCondition {
+    FieldCondition {
+        key: String::new(),
+        r#match: None,
+        range: None,
+        geo_bounding_box: None,
+        geo_radius: None,
+        values_count: None,
+        geo_polygon: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_comparison() {
+        let filter = parse_filter(r#"price > 100"#).unwrap();
+        assert_eq!(filter.must.unwrap().len(), 1);
+        assert!(filter.should.is_none());
+        assert!(filter.must_not.is_none());
+    }
+
+    #[test]
+    fn parses_and_into_must() {
+        let filter = parse_filter(r#"price > 100 AND color = "red""#).unwrap();
+        assert_eq!(filter.must.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn parses_or_into_should() {
+        let filter = parse_filter(r#"size = 42 OR color = "red""#).unwrap();
+        assert_eq!(filter.should.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn not_equal_lowers_to_must_not() {
+        let filter = parse_filter(r#"color != "red""#).unwrap();
+        assert_eq!(filter.must_not.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or_without_parens() {
+        // Equivalent to: size = 42 OR (color = "red" AND price > 100)
+        let filter = parse_filter(r#"size = 42 OR color = "red" AND price > 100"#).unwrap();
+        let should = filter.should.unwrap();
+        assert_eq!(should.len(), 2);
+        assert!(matches!(should[1], Condition::Filter(_)));
+    }
+
+    #[test]
+    fn parens_override_precedence() {
+        let filter = parse_filter(
+            r#"price > 100 AND color = "red" AND (size = 42 OR _geoRadius(40.7, -74.0, 2000))"#,
+        )
+        .unwrap();
+        let must = filter.must.unwrap();
+        assert_eq!(must.len(), 3);
+        assert!(matches!(must[2], Condition::Filter(_)));
+    }
+
+    #[test]
+    fn range_from_to_lowers_to_range_condition() {
+        let filter = parse_filter(r#"price FROM 10 TO 100"#).unwrap();
+        let must = filter.must.unwrap();
+        match &must[0] {
+            Condition::Field(field) => {
+                let range = field.range.as_ref().unwrap();
+                assert_eq!(range.gte, Some(10.0));
+                assert_eq!(range.lte, Some(100.0));
+            }
+            _ => panic!("expected a field condition"),
+        }
+    }
+
+    #[test]
+    fn geo_radius_without_prefix_targets_default_field() {
+        let filter = parse_filter(r#"_geoRadius(40.7, -74.0, 2000)"#).unwrap();
+        match &filter.must.unwrap()[0] {
+            Condition::Field(field) => {
+                assert_eq!(field.key, DEFAULT_GEO_FIELD);
+                let geo_radius = field.geo_radius.as_ref().unwrap();
+                assert_eq!(geo_radius.radius, 2000.0);
+            }
+            _ => panic!("expected a field condition"),
+        }
+    }
+
+    #[test]
+    fn geo_bounding_box_with_explicit_field() {
+        let filter = parse_filter(r#"warehouse._geoBoundingBox(52.6, 13.2, 52.4, 13.5)"#).unwrap();
+        match &filter.must.unwrap()[0] {
+            Condition::Field(field) => {
+                assert_eq!(field.key, "warehouse");
+                assert!(field.geo_bounding_box.is_some());
+            }
+            _ => panic!("expected a field condition"),
+        }
+    }
+
+    #[test]
+    fn not_wraps_nested_expression() {
+        let filter = parse_filter(r#"NOT (size = 42 OR color = "red")"#).unwrap();
+        assert_eq!(filter.must_not.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn error_carries_byte_span_of_offending_token() {
+        let err = parse_filter(r#"price > "oops""#).unwrap_err();
+        assert_eq!(err.span, 8..14);
+    }
+
+    #[test]
+    fn unterminated_string_is_reported() {
+        let err = parse_filter(r#"color = "red"#).unwrap_err();
+        assert!(err.message.contains("unterminated"));
+    }
+}
@@ -5,23 +5,29 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use atomic_refcell::AtomicRefCell;
-use log::debug;
+use log::{debug, warn};
 use parking_lot::RwLock;
+use roaring::RoaringBitmap;
 use rocksdb::DB;
 use schemars::_serde_json::Value;
 
 use crate::common::arc_atomic_ref_cell_iterator::ArcAtomicRefCellIterator;
-use crate::common::rocksdb_wrapper::open_db_with_existing_cf;
+use crate::common::compaction_filter::LiveIdsCompactionFilterFactory;
+use crate::common::rocksdb_wrapper::open_db_with_existing_cf_and_compaction_filter;
 use crate::common::utils::{IndexesMap, JsonPathPayload, MultiValue};
 use crate::common::Flusher;
 use crate::entry::entry_point::{OperationError, OperationResult};
 use crate::id_tracker::IdTrackerSS;
+use crate::index::field_index::geo_selectivity::estimate_geo_selectivity;
 use crate::index::field_index::index_selector::index_selector;
 use crate::index::field_index::{
     CardinalityEstimation, FieldIndex, PayloadBlockCondition, PrimaryCondition,
 };
 use crate::index::payload_config::PayloadConfig;
-use crate::index::query_estimator::estimate_filter;
+use crate::index::query_estimator::{
+    combine_must_bitmaps, combine_must_not_bitmap, combine_should_bitmaps, estimate_filter,
+    validate_filter_depth,
+};
 use crate::index::query_optimization::payload_provider::PayloadProvider;
 use crate::index::struct_filter_context::StructFilterContext;
 use crate::index::visited_pool::VisitedPool;
@@ -31,12 +37,71 @@ use crate::payload_storage::{FilterContext, PayloadStorage};
 use crate::telemetry::PayloadIndexTelemetry;
 use crate::types::{
     infer_collection_value_type, infer_value_type, Condition, FieldCondition, Filter,
-    IsEmptyCondition, IsNullCondition, Payload, PayloadContainer, PayloadField, PayloadFieldSchema,
-    PayloadKeyType, PayloadKeyTypeRef, PayloadSchemaType, PointOffsetType,
+    IsEmptyCondition, IsNullCondition, Match, MatchValue, Payload, PayloadContainer, PayloadField,
+    PayloadFieldSchema, PayloadKeyType, PayloadKeyTypeRef, PayloadSchemaType, PointOffsetType,
+    Range, ValueVariants,
 };
 
 pub const PAYLOAD_FIELD_INDEX_PATH: &str = "fields";
 
+/// Hashable stand-in for the handful of [`ValueVariants`] a `map_index`
+/// payload block can carry, so per-value counts can be accumulated in a
+/// `HashMap` before being turned back into [`Value`]s in
+/// [`StructPayloadIndex::facet_counts`].
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum FacetKey {
+    Bool(bool),
+    Keyword(String),
+    Integer(i64),
+}
+
+impl FacetKey {
+    fn into_value(self) -> Value {
+        match self {
+            FacetKey::Bool(b) => Value::Bool(b),
+            FacetKey::Keyword(k) => Value::String(k),
+            FacetKey::Integer(i) => Value::Number(i.into()),
+        }
+    }
+}
+
+/// Extract the single value a `payload_blocks` condition matches on, if it
+/// is a plain equality match (the only kind `map_index` ever emits blocks
+/// for).
+fn facet_key(condition: &FieldCondition) -> Option<FacetKey> {
+    match condition.r#match.as_ref()? {
+        Match::Value(MatchValue { value }) => Some(match value {
+            ValueVariants::Bool(b) => FacetKey::Bool(*b),
+            ValueVariants::Keyword(k) => FacetKey::Keyword(k.clone()),
+            ValueVariants::Integer(i) => FacetKey::Integer(*i),
+        }),
+        _ => None,
+    }
+}
+
+/// Direction to walk a numeric field's values in [`StructPayloadIndex::ordered_query`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+/// First stored value for `point_id` in a numeric field index, as `f64`, for
+/// sorting. `None` for any index type other than `IntIndex`/`FloatIndex`, or
+/// for a point with no value.
+fn numeric_sort_key(index: &FieldIndex, point_id: PointOffsetType) -> Option<f64> {
+    match index {
+        FieldIndex::IntIndex(num_index) => num_index
+            .get_values(point_id)
+            .and_then(|values| values.iter().copied().next())
+            .map(|value| value as f64),
+        FieldIndex::FloatIndex(num_index) => num_index
+            .get_values(point_id)
+            .and_then(|values| values.iter().copied().next()),
+        _ => None,
+    }
+}
+
 /// `PayloadIndex` implementation, which actually uses index structures for providing faster search
 pub struct StructPayloadIndex {
     /// Payload storage
@@ -93,6 +158,104 @@ impl StructPayloadIndex {
         indexes
     }
 
+    /// Resolve a single [`Condition`] to a [`RoaringBitmap`] purely from
+    /// indexes, without touching the payload storage.
+    ///
+    /// Returns `None` if any part of the condition has no index-backed
+    /// bitmap available (e.g. `IsEmpty`/`IsNull`, or a field with no index),
+    /// in which case the caller should fall back to the slower iterator path.
+    fn condition_bitmap(
+        &self,
+        condition: &Condition,
+        nested_path: Option<&JsonPathPayload>,
+    ) -> Option<RoaringBitmap> {
+        match condition {
+            Condition::Field(field_condition) => {
+                let full_path = JsonPathPayload::extend_or_new(nested_path, &field_condition.key);
+                let full_path_condition = FieldCondition {
+                    key: full_path.path,
+                    ..field_condition.clone()
+                };
+                self.field_indexes
+                    .get(&full_path_condition.key)?
+                    .iter()
+                    .find_map(|index| index.filter_bitmap(&full_path_condition))
+            }
+            Condition::HasId(has_id) => {
+                let id_tracker = self.id_tracker.borrow();
+                Some(
+                    has_id
+                        .has_id
+                        .iter()
+                        .filter_map(|external_id| id_tracker.internal_id(*external_id))
+                        .collect(),
+                )
+            }
+            Condition::Filter(filter) => self.filter_bitmap(filter, nested_path),
+            Condition::Nested(nested) => {
+                let full_path = JsonPathPayload::extend_or_new(nested_path, &nested.array_key());
+                self.filter_bitmap(nested.filter(), Some(&full_path))
+            }
+            // No index gives a cheap bitmap of points with an empty/null value for a field.
+            Condition::IsEmpty(_) | Condition::IsNull(_) => None,
+        }
+    }
+
+    /// Recursively fold a [`Filter`] tree into a single [`RoaringBitmap`] of
+    /// matching points: `must` is intersected, `should` is unioned and
+    /// `must_not` is subtracted, mirroring [`estimate_filter`] but computing
+    /// an exact result instead of an estimation.
+    ///
+    /// Returns `None` as soon as any condition in the tree has no
+    /// index-backed bitmap, so the caller can fall back to the iterator path
+    /// for that whole filter instead of mixing exact and approximate results.
+    fn filter_bitmap(
+        &self,
+        filter: &Filter,
+        nested_path: Option<&JsonPathPayload>,
+    ) -> Option<RoaringBitmap> {
+        let mut combined: Option<RoaringBitmap> = None;
+
+        if let Some(conditions) = filter.must.as_ref().filter(|c| !c.is_empty()) {
+            let mut bitmaps = conditions
+                .iter()
+                .map(|condition| self.condition_bitmap(condition, nested_path))
+                .collect::<Option<Vec<_>>>()?;
+            // Seed the AND-fold with the smallest bitmap first, so every
+            // intermediate result stays as small as possible.
+            bitmaps.sort_unstable_by_key(RoaringBitmap::len);
+            combined = Some(combine_must_bitmaps(&bitmaps));
+        }
+
+        if let Some(conditions) = filter.should.as_ref().filter(|c| !c.is_empty()) {
+            let bitmaps = conditions
+                .iter()
+                .map(|condition| self.condition_bitmap(condition, nested_path))
+                .collect::<Option<Vec<_>>>()?;
+            let should_bitmap = combine_should_bitmaps(&bitmaps);
+            combined = Some(match combined {
+                Some(acc) => combine_must_bitmaps(&[acc, should_bitmap]),
+                None => should_bitmap,
+            });
+        }
+
+        if let Some(conditions) = filter.must_not.as_ref().filter(|c| !c.is_empty()) {
+            let bitmaps = conditions
+                .iter()
+                .map(|condition| self.condition_bitmap(condition, nested_path))
+                .collect::<Option<Vec<_>>>()?;
+            let excluded = combine_should_bitmaps(&bitmaps);
+            combined = Some(match combined {
+                Some(acc) => combine_must_not_bitmap(&acc, &excluded),
+                // A bare `must_not` has no indexed base set to subtract from,
+                // so it can't be resolved into a bounded bitmap on its own.
+                None => return None,
+            });
+        }
+
+        combined
+    }
+
     fn config_path(&self) -> PathBuf {
         PayloadConfig::get_config_path(&self.path)
     }
@@ -148,8 +311,11 @@ impl StructPayloadIndex {
             PayloadConfig::default()
         };
 
-        let db = open_db_with_existing_cf(path)
-            .map_err(|err| OperationError::service_error(format!("RocksDB open error: {err}")))?;
+        let db = open_db_with_existing_cf_and_compaction_filter(
+            path,
+            LiveIdsCompactionFilterFactory::new(id_tracker.clone()),
+        )
+        .map_err(|err| OperationError::service_error(format!("RocksDB open error: {err}")))?;
 
         let mut index = StructPayloadIndex {
             payload,
@@ -310,6 +476,11 @@ impl StructPayloadIndex {
             }
             Condition::Field(field_condition) => self
                 .estimate_field_condition(field_condition, nested_path)
+                // No index for this field: a geo clause's own geometry still
+                // beats `unknown()`, even without an index to measure against.
+                .or_else(|| {
+                    estimate_geo_selectivity(field_condition, self.available_point_count(), None)
+                })
                 .unwrap_or_else(|| CardinalityEstimation::unknown(self.available_point_count())),
         }
     }
@@ -332,6 +503,130 @@ impl StructPayloadIndex {
     ) -> OperationResult<()> {
         crate::rocksdb_backup::restore(snapshot_path, &segment_path.join("payload_index"))
     }
+
+    /// Count matching points per distinct value of `field`, for a
+    /// keyword/integer field backed by `map_index`. Reuses the
+    /// `payload_blocks` machinery (one block per distinct value), but
+    /// reports the decoded `Value` and its count instead of block
+    /// conditions. When `filter` is given, each value's posting list is
+    /// intersected with the filter's matches instead of reporting the raw
+    /// indexed count. `top_k` caps the number of values returned, keeping
+    /// only the most frequent ones.
+    pub fn facet_counts(
+        &self,
+        field: PayloadKeyTypeRef,
+        filter: Option<&Filter>,
+        top_k: Option<usize>,
+    ) -> Vec<(Value, usize)> {
+        let Some(indexes) = self.field_indexes.get(field) else {
+            return Vec::new();
+        };
+
+        let preselected_bitmap = filter.map(|filter| {
+            self.filter_bitmap(filter, None)
+                .unwrap_or_else(|| self.query_points(filter).into_iter().collect())
+        });
+
+        let mut counts: HashMap<FacetKey, usize> = HashMap::new();
+        for index in indexes {
+            for block in index.payload_blocks(1, field.to_owned()) {
+                let Some(value) = facet_key(&block.condition) else {
+                    continue;
+                };
+                let count = match &preselected_bitmap {
+                    Some(bitmap) => index
+                        .filter_bitmap(&block.condition)
+                        .map(|value_bitmap| value_bitmap.intersection_len(bitmap) as usize)
+                        .unwrap_or(0),
+                    None => block.cardinality,
+                };
+                if count > 0 {
+                    *counts.entry(value).or_insert(0) += count;
+                }
+            }
+        }
+
+        let mut result: Vec<(Value, usize)> = counts
+            .into_iter()
+            .map(|(key, count)| (key.into_value(), count))
+            .collect();
+        result.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+        if let Some(top_k) = top_k {
+            result.truncate(top_k);
+        }
+        result
+    }
+
+    /// Top-N-by-field query over an `IntIndex`/`FloatIndex`-backed numeric
+    /// field: narrow to `range` via the index, sort the matches by value in
+    /// `direction`, and stop once `limit` points pass `remaining_filter`.
+    ///
+    /// Unlike [`Self::query_points`], points come back already ordered by
+    /// the field's value instead of in arbitrary index order, so this can
+    /// back a "sort by field" query directly instead of post-sorting a
+    /// larger unordered result set outside the index.
+    pub fn ordered_query(
+        &self,
+        field: PayloadKeyTypeRef,
+        range: Range,
+        direction: Direction,
+        limit: usize,
+        remaining_filter: Option<&Filter>,
+    ) -> Vec<PointOffsetType> {
+        let field_condition = FieldCondition {
+            key: field.to_owned(),
+            r#match: None,
+            range: Some(range),
+            geo_bounding_box: None,
+            geo_radius: None,
+            geo_polygon: None,
+            values_count: None,
+        };
+
+        let Some(indexes) = self.field_indexes.get(field) else {
+            return Vec::new();
+        };
+        let Some(index) = indexes
+            .iter()
+            .find(|index| matches!(index, FieldIndex::IntIndex(_) | FieldIndex::FloatIndex(_)))
+        else {
+            return Vec::new();
+        };
+        let Some(matched) = index.filter(&field_condition) else {
+            return Vec::new();
+        };
+
+        let mut candidates: Vec<(PointOffsetType, f64)> = matched
+            .filter_map(|point_id| numeric_sort_key(index, point_id).map(|value| (point_id, value)))
+            .collect();
+        match direction {
+            Direction::Ascending => candidates.sort_unstable_by(|a, b| a.1.total_cmp(&b.1)),
+            Direction::Descending => candidates.sort_unstable_by(|a, b| b.1.total_cmp(&a.1)),
+        }
+
+        let struct_filtered_context =
+            remaining_filter.map(|filter| self.struct_filtered_context(filter));
+
+        candidates
+            .into_iter()
+            .map(|(point_id, _)| point_id)
+            .filter(|&point_id| {
+                struct_filtered_context
+                    .as_ref()
+                    .map_or(true, |context| context.check(point_id))
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Force a full compaction of the payload index database, so the
+    /// `LiveIdsCompactionFilterFactory` installed in [`Self::open`] gets a
+    /// chance to drop entries for points removed since the last compaction,
+    /// without waiting for RocksDB to schedule one on its own.
+    pub fn trigger_index_compaction(&self) {
+        let db = self.db.read();
+        db.compact_range::<&[u8], &[u8]>(None, None);
+    }
 }
 
 impl PayloadIndex for StructPayloadIndex {
@@ -377,8 +672,13 @@ impl PayloadIndex for StructPayloadIndex {
 
     fn estimate_cardinality(&self, query: &Filter) -> CardinalityEstimation {
         let available_points = self.available_point_count();
+        if let Err(err) = validate_filter_depth(query) {
+            warn!("Rejecting cardinality estimation for over-nested filter: {err}");
+            return CardinalityEstimation::unknown(available_points);
+        }
         let estimator = |condition: &Condition| self.condition_cardinality(condition, None);
-        estimate_filter(&estimator, query, available_points)
+        estimate_filter(&estimator, query, available_points, 0)
+            .unwrap_or_else(|_| CardinalityEstimation::unknown(available_points))
     }
 
     fn estimate_nested_cardinality(
@@ -387,12 +687,33 @@ impl PayloadIndex for StructPayloadIndex {
         nested_path: &JsonPathPayload,
     ) -> CardinalityEstimation {
         let available_points = self.available_point_count();
+        if let Err(err) = validate_filter_depth(query) {
+            warn!("Rejecting nested cardinality estimation for over-nested filter: {err}");
+            return CardinalityEstimation::unknown(available_points);
+        }
         let estimator =
             |condition: &Condition| self.condition_cardinality(condition, Some(nested_path));
-        estimate_filter(&estimator, query, available_points)
+        estimate_filter(&estimator, query, available_points, 0)
+            .unwrap_or_else(|_| CardinalityEstimation::unknown(available_points))
     }
 
     fn query_points(&self, query: &Filter) -> Vec<PointOffsetType> {
+        // Reject an over-nested filter up front, before it's walked by
+        // either the index-backed bitmap fold below or the full-scan
+        // fallback further down - both recurse through the same tree and
+        // would otherwise overflow the stack on a malicious payload.
+        if let Err(err) = validate_filter_depth(query) {
+            warn!("Rejecting query for over-nested filter: {err}");
+            return Vec::new();
+        }
+
+        // If every condition in the filter tree resolves to an index-backed
+        // bitmap, the fold below is already exact: no need to re-verify
+        // against payload storage via `StructFilterContext`.
+        if let Some(bitmap) = self.filter_bitmap(query, None) {
+            return bitmap.into_iter().collect();
+        }
+
         // Assume query is already estimated to be small enough so we can iterate over all matched ids
 
         let query_cardinality = self.estimate_cardinality(query);
@@ -2,9 +2,10 @@
 
 use serde_json::Value;
 
+use crate::payload_storage::conversion::Conversion;
 use crate::types::{
-    AnyVariants, FieldCondition, GeoBoundingBox, GeoRadius, Match, MatchAny, MatchExcept,
-    MatchText, MatchValue, Range, ValueVariants, ValuesCount,
+    AnyVariants, FieldCondition, GeoBoundingBox, GeoPolygon, GeoRadius, Match, MatchAny,
+    MatchExcept, MatchText, MatchValue, Range, ValueVariants, ValuesCount,
 };
 
 pub trait ValueChecker {
@@ -23,6 +24,24 @@ pub trait ValueChecker {
     }
 }
 
+impl FieldCondition {
+    /// Like [`ValueChecker::check`], but first coerces `payload` through
+    /// `conversion` (if any) — e.g. so a `range` condition on a field
+    /// declared `"timestamp"` compares as a Unix timestamp rather than
+    /// lexically against the stored RFC 3339 string.
+    pub fn check_converted(&self, payload: &Value, conversion: Option<&Conversion>) -> bool {
+        let converted;
+        let payload = match conversion.and_then(|conversion| conversion.convert(payload).ok()) {
+            Some(value) => {
+                converted = value;
+                &converted
+            }
+            None => payload,
+        };
+        self.check(payload)
+    }
+}
+
 impl ValueChecker for FieldCondition {
     fn check_match(&self, payload: &Value) -> bool {
         let mut res = false;
@@ -47,6 +66,11 @@ impl ValueChecker for FieldCondition {
                 .geo_bounding_box
                 .as_ref()
                 .map_or(false, |condition| condition.check_match(payload));
+        res = res
+            || self
+                .geo_polygon
+                .as_ref()
+                .map_or(false, |condition| condition.check_match(payload));
         res = res
             || self
                 .values_count
@@ -150,6 +174,49 @@ impl ValueChecker for GeoRadius {
     }
 }
 
+impl GeoPolygon {
+    /// Point-in-polygon via ray-casting: cast a ray east from `(lon, lat)`
+    /// and count ring-edge crossings; an odd count means the point is
+    /// inside. The ring is treated as implicitly closed, so a caller doesn't
+    /// need to repeat the first vertex as the last.
+    fn check_point(&self, lon: f64, lat: f64) -> bool {
+        if self.points.len() < 3 {
+            return false;
+        }
+        let mut inside = false;
+        let vertices = &self.points;
+        let mut j = vertices.len() - 1;
+        for i in 0..vertices.len() {
+            let a = &vertices[j];
+            let b = &vertices[i];
+            if (a.lat > lat) != (b.lat > lat)
+                && lon < (b.lon - a.lon) * (lat - a.lat) / (b.lat - a.lat) + a.lon
+            {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+}
+
+impl ValueChecker for GeoPolygon {
+    fn check_match(&self, payload: &Value) -> bool {
+        match payload {
+            Value::Object(obj) => {
+                let lon_op = obj.get("lon").and_then(|x| x.as_f64());
+                let lat_op = obj.get("lat").and_then(|x| x.as_f64());
+
+                if let (Some(lon), Some(lat)) = (lon_op, lat_op) {
+                    return self.check_point(lon, lat);
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
 impl ValueChecker for ValuesCount {
     fn check_match(&self, payload: &Value) -> bool {
         self.check_count(payload)
@@ -199,6 +266,31 @@ mod tests {
         assert!(!miss_geo_query.check(&berlin_and_moscow));
     }
 
+    #[test]
+    fn test_geo_polygon_matching() {
+        let point_in_berlin = json!({
+            "lat": 52.52197645,
+            "lon": 13.413637435864272
+        });
+        let point_in_moscow = json!({
+            "lat": 55.7536283,
+            "lon": 37.62137960067377
+        });
+
+        // A rough box around Berlin, given unclosed (first != last vertex).
+        let berlin_box = GeoPolygon {
+            points: vec![
+                GeoPoint { lat: 52.4, lon: 13.2 },
+                GeoPoint { lat: 52.4, lon: 13.6 },
+                GeoPoint { lat: 52.6, lon: 13.6 },
+                GeoPoint { lat: 52.6, lon: 13.2 },
+            ],
+        };
+
+        assert!(berlin_box.check(&point_in_berlin));
+        assert!(!berlin_box.check(&point_in_moscow));
+    }
+
     #[test]
     fn test_value_count() {
         let countries = json!([
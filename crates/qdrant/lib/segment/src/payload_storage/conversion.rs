@@ -0,0 +1,130 @@
+//! Optional per-field value coercion, applied when a payload is written and
+//! again when a filter condition compares against it, so a field ingested as
+//! a string (e.g. an ISO timestamp) is still compared numerically instead of
+//! lexically.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde_json::{Number, Value};
+
+/// How to coerce a payload field's value before it's stored or compared.
+///
+/// Parsed from names like `"int"`, `"float"`, `"bool"`, `"timestamp"`, or
+/// `"timestamp|%Y-%m-%d"` (an explicit strftime format after a `|`) via
+/// [`FromStr`], matching how the field is declared in a collection's schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conversion {
+    /// Store/compare the value as-is.
+    Bytes,
+    Integer,
+    Float,
+    Boolean,
+    /// An RFC 3339 timestamp string, compared as a Unix timestamp.
+    Timestamp,
+    /// A timestamp in a custom `strftime`-style format, assumed UTC.
+    TimestampFmt(String),
+    /// A timestamp in a custom format that itself carries a timezone offset.
+    TimestampTzFmt(String),
+}
+
+/// A value couldn't be coerced to the declared [`Conversion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError(pub String);
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "payload conversion failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl FromStr for Conversion {
+    type Err = ConversionError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (name, format) = spec.split_once('|').map_or((spec, None), |(n, f)| (n, Some(f)));
+        match name {
+            "bytes" => Ok(Conversion::Bytes),
+            "int" | "integer" => Ok(Conversion::Integer),
+            "float" => Ok(Conversion::Float),
+            "bool" | "boolean" => Ok(Conversion::Boolean),
+            "timestamp" => Ok(match format {
+                Some(format) => Conversion::TimestampFmt(format.to_string()),
+                None => Conversion::Timestamp,
+            }),
+            "timestamp_tz" => format
+                .map(|format| Conversion::TimestampTzFmt(format.to_string()))
+                .ok_or_else(|| ConversionError("timestamp_tz requires a |format".to_string())),
+            other => Err(ConversionError(format!("unknown conversion {other:?}"))),
+        }
+    }
+}
+
+impl Conversion {
+    /// Coerces `raw` (as stored/deserialized from JSON) into the target
+    /// type. Timestamp variants normalize to a Unix timestamp `Value::Number`
+    /// so range filters compare numerically rather than lexically.
+    pub fn convert(&self, raw: &Value) -> Result<Value, ConversionError> {
+        match self {
+            Conversion::Bytes => Ok(raw.clone()),
+            Conversion::Integer => as_str(raw)
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| ConversionError(format!("{raw} is not an integer"))),
+            Conversion::Float => as_str(raw)
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| ConversionError(format!("{raw} is not a float"))),
+            Conversion::Boolean => match raw {
+                Value::Bool(value) => Ok(Value::Bool(*value)),
+                Value::String(value) => value
+                    .parse::<bool>()
+                    .map(Value::Bool)
+                    .map_err(|_| ConversionError(format!("{raw} is not a boolean"))),
+                _ => Err(ConversionError(format!("{raw} is not a boolean"))),
+            },
+            Conversion::Timestamp => {
+                let text = as_str(raw);
+                DateTime::parse_from_rfc3339(&text)
+                    .map(|timestamp| epoch(timestamp.with_timezone(&Utc)))
+                    .map_err(|_| ConversionError(format!("{raw} is not an RFC 3339 timestamp")))
+            }
+            Conversion::TimestampFmt(format) => {
+                let text = as_str(raw);
+                NaiveDateTime::parse_from_str(&text, format)
+                    .map(|naive| epoch(Utc.from_utc_datetime(&naive)))
+                    .map_err(|_| {
+                        ConversionError(format!("{raw} does not match timestamp format {format:?}"))
+                    })
+            }
+            Conversion::TimestampTzFmt(format) => {
+                let text = as_str(raw);
+                DateTime::parse_from_str(&text, format)
+                    .map(|timestamp| epoch(timestamp.with_timezone(&Utc)))
+                    .map_err(|_| {
+                        ConversionError(format!("{raw} does not match timestamp format {format:?}"))
+                    })
+            }
+        }
+    }
+}
+
+fn epoch(timestamp: DateTime<Utc>) -> Value {
+    Value::from(timestamp.timestamp())
+}
+
+/// Reads `value` as text for parsing, accepting either a JSON string or a
+/// JSON number (so a field that round-tripped through storage as a number
+/// can still be re-coerced).
+fn as_str(value: &Value) -> std::borrow::Cow<str> {
+    match value {
+        Value::String(text) => std::borrow::Cow::Borrowed(text.as_str()),
+        Value::Number(number) => std::borrow::Cow::Owned(number.to_string()),
+        _ => std::borrow::Cow::Borrowed(""),
+    }
+}
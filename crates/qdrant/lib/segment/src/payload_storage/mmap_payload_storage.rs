@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::entry::entry_point::{OperationError, OperationResult};
+use crate::types::{Payload, PayloadKeyType, PointOffsetType};
+
+const DATA_FILE_NAME: &str = "payloads.obkv";
+const OFFSETS_FILE_NAME: &str = "payloads.offsets";
+const FIELDS_FILE_NAME: &str = "payloads.fields";
+
+/// Byte size of a single entry in the offsets file: a `(file_offset: u64,
+/// len: u32)` pair, one per [`PointOffsetType`], padded to 16 bytes so the
+/// mmap can be indexed by `point_id * OFFSET_ENTRY_SIZE` without packing.
+const OFFSET_ENTRY_SIZE: usize = 16;
+/// Sentinel stored in an offset entry's `len` for a point that has no
+/// payload record (never written, or removed by a later empty `assign`).
+const NO_RECORD: u32 = u32::MAX;
+
+/// One point's payload, laid out as an "optimized bytes key-value" (OBKV)
+/// record: a header of `(field_id, value_offset)` pairs sorted by
+/// `field_id`, followed by the field values themselves, each CBOR-encoded.
+/// Sorting the header lets a single field be located with a binary search
+/// over the header alone, without decoding any value bytes.
+struct ObkvRecord<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ObkvRecord<'a> {
+    const HEADER_ENTRY_SIZE: usize = 6; // u16 field_id + u32 value_offset
+
+    fn field_count(&self) -> usize {
+        u16::from_le_bytes(self.bytes[0..2].try_into().unwrap()) as usize
+    }
+
+    fn header_entry(&self, index: usize) -> (u16, u32) {
+        let start = 2 + index * Self::HEADER_ENTRY_SIZE;
+        let field_id = u16::from_le_bytes(self.bytes[start..start + 2].try_into().unwrap());
+        let value_offset =
+            u32::from_le_bytes(self.bytes[start + 2..start + 6].try_into().unwrap());
+        (field_id, value_offset)
+    }
+
+    /// Bytes of the CBOR-encoded value for `field_id`, if present. Locating
+    /// one field only requires scanning the sorted header, not decoding any
+    /// value bytes other than the one returned.
+    fn field_bytes(&self, field_id: u16) -> Option<&'a [u8]> {
+        let count = self.field_count();
+        let index = (0..count).find(|&i| self.header_entry(i).0 == field_id)?;
+        let (_, start) = self.header_entry(index);
+        let end = (0..count)
+            .map(|i| self.header_entry(i).1)
+            .filter(|&offset| offset > start)
+            .min()
+            .unwrap_or(self.bytes.len() as u32);
+        Some(&self.bytes[start as usize..end as usize])
+    }
+
+    /// Decodes every field in the record into a [`Payload`].
+    fn to_payload(&self, field_names: &[PayloadKeyType]) -> OperationResult<Payload> {
+        let mut object = serde_json::Map::new();
+        for i in 0..self.field_count() {
+            let (field_id, _) = self.header_entry(i);
+            let value_bytes = self
+                .field_bytes(field_id)
+                .ok_or_else(|| OperationError::service_error("missing OBKV field bytes"))?;
+            let value: serde_json::Value = serde_cbor::from_slice(value_bytes)
+                .map_err(|_| OperationError::service_error("cannot deserialize OBKV field"))?;
+            let name = field_names
+                .get(field_id as usize)
+                .ok_or_else(|| OperationError::service_error("unknown OBKV field id"))?;
+            object.insert(name.clone(), value);
+        }
+        serde_json::from_value(serde_json::Value::Object(object))
+            .map_err(|_| OperationError::service_error("cannot build payload from OBKV record"))
+    }
+}
+
+/// Builds the bytes of an [`ObkvRecord`] for `payload`, interning any field
+/// name it hasn't seen before into `fields`/`field_ids`.
+fn encode_record(
+    payload: &Payload,
+    field_ids: &mut HashMap<PayloadKeyType, u16>,
+    fields: &mut Vec<PayloadKeyType>,
+) -> OperationResult<Vec<u8>> {
+    let value = serde_json::to_value(payload)
+        .map_err(|_| OperationError::service_error("cannot serialize payload"))?;
+    let object = value
+        .as_object()
+        .ok_or_else(|| OperationError::service_error("payload is not a JSON object"))?;
+
+    let mut entries: Vec<(u16, Vec<u8>)> = Vec::with_capacity(object.len());
+    for (name, value) in object {
+        let field_id = *field_ids.entry(name.clone()).or_insert_with(|| {
+            fields.push(name.clone());
+            (fields.len() - 1) as u16
+        });
+        let encoded =
+            serde_cbor::to_vec(value).map_err(|_| OperationError::service_error("cannot encode field"))?;
+        entries.push((field_id, encoded));
+    }
+    entries.sort_by_key(|(field_id, _)| *field_id);
+
+    let header_size = 2 + entries.len() * ObkvRecord::HEADER_ENTRY_SIZE;
+    let mut bytes = Vec::with_capacity(header_size + entries.iter().map(|(_, v)| v.len()).sum::<usize>());
+    bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+    let mut value_offset = header_size as u32;
+    for (field_id, encoded) in &entries {
+        bytes.extend_from_slice(&field_id.to_le_bytes());
+        bytes.extend_from_slice(&value_offset.to_le_bytes());
+        value_offset += encoded.len() as u32;
+    }
+    for (_, encoded) in &entries {
+        bytes.extend_from_slice(encoded);
+    }
+    Ok(bytes)
+}
+
+/// An on-disk, memory-mapped alternative to [`super::simple_payload_storage::SimplePayloadStorage`]
+/// that keeps payload bytes out of the heap.
+///
+/// Payloads are appended as OBKV records (see [`ObkvRecord`]) to a single
+/// growing data file; a fixed-stride `point_id -> (offset, len)` offset
+/// table is mmap'd separately so looking up a point's record never touches
+/// the payload bytes themselves. Re-assigning a point's payload appends a
+/// new record and repoints its offset entry; the old record's bytes become
+/// unreachable garbage, reclaimed the next time [`Self::recreate`] compacts
+/// the data file down to only the live records.
+pub struct MmapPayloadStorage {
+    base_path: PathBuf,
+    data_file: File,
+    data_mmap: Mmap,
+    offsets_file: File,
+    offsets_mmap: MmapMut,
+    /// Field name interner; small relative to payload data, so it is kept
+    /// resident and persisted as a plain sidecar file rather than mmap'd.
+    field_ids: HashMap<PayloadKeyType, u16>,
+    fields: Vec<PayloadKeyType>,
+}
+
+impl MmapPayloadStorage {
+    pub fn open(path: &Path) -> OperationResult<Self> {
+        std::fs::create_dir_all(path)
+            .map_err(|err| OperationError::service_error(format!("cannot create {path:?}: {err}")))?;
+
+        let data_path = path.join(DATA_FILE_NAME);
+        let data_file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&data_path)
+            .map_err(|err| OperationError::service_error(format!("cannot open {data_path:?}: {err}")))?;
+        let data_mmap = open_read_mmap(&data_file)?;
+
+        let offsets_path = path.join(OFFSETS_FILE_NAME);
+        let offsets_file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .open(&offsets_path)
+            .map_err(|err| OperationError::service_error(format!("cannot open {offsets_path:?}: {err}")))?;
+        let offsets_mmap = open_write_mmap(&offsets_file)?;
+
+        let (field_ids, fields) = load_fields(&path.join(FIELDS_FILE_NAME))?;
+
+        Ok(MmapPayloadStorage {
+            base_path: path.to_owned(),
+            data_file,
+            data_mmap,
+            offsets_file,
+            offsets_mmap,
+            field_ids,
+            fields,
+        })
+    }
+
+    fn offset_entry(&self, point_id: PointOffsetType) -> Option<(u64, u32)> {
+        let start = point_id as usize * OFFSET_ENTRY_SIZE;
+        let entry = self.offsets_mmap.get(start..start + OFFSET_ENTRY_SIZE)?;
+        let len = u32::from_le_bytes(entry[8..12].try_into().unwrap());
+        if len == NO_RECORD {
+            return None;
+        }
+        let offset = u64::from_le_bytes(entry[0..8].try_into().unwrap());
+        Some((offset, len))
+    }
+
+    fn set_offset_entry(&mut self, point_id: PointOffsetType, entry: Option<(u64, u32)>) -> OperationResult<()> {
+        let start = point_id as usize * OFFSET_ENTRY_SIZE;
+        let needed = start + OFFSET_ENTRY_SIZE;
+        if needed > self.offsets_mmap.len() {
+            self.grow_offsets(needed)?;
+        }
+
+        let (offset, len) = entry.unwrap_or((0, NO_RECORD));
+        self.offsets_mmap[start..start + 8].copy_from_slice(&offset.to_le_bytes());
+        self.offsets_mmap[start + 8..start + 12].copy_from_slice(&len.to_le_bytes());
+        self.offsets_mmap[start + 12..start + 16].copy_from_slice(&0u32.to_le_bytes());
+        Ok(())
+    }
+
+    fn grow_offsets(&mut self, min_len: usize) -> OperationResult<()> {
+        let new_len = (min_len.next_power_of_two()).max(OFFSET_ENTRY_SIZE * 16);
+        self.offsets_file
+            .set_len(new_len as u64)
+            .map_err(|err| OperationError::service_error(format!("cannot grow offsets file: {err}")))?;
+        self.offsets_mmap = open_write_mmap(&self.offsets_file)?;
+        Ok(())
+    }
+
+    /// Appends `record` to the data file and remaps it for reads.
+    fn append_record(&mut self, record: &[u8]) -> OperationResult<(u64, u32)> {
+        let offset = self.data_mmap.len() as u64;
+        self.data_file
+            .write_all(record)
+            .map_err(|err| OperationError::service_error(format!("cannot append payload record: {err}")))?;
+        self.data_file
+            .flush()
+            .map_err(|err| OperationError::service_error(format!("cannot flush payload record: {err}")))?;
+        self.data_mmap = open_read_mmap(&self.data_file)?;
+        Ok((offset, record.len() as u32))
+    }
+
+    /// Writes `payload` for `point_id`, or clears its record when `payload`
+    /// is `None`. Mirrors [`super::simple_payload_storage::SimplePayloadStorage::update_storage`]'s
+    /// role, except the record itself (not just an index into a `HashMap`)
+    /// has to be produced here.
+    pub fn update_storage(&mut self, point_id: PointOffsetType, payload: Option<&Payload>) -> OperationResult<()> {
+        match payload {
+            None => self.set_offset_entry(point_id, None),
+            Some(payload) => {
+                let mut field_ids = std::mem::take(&mut self.field_ids);
+                let mut fields = std::mem::take(&mut self.fields);
+                let record = encode_record(payload, &mut field_ids, &mut fields);
+                self.field_ids = field_ids;
+                self.fields = fields;
+                let record = record?;
+                let entry = self.append_record(&record)?;
+                self.set_offset_entry(point_id, Some(entry))?;
+                save_fields(&self.base_path.join(FIELDS_FILE_NAME), &self.fields)
+            }
+        }
+    }
+
+    /// Decodes and returns `point_id`'s payload, if it has one.
+    ///
+    /// Unlike [`super::simple_payload_storage::SimplePayloadStorage::payload_ptr`],
+    /// this can't hand back a `&Payload`: the mmap only holds encoded bytes,
+    /// so every call decodes a fresh owned [`Payload`] instead of indexing
+    /// an in-memory map.
+    pub fn payload_ptr(&self, point_id: PointOffsetType) -> OperationResult<Option<Payload>> {
+        let Some((offset, len)) = self.offset_entry(point_id) else {
+            return Ok(None);
+        };
+        let bytes = &self.data_mmap[offset as usize..offset as usize + len as usize];
+        let record = ObkvRecord { bytes };
+        record.to_payload(&self.fields).map(Some)
+    }
+
+    pub fn iter<F>(&self, mut callback: F) -> OperationResult<()>
+    where
+        F: FnMut(PointOffsetType, &Payload) -> OperationResult<bool>,
+    {
+        let count = self.offsets_mmap.len() / OFFSET_ENTRY_SIZE;
+        for point_id in 0..count as PointOffsetType {
+            let Some(payload) = self.payload_ptr(point_id)? else {
+                continue;
+            };
+            if !callback(point_id, &payload)? {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    /// Compacts the data file down to only the records still referenced by
+    /// the offset table, reclaiming the space of overwritten/removed
+    /// payloads, then rebuilds the offset table against the compacted file.
+    pub fn recreate(&mut self) -> OperationResult<()> {
+        let count = self.offsets_mmap.len() / OFFSET_ENTRY_SIZE;
+        let mut live: Vec<(PointOffsetType, Vec<u8>)> = Vec::new();
+        for point_id in 0..count as PointOffsetType {
+            if let Some((offset, len)) = self.offset_entry(point_id) {
+                let bytes = self.data_mmap[offset as usize..offset as usize + len as usize].to_vec();
+                live.push((point_id, bytes));
+            }
+        }
+
+        self.data_file
+            .set_len(0)
+            .map_err(|err| OperationError::service_error(format!("cannot truncate data file: {err}")))?;
+        self.data_mmap = open_read_mmap(&self.data_file)?;
+
+        for (point_id, bytes) in live {
+            let entry = self.append_record(&bytes)?;
+            self.set_offset_entry(point_id, Some(entry))?;
+        }
+        Ok(())
+    }
+}
+
+fn open_read_mmap(file: &File) -> OperationResult<Mmap> {
+    // Safety: the data/offsets files are only ever resized through
+    // `File::set_len`/append from this process, which is the same caveat
+    // every other mmap'd store in this crate (e.g. `MmapVectors`) accepts.
+    unsafe { Mmap::map(file) }
+        .map_err(|err| OperationError::service_error(format!("cannot mmap payload file: {err}")))
+}
+
+fn open_write_mmap(file: &File) -> OperationResult<MmapMut> {
+    if file.metadata().map(|meta| meta.len()).unwrap_or(0) == 0 {
+        file.set_len((OFFSET_ENTRY_SIZE * 16) as u64)
+            .map_err(|err| OperationError::service_error(format!("cannot size offsets file: {err}")))?;
+    }
+    // Safety: see `open_read_mmap`.
+    unsafe { MmapMut::map_mut(file) }
+        .map_err(|err| OperationError::service_error(format!("cannot mmap offsets file: {err}")))
+}
+
+fn load_fields(path: &Path) -> OperationResult<(HashMap<PayloadKeyType, u16>, Vec<PayloadKeyType>)> {
+    if !path.exists() {
+        return Ok((HashMap::new(), Vec::new()));
+    }
+    let bytes = std::fs::read(path)
+        .map_err(|err| OperationError::service_error(format!("cannot read {path:?}: {err}")))?;
+    let fields: Vec<PayloadKeyType> = serde_json::from_slice(&bytes)
+        .map_err(|_| OperationError::service_error("cannot deserialize field names"))?;
+    let field_ids = fields
+        .iter()
+        .enumerate()
+        .map(|(id, name)| (name.clone(), id as u16))
+        .collect();
+    Ok((field_ids, fields))
+}
+
+fn save_fields(path: &Path, fields: &[PayloadKeyType]) -> OperationResult<()> {
+    let bytes = serde_json::to_vec(fields)
+        .map_err(|_| OperationError::service_error("cannot serialize field names"))?;
+    std::fs::write(path, bytes)
+        .map_err(|err| OperationError::service_error(format!("cannot write {path:?}: {err}")))
+}
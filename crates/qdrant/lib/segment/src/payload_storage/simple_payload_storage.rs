@@ -6,13 +6,18 @@ use rocksdb::DB;
 
 use crate::common::rocksdb_wrapper::{DatabaseColumnWrapper, DB_PAYLOAD_CF};
 use crate::entry::entry_point::{OperationError, OperationResult};
-use crate::types::{Payload, PointOffsetType};
+use crate::payload_storage::conversion::Conversion;
+use crate::types::{Payload, PayloadKeyType, PointOffsetType};
 
 /// In-memory implementation of `PayloadStorage`.
 /// Persists all changes to disk using `store`, but only uses this storage during the initial load
 pub struct SimplePayloadStorage {
     pub(crate) payload: HashMap<PointOffsetType, Payload>,
     pub(crate) db_wrapper: DatabaseColumnWrapper,
+    /// Per-field coercion applied to a payload's values before it's stored,
+    /// so e.g. a field declared as `"timestamp"` is range-filterable
+    /// numerically even though it arrives as an RFC 3339 string.
+    pub(crate) conversions: HashMap<PayloadKeyType, Conversion>,
 }
 
 impl SimplePayloadStorage {
@@ -32,9 +37,48 @@ impl SimplePayloadStorage {
         Ok(SimplePayloadStorage {
             payload: payload_map,
             db_wrapper,
+            conversions: Default::default(),
         })
     }
 
+    /// Declares the coercion to apply to each named field's value before it's
+    /// stored by [`Self::assign`].
+    pub fn set_field_conversions(&mut self, conversions: HashMap<PayloadKeyType, Conversion>) {
+        self.conversions = conversions;
+    }
+
+    /// Applies the declared field conversions to `payload`, then stores it
+    /// for `point_id` and persists the change.
+    pub fn assign(&mut self, point_id: PointOffsetType, payload: Payload) -> OperationResult<()> {
+        let payload = self.convert_payload(payload)?;
+        self.payload.insert(point_id, payload);
+        self.update_storage(&point_id)
+    }
+
+    /// Coerces every field named in `self.conversions` to its declared type,
+    /// leaving fields with no declared conversion untouched.
+    fn convert_payload(&self, payload: Payload) -> OperationResult<Payload> {
+        if self.conversions.is_empty() {
+            return Ok(payload);
+        }
+        let mut value = serde_json::to_value(&payload)
+            .map_err(|_| OperationError::service_error("cannot serialize payload"))?;
+        if let Some(object) = value.as_object_mut() {
+            for (field, conversion) in &self.conversions {
+                if let Some(raw) = object.get(field) {
+                    let converted = conversion.convert(raw).map_err(|err| {
+                        OperationError::service_error(&format!(
+                            "cannot convert field {field:?}: {err}"
+                        ))
+                    })?;
+                    object.insert(field.clone(), converted);
+                }
+            }
+        }
+        serde_json::from_value(value)
+            .map_err(|_| OperationError::service_error("cannot deserialize converted payload"))
+    }
+
     pub(crate) fn update_storage(&self, point_id: &PointOffsetType) -> OperationResult<()> {
         match self.payload.get(point_id) {
             None => self
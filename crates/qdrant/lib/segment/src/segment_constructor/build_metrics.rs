@@ -0,0 +1,197 @@
+//! Lightweight atomic counters/histograms recording [`super::segment_builder::SegmentBuilder`]
+//! throughput, so an operator can watch optimization passes - and notice
+//! pathological merges where most copied work is immediately thrown away -
+//! without attaching a profiler. Recording is just atomic increments
+//! threaded through the existing `update_from`/`build`/`update_quantization`
+//! loops; it never changes merge semantics.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A minimal fixed-purpose histogram (count + sum only), good enough for
+/// duration/byte distributions without pulling in a metrics crate.
+#[derive(Debug, Default)]
+struct Histogram {
+    count: AtomicU64,
+    sum_millis: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_millis
+            .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            count: self.count.load(Ordering::Relaxed),
+            sum_millis: self.sum_millis.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistogramSnapshot {
+    pub count: u64,
+    pub sum_millis: u64,
+}
+
+/// Per-build counters and histograms for [`super::segment_builder::SegmentBuilder`].
+/// Updates are cheap atomic increments so they can be called from the hot
+/// `update_from` loop; a [`Self::snapshot`] is a cheap plain-struct copy that
+/// can be taken from any thread at any time, including while the builder is
+/// still running.
+#[derive(Default)]
+pub struct BuildMetrics {
+    vectors_merged: AtomicU64,
+    points_deduplicated: AtomicU64,
+    points_kept_other: AtomicU64,
+    points_kept_existing: AtomicU64,
+    build_index_duration: Histogram,
+    quantization_duration_by_vector: Mutex<HashMap<String, Histogram>>,
+    quantization_bytes_by_vector: Mutex<HashMap<String, AtomicU64>>,
+}
+
+impl BuildMetrics {
+    /// One point considered for copying from the `other` segment, regardless
+    /// of whether it was ultimately kept, deduplicated, or expired.
+    pub fn record_vector_merged(&self) {
+        self.vectors_merged.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A point existed in both the newly constructed and the `other`
+    /// segment and had to be deduplicated by version; `other_won` is whether
+    /// `other`'s version replaced the existing one.
+    pub fn record_deduplicated(&self, other_won: bool) {
+        self.points_deduplicated.fetch_add(1, Ordering::Relaxed);
+        if other_won {
+            self.points_kept_other.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.points_kept_existing.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_build_index(&self, duration: Duration) {
+        self.build_index_duration.observe(duration);
+    }
+
+    pub fn record_quantization(&self, vector_name: &str, duration: Duration, bytes: u64) {
+        self.quantization_duration_by_vector
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(vector_name.to_owned())
+            .or_default()
+            .observe(duration);
+        self.quantization_bytes_by_vector
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(vector_name.to_owned())
+            .or_default()
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> BuildMetricsSnapshot {
+        let quantization_duration = self
+            .quantization_duration_by_vector
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(name, histogram)| (name.clone(), histogram.snapshot()))
+            .collect();
+        let quantization_bytes = self
+            .quantization_bytes_by_vector
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(name, bytes)| (name.clone(), bytes.load(Ordering::Relaxed)))
+            .collect();
+
+        BuildMetricsSnapshot {
+            vectors_merged: self.vectors_merged.load(Ordering::Relaxed),
+            points_deduplicated: self.points_deduplicated.load(Ordering::Relaxed),
+            points_kept_other: self.points_kept_other.load(Ordering::Relaxed),
+            points_kept_existing: self.points_kept_existing.load(Ordering::Relaxed),
+            build_index_duration: self.build_index_duration.snapshot(),
+            quantization_duration_by_vector: quantization_duration,
+            quantization_bytes_by_vector: quantization_bytes,
+        }
+    }
+}
+
+/// A point-in-time copy of [`BuildMetrics`], safe to hold onto and print
+/// after the builder it came from has been consumed by `build()`.
+#[derive(Debug, Clone, Default)]
+pub struct BuildMetricsSnapshot {
+    pub vectors_merged: u64,
+    pub points_deduplicated: u64,
+    pub points_kept_other: u64,
+    pub points_kept_existing: u64,
+    pub build_index_duration: HistogramSnapshot,
+    pub quantization_duration_by_vector: HashMap<String, HistogramSnapshot>,
+    pub quantization_bytes_by_vector: HashMap<String, u64>,
+}
+
+impl BuildMetricsSnapshot {
+    /// Renders this snapshot as Prometheus text exposition format.
+    pub fn to_prometheus_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# TYPE qdrant_segment_build_vectors_merged_total counter\n\
+             qdrant_segment_build_vectors_merged_total {}",
+            self.vectors_merged
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE qdrant_segment_build_points_deduplicated_total counter\n\
+             qdrant_segment_build_points_deduplicated_total {}",
+            self.points_deduplicated
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE qdrant_segment_build_points_kept_other_total counter\n\
+             qdrant_segment_build_points_kept_other_total {}",
+            self.points_kept_other
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE qdrant_segment_build_points_kept_existing_total counter\n\
+             qdrant_segment_build_points_kept_existing_total {}",
+            self.points_kept_existing
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE qdrant_segment_build_index_duration_milliseconds histogram\n\
+             qdrant_segment_build_index_duration_milliseconds_count {}\n\
+             qdrant_segment_build_index_duration_milliseconds_sum {}",
+            self.build_index_duration.count, self.build_index_duration.sum_millis
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE qdrant_segment_build_quantization_duration_milliseconds histogram"
+        );
+        for (vector_name, histogram) in &self.quantization_duration_by_vector {
+            let _ = writeln!(
+                out,
+                "qdrant_segment_build_quantization_duration_milliseconds_count{{vector=\"{vector_name}\"}} {}\n\
+                 qdrant_segment_build_quantization_duration_milliseconds_sum{{vector=\"{vector_name}\"}} {}",
+                histogram.count, histogram.sum_millis
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# TYPE qdrant_segment_build_quantization_bytes_total counter"
+        );
+        for (vector_name, bytes) in &self.quantization_bytes_by_vector {
+            let _ = writeln!(
+                out,
+                "qdrant_segment_build_quantization_bytes_total{{vector=\"{vector_name}\"}} {bytes}"
+            );
+        }
+        out
+    }
+}
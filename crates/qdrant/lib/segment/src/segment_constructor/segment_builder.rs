@@ -2,9 +2,16 @@ use std::cmp;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Instant;
 
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+
+use super::build_metrics::BuildMetrics;
 use super::get_vector_storage_path;
 use crate::common::error_logging::LogError;
+use crate::common::utils::MultiValue;
 use crate::entry::entry_point::{
     check_process_stopped, OperationError, OperationResult, SegmentEntry,
 };
@@ -12,15 +19,47 @@ use crate::index::hnsw_index::max_rayon_threads;
 use crate::index::{PayloadIndex, VectorIndex};
 use crate::segment::Segment;
 use crate::segment_constructor::{build_segment, load_segment};
-use crate::types::{Indexes, PayloadFieldSchema, PayloadKeyType, SegmentConfig};
+use crate::types::{Indexes, PayloadFieldSchema, PayloadKeyType, PointOffsetType, SegmentConfig};
 use crate::vector_storage::VectorStorage;
 
+/// Collection-level object-lifecycle rule: a point is dropped instead of
+/// copied forward by [`SegmentBuilder::update_from`] once the unix-epoch
+/// timestamp in its `timestamp_field` is older than `max_age`, or (if set)
+/// older than the absolute `cutoff` date.
+///
+/// A point missing `timestamp_field`, or holding a value that isn't a
+/// timestamp, is treated as never-expiring rather than as an error.
+#[derive(Debug, Clone)]
+pub struct LifecycleConfig {
+    pub timestamp_field: PayloadKeyType,
+    pub max_age: Duration,
+    pub cutoff: Option<DateTime<Utc>>,
+}
+
+impl LifecycleConfig {
+    fn is_expired(&self, timestamp: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        now.signed_duration_since(timestamp) > self.max_age
+            || self.cutoff.is_some_and(|cutoff| timestamp < cutoff)
+    }
+}
+
 /// Structure for constructing segment out of several other segments
 pub struct SegmentBuilder {
     pub segment: Option<Segment>,
     pub destination_path: PathBuf,
     pub temp_path: PathBuf,
     pub indexed_fields: HashMap<PayloadKeyType, PayloadFieldSchema>,
+    /// Stale-point expiration rule applied while copying points in
+    /// `update_from`; `None` copies every point forward regardless of age.
+    pub lifecycle: Option<LifecycleConfig>,
+    /// Fixed at construction so every point processed by this builder, across
+    /// however many `update_from` calls happen before `build`, is judged
+    /// against the same instant.
+    now: DateTime<Utc>,
+    /// Throughput counters for this build. Kept behind an `Arc` so a caller
+    /// can clone a handle before calling `build()` (which consumes `self`)
+    /// and still read a snapshot afterwards.
+    pub metrics: Arc<BuildMetrics>,
 }
 
 impl SegmentBuilder {
@@ -39,9 +78,32 @@ impl SegmentBuilder {
             destination_path,
             temp_path,
             indexed_fields: Default::default(),
+            lifecycle: None,
+            now: Utc::now(),
+            metrics: Arc::new(BuildMetrics::default()),
         })
     }
 
+    /// Unix-epoch timestamp read from `old_internal_id`'s `timestamp_field`,
+    /// if present and parseable as a number.
+    fn point_timestamp(
+        other_payload_index: &dyn PayloadIndex,
+        lifecycle: &LifecycleConfig,
+        old_internal_id: PointOffsetType,
+    ) -> OperationResult<Option<DateTime<Utc>>> {
+        let payload = other_payload_index.payload(old_internal_id)?;
+        let value = match payload.get_value(&lifecycle.timestamp_field) {
+            MultiValue::Single(value) => value,
+            MultiValue::Multiple(values) => values.into_iter().next(),
+        };
+        let epoch_secs = value.and_then(|value| match value {
+            Value::Number(number) => number.as_i64(),
+            Value::String(text) => text.parse::<i64>().ok(),
+            _ => None,
+        });
+        Ok(epoch_secs.and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0)))
+    }
+
     /// Update current segment builder with all (not deleted) vectors and payload form `other` segment
     /// Perform index building at the end of update
     ///
@@ -134,6 +196,29 @@ impl SegmentBuilder {
                         continue;
                     };
                 let other_version = other_id_tracker.internal_version(old_internal_id).unwrap();
+                self.metrics.record_vector_merged();
+
+                if let Some(lifecycle) = &self.lifecycle {
+                    let timestamp = Self::point_timestamp(
+                        &*other_payload_index,
+                        lifecycle,
+                        old_internal_id,
+                    )?;
+                    if timestamp.is_some_and(|timestamp| lifecycle.is_expired(timestamp, self.now))
+                    {
+                        // Point is stale: drop it from the merged segment entirely,
+                        // rather than copying it forward, instead of requiring a
+                        // dedicated expiration scan.
+                        if let Some(existing_internal_id) = id_tracker.internal_id(external_id) {
+                            id_tracker.drop(external_id)?;
+                            payload_index.drop(existing_internal_id)?;
+                            for vector_storage in vector_storages.values_mut() {
+                                vector_storage.delete_vector(existing_internal_id)?;
+                            }
+                        }
+                        continue;
+                    }
+                }
 
                 match id_tracker.internal_id(external_id) {
                     None => {
@@ -150,6 +235,8 @@ impl SegmentBuilder {
                         // Based on version
                         let existing_version =
                             id_tracker.internal_version(existing_internal_id).unwrap();
+                        self.metrics
+                            .record_deduplicated(existing_version < other_version);
                         let remove_id = if existing_version < other_version {
                             // Other version is the newest, remove the existing one and replace
                             id_tracker.drop(external_id)?;
@@ -192,10 +279,12 @@ impl SegmentBuilder {
                 check_process_stopped(stopped)?;
             }
 
-            Self::update_quantization(&segment, stopped)?;
+            Self::update_quantization(&segment, stopped, &self.metrics)?;
 
             for vector_data in segment.vector_data.values_mut() {
+                let started_at = Instant::now();
                 vector_data.vector_index.borrow_mut().build_index(stopped)?;
+                self.metrics.record_build_index(started_at.elapsed());
             }
 
             segment.flush(true)?;
@@ -216,7 +305,11 @@ impl SegmentBuilder {
         Ok(loaded_segment)
     }
 
-    fn update_quantization(segment: &Segment, stopped: &AtomicBool) -> OperationResult<()> {
+    fn update_quantization(
+        segment: &Segment,
+        stopped: &AtomicBool,
+        metrics: &BuildMetrics,
+    ) -> OperationResult<()> {
         let config = segment.config();
         for (vector_name, vector_data) in &segment.vector_data {
             if let Some(quantization) = config.quantization_config(vector_name) {
@@ -233,12 +326,17 @@ impl SegmentBuilder {
                     Some(Indexes::Hnsw(hnsw)) => max_rayon_threads(hnsw.max_indexing_threads),
                     _ => 1,
                 };
+                let started_at = Instant::now();
                 vector_data.vector_storage.borrow_mut().quantize(
                     &vector_storage_path,
                     quantization,
                     max_threads,
                     stopped,
                 )?;
+                let bytes = std::fs::metadata(&vector_storage_path)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+                metrics.record_quantization(vector_name, started_at.elapsed(), bytes);
             }
         }
         Ok(())
@@ -0,0 +1,112 @@
+//! Consistency scrub for a [`Segment`], analogous to a storage-block repair
+//! scan. Checks for the kinds of dangling state the version-merge logic in
+//! [`super::segment_builder::SegmentBuilder::update_from`] can leave behind
+//! when a `build` is cut short by a `check_process_stopped` early return, and
+//! optionally fixes what it finds.
+
+use std::collections::HashSet;
+
+use crate::entry::entry_point::OperationResult;
+use crate::segment::Segment;
+use crate::types::PointOffsetType;
+
+/// Per-category counts of inconsistencies found by [`verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IntegrityReport {
+    /// Live internal ids with a deleted (or missing) vector in at least one
+    /// `vector_data` storage.
+    pub orphaned_vectors: usize,
+    /// Live internal ids with no `internal_version` set.
+    pub version_gaps: usize,
+    /// Live internal ids whose external link doesn't round-trip back to the
+    /// same internal id.
+    pub link_mismatches: usize,
+    /// Payload rows assigned to an internal id with no live external link.
+    pub dangling_payload_rows: usize,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn total(&self) -> usize {
+        self.orphaned_vectors + self.version_gaps + self.link_mismatches + self.dangling_payload_rows
+    }
+}
+
+/// Scans `segment` for inconsistencies, returning a per-category report. In
+/// repair mode (`repair = true`), also corrects what it finds: orphaned
+/// vectors are re-deleted via `delete_vector` in every storage, and dangling
+/// payload rows are dropped. Every correction is logged.
+pub fn verify(segment: &Segment, repair: bool) -> OperationResult<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+
+    let id_tracker = segment.id_tracker.borrow();
+    let live_internal_ids: HashSet<PointOffsetType> = id_tracker.iter_ids().collect();
+
+    for &internal_id in &live_internal_ids {
+        let external_id = match id_tracker.external_id(internal_id) {
+            Some(external_id) => external_id,
+            None => continue,
+        };
+
+        if id_tracker.internal_id(external_id) != Some(internal_id) {
+            report.link_mismatches += 1;
+            log::warn!(
+                "segment verify: internal id {internal_id} does not round-trip through external id {external_id:?}"
+            );
+            continue;
+        }
+
+        if id_tracker.internal_version(internal_id).is_none() {
+            report.version_gaps += 1;
+            log::warn!("segment verify: internal id {internal_id} has no internal_version set");
+        }
+
+        for (vector_name, vector_data) in &segment.vector_data {
+            let is_orphaned = vector_data
+                .vector_storage
+                .borrow()
+                .is_deleted_vector(internal_id);
+            if !is_orphaned {
+                continue;
+            }
+            report.orphaned_vectors += 1;
+            if repair {
+                vector_data
+                    .vector_storage
+                    .borrow_mut()
+                    .delete_vector(internal_id)?;
+                log::warn!(
+                    "segment repair: re-deleted orphaned vector {internal_id} in storage {vector_name:?}"
+                );
+            }
+        }
+    }
+    drop(id_tracker);
+
+    let total_points = segment.id_tracker.borrow().total_point_count();
+    let payload_index = segment.payload_index.borrow();
+    let mut dangling = Vec::new();
+    for internal_id in 0..total_points as PointOffsetType {
+        if live_internal_ids.contains(&internal_id) {
+            continue;
+        }
+        if !payload_index.payload(internal_id)?.is_empty() {
+            dangling.push(internal_id);
+        }
+    }
+    drop(payload_index);
+    report.dangling_payload_rows = dangling.len();
+
+    if repair {
+        let mut payload_index = segment.payload_index.borrow_mut();
+        for internal_id in dangling {
+            payload_index.drop(internal_id)?;
+            log::warn!("segment repair: dropped dangling payload row for internal id {internal_id}");
+        }
+    }
+
+    Ok(report)
+}
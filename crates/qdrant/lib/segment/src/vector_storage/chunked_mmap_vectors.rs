@@ -1,5 +1,7 @@
 use std::cmp::max;
+use std::collections::{HashMap, HashSet};
 use std::fs::{create_dir_all, OpenOptions};
+use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
@@ -21,6 +23,11 @@ const DEFAULT_CHUNK_SIZE: usize = 32 * 1024 * 1024; // 32Mb
 
 const CONFIG_FILE_NAME: &str = "config.json";
 const STATUS_FILE_NAME: &str = "status.dat";
+const FREELIST_FILE_NAME: &str = "freelist.dat";
+
+/// Minimum fraction of allocated slots that must be free before `vacuum`
+/// bothers compacting - below this a full rewrite costs more than it saves.
+const VACUUM_FREE_RATIO_THRESHOLD: f32 = 0.2;
 
 #[repr(C)]
 pub struct Status {
@@ -34,11 +41,32 @@ struct ChunkedMmapConfig {
     dim: usize,
 }
 
+/// Fill and dedup snapshot returned by [`ChunkedMmapVectors::stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkedMmapVectorsStats {
+    pub occupied_slots: usize,
+    pub allocated_slots: usize,
+    pub free_slots: usize,
+    pub chunk_count: usize,
+    /// Fraction of vectors in use per chunk, in chunk order.
+    pub per_chunk_fill: Vec<f32>,
+    pub bytes_on_disk: u64,
+    /// Estimated space held by allocated-but-unoccupied slots.
+    pub wasted_bytes: u64,
+    /// Number of vectors that are exact duplicates of another live vector,
+    /// present only when `count_duplicates` was requested.
+    pub duplicate_vectors: Option<usize>,
+}
+
 pub struct ChunkedMmapVectors {
     config: ChunkedMmapConfig,
     status: MmapType<Status>,
     chunks: Vec<MmapChunk>,
     directory: PathBuf,
+    /// Keys freed by `delete` and not yet reclaimed by `push` or `vacuum`,
+    /// persisted to `freelist.dat` so a deleted slot doesn't come back to
+    /// life as live data after a restart.
+    free_slots: Vec<PointOffsetType>,
 }
 
 impl ChunkedMmapVectors {
@@ -101,22 +129,90 @@ impl ChunkedMmapVectors {
     }
 
     pub fn open(directory: &Path, dim: usize) -> OperationResult<Self> {
+        Self::recover_vacuum(directory)?;
+
         create_dir_all(directory)?;
         let status_mmap = Self::ensure_status_file(directory)?;
         let status = unsafe { MmapType::from(status_mmap) };
 
         let config = Self::ensure_config(directory, dim)?;
         let chunks = read_mmaps(directory)?;
+        let free_slots = Self::read_freelist(directory)?;
 
         let vectors = Self {
             status,
             config,
             chunks,
             directory: directory.to_owned(),
+            free_slots,
         };
         Ok(vectors)
     }
 
+    fn freelist_file(directory: &Path) -> PathBuf {
+        directory.join(FREELIST_FILE_NAME)
+    }
+
+    fn read_freelist(directory: &Path) -> OperationResult<Vec<PointOffsetType>> {
+        let path = Self::freelist_file(directory);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = std::fs::File::open(&path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    fn write_freelist(&self) -> OperationResult<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(Self::freelist_file(&self.directory))?;
+        serde_json::to_writer(&mut file, &self.free_slots)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    fn vacuum_staging_dir(directory: &Path) -> PathBuf {
+        Self::sibling_dir(directory, "vacuum-new")
+    }
+
+    fn vacuum_backup_dir(directory: &Path) -> PathBuf {
+        Self::sibling_dir(directory, "vacuum-old")
+    }
+
+    fn sibling_dir(directory: &Path, suffix: &str) -> PathBuf {
+        let mut name = directory.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{suffix}"));
+        directory.with_file_name(name)
+    }
+
+    /// Finishes or rolls back an interrupted [`Self::vacuum`], so a crash
+    /// mid-swap doesn't leave the store stuck between the old and new
+    /// layout. Safe to call on a directory that was never vacuumed.
+    fn recover_vacuum(directory: &Path) -> OperationResult<()> {
+        let staging_dir = Self::vacuum_staging_dir(directory);
+        let backup_dir = Self::vacuum_backup_dir(directory);
+
+        if staging_dir.exists() && !directory.exists() {
+            // Crashed between renaming the live directory out of the way and
+            // renaming the compacted copy into place - finish the swap.
+            std::fs::rename(&staging_dir, directory)?;
+        }
+        if backup_dir.exists() {
+            // Crashed after the swap committed but before the old copy was
+            // cleaned up - the new layout is already live, so it's safe to
+            // discard the backup now.
+            let _ = std::fs::remove_dir_all(&backup_dir);
+        }
+        if staging_dir.exists() {
+            // Crashed while still building the compacted copy, before the
+            // swap began - the live directory was never touched.
+            let _ = std::fs::remove_dir_all(&staging_dir);
+        }
+        Ok(())
+    }
+
     #[inline]
     fn get_chunk_index(&self, key: usize) -> usize {
         key / self.config.chunk_size_vectors
@@ -175,11 +271,79 @@ impl ChunkedMmapVectors {
     }
 
     pub fn push(&mut self, vector: &[VectorElementType]) -> OperationResult<PointOffsetType> {
+        if let Some(key) = self.free_slots.pop() {
+            self.write_freelist()?;
+            self.insert(key, vector)?;
+            return Ok(key);
+        }
         let new_id = self.status.len as PointOffsetType;
         self.insert(new_id, vector)?;
         Ok(new_id)
     }
 
+    /// Marks `key`'s slot as reusable, so a later `push` reclaims it instead
+    /// of growing the store, and a later `vacuum` can drop it entirely.
+    /// Persisted immediately, so a crash doesn't resurrect the slot as live.
+    pub fn delete(&mut self, key: PointOffsetType) -> OperationResult<()> {
+        if (key as usize) >= self.status.len || self.free_slots.contains(&key) {
+            return Ok(());
+        }
+        self.free_slots.push(key);
+        self.write_freelist()
+    }
+
+    /// If the free ratio exceeds [`VACUUM_FREE_RATIO_THRESHOLD`], rewrites
+    /// every live vector into a fresh, compacted copy of the store and
+    /// atomically swaps it in, returning the `old_key -> new_key` remap so
+    /// the caller can fix up the RocksDB mapping column family. Returns an
+    /// empty remap (and does nothing) if the ratio isn't exceeded.
+    ///
+    /// Crash safety: the compacted copy is built and flushed to a sibling
+    /// `<dir>.vacuum-new` directory - the live directory is never touched
+    /// while this happens. The swap itself is two directory renames
+    /// (`<dir>` -> `<dir>.vacuum-old`, then `<dir>.vacuum-new` -> `<dir>`),
+    /// each atomic; [`Self::recover_vacuum`] (run by `open`) finishes or
+    /// rolls back a crash caught between them.
+    pub fn vacuum(&mut self) -> OperationResult<HashMap<PointOffsetType, PointOffsetType>> {
+        let mut remap = HashMap::new();
+        if self.status.len == 0 || self.free_slots.is_empty() {
+            return Ok(remap);
+        }
+        let free_ratio = self.free_slots.len() as f32 / self.status.len as f32;
+        if free_ratio < VACUUM_FREE_RATIO_THRESHOLD {
+            return Ok(remap);
+        }
+
+        let free: HashSet<PointOffsetType> = self.free_slots.iter().copied().collect();
+        let staging_dir = Self::vacuum_staging_dir(&self.directory);
+        if staging_dir.exists() {
+            std::fs::remove_dir_all(&staging_dir)?;
+        }
+        let mut staging = Self::open(&staging_dir, self.config.dim)?;
+        for old_key in 0..self.status.len as PointOffsetType {
+            if free.contains(&old_key) {
+                continue;
+            }
+            let vector = self.get(old_key).to_vec();
+            let new_key = staging.push(&vector)?;
+            remap.insert(old_key, new_key);
+        }
+        // Every live vector now has a durable new home - safe to commit.
+        staging.flusher()()?;
+        drop(staging);
+
+        let backup_dir = Self::vacuum_backup_dir(&self.directory);
+        if backup_dir.exists() {
+            std::fs::remove_dir_all(&backup_dir)?;
+        }
+        std::fs::rename(&self.directory, &backup_dir)?;
+        std::fs::rename(&staging_dir, &self.directory)?;
+        let _ = std::fs::remove_dir_all(&backup_dir);
+
+        *self = Self::open(&self.directory, self.config.dim)?;
+        Ok(remap)
+    }
+
     pub fn get<TKey>(&self, key: TKey) -> &[VectorElementType]
     where
         TKey: num_traits::cast::AsPrimitive<usize>,
@@ -214,6 +378,117 @@ impl ChunkedMmapVectors {
         }
         files
     }
+
+    /// Fill ratio, bytes-on-disk, and dedup signal for this store, to give
+    /// operators a read before deciding whether [`Self::vacuum`] is worth
+    /// running or whether an indexer is storing many identical embeddings.
+    /// Hashing every live vector to count duplicates is the most expensive
+    /// part, so it's behind `count_duplicates`.
+    pub fn stats(&self, count_duplicates: bool) -> ChunkedMmapVectorsStats {
+        let free: HashSet<PointOffsetType> = self.free_slots.iter().copied().collect();
+        let chunk_size_vectors = self.config.chunk_size_vectors;
+        let allocated_slots = self.chunks.len() * chunk_size_vectors;
+        let occupied_slots = self.status.len - free.len();
+
+        let per_chunk_fill = (0..self.chunks.len())
+            .map(|chunk_idx| {
+                let start = chunk_idx * chunk_size_vectors;
+                let end = (start + chunk_size_vectors).min(self.status.len);
+                if start >= end {
+                    return 0.0;
+                }
+                let used = (start..end)
+                    .filter(|key| !free.contains(&(*key as PointOffsetType)))
+                    .count();
+                used as f32 / chunk_size_vectors as f32
+            })
+            .collect();
+
+        let bytes_on_disk = self
+            .files()
+            .iter()
+            .filter_map(|file| std::fs::metadata(file).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        let vector_size_bytes = (self.config.dim * std::mem::size_of::<VectorElementType>()) as u64;
+        let wasted_bytes = (allocated_slots - occupied_slots) as u64 * vector_size_bytes;
+
+        let duplicate_vectors = count_duplicates.then(|| {
+            let mut hash_counts: HashMap<u64, usize> = HashMap::new();
+            for key in 0..self.status.len as PointOffsetType {
+                if free.contains(&key) {
+                    continue;
+                }
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                for value in self.get(key) {
+                    value.to_bits().hash(&mut hasher);
+                }
+                *hash_counts.entry(hasher.finish()).or_insert(0) += 1;
+            }
+            hash_counts
+                .values()
+                .filter(|&&count| count > 1)
+                .map(|count| count - 1)
+                .sum()
+        });
+
+        ChunkedMmapVectorsStats {
+            occupied_slots,
+            allocated_slots,
+            free_slots: free.len(),
+            chunk_count: self.chunks.len(),
+            per_chunk_fill,
+            bytes_on_disk,
+            wasted_bytes,
+            duplicate_vectors,
+        }
+    }
+
+    /// Flushes and copies `config.json`, `status.dat`, and every chunk file
+    /// into `target_dir`, hard-linking where possible so the snapshot costs
+    /// minimal extra disk space. `target_dir` is created if missing.
+    pub fn snapshot(&self, target_dir: &Path) -> OperationResult<()> {
+        self.flusher()()?;
+        create_dir_all(target_dir)?;
+        for file in self.files() {
+            let file_name = file.file_name().ok_or_else(|| {
+                OperationError::service_error(format!(
+                    "Chunked mmap vectors file has no file name: {}",
+                    file.display(),
+                ))
+            })?;
+            Self::link_or_copy(&file, &target_dir.join(file_name))?;
+        }
+        Ok(())
+    }
+
+    /// Reopens a copy of the files written by [`Self::snapshot`] into
+    /// `directory`, so a checkpoint directory can be restored without
+    /// assuming anything about the checkpoint's original location.
+    pub fn restore_from(
+        checkpoint_dir: &Path,
+        directory: &Path,
+        dim: usize,
+    ) -> OperationResult<Self> {
+        create_dir_all(directory)?;
+        for entry in std::fs::read_dir(checkpoint_dir)? {
+            let path = entry?.path();
+            if let Some(file_name) = path.file_name() {
+                Self::link_or_copy(&path, &directory.join(file_name))?;
+            }
+        }
+        Self::open(directory, dim)
+    }
+
+    /// Hard-links `src` to `dst`, falling back to a copy when hard links
+    /// aren't supported (e.g. `src` and `dst` are on different filesystems).
+    fn link_or_copy(src: &Path, dst: &Path) -> OperationResult<()> {
+        if std::fs::hard_link(src, dst).is_err() {
+            std::fs::copy(src, dst)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
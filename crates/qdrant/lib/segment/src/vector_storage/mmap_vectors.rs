@@ -1,7 +1,9 @@
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{Seek, SeekFrom, Write};
 use std::mem::{self, size_of, transmute};
-use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
@@ -14,33 +16,93 @@ use crate::common::error_logging::LogError;
 use crate::common::mmap_type::MmapBitSlice;
 use crate::common::{mmap_ops, Flusher};
 use crate::data_types::vectors::VectorElementType;
-use crate::entry::entry_point::OperationResult;
+use crate::entry::entry_point::{OperationError, OperationResult};
 use crate::types::{Distance, PointOffsetType, QuantizationConfig};
 #[cfg(target_os = "linux")]
-use crate::vector_storage::async_io::UringReader;
+use crate::vector_storage::async_io::{UringReader, UringWriter};
 #[cfg(not(target_os = "linux"))]
-use crate::vector_storage::async_io_mock::UringReader;
+use crate::vector_storage::async_io_mock::{UringReader, UringWriter};
 use crate::vector_storage::quantized::quantized_vectors::QuantizedVectors;
 
-const HEADER_SIZE: usize = 4;
-const VECTORS_HEADER: &[u8; HEADER_SIZE] = b"data";
-const DELETED_HEADER: &[u8; HEADER_SIZE] = b"drop";
+/// Format version written to new vectors-file headers by this build.
+/// Bumped whenever the header layout changes, so `open` can refuse to read a
+/// file written by an incompatible version instead of misinterpreting its
+/// fields.
+const HEADER_FORMAT_VERSION: u8 = 1;
+
+/// Offset of the single-byte header format version within the vectors file
+/// header.
+const VERSION_OFFSET: usize = 4;
+/// Offset of the little-endian `u32` vector dimensionality within the
+/// vectors file header.
+const DIM_OFFSET: usize = 8;
+/// Offset of the little-endian `u64` used-vector count within the vectors
+/// file header.
+const NUM_VECTORS_OFFSET: usize = 12;
+/// Offset of the little-endian `u64` xxh3 checksum - taken over every
+/// preceding header field - within the vectors file header. Catches a
+/// truncated or bit-rotted file at `open` time instead of letting a wrong
+/// `num_vectors` silently fall out of `(len - HEADER_SIZE) / dim / size_of`
+/// and produce out-of-bounds reads.
+const CHECKSUM_OFFSET: usize = 20;
+const HEADER_SIZE: usize = CHECKSUM_OFFSET + size_of::<u64>();
+const VECTORS_HEADER: &[u8; 4] = b"data";
+const DELETED_HEADER: &[u8; 4] = b"drop";
+
+/// Number of vector slots a freshly created vectors file is pre-allocated
+/// for, so a small segment doesn't pay for a larger file than it needs.
+/// `push_vector` doubles past this once it's reached.
+const DEFAULT_INITIAL_CAPACITY: usize = 32;
+
+/// Number of newly deleted vectors accumulated before their byte ranges are
+/// coalesced and punched out of the vectors file in one batch of
+/// `fallocate` calls - punching a hole per single deleted vector would be a
+/// syscall per delete, which is wasteful for a segment churning through
+/// many deletes in a row.
+const RECLAIM_BATCH_THRESHOLD: usize = 64;
 
 /// Mem-mapped file
 pub struct MmapVectors {
     pub dim: usize,
+    /// Number of vector slots actually in use. Distinct from `capacity`:
+    /// `data_offset` validates against this, not `capacity`.
     pub num_vectors: usize,
+    /// Number of vector slots the file currently has room for. Always a
+    /// power of two. `push_vector`/`reserve` grow the file (and double this)
+    /// once `num_vectors` would exceed it, so callers can stream vectors in
+    /// without knowing the final count up front.
+    capacity: usize,
+    vectors_path: PathBuf,
+    deleted_path: PathBuf,
+    with_async_io: bool,
     /// Memory mapped file for vector data
     ///
-    /// Has an exact size to fit a header and `num_vectors` of vectors.
+    /// Has an exact size to fit a header and `capacity` vectors.
     mmap: Arc<Mmap>,
     /// Context for io_uring-base async IO
     #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
     uring_reader: Mutex<Option<UringReader>>,
+    /// Context for io_uring-based async writes, mirroring `uring_reader`.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    uring_writer: Mutex<Option<UringWriter>>,
     /// Memory mapped deletion flags
     deleted: MmapBitSlice,
     /// Current number of deleted vectors.
     pub deleted_count: usize,
+    /// Whether deleted vector ranges are returned to the filesystem via
+    /// `fallocate(FALLOC_FL_PUNCH_HOLE)` (Linux only; a no-op elsewhere).
+    reclaim_deleted: bool,
+    /// Write-opened handle to the vectors file, kept open like
+    /// `uring_reader` so `fallocate` doesn't need to reopen the file on
+    /// every delete. Only present when `reclaim_deleted` is set.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    reclaim_file: Option<File>,
+    /// Byte offsets of deleted vectors queued for hole-punching once this
+    /// reaches `RECLAIM_BATCH_THRESHOLD`.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pending_reclaim: Vec<usize>,
+    /// Total bytes punched out of the vectors file so far, for observability.
+    reclaimed_bytes: usize,
     pub quantized_vectors: Option<QuantizedVectors>,
 }
 
@@ -50,15 +112,29 @@ impl MmapVectors {
         deleted_path: &Path,
         dim: usize,
         with_async_io: bool,
+        reclaim_deleted: bool,
     ) -> OperationResult<Self> {
-        // Allocate/open vectors mmap
-        ensure_mmap_file_size(vectors_path, VECTORS_HEADER, None)
+        let record_len = dim * size_of::<VectorElementType>();
+
+        // Allocate/open vectors mmap. A freshly created file is sized for
+        // `DEFAULT_INITIAL_CAPACITY` vectors up front; `ensure_mmap_file_size`
+        // zero-fills the body, and a full versioned header (magic, version,
+        // `dim`, `num_vectors`, checksum) is written right after, correctly
+        // recording a new file as holding 0 vectors.
+        let is_new_file = !vectors_path.exists();
+        let initial_size = HEADER_SIZE as u64 + (DEFAULT_INITIAL_CAPACITY * record_len) as u64;
+        ensure_mmap_file_size(vectors_path, VECTORS_HEADER, Some(initial_size))
             .describe("Create mmap data file")?;
+        if is_new_file {
+            write_num_vectors(vectors_path, dim, 0)?;
+        }
         let mmap = mmap_ops::open_read_mmap(vectors_path).describe("Open mmap for reading")?;
-        let num_vectors = (mmap.len() - HEADER_SIZE) / dim / size_of::<VectorElementType>();
+        let num_vectors = validate_vectors_header(&mmap, dim)?;
+        let capacity = (mmap.len() - HEADER_SIZE) / record_len;
 
-        // Allocate/open deleted mmap
-        let deleted_mmap_size = deleted_mmap_size(num_vectors);
+        // Allocate/open deleted mmap, sized for `capacity` (not just
+        // `num_vectors`) so it never needs resizing on the very next push.
+        let deleted_mmap_size = deleted_mmap_size(capacity);
         ensure_mmap_file_size(deleted_path, DELETED_HEADER, Some(deleted_mmap_size as u64))
             .describe("Create mmap deleted file")?;
         let deleted_mmap =
@@ -77,8 +153,20 @@ impl MmapVectors {
         let uring_reader = if with_async_io {
             // Keep file handle open for async IO
             let vectors_file = File::open(vectors_path)?;
-            let raw_size = dim * size_of::<VectorElementType>();
-            Some(UringReader::new(vectors_file, raw_size, HEADER_SIZE)?)
+            Some(UringReader::new(vectors_file, record_len, HEADER_SIZE)?)
+        } else {
+            None
+        };
+
+        let uring_writer = if with_async_io {
+            let vectors_file = OpenOptions::new().write(true).open(vectors_path)?;
+            Some(UringWriter::new(vectors_file, record_len, HEADER_SIZE)?)
+        } else {
+            None
+        };
+
+        let reclaim_file = if reclaim_deleted {
+            Some(OpenOptions::new().write(true).open(vectors_path)?)
         } else {
             None
         };
@@ -86,14 +174,107 @@ impl MmapVectors {
         Ok(MmapVectors {
             dim,
             num_vectors,
+            capacity,
+            vectors_path: vectors_path.to_path_buf(),
+            deleted_path: deleted_path.to_path_buf(),
+            with_async_io,
             mmap: mmap.into(),
             uring_reader: Mutex::new(uring_reader),
+            uring_writer: Mutex::new(uring_writer),
             deleted,
             deleted_count,
+            reclaim_deleted,
+            reclaim_file,
+            pending_reclaim: Vec::new(),
+            reclaimed_bytes: 0,
             quantized_vectors: None,
         })
     }
 
+    /// Appends `vector` as a new point, growing and remapping the file first
+    /// (doubling `capacity`) if there's no free slot left, and returns the
+    /// offset it was written at.
+    pub fn push_vector(
+        &mut self,
+        vector: &[VectorElementType],
+    ) -> OperationResult<PointOffsetType> {
+        if self.num_vectors == self.capacity {
+            self.grow_to((self.capacity + 1).next_power_of_two())?;
+        }
+        let offset = self.num_vectors as PointOffsetType;
+        self.write_vector_at(offset, vector)?;
+        self.num_vectors += 1;
+        write_num_vectors(&self.vectors_path, self.dim, self.num_vectors)?;
+        Ok(offset)
+    }
+
+    /// Extends the file exactly once to fit `num_vectors + additional`
+    /// slots (rounded up to the next power of two), so a bulk upsert that
+    /// knows its batch size up front doesn't pay for a remap per
+    /// `push_vector` call.
+    pub fn reserve(&mut self, additional: usize) -> OperationResult<()> {
+        let required = self.num_vectors + additional;
+        if required > self.capacity {
+            let new_capacity = required.next_power_of_two().max(DEFAULT_INITIAL_CAPACITY);
+            self.grow_to(new_capacity)?;
+        }
+        Ok(())
+    }
+
+    /// Grows the vectors file (and the deleted bitslice, in lockstep, via
+    /// the same `deleted_mmap_size` sizing `open` uses) to `new_capacity`
+    /// slots, then remaps both.
+    fn grow_to(&mut self, new_capacity: usize) -> OperationResult<()> {
+        let record_len = self.raw_size();
+        let new_len = HEADER_SIZE as u64 + (new_capacity * record_len) as u64;
+        {
+            let file = OpenOptions::new().write(true).open(&self.vectors_path)?;
+            file.set_len(new_len)?;
+        }
+        let mmap =
+            mmap_ops::open_read_mmap(&self.vectors_path).describe("Open mmap for reading")?;
+        self.mmap = Arc::new(mmap);
+
+        let deleted_mmap_size = deleted_mmap_size(new_capacity);
+        ensure_mmap_file_size(
+            &self.deleted_path,
+            DELETED_HEADER,
+            Some(deleted_mmap_size as u64),
+        )
+        .describe("Grow mmap deleted file")?;
+        let deleted_mmap = mmap_ops::open_write_mmap(&self.deleted_path)
+            .describe("Open mmap deleted for writing")?;
+        self.deleted = MmapBitSlice::try_from(deleted_mmap, deleted_mmap_data_start())?;
+
+        if self.with_async_io {
+            let vectors_file = File::open(&self.vectors_path)?;
+            *self.uring_reader.lock() =
+                Some(UringReader::new(vectors_file, record_len, HEADER_SIZE)?);
+
+            let vectors_file = OpenOptions::new().write(true).open(&self.vectors_path)?;
+            *self.uring_writer.lock() =
+                Some(UringWriter::new(vectors_file, record_len, HEADER_SIZE)?);
+        }
+
+        self.capacity = new_capacity;
+        Ok(())
+    }
+
+    fn write_vector_at(
+        &self,
+        key: PointOffsetType,
+        vector: &[VectorElementType],
+    ) -> OperationResult<()> {
+        let offset = HEADER_SIZE + (key as usize) * self.raw_size();
+        let bytes: &[u8] = unsafe {
+            std::slice::from_raw_parts(vector.as_ptr() as *const u8, mem::size_of_val(vector))
+        };
+        let mut file = OpenOptions::new().write(true).open(&self.vectors_path)?;
+        file.seek(SeekFrom::Start(offset as u64))?;
+        file.write_all(bytes)?;
+        Ok(())
+    }
+
     pub fn has_async_reader(&self) -> bool {
         self.uring_reader.lock().is_some()
     }
@@ -186,10 +367,89 @@ impl MmapVectors {
         let is_deleted = !self.deleted.replace(key as usize, true);
         if is_deleted {
             self.deleted_count += 1;
+            if self.reclaim_deleted {
+                // `data_offset` is guaranteed `Some` here: `key < num_vectors`
+                // was just established by the early return above.
+                let offset = self.data_offset(key).unwrap();
+                self.pending_reclaim.push(offset);
+                if self.pending_reclaim.len() >= RECLAIM_BATCH_THRESHOLD {
+                    self.flush_reclaim();
+                }
+            }
         }
         is_deleted
     }
 
+    /// Total bytes punched out of the vectors file via hole punching so far.
+    /// Always `0` when `reclaim_deleted` wasn't enabled at `open` time.
+    pub fn reclaimed_bytes(&self) -> usize {
+        self.reclaimed_bytes
+    }
+
+    /// Coalesces every pending deleted-vector offset into contiguous ranges
+    /// and punches each out of the vectors file with one `fallocate` call
+    /// per range, so a run of adjacent deletes costs a handful of syscalls
+    /// instead of one per vector. A no-op on non-Linux, where hole punching
+    /// isn't available and deleted vector bytes simply stay resident until
+    /// the segment is rebuilt.
+    #[cfg(target_os = "linux")]
+    fn flush_reclaim(&mut self) {
+        if self.pending_reclaim.is_empty() {
+            return;
+        }
+        let record_len = self.raw_size();
+        let mut offsets = mem::take(&mut self.pending_reclaim);
+        offsets.sort_unstable();
+
+        let Some(file) = self.reclaim_file.as_ref() else {
+            return;
+        };
+        let fd = file.as_raw_fd();
+
+        let mut range_start = offsets[0];
+        let mut range_len = record_len;
+        for &offset in &offsets[1..] {
+            if offset == range_start + range_len {
+                range_len += record_len;
+            } else {
+                Self::punch_hole(fd, range_start, range_len, &mut self.reclaimed_bytes);
+                range_start = offset;
+                range_len = record_len;
+            }
+        }
+        Self::punch_hole(fd, range_start, range_len, &mut self.reclaimed_bytes);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn punch_hole(
+        fd: std::os::unix::io::RawFd,
+        offset: usize,
+        len: usize,
+        reclaimed_bytes: &mut usize,
+    ) {
+        let ret = unsafe {
+            libc::fallocate(
+                fd,
+                libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+                offset as libc::off_t,
+                len as libc::off_t,
+            )
+        };
+        if ret == 0 {
+            *reclaimed_bytes += len;
+        } else {
+            log::error!(
+                "fallocate(FALLOC_FL_PUNCH_HOLE) failed for {len} bytes at offset {offset}: {}",
+                std::io::Error::last_os_error(),
+            );
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn flush_reclaim(&mut self) {
+        self.pending_reclaim.clear();
+    }
+
     pub fn is_deleted_vector(&self, key: PointOffsetType) -> bool {
         self.deleted[key as usize]
     }
@@ -250,20 +510,149 @@ impl MmapVectors {
             self.process_points_simple(points, callback)
         }
     }
+
+    #[cfg(target_os = "linux")]
+    fn write_points_uring<'a>(
+        &self,
+        points: impl Iterator<Item = (PointOffsetType, &'a [VectorElementType])>,
+    ) -> OperationResult<()> {
+        self.uring_writer
+            .lock()
+            .as_mut()
+            .expect("io_uring writer should be initialized")
+            .write_stream(points)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn write_points_simple<'a>(
+        &self,
+        points: impl Iterator<Item = (PointOffsetType, &'a [VectorElementType])>,
+    ) -> OperationResult<()> {
+        for (key, vector) in points {
+            self.write_vector_at(key, vector)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a batch of `(point, vector)` pairs and calls the callback for
+    /// each vector. Tries to utilize asynchronous IO if possible, submitting
+    /// the whole batch through a single io_uring wait on Linux; falls back
+    /// to a synchronous write per vector otherwise. Mirrors
+    /// [`Self::read_vectors_async`] on the write side, for bulk upsert and
+    /// optimizer flows that would otherwise pay for one `pwrite` per point.
+    pub fn write_vectors_async<'a>(
+        &self,
+        points: impl Iterator<Item = (PointOffsetType, &'a [VectorElementType])>,
+    ) -> OperationResult<()> {
+        #[cfg(target_os = "linux")]
+        {
+            self.write_points_uring(points)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            self.write_points_simple(points)
+        }
+    }
+}
+
+/// Reads the used-vector count stored at `NUM_VECTORS_OFFSET` in the
+/// vectors file header, without validating the rest of the header. Only
+/// safe to call after [`validate_vectors_header`] has already accepted the
+/// file this mmap belongs to.
+fn read_num_vectors(mmap: &Mmap) -> usize {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&mmap[NUM_VECTORS_OFFSET..NUM_VECTORS_OFFSET + 8]);
+    u64::from_le_bytes(bytes) as usize
+}
+
+/// Builds a full vectors-file header: magic, format version, `dim`,
+/// `num_vectors`, and an xxh3 checksum over the preceding fields.
+fn build_vectors_header(dim: usize, num_vectors: usize) -> [u8; HEADER_SIZE] {
+    let mut header = [0u8; HEADER_SIZE];
+    header[..4].copy_from_slice(VECTORS_HEADER);
+    header[VERSION_OFFSET] = HEADER_FORMAT_VERSION;
+    header[DIM_OFFSET..DIM_OFFSET + 4].copy_from_slice(&(dim as u32).to_le_bytes());
+    header[NUM_VECTORS_OFFSET..NUM_VECTORS_OFFSET + 8]
+        .copy_from_slice(&(num_vectors as u64).to_le_bytes());
+    let checksum = xxhash_rust::xxh3::xxh3_64(&header[..CHECKSUM_OFFSET]);
+    header[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 8].copy_from_slice(&checksum.to_le_bytes());
+    header
+}
+
+/// Writes `num_vectors` to the vectors file header and recomputes the
+/// header checksum over the updated fields, so a subsequent `open` both
+/// knows how many of its (possibly larger) `capacity` slots are actually in
+/// use and can detect a torn write via the checksum.
+fn write_num_vectors(path: &Path, dim: usize, num_vectors: usize) -> OperationResult<()> {
+    let header = build_vectors_header(dim, num_vectors);
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&header)?;
+    Ok(())
 }
 
-/// Ensure the given mmap file exists and is the given size
+/// Validates the header of a vectors file already mapped at `mmap`: magic,
+/// format version, the caller's expected `dim`, and the header checksum.
+/// Fails fast with a descriptive [`OperationError`] on any mismatch - a
+/// truncated or bit-rotted file would otherwise silently yield a wrong
+/// `num_vectors` and risk an out-of-bounds transmute in `get_vector`.
+/// Returns the recorded `num_vectors` on success.
+fn validate_vectors_header(mmap: &Mmap, expected_dim: usize) -> OperationResult<usize> {
+    if mmap.len() < HEADER_SIZE || mmap[..4] != *VECTORS_HEADER {
+        return Err(OperationError::service_error(
+            "vectors file header is missing or has the wrong magic - file is truncated or corrupted".to_string(),
+        ));
+    }
+
+    let version = mmap[VERSION_OFFSET];
+    if version != HEADER_FORMAT_VERSION {
+        return Err(OperationError::service_error(format!(
+            "vectors file header format version {version} is not supported by this build, \
+             which only reads version {HEADER_FORMAT_VERSION}"
+        )));
+    }
+
+    let mut dim_bytes = [0u8; 4];
+    dim_bytes.copy_from_slice(&mmap[DIM_OFFSET..DIM_OFFSET + 4]);
+    let stored_dim = u32::from_le_bytes(dim_bytes) as usize;
+    if stored_dim != expected_dim {
+        return Err(OperationError::service_error(format!(
+            "vectors file was created with dim {stored_dim}, but was opened with dim {expected_dim}"
+        )));
+    }
+
+    let mut checksum_bytes = [0u8; 8];
+    checksum_bytes.copy_from_slice(&mmap[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 8]);
+    let stored_checksum = u64::from_le_bytes(checksum_bytes);
+    let actual_checksum = xxhash_rust::xxh3::xxh3_64(&mmap[..CHECKSUM_OFFSET]);
+    if stored_checksum != actual_checksum {
+        return Err(OperationError::service_error(
+            "vectors file header checksum mismatch - file is truncated or corrupted".to_string(),
+        ));
+    }
+
+    Ok(read_num_vectors(mmap))
+}
+
+/// Ensure the given mmap file exists and is at least the given size
 ///
 /// # Arguments
 /// * `path`: path of the file.
 /// * `header`: header to set when the file is newly created.
-/// * `size`: set the file size in bytes, filled with zeroes.
+/// * `size`: ensure the file is at least this many bytes, filled with
+///   zeroes. Never shrinks an existing file - a caller that reopens a file
+///   `push_vector`/`reserve` already grew past this size (e.g. `open`'s own
+///   `DEFAULT_INITIAL_CAPACITY`-sized `size`) must not have its data
+///   truncated back down.
 fn ensure_mmap_file_size(path: &Path, header: &[u8], size: Option<u64>) -> OperationResult<()> {
-    // If it exists, only set the length
+    // If it exists, only grow the length, never shrink it
     if path.exists() {
         if let Some(size) = size {
             let file = OpenOptions::new().write(true).open(path)?;
-            file.set_len(size)?;
+            if file.metadata()?.len() < size {
+                file.set_len(size)?;
+            }
         }
         return Ok(());
     }
@@ -296,3 +685,209 @@ fn deleted_mmap_size(num: usize) -> usize {
     let data_size = num_usizes * unit_size;
     deleted_mmap_data_start() + data_size
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::Builder;
+
+    use super::*;
+
+    #[test]
+    fn test_push_vector_grows_past_initial_capacity() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+        let dim = 4;
+
+        let mut mmap_vectors =
+            MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+
+        let total = DEFAULT_INITIAL_CAPACITY * 2 + 1;
+        let vectors: Vec<Vec<VectorElementType>> = (0..total)
+            .map(|i| vec![i as VectorElementType; dim])
+            .collect();
+
+        for vector in &vectors {
+            mmap_vectors.push_vector(vector).unwrap();
+        }
+
+        assert_eq!(mmap_vectors.num_vectors, total);
+        for (i, vector) in vectors.iter().enumerate() {
+            assert_eq!(
+                mmap_vectors.get_vector(i as PointOffsetType),
+                vector.as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_vector_persists_across_reopen() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+        let dim = 4;
+
+        {
+            let mut mmap_vectors =
+                MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+            mmap_vectors.push_vector(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+            mmap_vectors.push_vector(&[5.0, 6.0, 7.0, 8.0]).unwrap();
+        }
+
+        let mmap_vectors =
+            MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+        assert_eq!(mmap_vectors.num_vectors, 2);
+        assert_eq!(mmap_vectors.get_vector(0), &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(mmap_vectors.get_vector(1), &[5.0, 6.0, 7.0, 8.0]);
+    }
+
+    #[test]
+    fn test_reopen_after_growth_does_not_truncate_data() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+        let dim = 4;
+
+        let total = DEFAULT_INITIAL_CAPACITY * 2 + 1;
+        {
+            let mut mmap_vectors =
+                MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+            for i in 0..total {
+                mmap_vectors
+                    .push_vector(&vec![i as VectorElementType; dim])
+                    .unwrap();
+            }
+        }
+
+        // Reopening used to re-apply `open`'s `DEFAULT_INITIAL_CAPACITY`-sized
+        // initial file length unconditionally, truncating a file that had
+        // since grown past it.
+        let mmap_vectors =
+            MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+        assert_eq!(mmap_vectors.num_vectors, total);
+        for i in 0..total {
+            assert_eq!(
+                mmap_vectors.get_vector(i as PointOffsetType),
+                vec![i as VectorElementType; dim].as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn test_reserve_grows_exactly_once() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+        let dim = 4;
+
+        let mut mmap_vectors =
+            MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+        mmap_vectors.reserve(DEFAULT_INITIAL_CAPACITY * 3).unwrap();
+        let capacity_after_reserve = mmap_vectors.capacity;
+        assert!(capacity_after_reserve >= DEFAULT_INITIAL_CAPACITY * 3);
+
+        for i in 0..DEFAULT_INITIAL_CAPACITY * 3 {
+            mmap_vectors
+                .push_vector(&vec![i as VectorElementType; dim])
+                .unwrap();
+        }
+        // `reserve` should have grown the file enough that none of the
+        // above pushes needed to grow it again.
+        assert_eq!(mmap_vectors.capacity, capacity_after_reserve);
+    }
+
+    #[test]
+    fn test_reclaimed_bytes_stays_zero_without_reclaim_deleted() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+        let dim = 4;
+
+        let mut mmap_vectors =
+            MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+        for i in 0..RECLAIM_BATCH_THRESHOLD * 2 {
+            mmap_vectors
+                .push_vector(&vec![i as VectorElementType; dim])
+                .unwrap();
+        }
+        for key in 0..RECLAIM_BATCH_THRESHOLD * 2 {
+            mmap_vectors.delete(key as PointOffsetType);
+        }
+
+        assert_eq!(mmap_vectors.reclaimed_bytes(), 0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_delete_punches_holes_once_batch_threshold_is_crossed() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+        let dim = 4;
+
+        let mut mmap_vectors =
+            MmapVectors::open(&vectors_path, &deleted_path, dim, false, true).unwrap();
+        for i in 0..RECLAIM_BATCH_THRESHOLD * 2 {
+            mmap_vectors
+                .push_vector(&vec![i as VectorElementType; dim])
+                .unwrap();
+        }
+
+        assert_eq!(mmap_vectors.reclaimed_bytes(), 0);
+
+        // Deleting adjacent keys one short of the threshold shouldn't have
+        // flushed yet.
+        for key in 0..RECLAIM_BATCH_THRESHOLD - 1 {
+            mmap_vectors.delete(key as PointOffsetType);
+        }
+        assert_eq!(mmap_vectors.reclaimed_bytes(), 0);
+
+        // Crossing the threshold flushes the whole coalesced run in one
+        // punched range.
+        mmap_vectors.delete((RECLAIM_BATCH_THRESHOLD - 1) as PointOffsetType);
+        assert_eq!(
+            mmap_vectors.reclaimed_bytes(),
+            RECLAIM_BATCH_THRESHOLD * mmap_vectors.raw_size()
+        );
+    }
+
+    #[test]
+    fn test_open_rejects_mismatched_dim() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+
+        {
+            let mut mmap_vectors =
+                MmapVectors::open(&vectors_path, &deleted_path, 4, false, false).unwrap();
+            mmap_vectors.push_vector(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+        }
+
+        let err = MmapVectors::open(&vectors_path, &deleted_path, 8, false, false).unwrap_err();
+        assert!(err.to_string().contains("dim"));
+    }
+
+    #[test]
+    fn test_open_rejects_corrupted_header_checksum() {
+        let dir = Builder::new().prefix("mmap_vectors").tempdir().unwrap();
+        let vectors_path = dir.path().join("vectors.dat");
+        let deleted_path = dir.path().join("deleted.dat");
+        let dim = 4;
+
+        {
+            let mut mmap_vectors =
+                MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap();
+            mmap_vectors.push_vector(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+        }
+
+        // Flip a byte in the `num_vectors` field without touching the
+        // checksum, simulating a torn write.
+        let mut file = OpenOptions::new().write(true).open(&vectors_path).unwrap();
+        file.seek(SeekFrom::Start(NUM_VECTORS_OFFSET as u64))
+            .unwrap();
+        file.write_all(&[0xff]).unwrap();
+
+        let err = MmapVectors::open(&vectors_path, &deleted_path, dim, false, false).unwrap_err();
+        assert!(err.to_string().contains("checksum"));
+    }
+}
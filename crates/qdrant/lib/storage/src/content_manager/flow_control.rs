@@ -0,0 +1,173 @@
+//! Per-peer credit-based admission control for `CollectionMetaOperations`.
+//!
+//! `Dispatcher::submit_collection_meta_op` proposes every meta operation to
+//! consensus as soon as it arrives, with no limit on how many expensive ops
+//! (`CreateCollection` being the worst offender - it also fans out into a
+//! batch of `initialize_replica` follow-ups) a single peer can queue up back
+//! to back. [`MetaOpFlowControl`] gives each peer a credit bucket that is
+//! debited by [`OperationCostTable::cost_for`]'s estimate of an operation's
+//! weight and recharges linearly with wall-clock time, so a storm of
+//! expensive operations from one peer is throttled while cheap ones (an
+//! alias rename) keep flowing - the same token-bucket shape used for
+//! request-credit flow control in lightweight P2P protocols.
+//!
+//! [`Dispatcher`](super::super::dispatcher::Dispatcher) only ever submits
+//! meta operations as itself, so [`MetaOpFlowControl::try_admit`] is keyed
+//! on `TableOfContent::this_peer_id` - there's no other notion of "the peer
+//! this operation is on behalf of" surfaced on this call path.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use collection::shards::shard::PeerId;
+use parking_lot::Mutex;
+
+use crate::content_manager::collection_meta_ops::CollectionMetaOperations;
+
+/// Credit cost charged per `CollectionMetaOperations` variant. Costs are in
+/// the same unit as [`MetaOpFlowControl`]'s `max`/`recharge_per_sec`, so e.g.
+/// `recharge_per_sec == create_collection` means a bucket starting full can
+/// admit one `CreateCollection` per second indefinitely.
+#[derive(Debug, Clone)]
+pub struct OperationCostTable {
+    pub create_collection: f64,
+    pub update_collection: f64,
+    pub delete_collection: f64,
+    pub change_aliases: f64,
+}
+
+impl Default for OperationCostTable {
+    fn default() -> Self {
+        Self {
+            create_collection: 10.0,
+            update_collection: 2.0,
+            delete_collection: 5.0,
+            change_aliases: 1.0,
+        }
+    }
+}
+
+impl OperationCostTable {
+    pub fn cost_for(&self, operation: &CollectionMetaOperations) -> f64 {
+        match operation {
+            CollectionMetaOperations::CreateCollection(_) => self.create_collection,
+            CollectionMetaOperations::UpdateCollection(_) => self.update_collection,
+            CollectionMetaOperations::DeleteCollection(_) => self.delete_collection,
+            CollectionMetaOperations::ChangeAliases(_) => self.change_aliases,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PeerCreditBucket {
+    current: f64,
+    last_recharge: Instant,
+}
+
+impl PeerCreditBucket {
+    fn full(max: f64) -> Self {
+        Self {
+            current: max,
+            last_recharge: Instant::now(),
+        }
+    }
+
+    /// Adds credits for the time elapsed since the last recharge, capped at
+    /// `max`, and resets the recharge clock.
+    fn recharge(&mut self, max: f64, recharge_per_sec: f64) {
+        let elapsed_secs = self.last_recharge.elapsed().as_secs_f64();
+        self.current = (self.current + elapsed_secs * recharge_per_sec).min(max);
+        self.last_recharge = Instant::now();
+    }
+}
+
+/// A rejection from [`MetaOpFlowControl::try_admit`]: the peer's bucket
+/// doesn't yet hold enough credits, and won't until `retry_after` has
+/// elapsed (assuming no other operation drains it further in the meantime).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InsufficientCredits {
+    pub retry_after: Duration,
+}
+
+/// Per-peer token-bucket admission control for `CollectionMetaOperations`.
+/// Every peer shares the same `max`/`recharge_per_sec`; buckets are created
+/// full on first use so a peer that has never submitted an operation isn't
+/// penalized for a cold start.
+pub struct MetaOpFlowControl {
+    max: f64,
+    recharge_per_sec: f64,
+    cost_table: OperationCostTable,
+    buckets: Mutex<HashMap<PeerId, PeerCreditBucket>>,
+}
+
+impl MetaOpFlowControl {
+    pub fn new(max: f64, recharge_per_sec: f64, cost_table: OperationCostTable) -> Self {
+        Self {
+            max,
+            recharge_per_sec,
+            cost_table,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Recharges `peer_id`'s bucket for elapsed time, then debits the cost
+    /// of `operation` if enough credits are available. Returns
+    /// [`InsufficientCredits`] with a retry-after hint instead of debiting
+    /// anything if they aren't.
+    pub fn try_admit(
+        &self,
+        peer_id: PeerId,
+        operation: &CollectionMetaOperations,
+    ) -> Result<(), InsufficientCredits> {
+        let cost = self.cost_table.cost_for(operation);
+
+        let mut buckets = self.buckets.lock();
+        let bucket = buckets
+            .entry(peer_id)
+            .or_insert_with(|| PeerCreditBucket::full(self.max));
+        bucket.recharge(self.max, self.recharge_per_sec);
+
+        if bucket.current >= cost {
+            bucket.current -= cost;
+            Ok(())
+        } else {
+            let shortfall = cost - bucket.current;
+            let retry_after = Duration::from_secs_f64((shortfall / self.recharge_per_sec).max(0.0));
+            Err(InsufficientCredits { retry_after })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_manager::collection_meta_ops::{
+        ChangeAliasesOperation, CollectionMetaOperations,
+    };
+
+    fn cheap_op() -> CollectionMetaOperations {
+        CollectionMetaOperations::ChangeAliases(ChangeAliasesOperation { actions: vec![] })
+    }
+
+    #[test]
+    fn admits_while_credits_remain() {
+        let control = MetaOpFlowControl::new(5.0, 1.0, OperationCostTable::default());
+        assert!(control.try_admit(1, &cheap_op()).is_ok());
+    }
+
+    #[test]
+    fn rejects_once_bucket_is_drained() {
+        let control = MetaOpFlowControl::new(1.0, 1.0, OperationCostTable::default());
+        assert!(control.try_admit(1, &cheap_op()).is_ok());
+        let rejection = control.try_admit(1, &cheap_op()).unwrap_err();
+        assert!(rejection.retry_after > Duration::ZERO);
+    }
+
+    #[test]
+    fn peers_have_independent_buckets() {
+        let control = MetaOpFlowControl::new(1.0, 1.0, OperationCostTable::default());
+        assert!(control.try_admit(1, &cheap_op()).is_ok());
+        // Peer 1's bucket is drained, but peer 2 has never submitted before.
+        assert!(control.try_admit(2, &cheap_op()).is_ok());
+    }
+}
@@ -0,0 +1,257 @@
+//! Health-aware replica selection for routing reads.
+//!
+//! `Dispatcher` derefs to `TableOfContent` but has no opinion on which
+//! replica of a shard a read should go to - callers either pick one
+//! arbitrarily or always hit the same fixed replica. [`ReplicaHealthMap`]
+//! tracks each `(ShardId, PeerId)`'s last-seen responsiveness, in-flight
+//! request count, and the consensus-applied index it last reported, and
+//! [`ReplicaHealthMap::select_replica`] uses that to prefer a responsive,
+//! caught-up, least-loaded candidate with automatic failover to the next one
+//! when the preferred replica turns out to be unhealthy.
+//!
+//! The map is updated from two sources: [`ReplicaHealthMap::record_heartbeat`]
+//! (meant to be driven by consensus heartbeats) and
+//! [`ReplicaHealthMap::record_request_outcome`]/[`ReplicaHealthMap::begin_request`]
+//! (driven by the outcome of reads actually sent to a replica). Nothing in
+//! this snapshot delivers consensus heartbeats to the storage crate - there's
+//! no heartbeat stream on `ConsensusStateRef` here to subscribe to - so
+//! wiring `record_heartbeat` to a live feed is left to whatever does expose
+//! one; `record_request_outcome`/`begin_request` are plain methods any read
+//! path can call today.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use collection::shards::shard::{PeerId, ShardId};
+use parking_lot::RwLock;
+
+/// A replica's last-known health, populated lazily: a `(ShardId, PeerId)`
+/// never observed reads as "unresponsive, unloaded, at index 0" rather than
+/// panicking or requiring pre-registration.
+#[derive(Debug, Clone)]
+pub struct ReplicaHealth {
+    pub last_responsive_at: Option<Instant>,
+    pub in_flight: usize,
+    pub applied_index: u64,
+}
+
+impl ReplicaHealth {
+    fn unknown() -> Self {
+        Self {
+            last_responsive_at: None,
+            in_flight: 0,
+            applied_index: 0,
+        }
+    }
+
+    fn is_responsive(&self, timeout: Duration, now: Instant) -> bool {
+        self.last_responsive_at
+            .is_some_and(|at| now.saturating_duration_since(at) <= timeout)
+    }
+}
+
+/// Concurrent cache of every `(ShardId, PeerId)`'s last-known health this
+/// node has observed.
+#[derive(Default)]
+pub struct ReplicaHealthMap {
+    health: RwLock<HashMap<(ShardId, PeerId), ReplicaHealth>>,
+}
+
+impl ReplicaHealthMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a consensus heartbeat from `peer_id` for `shard_id`,
+    /// reporting it responsive as of now and at `applied_index`.
+    pub fn record_heartbeat(&self, shard_id: ShardId, peer_id: PeerId, applied_index: u64) {
+        let mut health = self.health.write();
+        let entry = health
+            .entry((shard_id, peer_id))
+            .or_insert_with(ReplicaHealth::unknown);
+        entry.applied_index = applied_index;
+        entry.last_responsive_at = Some(Instant::now());
+    }
+
+    /// Records whether a request actually sent to `peer_id` succeeded,
+    /// independently of heartbeats - a replica can answer reads correctly
+    /// between heartbeats, or fail one despite a recent heartbeat.
+    pub fn record_request_outcome(&self, shard_id: ShardId, peer_id: PeerId, succeeded: bool) {
+        let mut health = self.health.write();
+        let entry = health
+            .entry((shard_id, peer_id))
+            .or_insert_with(ReplicaHealth::unknown);
+        if succeeded {
+            entry.last_responsive_at = Some(Instant::now());
+        }
+    }
+
+    /// Marks a request as in flight to `peer_id`, for load-aware selection.
+    /// The returned guard decrements the in-flight count on drop, so callers
+    /// don't need to remember to call a matching "end" method.
+    pub fn begin_request(&self, shard_id: ShardId, peer_id: PeerId) -> InFlightGuard<'_> {
+        let mut health = self.health.write();
+        let entry = health
+            .entry((shard_id, peer_id))
+            .or_insert_with(ReplicaHealth::unknown);
+        entry.in_flight += 1;
+        InFlightGuard {
+            map: self,
+            shard_id,
+            peer_id,
+        }
+    }
+
+    fn end_request(&self, shard_id: ShardId, peer_id: PeerId) {
+        let mut health = self.health.write();
+        if let Some(entry) = health.get_mut(&(shard_id, peer_id)) {
+            entry.in_flight = entry.in_flight.saturating_sub(1);
+        }
+    }
+
+    /// Picks the best candidate in `candidates` to route a read for
+    /// `shard_id` to: among replicas responsive within `responsive_timeout`,
+    /// prefers ones agreeing with the majority's latest applied index (so a
+    /// read doesn't land on a replica that's fallen behind), then the
+    /// least-loaded by `in_flight` count. Falls back to the least-loaded
+    /// candidate regardless of responsiveness if every candidate looks
+    /// unresponsive, so a read is still attempted somewhere rather than
+    /// refused outright. Returns `None` only if `candidates` is empty.
+    pub fn select_replica(
+        &self,
+        shard_id: ShardId,
+        candidates: &[PeerId],
+        responsive_timeout: Duration,
+    ) -> Option<PeerId> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let snapshot: Vec<(PeerId, ReplicaHealth)> = {
+            let health = self.health.read();
+            candidates
+                .iter()
+                .map(|peer_id| {
+                    let replica_health = health
+                        .get(&(shard_id, *peer_id))
+                        .cloned()
+                        .unwrap_or_else(ReplicaHealth::unknown);
+                    (*peer_id, replica_health)
+                })
+                .collect()
+        };
+
+        let responsive: Vec<&(PeerId, ReplicaHealth)> = snapshot
+            .iter()
+            .filter(|(_, health)| health.is_responsive(responsive_timeout, now))
+            .collect();
+        let pool: Vec<&(PeerId, ReplicaHealth)> = if responsive.is_empty() {
+            snapshot.iter().collect()
+        } else {
+            responsive
+        };
+
+        let majority_index = majority_applied_index(&pool);
+        let up_to_date: Vec<&&(PeerId, ReplicaHealth)> = pool
+            .iter()
+            .filter(|(_, health)| health.applied_index == majority_index)
+            .collect();
+        let final_pool = if up_to_date.is_empty() {
+            pool.iter().collect::<Vec<_>>()
+        } else {
+            up_to_date
+        };
+
+        final_pool
+            .into_iter()
+            .min_by_key(|(_, health)| health.in_flight)
+            .map(|(peer_id, _)| *peer_id)
+    }
+}
+
+/// The applied index most candidates in `pool` agree on - the "up-to-date
+/// majority" a read should prefer. Ties broken arbitrarily (by whichever
+/// index `HashMap` iteration visits last), since any majority index is
+/// equally valid to prefer.
+fn majority_applied_index(pool: &[&(PeerId, ReplicaHealth)]) -> u64 {
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for (_, health) in pool {
+        *counts.entry(health.applied_index).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+/// RAII handle from [`ReplicaHealthMap::begin_request`]: decrements the
+/// replica's in-flight count when dropped, however the request ends.
+pub struct InFlightGuard<'a> {
+    map: &'a ReplicaHealthMap,
+    shard_id: ShardId,
+    peer_id: PeerId,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.map.end_request(self.shard_id, self.peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn prefers_responsive_and_up_to_date_replica() {
+        let health = ReplicaHealthMap::new();
+        health.record_heartbeat(0, 1, 10);
+        health.record_heartbeat(0, 2, 10);
+        health.record_heartbeat(0, 3, 10);
+        // Peer 2 has fallen behind.
+        health.record_heartbeat(0, 2, 5);
+
+        let picked = health.select_replica(0, &[1, 2, 3], TIMEOUT).unwrap();
+        assert_ne!(picked, 2);
+    }
+
+    #[test]
+    fn prefers_least_loaded_among_equally_up_to_date() {
+        let health = ReplicaHealthMap::new();
+        health.record_heartbeat(0, 1, 10);
+        health.record_heartbeat(0, 2, 10);
+        let _guard = health.begin_request(0, 1);
+
+        let picked = health.select_replica(0, &[1, 2], TIMEOUT).unwrap();
+        assert_eq!(picked, 2);
+    }
+
+    #[test]
+    fn in_flight_guard_decrements_on_drop() {
+        let health = ReplicaHealthMap::new();
+        {
+            let _guard = health.begin_request(0, 1);
+            assert_eq!(health.health.read().get(&(0, 1)).unwrap().in_flight, 1);
+        }
+        assert_eq!(health.health.read().get(&(0, 1)).unwrap().in_flight, 0);
+    }
+
+    #[test]
+    fn falls_back_to_any_candidate_when_all_unresponsive() {
+        let health = ReplicaHealthMap::new();
+        // Never seen either peer, so neither is responsive - selection must
+        // still return a candidate rather than giving up.
+        let picked = health.select_replica(0, &[1, 2], TIMEOUT);
+        assert!(picked.is_some());
+    }
+
+    #[test]
+    fn empty_candidates_returns_none() {
+        let health = ReplicaHealthMap::new();
+        assert!(health.select_replica(0, &[], TIMEOUT).is_none());
+    }
+}
@@ -0,0 +1,257 @@
+//! Persistent, asynchronous task store for internal point mutations.
+//!
+//! Every mutating `PointsInternalService` RPC (upsert, delete, set_payload,
+//! create_field_index, ...) used to apply synchronously and hand back a
+//! `PointsOperationResponse` inline, giving an operator no visibility into a
+//! slow field-index build and no way to cancel one short of killing the
+//! node. [`TaskStore`] gives each such mutation a monotonically increasing
+//! [`TaskUid`], tracked through `Enqueued -> Processing -> Succeeded |
+//! Failed` (see [`TaskState`]) and durably appended to a per-node task log
+//! so a restart doesn't lose in-flight task history. The `get_task`/
+//! `list_tasks`/`cancel_task` RPCs this is meant to back, and the
+//! queue-draining background worker that drives a task through the state
+//! machine, live on the internal gRPC surface that this snapshot's `api`
+//! crate doesn't vendor (no generated `points_internal_server` code is
+//! present to extend) - this module is the store those RPCs and worker
+//! would be built on.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{DateTime, Utc};
+use collection::shards::shard::ShardId;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::content_manager::errors::StorageError;
+
+pub type TaskUid = u64;
+
+/// A mutation's position in its `Enqueued -> Processing -> Succeeded |
+/// Failed` lifecycle. Terminal once `Succeeded` or `Failed`; no transition
+/// is valid out of a terminal state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TaskState {
+    Enqueued,
+    Processing,
+    Succeeded,
+    Failed { error: String },
+}
+
+impl TaskState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, TaskState::Succeeded | TaskState::Failed { .. })
+    }
+}
+
+/// One durable record of an enqueued mutation: its identity, the shard it
+/// targets, its current [`TaskState`], and the timestamps marking each
+/// transition it has gone through so far.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub task_uid: TaskUid,
+    pub collection_name: String,
+    pub shard_id: ShardId,
+    /// Human-readable operation name (e.g. `"upsert_points"`,
+    /// `"create_field_index"`), kept as a string rather than the full
+    /// operation payload so the task log stays small and doesn't need to
+    /// version alongside every operation type.
+    pub operation_kind: String,
+    pub state: TaskState,
+    pub enqueued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Durable, per-node store of [`TaskRecord`]s, backed by an append-only
+/// JSON-lines log at `log_path`: every state transition is appended as a
+/// new line rather than rewriting the file in place, and [`Self::open`]
+/// replays the log keeping only the last line seen for each `task_uid`, so
+/// a crash mid-write loses at most the in-flight transition rather than the
+/// whole log.
+pub struct TaskStore {
+    next_uid: AtomicU64,
+    tasks: RwLock<BTreeMap<TaskUid, TaskRecord>>,
+    log_path: PathBuf,
+}
+
+impl TaskStore {
+    /// Opens (creating if absent) the task log at `log_path` and replays it
+    /// to rebuild in-memory state, so a node restart doesn't lose the
+    /// history of tasks that were in flight when it went down.
+    pub fn open(log_path: &Path) -> Result<Self, StorageError> {
+        let mut tasks = BTreeMap::new();
+        let mut max_uid = 0;
+
+        if log_path.exists() {
+            let file = OpenOptions::new()
+                .read(true)
+                .open(log_path)
+                .map_err(|err| {
+                    StorageError::service_error(&format!(
+                        "failed to open task log at {log_path:?}: {err}"
+                    ))
+                })?;
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|err| {
+                    StorageError::service_error(&format!(
+                        "failed to read task log at {log_path:?}: {err}"
+                    ))
+                })?;
+                if line.is_empty() {
+                    continue;
+                }
+                let record: TaskRecord = serde_json::from_str(&line).map_err(|err| {
+                    StorageError::service_error(&format!(
+                        "malformed task log entry in {log_path:?}: {err}"
+                    ))
+                })?;
+                max_uid = max_uid.max(record.task_uid);
+                tasks.insert(record.task_uid, record);
+            }
+        }
+
+        Ok(Self {
+            next_uid: AtomicU64::new(max_uid + 1),
+            tasks: RwLock::new(tasks),
+            log_path: log_path.to_path_buf(),
+        })
+    }
+
+    /// Appends a new `Enqueued` record for `operation_kind` against
+    /// `shard_id` and returns its freshly allocated [`TaskUid`].
+    pub fn enqueue(
+        &self,
+        collection_name: String,
+        shard_id: ShardId,
+        operation_kind: String,
+    ) -> Result<TaskUid, StorageError> {
+        let task_uid = self.next_uid.fetch_add(1, Ordering::SeqCst);
+        let record = TaskRecord {
+            task_uid,
+            collection_name,
+            shard_id,
+            operation_kind,
+            state: TaskState::Enqueued,
+            enqueued_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+        };
+        self.persist(&record)?;
+        self.tasks.write().insert(task_uid, record);
+        Ok(task_uid)
+    }
+
+    /// Transitions `task_uid` from `Enqueued` to `Processing`, stamping
+    /// `started_at`. Called by the queue-draining worker right before it
+    /// begins applying the underlying mutation.
+    pub fn mark_processing(&self, task_uid: TaskUid) -> Result<(), StorageError> {
+        self.transition(task_uid, |record| {
+            record.state = TaskState::Processing;
+            record.started_at = Some(Utc::now());
+        })
+    }
+
+    /// Transitions `task_uid` to `Succeeded`, stamping `finished_at`.
+    pub fn mark_succeeded(&self, task_uid: TaskUid) -> Result<(), StorageError> {
+        self.transition(task_uid, |record| {
+            record.state = TaskState::Succeeded;
+            record.finished_at = Some(Utc::now());
+        })
+    }
+
+    /// Transitions `task_uid` to `Failed`, recording `error` and stamping
+    /// `finished_at`.
+    pub fn mark_failed(&self, task_uid: TaskUid, error: String) -> Result<(), StorageError> {
+        self.transition(task_uid, |record| {
+            record.state = TaskState::Failed { error };
+            record.finished_at = Some(Utc::now());
+        })
+    }
+
+    /// Cancels `task_uid` if it hasn't already reached a terminal state,
+    /// recording it as `Failed` with a `"cancelled"` error so a stuck
+    /// operation can be unstuck without killing the node. Returns `Ok(false)`
+    /// without error if the task was already terminal, since cancelling an
+    /// already-finished task isn't a failure on the caller's part.
+    pub fn cancel(&self, task_uid: TaskUid) -> Result<bool, StorageError> {
+        let already_terminal = self
+            .tasks
+            .read()
+            .get(&task_uid)
+            .map(|record| record.state.is_terminal())
+            .ok_or_else(|| StorageError::not_found(&format!("task {task_uid} does not exist")))?;
+        if already_terminal {
+            return Ok(false);
+        }
+        self.mark_failed(task_uid, "cancelled".to_string())?;
+        Ok(true)
+    }
+
+    pub fn get_task(&self, task_uid: TaskUid) -> Option<TaskRecord> {
+        self.tasks.read().get(&task_uid).cloned()
+    }
+
+    /// Lists every task, optionally filtered by `collection_name`,
+    /// `shard_id`, and whether it has reached a terminal state, in
+    /// ascending `task_uid` order.
+    pub fn list_tasks(
+        &self,
+        collection_name: Option<&str>,
+        shard_id: Option<ShardId>,
+        terminal_only: Option<bool>,
+    ) -> Vec<TaskRecord> {
+        self.tasks
+            .read()
+            .values()
+            .filter(|record| {
+                collection_name.map_or(true, |name| record.collection_name == name)
+                    && shard_id.map_or(true, |id| record.shard_id == id)
+                    && terminal_only.map_or(true, |terminal| record.state.is_terminal() == terminal)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn transition(
+        &self,
+        task_uid: TaskUid,
+        mutate: impl FnOnce(&mut TaskRecord),
+    ) -> Result<(), StorageError> {
+        let record = {
+            let mut tasks = self.tasks.write();
+            let record = tasks.get_mut(&task_uid).ok_or_else(|| {
+                StorageError::not_found(&format!("task {task_uid} does not exist"))
+            })?;
+            mutate(record);
+            record.clone()
+        };
+        self.persist(&record)
+    }
+
+    fn persist(&self, record: &TaskRecord) -> Result<(), StorageError> {
+        let line = serde_json::to_string(record).map_err(|err| {
+            StorageError::service_error(&format!("failed to encode task log entry: {err}"))
+        })?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)
+            .map_err(|err| {
+                StorageError::service_error(&format!(
+                    "failed to open task log at {:?}: {err}",
+                    self.log_path
+                ))
+            })?;
+        writeln!(file, "{line}").map_err(|err| {
+            StorageError::service_error(&format!(
+                "failed to append to task log at {:?}: {err}",
+                self.log_path
+            ))
+        })
+    }
+}
@@ -0,0 +1,199 @@
+//! Zone-aware (rack-aware) shard replica placement.
+//!
+//! `Dispatcher::submit_collection_meta_op`'s `CreateCollection` branch asks
+//! `TableOfContent::suggest_shard_distribution` for an even spread of shard
+//! replicas across peers, with no notion of which peers share a failure
+//! domain - losing one zone can silently take every copy of a shard down
+//! with it. [`plan_zone_aware_distribution`] is the placement function that
+//! belongs behind that call instead: for each shard, it hands out
+//! `replication_factor` replicas one at a time to the least-loaded peer
+//! whose zone isn't already used by that shard (randomly tie-broken among
+//! equally-loaded candidates, same as [`segment_holder`](crate)'s replica
+//! selection elsewhere in this codebase), falling back to "distinct peer
+//! only" placement - with a recorded warning - once a shard runs out of
+//! zone-distinct candidates.
+//!
+//! `TableOfContent` has no definition anywhere in this snapshot (no
+//! `table_of_content.rs`/`toc.rs` exists under `lib/storage`, despite
+//! `dispatcher.rs` calling `self.toc.suggest_shard_distribution(...)`), so
+//! this can't actually be wired into that call site here. This module is
+//! the placement algorithm that call site is meant to use once it exists,
+//! exposed standalone and unit-testable in the meantime, the same gap
+//! documented for `wal_watch`/`repair`/`layout` in `lib/collection`.
+
+use std::collections::{HashMap, HashSet};
+
+use collection::shards::shard::{PeerId, ShardId};
+use rand::seq::SliceRandom;
+
+pub type Zone = String;
+
+/// A peer as seen by the placement planner: its id, current replica load,
+/// and optional failure-domain label. Peers with no zone set can still be
+/// placed, just never preferred over a zone-distinct choice.
+#[derive(Debug, Clone)]
+pub struct PeerZone {
+    pub peer_id: PeerId,
+    pub zone: Option<Zone>,
+}
+
+/// Result of [`plan_zone_aware_distribution`]: the chosen replica sets, plus
+/// one warning per shard that couldn't get `replication_factor`
+/// zone-distinct peers and fell back to distinct-peer-only placement.
+#[derive(Debug, Clone, Default)]
+pub struct ZoneAwareDistribution {
+    pub shards: HashMap<ShardId, HashSet<PeerId>>,
+    pub zone_shortfall_warnings: Vec<String>,
+}
+
+/// Plans replica placement for `shard_count` shards, each replicated
+/// `replication_factor` times across `peers`, starting from each peer's
+/// `initial_load` (its current shard count, so a fresh cluster and a
+/// rebalance of an existing one both spread placements fairly).
+///
+/// For each shard, in ascending `shard_id` order, `replication_factor`
+/// replicas are chosen one at a time: among peers not yet holding this
+/// shard whose zone isn't already used by it, pick the least-loaded
+/// (ties broken randomly), and increment that peer's load. If a shard runs
+/// out of zone-distinct candidates before reaching `replication_factor`
+/// (fewer distinct zones than replicas), placement relaxes to "any peer not
+/// already holding this shard" for its remaining replicas and the shard's
+/// id is recorded in [`ZoneAwareDistribution::zone_shortfall_warnings`].
+pub fn plan_zone_aware_distribution(
+    shard_count: ShardId,
+    replication_factor: usize,
+    peers: &[PeerZone],
+    initial_load: &HashMap<PeerId, usize>,
+) -> ZoneAwareDistribution {
+    let mut load: HashMap<PeerId, usize> = peers
+        .iter()
+        .map(|peer| {
+            (
+                peer.peer_id,
+                initial_load.get(&peer.peer_id).copied().unwrap_or(0),
+            )
+        })
+        .collect();
+
+    let mut rng = rand::thread_rng();
+    let mut shards: HashMap<ShardId, HashSet<PeerId>> = HashMap::new();
+    let mut zone_shortfall_warnings = Vec::new();
+
+    for shard_id in 0..shard_count {
+        let mut chosen: HashSet<PeerId> = HashSet::new();
+        let mut used_zones: HashSet<Zone> = HashSet::new();
+        let mut relaxed = false;
+
+        for _ in 0..replication_factor.min(peers.len()) {
+            let zone_distinct_candidates: Vec<&PeerZone> = peers
+                .iter()
+                .filter(|peer| !chosen.contains(&peer.peer_id))
+                .filter(|peer| match &peer.zone {
+                    Some(zone) => !used_zones.contains(zone),
+                    None => true,
+                })
+                .collect();
+
+            let candidates = if zone_distinct_candidates.is_empty() {
+                relaxed = true;
+                peers
+                    .iter()
+                    .filter(|peer| !chosen.contains(&peer.peer_id))
+                    .collect::<Vec<_>>()
+            } else {
+                zone_distinct_candidates
+            };
+
+            let Some(&min_load) = candidates.iter().map(|peer| &load[&peer.peer_id]).min() else {
+                break;
+            };
+            let least_loaded: Vec<&PeerZone> = candidates
+                .into_iter()
+                .filter(|peer| load[&peer.peer_id] == min_load)
+                .collect();
+            let picked = *least_loaded.choose(&mut rng).expect("non-empty candidates");
+
+            chosen.insert(picked.peer_id);
+            if let Some(zone) = &picked.zone {
+                used_zones.insert(zone.clone());
+            }
+            *load.get_mut(&picked.peer_id).unwrap() += 1;
+        }
+
+        if relaxed {
+            zone_shortfall_warnings.push(format!(
+                "shard {shard_id}: fewer distinct zones than replicas ({replication_factor}); \
+                 fell back to distinct-peer-only placement"
+            ));
+        }
+        shards.insert(shard_id, chosen);
+    }
+
+    ZoneAwareDistribution {
+        shards,
+        zone_shortfall_warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(peer_id: PeerId, zone: &str) -> PeerZone {
+        PeerZone {
+            peer_id,
+            zone: Some(zone.to_owned()),
+        }
+    }
+
+    #[test]
+    fn spreads_replicas_across_distinct_zones() {
+        let peers = vec![
+            peer(1, "zone-a"),
+            peer(2, "zone-a"),
+            peer(3, "zone-b"),
+            peer(4, "zone-c"),
+        ];
+        let result = plan_zone_aware_distribution(4, 3, &peers, &HashMap::new());
+
+        assert!(result.zone_shortfall_warnings.is_empty());
+        for peer_ids in result.shards.values() {
+            assert_eq!(peer_ids.len(), 3);
+            // With only one zone-a/zone-b/zone-c peer usable per shard (at
+            // most one of {1, 2} since both are zone-a), the 3-replica
+            // shard must include peer 3 and peer 4.
+            assert!(peer_ids.contains(&3));
+            assert!(peer_ids.contains(&4));
+        }
+    }
+
+    #[test]
+    fn falls_back_to_distinct_peer_when_zones_are_scarce() {
+        // Only two distinct zones but replication factor 3: one replica per
+        // shard has no zone-distinct candidate left.
+        let peers = vec![peer(1, "zone-a"), peer(2, "zone-a"), peer(3, "zone-b")];
+        let result = plan_zone_aware_distribution(2, 3, &peers, &HashMap::new());
+
+        assert_eq!(result.zone_shortfall_warnings.len(), 2);
+        for peer_ids in result.shards.values() {
+            assert_eq!(peer_ids.len(), 3);
+        }
+    }
+
+    #[test]
+    fn balances_load_starting_from_initial_counts() {
+        let peers = vec![peer(1, "zone-a"), peer(2, "zone-b"), peer(3, "zone-c")];
+        let mut initial_load = HashMap::new();
+        initial_load.insert(1, 10);
+
+        let result = plan_zone_aware_distribution(3, 1, &peers, &initial_load);
+        // Peer 1 starts heavily loaded, so it should never be preferred
+        // over peers 2/3 while they remain less loaded.
+        let picked_peer_1_count = result
+            .shards
+            .values()
+            .filter(|peer_ids| peer_ids.contains(&1))
+            .count();
+        assert_eq!(picked_peer_1_count, 0);
+    }
+}
@@ -1,16 +1,143 @@
+use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
+use collection::shards::shard::{PeerId, ShardId};
+use rand::Rng;
+use tokio::sync::watch;
+
+use crate::content_manager::flow_control::{MetaOpFlowControl, OperationCostTable};
+use crate::content_manager::read_routing::ReplicaHealthMap;
 use crate::{
     ClusterStatus, CollectionMetaOperations, ConsensusOperations, ConsensusStateRef, StorageError,
     TableOfContent,
 };
 
+/// A single replica's progress toward becoming an active copy of its shard,
+/// as tracked by [`CollectionBootstrapProgress`]. The consensus awaiters
+/// `submit_collection_meta_op` registers only report success/failure for a
+/// whole batch of replicas at once (see
+/// [`ReplicaActivationRetryConfig::parallelism`]), not per replica, so every
+/// replica in a batch transitions together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicaActivationStatus {
+    Pending,
+    Initializing,
+    Active,
+    Failed,
+}
+
+/// Live snapshot of a `CreateCollection` (or rebalance)'s replica bootstrap,
+/// published on a [`tokio::sync::watch`] channel so API/UI layers can show
+/// progress (e.g. "3/6 replicas active") instead of blocking on
+/// `submit_collection_meta_op`'s single bool, and so a stalled timeout can
+/// name the replicas that never reached [`ReplicaActivationStatus::Active`].
+#[derive(Debug, Clone, Default)]
+pub struct CollectionBootstrapProgress {
+    pub replicas: HashMap<(ShardId, PeerId), ReplicaActivationStatus>,
+}
+
+impl CollectionBootstrapProgress {
+    /// Returns `(active, total)`, e.g. for rendering "3/6 replicas active".
+    pub fn active_count(&self) -> (usize, usize) {
+        let active = self
+            .replicas
+            .values()
+            .filter(|status| **status == ReplicaActivationStatus::Active)
+            .count();
+        (active, self.replicas.len())
+    }
+
+    /// `true` once every tracked replica has reached a terminal status
+    /// (`Active` or `Failed`).
+    pub fn is_complete(&self) -> bool {
+        self.replicas.values().all(|status| {
+            matches!(
+                status,
+                ReplicaActivationStatus::Active | ReplicaActivationStatus::Failed
+            )
+        })
+    }
+}
+
+/// Governs the retries `Dispatcher::submit_collection_meta_op` runs when its
+/// initial wait for replica activation (`expect_operations`) times out or
+/// comes back incomplete, instead of logging a warning and reporting success
+/// on a half-initialized collection.
+///
+/// Each attempt re-proposes the still-outstanding `initialize_replica`
+/// operations in batches of `parallelism`; this is safe because
+/// `ConsensusOperations::initialize_replica` is idempotent, so re-proposing
+/// for an already-active replica is a no-op. Delay between attempts grows as
+/// `initial_delay * 2^attempt`, plus up to `jitter` chosen uniformly at
+/// random, mirroring `collection`'s [`retry_with_backoff`](collection::operations::types::retry_with_backoff).
+#[derive(Debug, Clone)]
+pub struct ReplicaActivationRetryConfig {
+    pub initial_delay: Duration,
+    pub max_retries: usize,
+    pub parallelism: usize,
+    pub jitter: Duration,
+}
+
+impl Default for ReplicaActivationRetryConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(200),
+            max_retries: 5,
+            parallelism: 4,
+            jitter: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Default credit bucket size and recharge rate shared by every peer in
+/// [`Dispatcher`]'s flow control - tuned so a single `CreateCollection` every
+/// other second sustains indefinitely, while a burst of several can still be
+/// absorbed up to `max`.
+const DEFAULT_FLOW_CONTROL_MAX: f64 = 20.0;
+const DEFAULT_FLOW_CONTROL_RECHARGE_PER_SEC: f64 = 5.0;
+
+/// A replica with no heartbeat or successful request in this long is treated
+/// as unresponsive by [`Dispatcher::select_read_replica`].
+const DEFAULT_REPLICA_RESPONSIVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What [`Dispatcher::plan_meta_op`] decided to do with an operation: the
+/// operation as it should actually be proposed to consensus (distribution
+/// filled in, for `CreateCollection`), and the `initialize_replica`
+/// operations expected to follow it.
+#[derive(Debug, Clone)]
+pub struct MetaOpPlan {
+    pub rewritten_op: CollectionMetaOperations,
+    pub expect_operations: Vec<ConsensusOperations>,
+    /// The `(ShardId, PeerId)` each entry of `expect_operations` activates,
+    /// aligned by index. Kept alongside the plain `ConsensusOperations`
+    /// payload because `submit_collection_meta_op_with_progress`'s
+    /// per-replica progress tracking needs it, and nothing reads a
+    /// constructed `ConsensusOperations`'s target back out generically.
+    pub targets: Vec<(ShardId, PeerId)>,
+}
+
+/// Result of [`Dispatcher::submit_collection_meta_op_or_plan`]: either the
+/// operation actually ran, or (in `dry_run` mode) only its [`MetaOpPlan`]
+/// was computed.
+#[derive(Debug, Clone)]
+pub enum MetaOpOutcome {
+    Executed {
+        res: bool,
+        progress: Option<watch::Receiver<CollectionBootstrapProgress>>,
+    },
+    Planned(MetaOpPlan),
+}
+
 pub struct Dispatcher {
     toc: Arc<TableOfContent>,
     consensus_state: Option<ConsensusStateRef>,
+    replica_activation_retry: ReplicaActivationRetryConfig,
+    flow_control: Arc<MetaOpFlowControl>,
+    replica_health: Arc<ReplicaHealthMap>,
+    dry_run: bool,
 }
 
 impl Dispatcher {
@@ -18,9 +145,25 @@ impl Dispatcher {
         Self {
             toc,
             consensus_state: None,
+            replica_activation_retry: ReplicaActivationRetryConfig::default(),
+            flow_control: Arc::new(MetaOpFlowControl::new(
+                DEFAULT_FLOW_CONTROL_MAX,
+                DEFAULT_FLOW_CONTROL_RECHARGE_PER_SEC,
+                OperationCostTable::default(),
+            )),
+            replica_health: Arc::new(ReplicaHealthMap::new()),
+            dry_run: false,
         }
     }
 
+    /// When `true`, `Self::submit_collection_meta_op_or_plan` only computes
+    /// and returns the [`MetaOpPlan`] for an operation instead of proposing
+    /// it to consensus - lets an operator preview shard/replica placement
+    /// before committing to it.
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run, ..self }
+    }
+
     pub fn with_consensus(self, state_ref: ConsensusStateRef) -> Self {
         Self {
             consensus_state: Some(state_ref),
@@ -28,6 +171,24 @@ impl Dispatcher {
         }
     }
 
+    /// Overrides the default retry schedule used to chase down replicas that
+    /// are still not active after the initial `expect_operations` wait.
+    pub fn with_replica_activation_retry(self, config: ReplicaActivationRetryConfig) -> Self {
+        Self {
+            replica_activation_retry: config,
+            ..self
+        }
+    }
+
+    /// Overrides the default per-peer credit bucket used to admit
+    /// `CollectionMetaOperations` into consensus.
+    pub fn with_flow_control(self, flow_control: MetaOpFlowControl) -> Self {
+        Self {
+            flow_control: Arc::new(flow_control),
+            ..self
+        }
+    }
+
     pub fn toc(&self) -> &Arc<TableOfContent> {
         &self.toc
     }
@@ -36,6 +197,108 @@ impl Dispatcher {
         self.consensus_state.as_ref()
     }
 
+    /// The live replica health view backing [`Self::select_read_replica`],
+    /// exposed so a heartbeat listener or request-completion hook elsewhere
+    /// can feed it observations via [`ReplicaHealthMap::record_heartbeat`]/
+    /// [`ReplicaHealthMap::record_request_outcome`].
+    pub fn replica_health(&self) -> &ReplicaHealthMap {
+        &self.replica_health
+    }
+
+    /// Picks the best of `candidates` to route a read for `shard_id` to,
+    /// preferring a responsive replica that agrees with the majority's
+    /// latest applied index, least-loaded among those - with automatic
+    /// failover to the next-best candidate if the preferred one turns out
+    /// unresponsive. See [`ReplicaHealthMap::select_replica`].
+    pub fn select_read_replica(&self, shard_id: ShardId, candidates: &[PeerId]) -> Option<PeerId> {
+        self.replica_health
+            .select_replica(shard_id, candidates, DEFAULT_REPLICA_RESPONSIVE_TIMEOUT)
+    }
+
+    /// Computes what `Self::submit_collection_meta_op_with_progress` would
+    /// send to consensus for `operation`, without a running consensus or any
+    /// side effects: the "decide what to do" half of that method, split out
+    /// so the shard-distribution suggestion is unit-testable on its own and
+    /// so `self.dry_run` can preview placement without proposing anything
+    /// (see [`Self::submit_collection_meta_op_or_plan`]). `peer_count` stands
+    /// in for `state.0.peer_count()`, which the live call site reads off a
+    /// running consensus; a test can pass any value here instead.
+    pub async fn plan_meta_op(
+        &self,
+        operation: CollectionMetaOperations,
+        peer_count: u32,
+    ) -> MetaOpPlan {
+        let mut expect_operations = vec![];
+
+        let rewritten_op = match operation {
+            CollectionMetaOperations::CreateCollection(mut op) => {
+                if !op.is_distribution_set() {
+                    let shard_distribution = self
+                        .toc
+                        .suggest_shard_distribution(
+                            &op,
+                            NonZeroU32::new(peer_count).expect("Peer count should be always >= 1"),
+                        )
+                        .await;
+
+                    // Expect all replicas to become active eventually
+                    for (shard_id, peer_ids) in &shard_distribution.distribution {
+                        for peer_id in peer_ids {
+                            expect_operations.push((
+                                ConsensusOperations::initialize_replica(
+                                    op.collection_name.clone(),
+                                    *shard_id,
+                                    *peer_id,
+                                ),
+                                (*shard_id, *peer_id),
+                            ));
+                        }
+                    }
+
+                    op.set_distribution(shard_distribution);
+                }
+                CollectionMetaOperations::CreateCollection(op)
+            }
+            op => op,
+        };
+
+        MetaOpPlan {
+            rewritten_op,
+            expect_operations: expect_operations.iter().map(|(op, _)| op.clone()).collect(),
+            targets: expect_operations
+                .into_iter()
+                .map(|(_, target)| target)
+                .collect(),
+        }
+    }
+
+    /// Plans `operation` and, unless `self.dry_run` is set, executes it
+    /// exactly as `Self::submit_collection_meta_op_with_progress` would. In
+    /// dry-run mode nothing is proposed to consensus or written anywhere -
+    /// the caller gets back the [`MetaOpPlan`] that would have been executed,
+    /// so an operator can preview shard/replica placement before committing.
+    pub async fn submit_collection_meta_op_or_plan(
+        &self,
+        operation: CollectionMetaOperations,
+        wait_timeout: Option<Duration>,
+    ) -> Result<MetaOpOutcome, StorageError> {
+        if self.dry_run {
+            let peer_count = self
+                .consensus_state
+                .as_ref()
+                .map(|state| state.0.peer_count())
+                .unwrap_or(1);
+            return Ok(MetaOpOutcome::Planned(
+                self.plan_meta_op(operation, peer_count).await,
+            ));
+        }
+
+        let (res, progress) = self
+            .submit_collection_meta_op_with_progress(operation, wait_timeout)
+            .await?;
+        Ok(MetaOpOutcome::Executed { res, progress })
+    }
+
     /// If `wait_timeout` is not supplied - then default duration will be used.
     /// This function needs to be called from a runtime with timers enabled.
     pub async fn submit_collection_meta_op(
@@ -43,50 +306,83 @@ impl Dispatcher {
         operation: CollectionMetaOperations,
         wait_timeout: Option<Duration>,
     ) -> Result<bool, StorageError> {
+        let (res, _progress) = self
+            .submit_collection_meta_op_with_progress(operation, wait_timeout)
+            .await?;
+        Ok(res)
+    }
+
+    /// Same as [`Self::submit_collection_meta_op`], but for a `CreateCollection`
+    /// (or rebalance) that expects replicas to activate, also returns a
+    /// [`watch::Receiver`] of [`CollectionBootstrapProgress`] so a caller can
+    /// subscribe to per-replica status instead of waiting on the bool alone.
+    /// The receiver is `None` for operations that don't register any replica
+    /// awaiters (e.g. alias changes).
+    pub async fn submit_collection_meta_op_with_progress(
+        &self,
+        operation: CollectionMetaOperations,
+        wait_timeout: Option<Duration>,
+    ) -> Result<(bool, Option<watch::Receiver<CollectionBootstrapProgress>>), StorageError> {
         // if distributed deployment is enabled
         if let Some(state) = self.consensus_state.as_ref() {
-            // List of operations to await for collection to be operational
-            let mut expect_operations: Vec<ConsensusOperations> = vec![];
-
-            let op = match operation {
-                CollectionMetaOperations::CreateCollection(mut op) => {
-                    self.toc.check_write_lock()?;
-                    if !op.is_distribution_set() {
-                        // Suggest even distribution of shards across nodes
-                        let number_of_peers = state.0.peer_count();
-                        let shard_distribution = self
-                            .toc
-                            .suggest_shard_distribution(
-                                &op,
-                                NonZeroU32::new(number_of_peers as u32)
-                                    .expect("Peer count should be always >= 1"),
-                            )
-                            .await;
+            // Guard consensus against a flood of expensive meta operations from
+            // any one peer before doing any of the work below.
+            if let Err(rejection) = self
+                .flow_control
+                .try_admit(self.toc.this_peer_id, &operation)
+            {
+                return Err(StorageError::service_error(&format!(
+                    "too many collection meta operations from this peer, retry after {:?}",
+                    rejection.retry_after,
+                )));
+            }
 
-                        // Expect all replicas to become active eventually
-                        for (shard_id, peer_ids) in &shard_distribution.distribution {
-                            for peer_id in peer_ids {
-                                expect_operations.push(ConsensusOperations::initialize_replica(
-                                    op.collection_name.clone(),
-                                    *shard_id,
-                                    *peer_id,
-                                ));
-                            }
-                        }
+            if let CollectionMetaOperations::CreateCollection(_) = &operation {
+                self.toc.check_write_lock()?;
+            }
 
-                        op.set_distribution(shard_distribution);
-                    }
-                    CollectionMetaOperations::CreateCollection(op)
-                }
-                op => op,
+            // Decide what to actually propose, and which replicas to expect to
+            // activate afterward, via the same planner `submit_collection_meta_op_or_plan`
+            // uses in dry-run mode.
+            let plan = self
+                .plan_meta_op(operation, state.0.peer_count() as u32)
+                .await;
+            let op = plan.rewritten_op;
+            // Operations to await for collection to be operational, paired with
+            // the replica each one activates so progress can be reported per
+            // replica rather than for the batch as a whole.
+            let expect_operations: Vec<(ConsensusOperations, (ShardId, PeerId))> = plan
+                .expect_operations
+                .into_iter()
+                .zip(plan.targets)
+                .collect();
+
+            let progress_tx = if expect_operations.is_empty() {
+                None
+            } else {
+                let replicas = expect_operations
+                    .iter()
+                    .map(|(_, target)| (*target, ReplicaActivationStatus::Pending))
+                    .collect();
+                Some(watch::Sender::new(CollectionBootstrapProgress { replicas }))
             };
+            let progress_rx = progress_tx.as_ref().map(watch::Sender::subscribe);
+            if let Some(tx) = &progress_tx {
+                tx.send_modify(|progress| {
+                    for status in progress.replicas.values_mut() {
+                        *status = ReplicaActivationStatus::Initializing;
+                    }
+                });
+            }
 
+            let ops_only: Vec<ConsensusOperations> =
+                expect_operations.iter().map(|(op, _)| op.clone()).collect();
             let operation_awaiter =
                 // If explicit timeout is set - then we need to wait for all expected operations.
                 // E.g. in case of `CreateCollection` we will explicitly wait for all replicas to be activated.
                 // We need to register receivers(by calling the function) before submitting the operation.
-                if !expect_operations.is_empty() {
-                    Some(state.await_for_multiple_operations(expect_operations, wait_timeout))
+                if !ops_only.is_empty() {
+                    Some(state.await_for_multiple_operations(ops_only, wait_timeout))
                 } else {
                     None
                 };
@@ -100,24 +396,139 @@ impl Dispatcher {
 
             if let Some(operation_awaiter) = operation_awaiter {
                 // Actually await for expected operations to complete on the consensus
-                match operation_awaiter.await {
-                    Ok(Ok(())) => {} // all good
-                    Ok(Err(err)) => {
-                        log::warn!("Not all expected operations were completed: {}", err)
+                let initial_outcome = match operation_awaiter.await {
+                    Ok(Ok(())) => Ok(()),
+                    Ok(Err(err)) => Err(err.to_string()),
+                    Err(err) => Err(err.to_string()),
+                };
+
+                match initial_outcome {
+                    Ok(()) => {
+                        if let Some(tx) = &progress_tx {
+                            tx.send_modify(|progress| {
+                                for status in progress.replicas.values_mut() {
+                                    *status = ReplicaActivationStatus::Active;
+                                }
+                            });
+                        }
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "Not all expected operations completed on first attempt, retrying: {}",
+                            err
+                        );
+                        if !self
+                            .retry_replica_activation(
+                                state,
+                                expect_operations,
+                                wait_timeout,
+                                progress_tx.as_ref(),
+                            )
+                            .await
+                        {
+                            log::warn!(
+                                "Replica activation still incomplete after exhausting retries; \
+                                 collection is degraded"
+                            );
+                            return Ok((false, progress_rx));
+                        }
                     }
-                    Err(err) => log::warn!("Awaiting for expected operations timed out: {}", err),
                 }
             }
 
-            Ok(res)
+            Ok((res, progress_rx))
         } else {
             if let CollectionMetaOperations::CreateCollection(_) = &operation {
                 self.toc.check_write_lock()?;
             }
-            self.toc.perform_collection_meta_op(operation).await
+            let res = self.toc.perform_collection_meta_op(operation).await?;
+            Ok((res, None))
         }
     }
 
+    /// Re-proposes the operations in `expect_operations` against `state` up
+    /// to `self.replica_activation_retry.max_retries` times, in batches of
+    /// `parallelism`, backing off `initial_delay * 2^attempt` plus random
+    /// jitter between attempts. Batches that succeed move their replicas to
+    /// [`ReplicaActivationStatus::Active`] in `progress`; replicas in a batch
+    /// that's still failing once retries are exhausted move to
+    /// [`ReplicaActivationStatus::Failed`]. Returns `true` once every replica
+    /// reaches `Active`, `false` if retries are exhausted first.
+    async fn retry_replica_activation(
+        &self,
+        state: &ConsensusStateRef,
+        expect_operations: Vec<(ConsensusOperations, (ShardId, PeerId))>,
+        wait_timeout: Option<Duration>,
+        progress: Option<&watch::Sender<CollectionBootstrapProgress>>,
+    ) -> bool {
+        let config = &self.replica_activation_retry;
+        let mut outstanding = expect_operations;
+
+        for attempt in 0..config.max_retries {
+            let delay = config
+                .initial_delay
+                .saturating_mul(1 << attempt.min(31))
+                .min(Duration::from_secs(60));
+            let jitter = config
+                .jitter
+                .mul_f64(rand::thread_rng().gen_range(0.0..=1.0));
+            tokio::time::sleep(delay + jitter).await;
+
+            let mut still_outstanding = vec![];
+            for batch in outstanding.chunks(config.parallelism.max(1)) {
+                let ops: Vec<ConsensusOperations> =
+                    batch.iter().map(|(op, _)| op.clone()).collect();
+                let awaiter = state.await_for_multiple_operations(ops, wait_timeout);
+                match awaiter.await {
+                    Ok(Ok(())) => {
+                        if let Some(tx) = progress {
+                            tx.send_modify(|progress| {
+                                for (_, target) in batch {
+                                    progress
+                                        .replicas
+                                        .insert(*target, ReplicaActivationStatus::Active);
+                                }
+                            });
+                        }
+                    }
+                    Ok(Err(err)) => {
+                        log::warn!(
+                            "Retry {} of replica activation failed: {}",
+                            attempt + 1,
+                            err
+                        );
+                        still_outstanding.extend(batch.iter().cloned());
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "Retry {} of replica activation timed out: {}",
+                            attempt + 1,
+                            err
+                        );
+                        still_outstanding.extend(batch.iter().cloned());
+                    }
+                }
+            }
+
+            if still_outstanding.is_empty() {
+                return true;
+            }
+            outstanding = still_outstanding;
+        }
+
+        if let Some(tx) = progress {
+            tx.send_modify(|progress| {
+                for (_, target) in &outstanding {
+                    progress
+                        .replicas
+                        .insert(*target, ReplicaActivationStatus::Failed);
+                }
+            });
+        }
+
+        false
+    }
+
     pub fn cluster_status(&self) -> ClusterStatus {
         match self.consensus_state.as_ref() {
             Some(state) => state.cluster_status(),
@@ -139,6 +550,10 @@ impl Clone for Dispatcher {
         Self {
             toc: self.toc.clone(),
             consensus_state: self.consensus_state.clone(),
+            replica_activation_retry: self.replica_activation_retry.clone(),
+            flow_control: self.flow_control.clone(),
+            replica_health: self.replica_health.clone(),
+            dry_run: self.dry_run,
         }
     }
 }
@@ -0,0 +1,35 @@
+use actix_web::rt::time::Instant;
+use actix_web::{get, web, Responder};
+use actix_web_validator::Path;
+use collection::operations::types::CollectionClusterInfo;
+use storage::content_manager::errors::StorageError;
+use storage::content_manager::toc::TableOfContent;
+
+use super::CollectionPath;
+use crate::actix::helpers::process_response;
+
+async fn do_get_cluster_info(
+    toc: &TableOfContent,
+    collection_name: &str,
+) -> Result<CollectionClusterInfo, StorageError> {
+    toc.cluster_info(collection_name).await
+}
+
+/// Shard topology and in-flight transfer state for `{name}`: each shard's
+/// local/remote replica peers and their [`ReplicaState`](collection::shards::replica_set::ReplicaState),
+/// and the active transfers reported by `ShardHolder::get_shard_transfer_info`,
+/// for dashboards to render cluster topology without polling per-shard
+/// endpoints.
+#[get("/collections/{name}/cluster")]
+async fn get_cluster_info(
+    toc: web::Data<TableOfContent>,
+    collection: Path<CollectionPath>,
+) -> impl Responder {
+    let timing = Instant::now();
+    let response = do_get_cluster_info(toc.get_ref(), &collection.name).await;
+    process_response(response, timing)
+}
+
+pub fn config_cluster_api(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_cluster_info);
+}
@@ -2,8 +2,12 @@ use actix_web::rt::time::Instant;
 use actix_web::{get, post, web, Responder};
 use actix_web_validator::{Json, Path, Query};
 use collection::operations::consistency_params::ReadConsistency;
-use collection::operations::types::{PointRequest, Record, ScrollRequest, ScrollResult};
-use segment::types::{PointIdType, WithPayloadInterface};
+use collection::operations::types::{
+    LazyRecord, LazyScrollResult, PointRequest, QueryBatchRequest, QueryRequest, QueryResult,
+    Record, ScrollRequest, ScrollResult, SearchRequest,
+};
+use futures::future::try_join_all;
+use segment::types::{PointIdType, ScoredPoint, WithPayloadInterface};
 use serde::Deserialize;
 use storage::content_manager::errors::StorageError;
 use storage::content_manager::toc::TableOfContent;
@@ -14,6 +18,15 @@ use super::CollectionPath;
 use crate::actix::helpers::process_response;
 use crate::common::points::do_get_points;
 
+/// Converts `record` to its [`LazyRecord`] form, deferring payload parsing
+/// until something actually reads it back out.
+fn into_lazy_record(record: Record) -> Result<LazyRecord, StorageError> {
+    LazyRecord::try_from(record).map_err(|err| StorageError::ServiceError {
+        description: format!("failed to encode point payload: {err}"),
+        backtrace: None,
+    })
+}
+
 #[derive(Deserialize, Validate)]
 struct PointPath {
     #[validate(length(min = 1))]
@@ -41,13 +54,59 @@ async fn do_get_point(
 async fn scroll_get_points(
     toc: &TableOfContent,
     collection_name: &str,
-    request: ScrollRequest,
+    mut request: ScrollRequest,
     read_consistency: Option<ReadConsistency>,
 ) -> Result<ScrollResult, StorageError> {
+    request.offset = request.resolve_offset().map_err(|_| StorageError::BadInput {
+        description: "page_token is malformed or was not issued by this server".to_string(),
+    })?;
+
     toc.scroll(collection_name, request, read_consistency, None)
         .await
 }
 
+async fn do_search_points(
+    toc: &TableOfContent,
+    collection_name: &str,
+    mut request: SearchRequest,
+    read_consistency: Option<ReadConsistency>,
+) -> Result<Vec<ScoredPoint>, StorageError> {
+    request.offset = request.resolve_offset().map_err(|_| StorageError::BadInput {
+        description: "page_token is malformed or was not issued by this server".to_string(),
+    })?;
+
+    toc.search(collection_name, request, read_consistency, None)
+        .await
+}
+
+/// Dispatches a single entry of a heterogeneous query batch to whichever
+/// `TableOfContent` method matches its kind.
+async fn do_query(
+    toc: &TableOfContent,
+    collection_name: &str,
+    query: QueryRequest,
+    read_consistency: Option<ReadConsistency>,
+) -> Result<QueryResult, StorageError> {
+    match query {
+        QueryRequest::Search(request) => {
+            do_search_points(toc, collection_name, request, read_consistency)
+                .await
+                .map(QueryResult::Search)
+        }
+        QueryRequest::Recommend(request) => toc
+            .recommend(collection_name, request, read_consistency, None)
+            .await
+            .map(QueryResult::Recommend),
+        QueryRequest::Scroll(request) => scroll_get_points(toc, collection_name, request, read_consistency)
+            .await
+            .map(QueryResult::Scroll),
+        QueryRequest::Count(request) => toc
+            .count(collection_name, request, read_consistency, None)
+            .await
+            .map(QueryResult::Count),
+    }
+}
+
 #[get("/collections/{name}/points/{id}")]
 async fn get_point(
     toc: web::Data<TableOfContent>,
@@ -86,7 +145,8 @@ async fn get_point(
             Some(record) => Ok(record),
         },
         Err(e) => Err(e),
-    };
+    }
+    .and_then(into_lazy_record);
     process_response(response, timing)
 }
 
@@ -106,7 +166,8 @@ async fn get_points(
         params.consistency,
         None,
     )
-    .await;
+    .await
+    .and_then(|records| records.into_iter().map(into_lazy_record).collect::<Result<Vec<_>, _>>());
     process_response(response, timing)
 }
 
@@ -125,6 +186,31 @@ async fn scroll_points(
         request.into_inner(),
         params.consistency,
     )
-    .await;
+    .await
+    .and_then(|result| {
+        LazyScrollResult::try_from(result).map_err(|err| StorageError::ServiceError {
+            description: format!("failed to encode point payload: {err}"),
+            backtrace: None,
+        })
+    });
+    process_response(response, timing)
+}
+
+#[post("/collections/{name}/points/query/batch")]
+async fn query_batch_points(
+    toc: web::Data<TableOfContent>,
+    collection: Path<CollectionPath>,
+    request: Json<QueryBatchRequest>,
+    params: Query<ReadParams>,
+) -> impl Responder {
+    let timing = Instant::now();
+
+    let queries = request
+        .into_inner()
+        .queries
+        .into_iter()
+        .map(|query| do_query(toc.get_ref(), &collection.name, query, params.consistency));
+
+    let response = try_join_all(queries).await;
     process_response(response, timing)
 }
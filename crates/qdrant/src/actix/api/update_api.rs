@@ -1,16 +1,28 @@
+use std::fmt::Debug;
+use std::future::Future;
+
+use actix_web::http::StatusCode;
 use actix_web::rt::time::Instant;
-use actix_web::{delete, post, put, web, Responder};
+use actix_web::{delete, post, put, web, HttpResponse, Responder};
 use actix_web_validator::{Json, Path, Query};
+use api::grpc::models::{ApiResponse, ApiStatus};
 use collection::operations::payload_ops::{DeletePayload, SetPayload};
-use collection::operations::point_ops::{PointInsertOperations, PointsSelector, WriteOrdering};
+use collection::operations::point_ops::{
+    PointInsertOperations, PointStruct, PointsSelector, WriteOrdering,
+};
 use collection::operations::vector_ops::{DeleteVectors, UpdateVectors};
 use schemars::JsonSchema;
+use segment::types::{Payload, PointIdType};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use storage::content_manager::errors::StorageError;
 use storage::content_manager::toc::TableOfContent;
 use validator::Validate;
 
 use super::CollectionPath;
+use crate::actix::batch_upload::{BatchUploadError, BatchUploadStore};
 use crate::actix::helpers::process_response;
+use crate::actix::idempotency::{ClaimOutcome, IdempotencyStore, IN_FLIGHT_WAIT};
 use crate::common::points::{
     do_batch_update_points, do_clear_payload, do_create_index, do_delete_index, do_delete_payload,
     do_delete_points, do_delete_vectors, do_overwrite_payload, do_set_payload, do_update_vectors,
@@ -28,11 +40,132 @@ struct FieldPath {
 pub struct UpdateParam {
     pub wait: Option<bool>,
     pub ordering: Option<WriteOrdering>,
+    /// Replays the stored result of a prior request that used this same
+    /// key (and request body) instead of re-applying the mutation, so a
+    /// client retrying after a dropped connection can't double-apply an
+    /// update. A key reused with a *different* body is a conflict - see
+    /// `crate::actix::idempotency`.
+    pub idempotency_key: Option<String>,
+}
+
+/// Serializes `response` into the same `(status, body)` shape
+/// [`process_response`] would send over the wire, so an idempotent
+/// handler can both answer the live request and cache the bytes to
+/// replay verbatim next time.
+fn encode_response<D>(response: &Result<D, StorageError>, timing: Instant) -> (u16, Vec<u8>)
+where
+    D: Serialize + Debug,
+{
+    match response {
+        Ok(res) => {
+            let body = serde_json::to_vec(&ApiResponse {
+                result: Some(res),
+                status: ApiStatus::Ok,
+                time: timing.elapsed().as_secs_f64(),
+            })
+            .unwrap_or_default();
+            (200, body)
+        }
+        Err(err) => {
+            let status = match err {
+                StorageError::BadInput { .. } => 400,
+                StorageError::NotFound { .. } => 404,
+                StorageError::ServiceError { .. } => 500,
+                StorageError::BadRequest { .. } => 400,
+                StorageError::Locked { .. } => 403,
+                StorageError::Timeout { .. } => 408,
+            };
+            let body = serde_json::to_vec(&ApiResponse::<()> {
+                result: None,
+                status: ApiStatus::Error(format!("{err}")),
+                time: timing.elapsed().as_secs_f64(),
+            })
+            .unwrap_or_default();
+            (status, body)
+        }
+    }
+}
+
+fn json_response(status: u16, body: Vec<u8>) -> HttpResponse {
+    HttpResponse::build(StatusCode::from_u16(status).unwrap_or(StatusCode::OK))
+        .content_type("application/json")
+        .body(body)
+}
+
+/// Runs `run` and answers with its result, honoring `idempotency_key` (see
+/// `UpdateParam::idempotency_key`): a key claimed for the first time runs
+/// `run` and records its response, a key matching a previously completed
+/// claim's body hash replays that response without running `run` again, a
+/// key whose body hash doesn't match returns a conflict, and a key another
+/// in-flight request already claimed waits for that request to complete
+/// (or reports itself still-processing after `IN_FLIGHT_WAIT`) instead of
+/// running `run` a second time.
+async fn respond_idempotently<D, Fut>(
+    store: &IdempotencyStore,
+    collection: &str,
+    idempotency_key: Option<&str>,
+    body_hash: u64,
+    timing: Instant,
+    run: impl FnOnce() -> Fut,
+) -> HttpResponse
+where
+    D: Serialize + Debug,
+    Fut: Future<Output = Result<D, StorageError>>,
+{
+    let Some(key) = idempotency_key else {
+        let response = run().await;
+        let (status, body) = encode_response(&response, timing);
+        return json_response(status, body);
+    };
+
+    loop {
+        match store.claim(collection, key, body_hash) {
+            ClaimOutcome::Replay { status, body } => return json_response(status, body),
+            ClaimOutcome::Conflict => {
+                return json_response(
+                    409,
+                    serde_json::to_vec(&ApiResponse::<()> {
+                        result: None,
+                        status: ApiStatus::Error(
+                            "idempotency key reused with a different request body".to_string(),
+                        ),
+                        time: timing.elapsed().as_secs_f64(),
+                    })
+                    .unwrap_or_default(),
+                );
+            }
+            ClaimOutcome::InFlight(notify) => {
+                if tokio::time::timeout(IN_FLIGHT_WAIT, notify.notified())
+                    .await
+                    .is_err()
+                {
+                    return json_response(
+                        202,
+                        serde_json::to_vec(&ApiResponse::<()> {
+                            result: None,
+                            status: ApiStatus::Accepted,
+                            time: timing.elapsed().as_secs_f64(),
+                        })
+                        .unwrap_or_default(),
+                    );
+                }
+                // Woken up by the in-flight request's `complete` call - loop
+                // back around to replay its now-recorded result.
+            }
+            ClaimOutcome::Claimed => {
+                let response = run().await;
+                let (status, body) = encode_response(&response, timing);
+                store.complete(collection, key, body_hash, status, body.clone());
+                return json_response(status, body);
+            }
+        }
+    }
 }
 
 #[put("/collections/{name}/points")]
 async fn upsert_points(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<PointInsertOperations>,
     params: Query<UpdateParam>,
@@ -41,22 +174,32 @@ async fn upsert_points(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_upsert_points(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_upsert_points(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[post("/collections/{name}/points/delete")]
 async fn delete_points(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<PointsSelector>,
     params: Query<UpdateParam>,
@@ -65,22 +208,32 @@ async fn delete_points(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_delete_points(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_delete_points(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[put("/collections/{name}/points/vectors")]
 async fn update_vectors(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<UpdateVectors>,
     params: Query<UpdateParam>,
@@ -89,22 +242,32 @@ async fn update_vectors(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_update_vectors(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_update_vectors(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[post("/collections/{name}/points/vectors/delete")]
 async fn delete_vectors(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<DeleteVectors>,
     params: Query<UpdateParam>,
@@ -113,22 +276,32 @@ async fn delete_vectors(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_delete_vectors(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_delete_vectors(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[post("/collections/{name}/points/payload")]
 async fn set_payload(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<SetPayload>,
     params: Query<UpdateParam>,
@@ -137,22 +310,32 @@ async fn set_payload(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_set_payload(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_set_payload(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[put("/collections/{name}/points/payload")]
 async fn overwrite_payload(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<SetPayload>,
     params: Query<UpdateParam>,
@@ -161,22 +344,32 @@ async fn overwrite_payload(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_overwrite_payload(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_overwrite_payload(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[post("/collections/{name}/points/payload/delete")]
 async fn delete_payload(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<DeletePayload>,
     params: Query<UpdateParam>,
@@ -185,22 +378,32 @@ async fn delete_payload(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_delete_payload(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_delete_payload(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[post("/collections/{name}/points/payload/clear")]
 async fn clear_payload(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<PointsSelector>,
     params: Query<UpdateParam>,
@@ -209,22 +412,32 @@ async fn clear_payload(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_clear_payload(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_clear_payload(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[post("/collections/{name}/points/batch")]
 async fn update_batch(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operations: Json<UpdateOperations>,
     params: Query<UpdateParam>,
@@ -233,21 +446,278 @@ async fn update_batch(
     let operations = operations.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operations.operations);
 
-    let response = do_batch_update_points(
-        &toc,
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operations.operations,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_batch_update_points(
+                &toc,
+                &collection.name,
+                operations.operations,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
+}
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum ImportFormat {
+    Ndjson,
+    Csv,
+}
+
+fn default_import_batch_size() -> usize {
+    1000
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+struct ImportParams {
+    #[serde(flatten)]
+    #[validate]
+    update: UpdateParam,
+    format: ImportFormat,
+    /// Points are parsed and upserted in batches of this size, so memory
+    /// stays bounded on million-row files.
+    #[serde(default = "default_import_batch_size")]
+    batch_size: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ImportBatchReport {
+    accepted: usize,
+    failed: usize,
 }
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+struct ImportReport {
+    batches: Vec<ImportBatchReport>,
+}
+
+/// Parses one JSON object per line into a [`PointStruct`], skipping blank
+/// lines. Returns the 1-based line number and message of the first parse
+/// error encountered.
+fn parse_ndjson_points(body: &str) -> Result<Vec<PointStruct>, (usize, String)> {
+    let mut points = Vec::new();
+    for (index, line) in body.lines().enumerate() {
+        let line_no = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let point: PointStruct =
+            serde_json::from_str(line).map_err(|err| (line_no, format!("invalid point: {err}")))?;
+        points.push(point);
+    }
+    Ok(points)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CsvColumnType {
+    String,
+    Number,
+    StringArray,
+    FloatArray,
+}
+
+/// Splits a CSV header cell into its column name and declared type, e.g.
+/// `price:number` -> (`price`, Number). A column without a `:type` suffix is
+/// treated as a plain string.
+fn parse_csv_column_header(header: &str) -> (String, CsvColumnType) {
+    match header.trim().split_once(':') {
+        Some((name, "number")) => (name.to_string(), CsvColumnType::Number),
+        Some((name, "string[]")) => (name.to_string(), CsvColumnType::StringArray),
+        Some((name, "float[]")) => (name.to_string(), CsvColumnType::FloatArray),
+        Some((name, _unknown)) => (name.to_string(), CsvColumnType::String),
+        None => (header.trim().to_string(), CsvColumnType::String),
+    }
+}
+
+/// Coerces one CSV cell into a JSON value according to its column's
+/// declared type. Array-typed cells are `;`-separated; a cell that can't be
+/// coerced (e.g. `number` on non-numeric text) becomes `null` rather than
+/// failing the whole row.
+fn parse_csv_cell(raw: &str, column_type: CsvColumnType) -> Value {
+    let raw = raw.trim();
+    match column_type {
+        CsvColumnType::String => Value::String(raw.to_string()),
+        CsvColumnType::Number => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        CsvColumnType::StringArray => Value::Array(
+            raw.split(';')
+                .filter(|cell| !cell.is_empty())
+                .map(|cell| Value::String(cell.to_string()))
+                .collect(),
+        ),
+        CsvColumnType::FloatArray => Value::Array(
+            raw.split(';')
+                .filter(|cell| !cell.is_empty())
+                .filter_map(|cell| cell.parse::<f64>().ok())
+                .filter_map(|number| serde_json::Number::from_f64(number).map(Value::Number))
+                .collect(),
+        ),
+    }
+}
+
+/// Parses a CSV body whose header row names a designated `id` column and a
+/// `vector` column (declared `float[]`); every other column becomes a
+/// payload key coerced per its `:type` suffix. Returns the 1-based line
+/// number and message of the first parse error.
+fn parse_csv_points(body: &str) -> Result<Vec<PointStruct>, (usize, String)> {
+    let mut lines = body.lines().enumerate();
+    let (_, header_line) = lines
+        .next()
+        .ok_or_else(|| (1, "CSV body is empty, missing header row".to_string()))?;
+    let columns: Vec<(String, CsvColumnType)> = header_line
+        .split(',')
+        .map(parse_csv_column_header)
+        .collect();
+
+    let id_index = columns
+        .iter()
+        .position(|(name, _)| name == "id")
+        .ok_or_else(|| (1, "CSV header must designate an `id` column".to_string()))?;
+    let vector_index = columns
+        .iter()
+        .position(|(name, _)| name == "vector")
+        .ok_or_else(|| (1, "CSV header must designate a `vector` column".to_string()))?;
+
+    let mut points = Vec::new();
+    for (index, line) in lines {
+        let line_no = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(',').collect();
+        if cells.len() != columns.len() {
+            return Err((
+                line_no,
+                format!("expected {} columns, got {}", columns.len(), cells.len()),
+            ));
+        }
+
+        let id: PointIdType = cells[id_index]
+            .trim()
+            .parse()
+            .map_err(|_| (line_no, format!("invalid point id {:?}", cells[id_index])))?;
+
+        let vector: Vec<f32> = parse_csv_cell(cells[vector_index], CsvColumnType::FloatArray)
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_f64())
+            .map(|value| value as f32)
+            .collect();
+
+        let mut payload = serde_json::Map::new();
+        for (column_index, (name, column_type)) in columns.iter().enumerate() {
+            if column_index == id_index || column_index == vector_index {
+                continue;
+            }
+            payload.insert(
+                name.clone(),
+                parse_csv_cell(cells[column_index], *column_type),
+            );
+        }
+
+        let payload: Payload = serde_json::from_value(Value::Object(payload))
+            .map_err(|err| (line_no, format!("invalid payload: {err}")))?;
+
+        points.push(PointStruct {
+            id,
+            vector: vector.into(),
+            payload: Some(payload),
+        });
+    }
+    Ok(points)
+}
+
+/// Streaming bulk-import endpoint: parses `body` as NDJSON or CSV (per
+/// `format`) into points, then upserts them in `batch_size`-sized chunks so
+/// memory stays bounded on million-row files, returning per-batch
+/// accepted/failed counts.
+#[post("/collections/{name}/points/import")]
+async fn import_points(
+    toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
+    collection: Path<CollectionPath>,
+    params: Query<ImportParams>,
+    body: String,
+) -> impl Responder {
+    let timing = Instant::now();
+    let wait = params.update.wait.unwrap_or(false);
+    let ordering = params.update.ordering.unwrap_or_default();
+    let batch_size = params.batch_size.max(1);
+    let body_hash = IdempotencyStore::hash_body(&body);
+
+    respond_idempotently(
+        &idempotency,
+        &collection.name,
+        params.update.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || async {
+            let parsed = match params.format {
+                ImportFormat::Ndjson => parse_ndjson_points(&body),
+                ImportFormat::Csv => parse_csv_points(&body),
+            };
+
+            let points = match parsed {
+                Ok(points) => points,
+                Err((line_no, message)) => {
+                    return Err(StorageError::BadInput {
+                        description: format!(
+                            "failed to parse import body at line {line_no}: {message}"
+                        ),
+                    });
+                }
+            };
+
+            let mut report = ImportReport::default();
+            for batch in points.chunks(batch_size) {
+                let accepted = batch.len();
+                let response = do_upsert_points(
+                    toc.get_ref(),
+                    &collection.name,
+                    PointInsertOperations::PointsList(batch.to_vec()),
+                    None,
+                    wait,
+                    ordering,
+                )
+                .await;
+
+                report.batches.push(match response {
+                    Ok(_) => ImportBatchReport {
+                        accepted,
+                        failed: 0,
+                    },
+                    Err(_) => ImportBatchReport {
+                        accepted: 0,
+                        failed: accepted,
+                    },
+                });
+            }
+
+            Ok(report)
+        },
+    )
+    .await
+}
+
 #[put("/collections/{name}/index")]
 async fn create_field_index(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     operation: Json<CreateFieldIndex>,
     params: Query<UpdateParam>,
@@ -256,22 +726,32 @@ async fn create_field_index(
     let operation = operation.into_inner();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&operation);
 
-    let response = do_create_index(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        operation,
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_create_index(
+                toc.get_ref(),
+                &collection.name,
+                operation,
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
-    process_response(response, timing)
+    .await
 }
 
 #[delete("/collections/{name}/index/{field_name}")]
 async fn delete_field_index(
     toc: web::Data<TableOfContent>,
+    idempotency: web::Data<IdempotencyStore>,
     collection: Path<CollectionPath>,
     field: Path<FieldPath>,
     params: Query<UpdateParam>,
@@ -279,16 +759,123 @@ async fn delete_field_index(
     let timing = Instant::now();
     let wait = params.wait.unwrap_or(false);
     let ordering = params.ordering.unwrap_or_default();
+    let body_hash = IdempotencyStore::hash_body(&field.name);
 
-    let response = do_delete_index(
-        toc.get_ref(),
+    respond_idempotently(
+        &idempotency,
         &collection.name,
-        field.name.clone(),
-        None,
-        wait,
-        ordering,
+        params.idempotency_key.as_deref(),
+        body_hash,
+        timing,
+        || {
+            do_delete_index(
+                toc.get_ref(),
+                &collection.name,
+                field.name.clone(),
+                None,
+                wait,
+                ordering,
+            )
+        },
     )
-    .await;
+    .await
+}
+
+#[derive(Deserialize, Validate)]
+struct UploadIdPath {
+    upload_id: String,
+}
+
+#[derive(Deserialize, Validate)]
+struct UploadPartPath {
+    upload_id: String,
+    n: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct BatchUploadCreated {
+    upload_id: String,
+}
+
+fn batch_upload_error(err: BatchUploadError) -> StorageError {
+    match err {
+        BatchUploadError::NotFound => StorageError::NotFound {
+            description: "upload session not found".to_string(),
+        },
+        BatchUploadError::CollectionMismatch => StorageError::BadInput {
+            description: "upload id does not belong to this collection".to_string(),
+        },
+    }
+}
+
+/// Opens a new chunked batch-upload session for this collection; see
+/// `crate::actix::batch_upload`.
+#[post("/collections/{name}/points/batch/create")]
+async fn create_batch_upload(
+    uploads: web::Data<BatchUploadStore>,
+    collection: Path<CollectionPath>,
+) -> impl Responder {
+    let timing = Instant::now();
+    let upload_id = uploads.create(&collection.name);
+    process_response(Ok(BatchUploadCreated { upload_id }), timing)
+}
+
+/// Stages one ordered part of a chunked batch upload, resending-safe: a
+/// part number already staged is simply replaced.
+#[put("/collections/{name}/points/batch/{upload_id}/part/{n}")]
+async fn put_batch_upload_part(
+    uploads: web::Data<BatchUploadStore>,
+    collection: Path<CollectionPath>,
+    part: Path<UploadPartPath>,
+    operation: Json<PointInsertOperations>,
+) -> impl Responder {
+    let timing = Instant::now();
+    let response = uploads
+        .put_part(
+            &collection.name,
+            &part.upload_id,
+            part.n,
+            operation.into_inner(),
+        )
+        .map_err(batch_upload_error);
+    process_response(response, timing)
+}
+
+/// Commits every staged part of a chunked batch upload, in part-number
+/// order, through a single [`do_batch_update_points`] call under the
+/// requested `WriteOrdering`.
+#[post("/collections/{name}/points/batch/{upload_id}/complete")]
+async fn complete_batch_upload(
+    toc: web::Data<TableOfContent>,
+    uploads: web::Data<BatchUploadStore>,
+    collection: Path<CollectionPath>,
+    upload: Path<UploadIdPath>,
+    params: Query<UpdateParam>,
+) -> impl Responder {
+    let timing = Instant::now();
+    let wait = params.wait.unwrap_or(false);
+    let ordering = params.ordering.unwrap_or_default();
+
+    let response = match uploads.complete(&collection.name, &upload.upload_id) {
+        Ok(parts) => {
+            do_batch_update_points(&toc, &collection.name, parts, None, wait, ordering).await
+        }
+        Err(err) => Err(batch_upload_error(err)),
+    };
+    process_response(response, timing)
+}
+
+/// Discards a chunked batch upload's staged parts without committing them.
+#[post("/collections/{name}/points/batch/{upload_id}/abort")]
+async fn abort_batch_upload(
+    uploads: web::Data<BatchUploadStore>,
+    collection: Path<CollectionPath>,
+    upload: Path<UploadIdPath>,
+) -> impl Responder {
+    let timing = Instant::now();
+    let response = uploads
+        .abort(&collection.name, &upload.upload_id)
+        .map_err(batch_upload_error);
     process_response(response, timing)
 }
 
@@ -304,5 +891,10 @@ pub fn config_update_api(cfg: &mut web::ServiceConfig) {
         .service(clear_payload)
         .service(create_field_index)
         .service(delete_field_index)
-        .service(update_batch);
+        .service(update_batch)
+        .service(import_points)
+        .service(create_batch_upload)
+        .service(put_batch_upload_part)
+        .service(complete_batch_upload)
+        .service(abort_batch_upload);
 }
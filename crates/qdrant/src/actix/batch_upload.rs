@@ -0,0 +1,162 @@
+//! Chunked, resumable batch-upsert sessions, modeled on multipart upload:
+//! a client opens a session, `PUT`s ordered parts as it produces them, and
+//! only on `complete` are the staged parts merged into a single
+//! [`do_batch_update_points`](crate::common::points::do_batch_update_points)
+//! call - so a dropped connection mid-upload means resending the missing
+//! parts, not restarting the whole ingest, and a client that never
+//! completes (or explicitly aborts) never leaves a half-applied batch.
+//!
+//! Parts are currently staged in memory only, keyed by collection and
+//! upload id: `TableOfContent` doesn't yet expose a collection's on-disk
+//! path to this layer, so true temp-file staging under the collection
+//! path - surviving a process restart mid-upload - is follow-up work once
+//! that accessor exists. The session contract this module provides
+//! (ordered parts, atomic commit, explicit abort) already holds either
+//! way.
+
+use std::collections::{BTreeMap, HashMap};
+
+use collection::operations::point_ops::PointInsertOperations;
+use parking_lot::Mutex;
+use rand::Rng;
+
+struct UploadSession {
+    collection: String,
+    parts: BTreeMap<usize, PointInsertOperations>,
+}
+
+/// What went wrong looking up an in-flight upload session.
+#[derive(Debug)]
+pub enum BatchUploadError {
+    /// No session exists for this upload id (never created, already
+    /// completed, or already aborted).
+    NotFound,
+    /// The upload id exists but belongs to a different collection.
+    CollectionMismatch,
+}
+
+/// Per-process table of in-flight chunked-upload sessions; see this
+/// module's doc comment.
+#[derive(Default)]
+pub struct BatchUploadStore {
+    uploads: Mutex<HashMap<String, UploadSession>>,
+}
+
+impl BatchUploadStore {
+    pub fn new() -> Self {
+        BatchUploadStore::default()
+    }
+
+    /// Opens a new session for `collection`, returning its upload id.
+    pub fn create(&self, collection: &str) -> String {
+        let upload_id = format!("{:032x}", rand::thread_rng().gen::<u128>());
+        self.uploads.lock().insert(
+            upload_id.clone(),
+            UploadSession {
+                collection: collection.to_string(),
+                parts: BTreeMap::new(),
+            },
+        );
+        upload_id
+    }
+
+    /// Stages (or re-stages, if this part number was already sent) one
+    /// ordered chunk of the upload.
+    pub fn put_part(
+        &self,
+        collection: &str,
+        upload_id: &str,
+        part: usize,
+        operations: PointInsertOperations,
+    ) -> Result<(), BatchUploadError> {
+        let mut uploads = self.uploads.lock();
+        let session = uploads
+            .get_mut(upload_id)
+            .ok_or(BatchUploadError::NotFound)?;
+        if session.collection != collection {
+            return Err(BatchUploadError::CollectionMismatch);
+        }
+        session.parts.insert(part, operations);
+        Ok(())
+    }
+
+    /// Removes and returns the session's parts in ascending part-number
+    /// order, ready to be merged via a single `do_batch_update_points`
+    /// call.
+    pub fn complete(
+        &self,
+        collection: &str,
+        upload_id: &str,
+    ) -> Result<Vec<PointInsertOperations>, BatchUploadError> {
+        let mut uploads = self.uploads.lock();
+        let session = uploads.get(upload_id).ok_or(BatchUploadError::NotFound)?;
+        if session.collection != collection {
+            return Err(BatchUploadError::CollectionMismatch);
+        }
+        let session = uploads.remove(upload_id).unwrap();
+        Ok(session.parts.into_values().collect())
+    }
+
+    /// Discards a session's staged parts without committing them.
+    pub fn abort(&self, collection: &str, upload_id: &str) -> Result<(), BatchUploadError> {
+        let mut uploads = self.uploads.lock();
+        let session = uploads.get(upload_id).ok_or(BatchUploadError::NotFound)?;
+        if session.collection != collection {
+            return Err(BatchUploadError::CollectionMismatch);
+        }
+        uploads.remove(upload_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_points() -> PointInsertOperations {
+        PointInsertOperations::PointsList(Vec::new())
+    }
+
+    #[test]
+    fn test_complete_returns_parts_in_order() {
+        let store = BatchUploadStore::new();
+        let upload_id = store.create("coll");
+        store
+            .put_part("coll", &upload_id, 2, empty_points())
+            .unwrap();
+        store
+            .put_part("coll", &upload_id, 1, empty_points())
+            .unwrap();
+        let parts = store.complete("coll", &upload_id).unwrap();
+        assert_eq!(parts.len(), 2);
+        // A second complete finds nothing: the session was removed.
+        assert!(matches!(
+            store.complete("coll", &upload_id),
+            Err(BatchUploadError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_collection_mismatch_is_rejected() {
+        let store = BatchUploadStore::new();
+        let upload_id = store.create("coll-a");
+        assert!(matches!(
+            store.put_part("coll-b", &upload_id, 0, empty_points()),
+            Err(BatchUploadError::CollectionMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_abort_discards_session() {
+        let store = BatchUploadStore::new();
+        let upload_id = store.create("coll");
+        store
+            .put_part("coll", &upload_id, 0, empty_points())
+            .unwrap();
+        store.abort("coll", &upload_id).unwrap();
+        assert!(matches!(
+            store.complete("coll", &upload_id),
+            Err(BatchUploadError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+//! Idempotency-key tracking for update operations, so a client retrying an
+//! update after a dropped connection during at-least-once delivery gets
+//! the original result replayed instead of the mutation being applied a
+//! second time - the same guarantee idempotent-PUT object-store APIs give.
+//!
+//! Every key is recorded together with a hash of the request body it was
+//! first seen with, scoped per collection, in a bounded LRU table (the
+//! least-recently-used key is evicted once a collection's table exceeds
+//! [`IdempotencyStore::capacity`]). A key seen again with a *different*
+//! body hash is a conflict, since replaying would silently apply the
+//! wrong mutation and staying silent would double-apply it.
+//!
+//! Kept in memory only: the request to persist this per-collection is not
+//! yet wired to real on-disk storage in this tree, so a process restart
+//! forgets every key - callers should treat this as a best-effort
+//! de-duplication window, not a durability guarantee.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+/// Default number of idempotency keys remembered per collection.
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+/// How long [`IdempotencyStore::claim`]'s caller should wait on an in-flight
+/// request's [`Notify`] before giving up and reporting the key as still
+/// running, rather than waiting forever for a mutation that may never
+/// complete (e.g. the task driving it was dropped).
+pub const IN_FLIGHT_WAIT: Duration = Duration::from_secs(30);
+
+/// A claimed-but-not-yet-completed mutation, or one whose result is
+/// recorded. Kept as one enum (rather than two maps) so a key's body hash
+/// and the conflict check against it cover both states uniformly.
+enum EntryState {
+    /// `run` is in progress for this key; `notify` wakes everyone waiting
+    /// on it once [`IdempotencyStore::complete`] stores the result.
+    Pending { notify: Arc<Notify> },
+    /// `run`'s result, ready to replay.
+    Done { status: u16, body: Vec<u8> },
+}
+
+struct Entry {
+    body_hash: u64,
+    state: EntryState,
+}
+
+#[derive(Default)]
+struct CollectionTable {
+    entries: HashMap<String, Entry>,
+    /// Least-recently-used key first; re-accessed keys are moved to the back.
+    order: VecDeque<String>,
+}
+
+impl CollectionTable {
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(pos).unwrap();
+            self.order.push_back(key);
+        }
+    }
+
+    fn insert(&mut self, key: String, entry: Entry, capacity: usize) {
+        if self.entries.insert(key.clone(), entry).is_none() {
+            self.order.push_back(key);
+        } else {
+            self.touch(&key);
+        }
+        while self.entries.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// What to do with an update request carrying an idempotency key, per
+/// [`IdempotencyStore::claim`].
+pub enum ClaimOutcome {
+    /// Key not seen before (or the store holds no key at all) - this caller
+    /// has exclusively claimed it and must run the mutation, then call
+    /// [`IdempotencyStore::complete`] with its result.
+    Claimed,
+    /// Key seen before with the same body hash and already completed -
+    /// replay this stored response instead of re-applying the mutation.
+    Replay { status: u16, body: Vec<u8> },
+    /// Key seen before with a *different* body hash.
+    Conflict,
+    /// Key seen before with the same body hash, but another request is
+    /// still running the mutation for it - wait on `notify`, then call
+    /// `claim` again to pick up its result.
+    InFlight(Arc<Notify>),
+}
+
+/// Per-process idempotency-key table, scoped per collection; see this
+/// module's doc comment.
+pub struct IdempotencyStore {
+    collections: Mutex<HashMap<String, CollectionTable>>,
+    capacity: usize,
+}
+
+impl IdempotencyStore {
+    pub fn new(capacity: usize) -> Self {
+        IdempotencyStore {
+            collections: Mutex::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    /// Hashes `value`'s JSON serialization, for comparing a retried
+    /// request's body against the one an idempotency key was first
+    /// recorded with.
+    pub fn hash_body<T: Serialize>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        // Serialization can't fail for the request types this is used on;
+        // falling back to hashing nothing would only widen, never narrow,
+        // what counts as a body match.
+        if let Ok(bytes) = serde_json::to_vec(value) {
+            bytes.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Atomically checks `key` against the collection's table and, if it is
+    /// unclaimed, inserts a pending placeholder for it - all under the same
+    /// lock acquisition, so two concurrent requests for the same fresh key
+    /// can never both observe [`ClaimOutcome::Claimed`].
+    pub fn claim(&self, collection: &str, key: &str, body_hash: u64) -> ClaimOutcome {
+        let mut collections = self.collections.lock();
+        let table = collections.entry(collection.to_string()).or_default();
+
+        match table.entries.get(key) {
+            Some(entry) if entry.body_hash != body_hash => ClaimOutcome::Conflict,
+            Some(entry) => match &entry.state {
+                EntryState::Done { status, body } => {
+                    let (status, body) = (*status, body.clone());
+                    table.touch(key);
+                    ClaimOutcome::Replay { status, body }
+                }
+                EntryState::Pending { notify } => ClaimOutcome::InFlight(notify.clone()),
+            },
+            None => {
+                table.insert(
+                    key.to_string(),
+                    Entry {
+                        body_hash,
+                        state: EntryState::Pending {
+                            notify: Arc::new(Notify::new()),
+                        },
+                    },
+                    self.capacity,
+                );
+                ClaimOutcome::Claimed
+            }
+        }
+    }
+
+    /// Stores the result of a mutation run after a [`ClaimOutcome::Claimed`]
+    /// and wakes any requests waiting on the same key's
+    /// [`ClaimOutcome::InFlight`] notify.
+    pub fn complete(
+        &self,
+        collection: &str,
+        key: &str,
+        body_hash: u64,
+        status: u16,
+        body: Vec<u8>,
+    ) {
+        let mut collections = self.collections.lock();
+        let table = collections.entry(collection.to_string()).or_default();
+
+        let notify = match table.entries.get(key) {
+            Some(Entry {
+                state: EntryState::Pending { notify },
+                ..
+            }) => Some(notify.clone()),
+            _ => None,
+        };
+
+        table.insert(
+            key.to_string(),
+            Entry {
+                body_hash,
+                state: EntryState::Done { status, body },
+            },
+            self.capacity,
+        );
+
+        if let Some(notify) = notify {
+            notify.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_on_matching_body() {
+        let store = IdempotencyStore::new(10);
+        let hash = IdempotencyStore::hash_body(&serde_json::json!({"a": 1}));
+        assert!(matches!(
+            store.claim("coll", "key-1", hash),
+            ClaimOutcome::Claimed
+        ));
+        store.complete("coll", "key-1", hash, 200, b"cached".to_vec());
+        match store.claim("coll", "key-1", hash) {
+            ClaimOutcome::Replay { status, body } => {
+                assert_eq!(status, 200);
+                assert_eq!(body, b"cached");
+            }
+            _ => panic!("expected a replay"),
+        }
+    }
+
+    #[test]
+    fn test_second_claim_of_fresh_key_is_in_flight() {
+        let store = IdempotencyStore::new(10);
+        let hash = IdempotencyStore::hash_body(&serde_json::json!({"a": 1}));
+        assert!(matches!(
+            store.claim("coll", "key-1", hash),
+            ClaimOutcome::Claimed
+        ));
+        // A second concurrent claim of the same still-running key must not
+        // also see Claimed, or the mutation would run twice.
+        assert!(matches!(
+            store.claim("coll", "key-1", hash),
+            ClaimOutcome::InFlight(_)
+        ));
+    }
+
+    #[test]
+    fn test_conflict_on_mismatched_body() {
+        let store = IdempotencyStore::new(10);
+        let hash_a = IdempotencyStore::hash_body(&serde_json::json!({"a": 1}));
+        let hash_b = IdempotencyStore::hash_body(&serde_json::json!({"a": 2}));
+        store.claim("coll", "key-1", hash_a);
+        store.complete("coll", "key-1", hash_a, 200, b"cached".to_vec());
+        assert!(matches!(
+            store.claim("coll", "key-1", hash_b),
+            ClaimOutcome::Conflict
+        ));
+    }
+
+    #[test]
+    fn test_conflict_on_mismatched_body_while_in_flight() {
+        let store = IdempotencyStore::new(10);
+        let hash_a = IdempotencyStore::hash_body(&serde_json::json!({"a": 1}));
+        let hash_b = IdempotencyStore::hash_body(&serde_json::json!({"a": 2}));
+        store.claim("coll", "key-1", hash_a);
+        assert!(matches!(
+            store.claim("coll", "key-1", hash_b),
+            ClaimOutcome::Conflict
+        ));
+    }
+
+    #[test]
+    fn test_capacity_evicts_least_recently_used() {
+        let store = IdempotencyStore::new(2);
+        let hash = IdempotencyStore::hash_body(&serde_json::json!({}));
+        store.claim("coll", "key-1", hash);
+        store.complete("coll", "key-1", hash, 200, b"1".to_vec());
+        store.claim("coll", "key-2", hash);
+        store.complete("coll", "key-2", hash, 200, b"2".to_vec());
+        // Touch key-1 so key-2 becomes the least-recently-used entry.
+        assert!(matches!(
+            store.claim("coll", "key-1", hash),
+            ClaimOutcome::Replay { .. }
+        ));
+        store.claim("coll", "key-3", hash);
+        store.complete("coll", "key-3", hash, 200, b"3".to_vec());
+
+        assert!(matches!(
+            store.claim("coll", "key-2", hash),
+            ClaimOutcome::Claimed
+        ));
+        assert!(matches!(
+            store.claim("coll", "key-1", hash),
+            ClaimOutcome::Replay { .. }
+        ));
+        assert!(matches!(
+            store.claim("coll", "key-3", hash),
+            ClaimOutcome::Replay { .. }
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use std::env;
+use std::error::Error;
+use std::process::Command;
+use std::time::Instant;
+
+/// Workload runner invoked as `cargo xtask <task>`.
+///
+/// Currently supports `bench`, which times a release build of `codeindex`
+/// walking a target directory, to track indexing throughput regressions
+/// across changes to the walker/outline pipeline.
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        Some("bench") => bench(args.next()),
+        Some(other) => Err(format!("unknown xtask `{other}`, expected `bench`").into()),
+        None => Err("usage: cargo xtask bench [path]".into()),
+    }
+}
+
+fn bench(target: Option<String>) -> Result<(), Box<dyn Error>> {
+    let target = target.unwrap_or_else(|| ".".to_string());
+
+    let status = Command::new("cargo")
+        .args(["build", "--release", "--package", "codeindex"])
+        .status()?;
+    if !status.success() {
+        return Err("failed to build codeindex in release mode".into());
+    }
+
+    let binary = release_binary_path("codeindex");
+    let start = Instant::now();
+    let status = Command::new(&binary).current_dir(&target).status()?;
+    let elapsed = start.elapsed();
+
+    if !status.success() {
+        return Err(format!("{} exited with {status}", binary.display()).into());
+    }
+
+    println!("indexed {target} in {:.3}s", elapsed.as_secs_f64());
+    Ok(())
+}
+
+fn release_binary_path(name: &str) -> std::path::PathBuf {
+    let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.pop(); // crates/
+    path.pop(); // workspace root
+    path.push("target/release");
+    path.push(name);
+    path
+}